@@ -0,0 +1,485 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Binary serialization of a `ParsedSource` for persistent caches.
+//!
+//! The bytes start with a header containing a format version and the
+//! version of this crate, so a cache written by a different version
+//! (ex. one with a different version of swc) fails to deserialize with
+//! `CacheError::VersionMismatch` rather than producing a different AST.
+//! The rest is MessagePack, which is used instead of a non-self-describing
+//! format (ex. bincode) because swc's AST uses internally tagged enums.
+//!
+//! Some parts of a parsed source are not stored:
+//!
+//! - Tokens are lexed again when deserializing a source that was parsed
+//!   with tokens because swc's tokens are not serializable.
+//! - Syntax contexts from scope analysis are only valid in the process
+//!   that created them, so scope analysis is done again when deserializing.
+//! - Diagnostics contain swc errors that are not serializable, so a source
+//!   with diagnostics is parsed again when deserializing. This is not
+//!   expected to be common because the cached source would be one that
+//!   parsed successfully.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::encoding::text_info_with_bom;
+use crate::parse;
+use crate::parse_json;
+use crate::parse_module_or_script;
+use crate::parsing::capture_tokens;
+use crate::remove_syntax_contexts;
+use crate::swc::ast::EsVersion;
+use crate::swc::ast::Program;
+use crate::swc::common::comments::SingleThreadedCommentsMapInner;
+use crate::swc::parser::Syntax;
+use crate::Diagnostic;
+use crate::MediaType;
+use crate::ModuleDetectionReason;
+use crate::MultiThreadedComments;
+use crate::ParseMode;
+use crate::ParseParams;
+use crate::ParsedSource;
+use crate::ParsedSourceOptions;
+use crate::SourceTextInfo;
+use crate::SyntaxOptions;
+
+const MAGIC: &[u8; 4] = b"DAST";
+/// Version of the format, which must be bumped when the serialized data
+/// changes in a way the crate version doesn't capture.
+const FORMAT_VERSION: u8 = 1;
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Error deserializing a `ParsedSource`.
+#[derive(Debug, Clone)]
+pub enum CacheError {
+  /// The bytes were not a serialized parsed source.
+  InvalidHeader,
+  /// The bytes were serialized by a different version of the format or
+  /// of this crate.
+  VersionMismatch {
+    format_version: u8,
+    crate_version: String,
+  },
+  /// The provided text is not the text the source was parsed from.
+  TextMismatch,
+  /// The data after the header could not be decoded.
+  Decode(String),
+  /// Parsing the text again failed for a source that had diagnostics.
+  Parse(Box<Diagnostic>),
+}
+
+impl std::error::Error for CacheError {}
+
+impl fmt::Display for CacheError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CacheError::InvalidHeader => {
+        write!(f, "Bytes were not a serialized parsed source.")
+      }
+      CacheError::VersionMismatch {
+        format_version,
+        crate_version,
+      } => write!(
+        f,
+        "Parsed source was serialized with format version {} of deno_ast {}, but expected format version {} of deno_ast {}.",
+        format_version, crate_version, FORMAT_VERSION, CRATE_VERSION
+      ),
+      CacheError::TextMismatch => write!(
+        f,
+        "Text was not the text the serialized parsed source was parsed from."
+      ),
+      CacheError::Decode(message) => {
+        write!(f, "Failed decoding parsed source. {}", message)
+      }
+      CacheError::Parse(diagnostic) => {
+        write!(f, "Failed parsing source again. {}", diagnostic)
+      }
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedSource<'a> {
+  specifier: Cow<'a, str>,
+  #[serde(with = "crate::serialization::media_type")]
+  media_type: MediaType,
+  text_len: usize,
+  text_hash: u64,
+  had_bom: bool,
+  parse_mode: ParseMode,
+  syntax: Syntax,
+  syntax_options: Option<SyntaxOptions>,
+  es_version: EsVersion,
+  max_diagnostics: Option<usize>,
+  lint_deprecations: bool,
+  strip_bom: bool,
+  capture_tokens: bool,
+  scope_analysis: bool,
+  module_detection_reason: ModuleDetectionReason,
+  /// The program and comments, which is `None` when the source had
+  /// diagnostics and needs to be parsed again.
+  parsed: Option<CachedParse<'a>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedParse<'a> {
+  program: Cow<'a, Program>,
+  leading_comments: Cow<'a, SingleThreadedCommentsMapInner>,
+  trailing_comments: Cow<'a, SingleThreadedCommentsMapInner>,
+}
+
+impl ParsedSource {
+  /// Serializes the parsed source for storing in a persistent cache.
+  ///
+  /// The text is not included, so it must be provided when deserializing
+  /// (see `ParsedSource::deserialize`).
+  pub fn serialize(&self) -> Vec<u8> {
+    let options = self.options();
+    let text = self.text_info().text_str();
+    let has_diagnostics =
+      !self.diagnostics().is_empty() || !self.fallback_diagnostics().is_empty();
+    let cached = CachedSource {
+      specifier: Cow::Borrowed(self.specifier()),
+      media_type: self.media_type(),
+      text_len: text.len(),
+      text_hash: hash_text(text),
+      had_bom: self.had_bom(),
+      parse_mode: options.parse_mode,
+      syntax: options.syntax,
+      syntax_options: options.syntax_options,
+      es_version: options.es_version,
+      max_diagnostics: options.max_diagnostics,
+      lint_deprecations: options.lint_deprecations,
+      strip_bom: options.strip_bom,
+      capture_tokens: self.has_captured_tokens(),
+      scope_analysis: self.has_scope_analysis(),
+      module_detection_reason: self.module_detection_reason(),
+      parsed: (!has_diagnostics).then(|| CachedParse {
+        program: Cow::Borrowed(self.program_ref()),
+        leading_comments: Cow::Borrowed(self.comments().leading_map()),
+        trailing_comments: Cow::Borrowed(self.comments().trailing_map()),
+      }),
+    };
+    let mut bytes = header();
+    // named so that swc's internally tagged enums can be deserialized
+    rmp_serde::encode::write_named(&mut bytes, &cached)
+      .expect("serializing to a vector should not fail");
+    bytes
+  }
+
+  /// Deserializes a parsed source serialized by `ParsedSource::serialize`.
+  ///
+  /// The text info must have the text of the parsed source (excluding
+  /// a BOM that was stripped when parsing).
+  pub fn deserialize(
+    bytes: &[u8],
+    text_info: SourceTextInfo,
+  ) -> Result<ParsedSource, CacheError> {
+    let data = read_header(bytes)?;
+    let mut cached = rmp_serde::from_slice::<CachedSource>(data)
+      .map_err(|err| CacheError::Decode(err.to_string()))?;
+    let text = text_info.text_str();
+    if cached.text_len != text.len() || cached.text_hash != hash_text(text) {
+      return Err(CacheError::TextMismatch);
+    }
+    let Some(parsed) = cached.parsed.take() else {
+      return reparse(cached, text_info);
+    };
+    let mut program = parsed.program.into_owned();
+    if cached.scope_analysis {
+      // syntax contexts are only valid in the process that created them
+      remove_syntax_contexts(&mut program);
+    }
+    let tokens = cached.capture_tokens.then(|| {
+      Arc::new(capture_tokens(
+        &text_info,
+        cached.parse_mode,
+        cached.syntax,
+        cached.es_version,
+      ))
+    });
+    let parsed_source = ParsedSource::new(
+      cached.specifier.into_owned(),
+      cached.media_type,
+      text_info,
+      MultiThreadedComments::from_leading_and_trailing(
+        parsed.leading_comments.into_owned(),
+        parsed.trailing_comments.into_owned(),
+      ),
+      Arc::new(program),
+      tokens,
+      None,
+      Vec::new(),
+      ParsedSourceOptions {
+        parse_mode: cached.parse_mode,
+        syntax: cached.syntax,
+        syntax_options: cached.syntax_options,
+        es_version: cached.es_version,
+        max_diagnostics: cached.max_diagnostics,
+        lint_deprecations: cached.lint_deprecations,
+        strip_bom: cached.strip_bom,
+      },
+      cached.module_detection_reason,
+      cached.had_bom,
+    );
+    Ok(if cached.scope_analysis {
+      parsed_source.into_with_scope_analysis()
+    } else {
+      parsed_source
+    })
+  }
+}
+
+fn header() -> Vec<u8> {
+  let mut bytes = Vec::new();
+  bytes.extend_from_slice(MAGIC);
+  bytes.push(FORMAT_VERSION);
+  bytes.push(CRATE_VERSION.len() as u8);
+  bytes.extend_from_slice(CRATE_VERSION.as_bytes());
+  bytes
+}
+
+/// Reads the header, returning the data after it.
+fn read_header(bytes: &[u8]) -> Result<&[u8], CacheError> {
+  let Some(bytes) = bytes.strip_prefix(MAGIC.as_slice()) else {
+    return Err(CacheError::InvalidHeader);
+  };
+  let [format_version, crate_version_len, bytes @ ..] = bytes else {
+    return Err(CacheError::InvalidHeader);
+  };
+  let crate_version_len = *crate_version_len as usize;
+  if bytes.len() < crate_version_len {
+    return Err(CacheError::InvalidHeader);
+  }
+  let (crate_version, data) = bytes.split_at(crate_version_len);
+  if *format_version != FORMAT_VERSION
+    || crate_version != CRATE_VERSION.as_bytes()
+  {
+    return Err(CacheError::VersionMismatch {
+      format_version: *format_version,
+      crate_version: String::from_utf8_lossy(crate_version).to_string(),
+    });
+  }
+  Ok(data)
+}
+
+/// Parses the text again with the options of a cached source, which is
+/// done for sources that had diagnostics.
+fn reparse(
+  cached: CachedSource,
+  text_info: SourceTextInfo,
+) -> Result<ParsedSource, CacheError> {
+  let text_info = if cached.had_bom {
+    // add it back so it's stripped in the same way
+    text_info_with_bom(text_info.text_str())
+  } else {
+    text_info
+  };
+  let params = ParseParams {
+    specifier: cached.specifier.into_owned(),
+    text_info,
+    media_type: cached.media_type,
+    capture_tokens: cached.capture_tokens,
+    scope_analysis: cached.scope_analysis,
+    maybe_syntax: Some(cached.syntax),
+    syntax_options: cached.syntax_options,
+    es_version: Some(cached.es_version),
+    maybe_cancellation: None,
+    max_diagnostics: cached.max_diagnostics,
+    lint_deprecations: cached.lint_deprecations,
+    strip_bom: cached.strip_bom,
+  };
+  let result = if cached.media_type == MediaType::Json {
+    parse_json(params)
+  } else if cached.module_detection_reason
+    == ModuleDetectionReason::ScriptFallback
+  {
+    parse_module_or_script(params)
+  } else {
+    parse(params, cached.parse_mode)
+  };
+  result.map_err(|diagnostic| CacheError::Parse(Box::new(diagnostic)))
+}
+
+/// Hashes the text with FNV-1a, which unlike the standard library's
+/// hasher is stable across Rust versions.
+fn hash_text(text: &str) -> u64 {
+  const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+  const PRIME: u64 = 0x100000001b3;
+  text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+    (hash ^ byte as u64).wrapping_mul(PRIME)
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::ImportAttributesMode;
+  use crate::SourceRangedForSpanned;
+
+  use super::*;
+
+  fn parse_ts(text: &str, capture_tokens: bool) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(capture_tokens)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  const TEXT: &str = concat!(
+    "// leading\n",
+    "import { a } from './a.ts';\n",
+    "/** doc */\n",
+    "export class B<T> extends a { #c: T[] = []; get d() { return 1.5; } }\n",
+    "const e = `f${a}` as const; // trailing\n",
+  );
+
+  #[test]
+  fn round_trips() {
+    let parsed_source = parse_ts(TEXT, true);
+    let bytes = parsed_source.serialize();
+    let deserialized =
+      ParsedSource::deserialize(&bytes, parsed_source.text_info().clone())
+        .unwrap();
+    assert_eq!(deserialized.specifier(), parsed_source.specifier());
+    assert_eq!(deserialized.media_type(), parsed_source.media_type());
+    assert_eq!(deserialized.parse_mode(), parsed_source.parse_mode());
+    // compares spans too
+    assert_eq!(deserialized.program_ref(), parsed_source.program_ref());
+    assert_eq!(
+      deserialized.comments().get_vec(),
+      parsed_source.comments().get_vec()
+    );
+    assert_eq!(
+      deserialized
+        .tokens()
+        .iter()
+        .map(|token| (token.token.clone(), token.range()))
+        .collect::<Vec<_>>(),
+      parsed_source
+        .tokens()
+        .iter()
+        .map(|token| (token.token.clone(), token.range()))
+        .collect::<Vec<_>>(),
+    );
+    assert!(deserialized.diagnostics().is_empty());
+
+    // tokens are only available when they were captured
+    let parsed_source = parse_ts(TEXT, false);
+    let deserialized = ParsedSource::deserialize(
+      &parsed_source.serialize(),
+      parsed_source.text_info().clone(),
+    )
+    .unwrap();
+    assert_eq!(deserialized.program_ref(), parsed_source.program_ref());
+    assert!(!deserialized.has_captured_tokens());
+  }
+
+  #[cfg(feature = "transforms")]
+  #[test]
+  fn recomputes_scope_analysis() {
+    use crate::swc::common::EqIgnoreSpan;
+
+    let parsed_source = parse_ts(TEXT, false).into_with_scope_analysis();
+    let deserialized = ParsedSource::deserialize(
+      &parsed_source.serialize(),
+      parsed_source.text_info().clone(),
+    )
+    .unwrap();
+    assert!(deserialized.has_scope_analysis());
+    assert!(deserialized
+      .program_ref()
+      .eq_ignore_span(parsed_source.program_ref()));
+  }
+
+  #[test]
+  fn reparses_with_diagnostics() {
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string(
+          "import data from './a.json' assert { type: 'json' };".to_string(),
+        ),
+      )
+      .syntax_options(SyntaxOptions {
+        import_attributes_mode: Some(
+          ImportAttributesMode::AssertDeprecatedWarning,
+        ),
+        ..Default::default()
+      })
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.diagnostics().len(), 1);
+    let deserialized = ParsedSource::deserialize(
+      &parsed_source.serialize(),
+      parsed_source.text_info().clone(),
+    )
+    .unwrap();
+    assert_eq!(deserialized.program_ref(), parsed_source.program_ref());
+    assert_eq!(deserialized.diagnostics(), parsed_source.diagnostics());
+  }
+
+  #[test]
+  fn errors() {
+    let parsed_source = parse_ts(TEXT, false);
+    let bytes = parsed_source.serialize();
+    let text_info = parsed_source.text_info().clone();
+
+    // different format version
+    let mut other_bytes = bytes.clone();
+    other_bytes[MAGIC.len()] = FORMAT_VERSION + 1;
+    let err =
+      ParsedSource::deserialize(&other_bytes, text_info.clone()).unwrap_err();
+    assert!(matches!(
+      err,
+      CacheError::VersionMismatch { format_version, .. }
+        if format_version == FORMAT_VERSION + 1
+    ));
+
+    // different crate version
+    let mut other_bytes = bytes.clone();
+    let version_index = MAGIC.len() + 2;
+    other_bytes[version_index] = b'x';
+    let err =
+      ParsedSource::deserialize(&other_bytes, text_info.clone()).unwrap_err();
+    assert!(matches!(err, CacheError::VersionMismatch { .. }));
+
+    // not a serialized source
+    let err = ParsedSource::deserialize(b"{}", text_info.clone()).unwrap_err();
+    assert!(matches!(err, CacheError::InvalidHeader));
+    let err =
+      ParsedSource::deserialize(&bytes[..MAGIC.len() + 1], text_info.clone())
+        .unwrap_err();
+    assert!(matches!(err, CacheError::InvalidHeader));
+
+    // truncated data
+    let err =
+      ParsedSource::deserialize(&bytes[..bytes.len() - 10], text_info.clone())
+        .unwrap_err();
+    assert!(matches!(err, CacheError::Decode(_)));
+
+    // different text
+    let err = ParsedSource::deserialize(
+      &bytes,
+      SourceTextInfo::from_string(TEXT.replace("1.5", "2.5")),
+    )
+    .unwrap_err();
+    assert!(matches!(err, CacheError::TextMismatch));
+  }
+}
@@ -0,0 +1,185 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::sync::OnceLock;
+
+use crate::lines::clamp_pos;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+use crate::SourceTextInfoLines;
+
+/// Conversions between positions in the source text and character
+/// (Unicode scalar value) indexes.
+///
+/// Each conversion scans the text up to the position. Use a
+/// `CharIndexMap` when doing many conversions on the same text.
+pub trait SourceTextInfoCharIndex {
+  /// Gets the position of a character index, clamping an index past the
+  /// end of the text to the end of the text.
+  fn char_index_to_pos(&self, char_index: usize) -> SourcePos;
+
+  /// Gets the character index of a position.
+  ///
+  /// Positions outside the text are clamped to the start or end of the
+  /// text and positions within a character are moved to its start.
+  fn pos_to_char_index(&self, pos: SourcePos) -> usize;
+}
+
+impl SourceTextInfoCharIndex for SourceTextInfo {
+  fn char_index_to_pos(&self, char_index: usize) -> SourcePos {
+    let text = self.text_str();
+    let byte_index = text
+      .char_indices()
+      .nth(char_index)
+      .map(|(byte_index, _)| byte_index)
+      .unwrap_or(text.len());
+    self.range().start + byte_index
+  }
+
+  fn pos_to_char_index(&self, pos: SourcePos) -> usize {
+    let byte_index = clamp_pos(self, pos) - self.range().start;
+    self.text_str()[..byte_index].chars().count()
+  }
+}
+
+/// Converts between positions in the source text and character
+/// (Unicode scalar value) indexes, only scanning the line of the
+/// position after the first conversion.
+///
+/// The character count of each line is computed on the first
+/// conversion.
+pub struct CharIndexMap {
+  text_info: SourceTextInfo,
+  /// Character index of the start of each line along with the total
+  /// number of characters.
+  line_char_starts: OnceLock<Vec<usize>>,
+}
+
+impl CharIndexMap {
+  pub fn new(text_info: SourceTextInfo) -> Self {
+    Self {
+      text_info,
+      line_char_starts: OnceLock::new(),
+    }
+  }
+
+  /// Gets the position of a character index, clamping an index past the
+  /// end of the text to the end of the text.
+  pub fn char_index_to_pos(&self, char_index: usize) -> SourcePos {
+    let line_char_starts = self.line_char_starts();
+    let line_index = line_char_starts
+      .partition_point(|line_char_start| *line_char_start <= char_index)
+      - 1;
+    if line_index >= self.text_info.lines_count() {
+      return self.text_info.range().end;
+    }
+    let line_range = self.text_info.range_of_line(line_index);
+    let line_text = self.text_info.range_text(&line_range);
+    let column_index = char_index - line_char_starts[line_index];
+    let byte_index = if line_text.is_ascii() {
+      column_index
+    } else {
+      line_text
+        .char_indices()
+        .nth(column_index)
+        .map(|(byte_index, _)| byte_index)
+        .unwrap_or(line_text.len())
+    };
+    line_range.start + byte_index
+  }
+
+  /// Gets the character index of a position.
+  ///
+  /// Positions outside the text are clamped to the start or end of the
+  /// text and positions within a character are moved to its start.
+  pub fn pos_to_char_index(&self, pos: SourcePos) -> usize {
+    let pos = clamp_pos(&self.text_info, pos);
+    let line_index = self.text_info.line_index(pos);
+    let line_start = self.text_info.line_start(line_index);
+    let column_text = self
+      .text_info
+      .range_text(&SourceRange::new(line_start, pos));
+    self.line_char_starts()[line_index] + column_text.chars().count()
+  }
+
+  fn line_char_starts(&self) -> &[usize] {
+    self.line_char_starts.get_or_init(|| {
+      let lines_count = self.text_info.lines_count();
+      let mut line_char_starts = Vec::with_capacity(lines_count + 1);
+      let mut char_count = 0;
+      for line_index in 0..lines_count {
+        line_char_starts.push(char_count);
+        let line_range = self.text_info.range_of_line(line_index);
+        char_count += self.text_info.range_text(&line_range).chars().count();
+      }
+      line_char_starts.push(char_count);
+      line_char_starts
+    })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const TEXT: &str = concat!(
+    "const café = 'é';\n",
+    "const a = '😀👍🏽';\r\n",
+    "\n",
+    "// 日本語のコメント\n",
+    "const 𝑥 = 1;",
+  );
+
+  #[test]
+  fn agrees_with_char_indices() {
+    let text_info = SourceTextInfo::from_string(TEXT.to_string());
+    let map = CharIndexMap::new(text_info.clone());
+    let start = text_info.range().start;
+    let char_indices = TEXT
+      .char_indices()
+      .map(|(byte_index, _)| byte_index)
+      .chain([TEXT.len()]);
+    for (char_index, byte_index) in char_indices.enumerate() {
+      let pos = start + byte_index;
+      assert_eq!(text_info.char_index_to_pos(char_index), pos);
+      assert_eq!(text_info.pos_to_char_index(pos), char_index);
+      assert_eq!(map.char_index_to_pos(char_index), pos);
+      assert_eq!(map.pos_to_char_index(pos), char_index);
+    }
+  }
+
+  #[test]
+  fn clamps_out_of_range_input() {
+    let text_info = SourceTextInfo::from_string(TEXT.to_string());
+    let map = CharIndexMap::new(text_info.clone());
+    let start = text_info.range().start;
+    let end = text_info.range().end;
+    let char_count = TEXT.chars().count();
+    let within_char_pos = start + TEXT.find('é').unwrap() + 1;
+    let within_char_index = TEXT[..within_char_pos - start - 1].chars().count();
+    for (char_index, pos) in
+      [(char_count, end), (char_count + 1, end), (usize::MAX, end)]
+    {
+      assert_eq!(text_info.char_index_to_pos(char_index), pos);
+      assert_eq!(map.char_index_to_pos(char_index), pos);
+    }
+    for (pos, char_index) in
+      [(end + 1, char_count), (within_char_pos, within_char_index)]
+    {
+      assert_eq!(text_info.pos_to_char_index(pos), char_index);
+      assert_eq!(map.pos_to_char_index(pos), char_index);
+    }
+  }
+
+  #[test]
+  fn empty_text() {
+    let text_info = SourceTextInfo::from_string(String::new());
+    let map = CharIndexMap::new(text_info.clone());
+    let start = text_info.range().start.as_source_pos();
+    assert_eq!(map.char_index_to_pos(0), start);
+    assert_eq!(map.char_index_to_pos(1), start);
+    assert_eq!(map.pos_to_char_index(start), 0);
+  }
+}
@@ -603,14 +603,12 @@ mod test {
   }
 
   fn parse_cjs(source: &str) -> CjsAnalysisTester {
-    let parsed_source = parse_script(ParseParams {
-      specifier: "".to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Cjs,
-      capture_tokens: true,
-      scope_analysis: false,
-      maybe_syntax: None,
-    })
+    let parsed_source = parse_script(
+      ParseParams::builder("", SourceTextInfo::from_string(source.to_string()))
+        .media_type(MediaType::Cjs)
+        .capture_tokens(true)
+        .build(),
+    )
     .unwrap();
     let analysis = parsed_source.analyze_cjs();
     CjsAnalysisTester {
@@ -0,0 +1,501 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::swc::ast::*;
+use crate::swc::atoms::Atom;
+use crate::swc::common::SyntaxContext;
+use crate::swc::visit::noop_visit_type;
+use crate::swc::visit::Visit;
+use crate::swc::visit::VisitWith;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::UnresolvedRef;
+
+const CJS_GLOBALS: [&str; 5] =
+  ["require", "module", "exports", "__dirname", "__filename"];
+
+/// CommonJS patterns found by `ParsedSource::cjs_patterns()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CjsPatterns {
+  /// `require("...")` calls in source order.
+  pub requires: Vec<CjsRequire>,
+  /// Assignments to `module.exports` or its properties in source order.
+  pub export_assignments: Vec<CjsExportAssignment>,
+  /// References to `__dirname` and `__filename` sorted by name.
+  pub globals: Vec<UnresolvedRef>,
+}
+
+impl CjsPatterns {
+  /// Gets if any CommonJS pattern was found.
+  pub fn is_cjs(&self) -> bool {
+    !self.requires.is_empty()
+      || !self.export_assignments.is_empty()
+      || !self.globals.is_empty()
+  }
+}
+
+/// A `require("...")` call with a string specifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CjsRequire {
+  pub specifier: Atom,
+  /// Range of the call.
+  pub range: SourceRange,
+  /// Range of the specifier string.
+  pub specifier_range: SourceRange,
+}
+
+/// Kind of a CommonJS export assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CjsExportKind {
+  /// ex. `module.exports = {};`
+  ModuleExports,
+  /// ex. `module.exports = require("./x.js");` with the specifier.
+  Reexport(Atom),
+  /// ex. `exports.a = 1;` or `module.exports["a"] = 1;` with the name.
+  Named(Atom),
+}
+
+/// An assignment to `module.exports` or one of its properties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CjsExportAssignment {
+  pub kind: CjsExportKind,
+  /// Range of the assignment expression.
+  pub range: SourceRange,
+}
+
+impl ParsedSource {
+  /// Finds the CommonJS `require` calls, export assignments, and
+  /// references to `__dirname` and `__filename`.
+  ///
+  /// Only identifiers that refer to the CommonJS globals are included.
+  /// With scope analysis, this is identifiers with the unresolved context.
+  /// Otherwise, it conservatively excludes names that are declared
+  /// anywhere in the source (ex. a local `require` function).
+  pub fn cjs_patterns(&self) -> CjsPatterns {
+    let program = self.program_ref();
+    let globals = match self.maybe_unresolved_context() {
+      Some(unresolved_context) => CjsGlobals::Unresolved(unresolved_context),
+      None => {
+        let mut collector = DeclaredNamesCollector::default();
+        program.visit_with(&mut collector);
+        CjsGlobals::UnlessDeclared(collector.names)
+      }
+    };
+    let mut visitor = CjsPatternsVisitor {
+      globals,
+      patterns: Default::default(),
+      global_refs: HashMap::new(),
+    };
+    program.visit_with(&mut visitor);
+    let mut patterns = visitor.patterns;
+    patterns.globals = visitor
+      .global_refs
+      .into_iter()
+      .map(|(name, ranges)| UnresolvedRef { name, ranges })
+      .collect();
+    patterns
+      .globals
+      .sort_by(|a, b| a.name.as_ref().cmp(b.name.as_ref()));
+    patterns
+  }
+}
+
+enum CjsGlobals {
+  Unresolved(SyntaxContext),
+  /// CommonJS global names are globals unless they're declared
+  /// somewhere in the source.
+  UnlessDeclared(HashSet<&'static str>),
+}
+
+impl CjsGlobals {
+  fn is_global(&self, ident: &Ident, name: &str) -> bool {
+    if &*ident.sym != name {
+      return false;
+    }
+    match self {
+      CjsGlobals::Unresolved(unresolved_context) => {
+        ident.span.ctxt == *unresolved_context
+      }
+      CjsGlobals::UnlessDeclared(declared_names) => {
+        !declared_names.contains(name)
+      }
+    }
+  }
+}
+
+struct CjsPatternsVisitor {
+  globals: CjsGlobals,
+  patterns: CjsPatterns,
+  global_refs: HashMap<Atom, Vec<SourceRange>>,
+}
+
+impl CjsPatternsVisitor {
+  fn is_global_expr(&self, expr: &Expr, name: &str) -> bool {
+    matches!(expr, Expr::Ident(ident) if self.globals.is_global(ident, name))
+  }
+
+  /// Gets the specifier of a `require("...")` call.
+  fn require_specifier<'a>(&self, call_expr: &'a CallExpr) -> Option<&'a Str> {
+    let Callee::Expr(callee) = &call_expr.callee else {
+      return None;
+    };
+    if !self.is_global_expr(callee, "require") {
+      return None;
+    }
+    match &*call_expr.args.first()?.expr {
+      Expr::Lit(Lit::Str(specifier)) => Some(specifier),
+      _ => None,
+    }
+  }
+
+  /// Gets if the expression is `module.exports`.
+  fn is_module_exports(&self, expr: &Expr) -> bool {
+    let Expr::Member(member) = expr else {
+      return false;
+    };
+    self.is_global_expr(&member.obj, "module")
+      && member_prop_name(&member.prop).as_deref() == Some("exports")
+  }
+
+  /// Gets if the expression is `module.exports` or `exports`.
+  fn is_exports_object(&self, expr: &Expr) -> bool {
+    self.is_module_exports(expr) || self.is_global_expr(expr, "exports")
+  }
+
+  fn export_kind(&self, assign_expr: &AssignExpr) -> Option<CjsExportKind> {
+    let left = match &assign_expr.left {
+      PatOrExpr::Expr(expr) => &**expr,
+      PatOrExpr::Pat(pat) => &**pat.as_expr()?,
+    };
+    if self.is_module_exports(left) {
+      return Some(match &*assign_expr.right {
+        Expr::Call(call_expr) => match self.require_specifier(call_expr) {
+          Some(specifier) => CjsExportKind::Reexport(specifier.value.clone()),
+          None => CjsExportKind::ModuleExports,
+        },
+        _ => CjsExportKind::ModuleExports,
+      });
+    }
+    let Expr::Member(member) = left else {
+      return None;
+    };
+    if self.is_exports_object(&member.obj) {
+      member_prop_name(&member.prop).map(CjsExportKind::Named)
+    } else {
+      None
+    }
+  }
+}
+
+impl Visit for CjsPatternsVisitor {
+  noop_visit_type!();
+
+  fn visit_call_expr(&mut self, n: &CallExpr) {
+    if let Some(specifier) = self.require_specifier(n) {
+      self.patterns.requires.push(CjsRequire {
+        specifier: specifier.value.clone(),
+        range: n.range(),
+        specifier_range: specifier.range(),
+      });
+    }
+    n.visit_children_with(self);
+  }
+
+  fn visit_assign_expr(&mut self, n: &AssignExpr) {
+    if n.op == AssignOp::Assign {
+      if let Some(kind) = self.export_kind(n) {
+        self.patterns.export_assignments.push(CjsExportAssignment {
+          kind,
+          range: n.range(),
+        });
+      }
+    }
+    n.visit_children_with(self);
+  }
+
+  fn visit_ident(&mut self, n: &Ident) {
+    for name in ["__dirname", "__filename"] {
+      if self.globals.is_global(n, name) {
+        self
+          .global_refs
+          .entry(n.sym.clone())
+          .or_default()
+          .push(n.range());
+      }
+    }
+  }
+
+  // property names are not references, so only visit computed ones
+
+  fn visit_member_prop(&mut self, n: &MemberProp) {
+    if let MemberProp::Computed(computed) = n {
+      computed.visit_with(self);
+    }
+  }
+
+  fn visit_prop_name(&mut self, n: &PropName) {
+    if let PropName::Computed(computed) = n {
+      computed.visit_with(self);
+    }
+  }
+}
+
+fn member_prop_name(prop: &MemberProp) -> Option<Atom> {
+  match prop {
+    MemberProp::Ident(ident) => Some(ident.sym.clone()),
+    MemberProp::Computed(computed) => match &*computed.expr {
+      Expr::Lit(Lit::Str(string)) => Some(string.value.clone()),
+      _ => None,
+    },
+    MemberProp::PrivateName(_) => None,
+  }
+}
+
+/// Collects the CommonJS global names that are declared in the source.
+#[derive(Default)]
+struct DeclaredNamesCollector {
+  names: HashSet<&'static str>,
+}
+
+impl DeclaredNamesCollector {
+  fn add(&mut self, ident: &Ident) {
+    let maybe_name = CJS_GLOBALS
+      .iter()
+      .copied()
+      .find(|name| *name == &*ident.sym);
+    if let Some(name) = maybe_name {
+      self.names.insert(name);
+    }
+  }
+}
+
+impl Visit for DeclaredNamesCollector {
+  noop_visit_type!();
+
+  fn visit_binding_ident(&mut self, n: &BindingIdent) {
+    self.add(&n.id);
+  }
+
+  fn visit_fn_decl(&mut self, n: &FnDecl) {
+    self.add(&n.ident);
+    n.visit_children_with(self);
+  }
+
+  fn visit_fn_expr(&mut self, n: &FnExpr) {
+    if let Some(ident) = &n.ident {
+      self.add(ident);
+    }
+    n.visit_children_with(self);
+  }
+
+  fn visit_class_decl(&mut self, n: &ClassDecl) {
+    self.add(&n.ident);
+    n.visit_children_with(self);
+  }
+
+  fn visit_class_expr(&mut self, n: &ClassExpr) {
+    if let Some(ident) = &n.ident {
+      self.add(ident);
+    }
+    n.visit_children_with(self);
+  }
+
+  fn visit_import_named_specifier(&mut self, n: &ImportNamedSpecifier) {
+    self.add(&n.local);
+  }
+
+  fn visit_import_default_specifier(&mut self, n: &ImportDefaultSpecifier) {
+    self.add(&n.local);
+  }
+
+  fn visit_import_star_as_specifier(&mut self, n: &ImportStarAsSpecifier) {
+    self.add(&n.local);
+  }
+
+  fn visit_ts_import_equals_decl(&mut self, n: &TsImportEqualsDecl) {
+    self.add(&n.id);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::parse_script;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(
+    text: &str,
+    media_type: MediaType,
+    scope_analysis: bool,
+  ) -> ParsedSource {
+    let params = ParseParams::builder(
+      "file:///mod.js",
+      SourceTextInfo::from_string(text.to_string()),
+    )
+    .media_type(media_type)
+    .scope_analysis(scope_analysis)
+    .build();
+    if media_type == MediaType::Cjs {
+      parse_script(params).unwrap()
+    } else {
+      parse_module(params).unwrap()
+    }
+  }
+
+  fn requires(parsed_source: &ParsedSource) -> Vec<(String, &str)> {
+    parsed_source
+      .cjs_patterns()
+      .requires
+      .into_iter()
+      .map(|require| {
+        let specifier_text =
+          parsed_source.text_for_range(&require.specifier_range);
+        assert_eq!(
+          &specifier_text[1..specifier_text.len() - 1],
+          &*require.specifier
+        );
+        (
+          require.specifier.to_string(),
+          parsed_source.text_for_range(&require.range),
+        )
+      })
+      .collect()
+  }
+
+  const CJS_TEXT: &str = r#"const fs = require("fs");
+const path = require("path");
+exports.a = 1;
+module.exports.b = function() {};
+exports["c"] = 2;
+console.log(path.join(__dirname, "d.txt"), __dirname, __filename);
+"#;
+
+  #[test]
+  fn typical_cjs() {
+    for scope_analysis in [false, true] {
+      if scope_analysis && cfg!(not(feature = "transforms")) {
+        continue;
+      }
+      let parsed_source = parse(CJS_TEXT, MediaType::Cjs, scope_analysis);
+      assert_eq!(
+        requires(&parsed_source),
+        vec![
+          ("fs".to_string(), r#"require("fs")"#),
+          ("path".to_string(), r#"require("path")"#),
+        ]
+      );
+      let patterns = parsed_source.cjs_patterns();
+      assert!(patterns.is_cjs());
+      assert_eq!(
+        patterns
+          .export_assignments
+          .iter()
+          .map(|assignment| assignment.kind.clone())
+          .collect::<Vec<_>>(),
+        vec![
+          CjsExportKind::Named("a".into()),
+          CjsExportKind::Named("b".into()),
+          CjsExportKind::Named("c".into()),
+        ]
+      );
+      assert_eq!(
+        parsed_source.text_for_range(&patterns.export_assignments[0].range),
+        "exports.a = 1"
+      );
+      assert_eq!(
+        patterns
+          .globals
+          .iter()
+          .map(|global| (global.name.to_string(), global.ranges.len()))
+          .collect::<Vec<_>>(),
+        vec![("__dirname".to_string(), 2), ("__filename".to_string(), 1)]
+      );
+    }
+  }
+
+  #[test]
+  fn reexport() {
+    let parsed_source =
+      parse("module.exports = require('./x.js');", MediaType::Cjs, false);
+    let patterns = parsed_source.cjs_patterns();
+    assert_eq!(patterns.export_assignments.len(), 1);
+    let assignment = &patterns.export_assignments[0];
+    assert_eq!(assignment.kind, CjsExportKind::Reexport("./x.js".into()));
+    assert_eq!(
+      parsed_source.text_for_range(&assignment.range),
+      "module.exports = require('./x.js')"
+    );
+    assert_eq!(requires(&parsed_source).len(), 1);
+  }
+
+  #[test]
+  fn conditional_require() {
+    let parsed_source = parse(
+      concat!(
+        "if (process.env.A) {\n",
+        "  require('./a.js');\n",
+        "} else {\n",
+        "  module.exports = {};\n",
+        "}\n",
+      ),
+      MediaType::Cjs,
+      false,
+    );
+    assert_eq!(
+      requires(&parsed_source),
+      vec![("./a.js".to_string(), "require('./a.js')")]
+    );
+    assert_eq!(
+      parsed_source
+        .cjs_patterns()
+        .export_assignments
+        .into_iter()
+        .map(|assignment| assignment.kind)
+        .collect::<Vec<_>>(),
+      vec![CjsExportKind::ModuleExports]
+    );
+  }
+
+  #[test]
+  fn esm_with_local_require() {
+    let text = concat!(
+      "function require(name) { return name; }\n",
+      "const module = { exports: {} };\n",
+      "const exports = {};\n",
+      "export const a = require('./a.js');\n",
+      "module.exports = require('./b.js');\n",
+      "exports.c = 1;\n",
+    );
+    for scope_analysis in [false, true] {
+      if scope_analysis && cfg!(not(feature = "transforms")) {
+        continue;
+      }
+      let parsed_source = parse(text, MediaType::JavaScript, scope_analysis);
+      assert_eq!(parsed_source.cjs_patterns(), CjsPatterns::default());
+    }
+  }
+
+  #[cfg(feature = "transforms")]
+  #[test]
+  fn scope_analysis_shadowed_in_function() {
+    // only the scope analysis knows the outer require is the global
+    let text = concat!(
+      "const a = require('./a.js');\n",
+      "function load(require) { return require('./b.js'); }\n",
+    );
+    assert_eq!(
+      requires(&parse(text, MediaType::Cjs, true)),
+      vec![("./a.js".to_string(), "require('./a.js')")]
+    );
+    assert_eq!(requires(&parse(text, MediaType::Cjs, false)), Vec::new());
+  }
+}
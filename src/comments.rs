@@ -10,6 +10,9 @@ use crate::swc::common::comments::SingleThreadedComments;
 use crate::swc::common::comments::SingleThreadedCommentsMapInner;
 use crate::swc::common::BytePos as SwcBytePos;
 use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
 
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -89,6 +92,20 @@ impl MultiThreadedComments {
     comments
   }
 
+  /// Gets the comments that are within a range sorted by position.
+  pub fn get_in_range(&self, range: SourceRange) -> Vec<Comment> {
+    let mut comments = self
+      .iter_unstable()
+      .filter(|comment| {
+        let comment_range = comment.range();
+        comment_range.start >= range.start && comment_range.end <= range.end
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+    comments.sort_by_key(|comment| comment.start());
+    comments
+  }
+
   /// Iterates through all the comments in an unstable order.
   pub fn iter_unstable(&self) -> impl Iterator<Item = &Comment> {
     self
@@ -194,6 +211,17 @@ fn panic_readonly() -> ! {
   panic!("MultiThreadedComments do not support write operations")
 }
 
+/// Gets if a comment starts on the same line as a position, such as a
+/// trailing comment after a statement (ex. `a(); // comment`) as opposed
+/// to one on the next line.
+pub fn is_comment_on_same_line(
+  text_info: &SourceTextInfo,
+  pos: SourcePos,
+  comment: &Comment,
+) -> bool {
+  text_info.line_index(pos) == text_info.line_index(comment.start())
+}
+
 #[cfg(test)]
 mod test {
   use crate::parse_module;
@@ -252,14 +280,14 @@ mod test {
   fn get_single_threaded_comments(
     text: &str,
   ) -> (SingleThreadedComments, StartSourcePos) {
-    let module = parse_module(ParseParams {
-      specifier: "file.ts".to_string(),
-      text_info: SourceTextInfo::from_string(text.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        "file.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .expect("expects a module");
     (
       module.comments().as_single_threaded(),
@@ -21,22 +21,71 @@ use crate::SourceRangedForSpanned;
 
 impl ParsedSource {
   /// Analyzes the module for a list of its imports and exports.
+  ///
+  /// This doesn't require scope analysis or tokens.
   pub fn analyze_dependencies(&self) -> Vec<DependencyDescriptor> {
+    self.analyze_dependencies_with_options(&Default::default())
+  }
+
+  /// Analyzes the module for a list of its imports and exports with
+  /// the provided options.
+  pub fn analyze_dependencies_with_options(
+    &self,
+    options: &DependencyAnalysisOptions,
+  ) -> Vec<DependencyDescriptor> {
     match self.program_ref() {
-      ast::Program::Module(module) => {
-        analyze_module_dependencies(module, self.comments())
-      }
+      ast::Program::Module(module) => analyze_module_dependencies_with_options(
+        module,
+        self.comments(),
+        options,
+      ),
       ast::Program::Script(_) => vec![],
     }
   }
+
+  /// Gets the dynamic imports in the module, which are the dependencies
+  /// that may be split into separate chunks.
+  pub fn dynamic_dependencies(
+    &self,
+    options: &DependencyAnalysisOptions,
+  ) -> Vec<DynamicDependencyDescriptor> {
+    self
+      .analyze_dependencies_with_options(options)
+      .into_iter()
+      .filter_map(|descriptor| match descriptor {
+        DependencyDescriptor::Dynamic(descriptor) => Some(descriptor),
+        DependencyDescriptor::Static(_) => None,
+      })
+      .collect()
+  }
+}
+
+/// Options for `ParsedSource::analyze_dependencies_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyAnalysisOptions {
+  /// Include `import.meta.resolve("...")` calls as dynamic dependencies.
+  pub include_import_meta_resolve: bool,
 }
 
 pub fn analyze_module_dependencies(
   module: &Module,
   comments: &MultiThreadedComments,
+) -> Vec<DependencyDescriptor> {
+  analyze_module_dependencies_with_options(
+    module,
+    comments,
+    &Default::default(),
+  )
+}
+
+pub fn analyze_module_dependencies_with_options(
+  module: &Module,
+  comments: &MultiThreadedComments,
+  options: &DependencyAnalysisOptions,
 ) -> Vec<DependencyDescriptor> {
   let mut v = DependencyCollector {
     comments,
+    options,
     items: vec![],
   };
   module.visit_with(&mut v);
@@ -128,8 +177,19 @@ impl From<StaticDependencyDescriptor> for DependencyDescriptor {
   }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DynamicDependencyKind {
+  /// ex. `import("./a.ts")`
+  Import,
+  /// ex. `import.meta.resolve("./a.ts")`, which is only included when
+  /// enabled in the `DependencyAnalysisOptions`.
+  ImportMetaResolve,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DynamicDependencyDescriptor {
+  pub kind: DynamicDependencyKind,
   /// Any leading comments associated with the dependency. This is used for
   /// further processing of supported pragma that impact the dependency.
   pub leading_comments: Vec<DependencyComment>,
@@ -166,6 +226,7 @@ pub enum DynamicTemplatePart {
 
 struct DependencyCollector<'a> {
   comments: &'a MultiThreadedComments,
+  options: &'a DependencyAnalysisOptions,
   pub items: Vec<DependencyDescriptor>,
 }
 
@@ -279,47 +340,35 @@ impl<'a> Visit for DependencyCollector<'a> {
   fn visit_call_expr(&mut self, node: &ast::CallExpr) {
     swc_ecma_visit::visit_call_expr(self, node);
 
-    if !matches!(&node.callee, Callee::Import(_)) {
-      return;
-    }
+    let kind = match &node.callee {
+      Callee::Import(_) => DynamicDependencyKind::Import,
+      Callee::Expr(expr)
+        if self.options.include_import_meta_resolve
+          && is_import_meta_resolve(expr) =>
+      {
+        DynamicDependencyKind::ImportMetaResolve
+      }
+      _ => return,
+    };
     let Some(arg) = node.args.first() else {
       return;
     };
 
-    let argument = match &*arg.expr {
-      Expr::Lit(ast::Lit::Str(specifier)) => {
-        DynamicArgument::String(specifier.value.clone())
-      }
-      Expr::Tpl(tpl) => {
-        if tpl.quasis.len() == 1 && tpl.exprs.is_empty() {
-          DynamicArgument::String(tpl.quasis[0].raw.clone())
-        } else {
-          let mut parts =
-            Vec::with_capacity(tpl.quasis.len() + tpl.exprs.len());
-          for i in 0..tpl.quasis.len() {
-            if tpl.quasis[i].raw.len() > 0 {
-              parts
-                .push(DynamicTemplatePart::String(tpl.quasis[i].raw.clone()));
-            }
-            if tpl.exprs.get(i).is_some() {
-              parts.push(DynamicTemplatePart::Expr);
-            }
-          }
-          DynamicArgument::Template(parts)
-        }
+    let import_attributes = match kind {
+      DynamicDependencyKind::Import => {
+        parse_dynamic_import_attributes(node.args.get(1))
       }
-      _ => DynamicArgument::Expr,
+      DynamicDependencyKind::ImportMetaResolve => ImportAttributes::None,
     };
-    let dynamic_import_attributes =
-      parse_dynamic_import_attributes(node.args.get(1));
     let leading_comments = self.get_leading_comments(node.start());
     self.items.push(
       DynamicDependencyDescriptor {
+        kind,
         leading_comments,
         range: node.range(),
-        argument,
+        argument: dynamic_argument(&arg.expr),
         argument_range: arg.range(),
-        import_attributes: dynamic_import_attributes,
+        import_attributes,
       }
       .into(),
     );
@@ -356,6 +405,47 @@ impl<'a> Visit for DependencyCollector<'a> {
   }
 }
 
+fn dynamic_argument(expr: &Expr) -> DynamicArgument {
+  match expr {
+    Expr::Lit(ast::Lit::Str(specifier)) => {
+      DynamicArgument::String(specifier.value.clone())
+    }
+    Expr::Tpl(tpl) => {
+      if tpl.quasis.len() == 1 && tpl.exprs.is_empty() {
+        DynamicArgument::String(tpl.quasis[0].raw.clone())
+      } else {
+        let mut parts = Vec::with_capacity(tpl.quasis.len() + tpl.exprs.len());
+        for i in 0..tpl.quasis.len() {
+          if tpl.quasis[i].raw.len() > 0 {
+            parts.push(DynamicTemplatePart::String(tpl.quasis[i].raw.clone()));
+          }
+          if tpl.exprs.get(i).is_some() {
+            parts.push(DynamicTemplatePart::Expr);
+          }
+        }
+        DynamicArgument::Template(parts)
+      }
+    }
+    _ => DynamicArgument::Expr,
+  }
+}
+
+fn is_import_meta_resolve(expr: &Expr) -> bool {
+  let Expr::Member(member) = expr else {
+    return false;
+  };
+  let is_import_meta = matches!(
+    &*member.obj,
+    Expr::MetaProp(meta_prop)
+      if matches!(meta_prop.kind, ast::MetaPropKind::ImportMeta)
+  );
+  is_import_meta
+    && matches!(
+      &member.prop,
+      ast::MemberProp::Ident(ident) if &*ident.sym == "resolve"
+    )
+}
+
 /// Parses import attributes into a hashmap. According to proposal the values
 /// can only be strings (https://github.com/tc39/proposal-import-attributes#should-more-than-just-strings-be-supported-as-attribute-values)
 /// and thus non-string values are skipped.
@@ -482,14 +572,14 @@ mod tests {
     file_name: &str,
     source: &str,
   ) -> (SourcePos, Vec<DependencyDescriptor>) {
-    let source = crate::parse_module(crate::ParseParams {
-      specifier: file_name.to_string(),
-      text_info: crate::SourceTextInfo::from_string(source.to_string()),
-      media_type: crate::MediaType::Tsx,
-      capture_tokens: false,
-      scope_analysis: false,
-      maybe_syntax: None,
-    })
+    let source = crate::parse_module(
+      crate::ParseParams::builder(
+        file_name.to_string(),
+        crate::SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(crate::MediaType::Tsx)
+      .build(),
+    )
     .unwrap();
     (source.module().start(), source.analyze_dependencies())
   }
@@ -589,6 +679,7 @@ try {
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 272, start_pos + 291),
           argument: DynamicArgument::String(JsWord::from("./foo1.ts")),
@@ -597,6 +688,7 @@ try {
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 321, start_pos + 339),
           argument: DynamicArgument::String(JsWord::from("./foo.ts")),
@@ -719,6 +811,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 251, start_pos + 302),
           argument: DynamicArgument::String(JsWord::from("./fizz.json")),
@@ -727,6 +820,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 323, start_pos + 374),
           argument: DynamicArgument::String(JsWord::from("./buzz.json")),
@@ -735,6 +829,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 393, start_pos + 412),
           argument: DynamicArgument::String(JsWord::from("./d1.json")),
@@ -743,6 +838,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 431, start_pos + 454),
           argument: DynamicArgument::String(JsWord::from("./d2.json")),
@@ -751,6 +847,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 473, start_pos + 497),
           argument: DynamicArgument::String(JsWord::from("./d3.json")),
@@ -759,6 +856,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 516, start_pos + 549),
           argument: DynamicArgument::String(JsWord::from("./d4.json")),
@@ -767,6 +865,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 568, start_pos + 602),
           argument: DynamicArgument::String(JsWord::from("./d5.json")),
@@ -775,6 +874,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 621, start_pos + 662),
           argument: DynamicArgument::String(JsWord::from("./d6.json")),
@@ -783,6 +883,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 681, start_pos + 733),
           argument: DynamicArgument::String(JsWord::from("./d7.json")),
@@ -791,6 +892,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 752, start_pos + 796),
           argument: DynamicArgument::String(JsWord::from("./d8.json")),
@@ -803,6 +905,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 815, start_pos + 870),
           argument: DynamicArgument::String(JsWord::from("./d9.json")),
@@ -811,6 +914,7 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 890, start_pos + 955),
           argument: DynamicArgument::String(JsWord::from("./d10.json")),
@@ -822,6 +926,65 @@ const d10 = await import("./d10.json", { with: { type: "json", ["type"]: "bad" }
     );
   }
 
+  #[test]
+  fn test_side_effect_and_escaped_imports() {
+    let source = r#"import "./side_effect.ts";
+import "./\u0061.ts";
+import type { T } from "./types.ts";
+export { b } from "./b.json" with { type: "json" };
+"#;
+    let (start_pos, dependencies) = helper("test.ts", source);
+    assert_eq!(
+      dependencies,
+      vec![
+        StaticDependencyDescriptor {
+          kind: DependencyKind::Import,
+          leading_comments: Vec::new(),
+          range: SourceRange::new(start_pos, start_pos + 26),
+          specifier: JsWord::from("./side_effect.ts"),
+          specifier_range: SourceRange::new(start_pos + 7, start_pos + 25),
+          import_attributes: ImportAttributes::None,
+        }
+        .into(),
+        // the specifier is the cooked value of the string
+        StaticDependencyDescriptor {
+          kind: DependencyKind::Import,
+          leading_comments: Vec::new(),
+          range: SourceRange::new(start_pos + 27, start_pos + 48),
+          specifier: JsWord::from("./a.ts"),
+          specifier_range: SourceRange::new(start_pos + 34, start_pos + 47),
+          import_attributes: ImportAttributes::None,
+        }
+        .into(),
+        StaticDependencyDescriptor {
+          kind: DependencyKind::ImportType,
+          leading_comments: Vec::new(),
+          range: SourceRange::new(start_pos + 49, start_pos + 85),
+          specifier: JsWord::from("./types.ts"),
+          specifier_range: SourceRange::new(start_pos + 72, start_pos + 84),
+          import_attributes: ImportAttributes::None,
+        }
+        .into(),
+        StaticDependencyDescriptor {
+          kind: DependencyKind::Export,
+          leading_comments: Vec::new(),
+          range: SourceRange::new(start_pos + 86, start_pos + 137),
+          specifier: JsWord::from("./b.json"),
+          specifier_range: SourceRange::new(start_pos + 104, start_pos + 114),
+          import_attributes: ImportAttributes::Known({
+            let mut map = HashMap::new();
+            map.insert(
+              "type".to_string(),
+              ImportAttribute::Known("json".to_string()),
+            );
+            map
+          }),
+        }
+        .into(),
+      ]
+    );
+  }
+
   #[test]
   fn test_dynamic_imports() {
     let source = r#"const d1 = await import(`./d1.json`);
@@ -838,6 +1001,7 @@ const d8 = await import(expr);
       dependencies,
       vec![
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 17, start_pos + 36),
           argument: DynamicArgument::String(JsWord::from("./d1.json")),
@@ -846,6 +1010,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 55, start_pos + 73),
           argument: DynamicArgument::Template(vec![DynamicTemplatePart::Expr]),
@@ -854,6 +1019,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 92, start_pos + 117),
           argument: DynamicArgument::Template(vec![
@@ -865,6 +1031,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 136, start_pos + 159),
           argument: DynamicArgument::Template(vec![
@@ -876,6 +1043,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 178, start_pos + 205),
           argument: DynamicArgument::Template(vec![
@@ -887,6 +1055,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 224, start_pos + 257),
           argument: DynamicArgument::Template(vec![
@@ -899,6 +1068,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 276, start_pos + 312),
           argument: DynamicArgument::Template(vec![
@@ -913,6 +1083,7 @@ const d8 = await import(expr);
         }
         .into(),
         DynamicDependencyDescriptor {
+          kind: DynamicDependencyKind::Import,
           leading_comments: Vec::new(),
           range: SourceRange::new(start_pos + 331, start_pos + 343),
           argument: DynamicArgument::Expr,
@@ -965,4 +1136,99 @@ export declare const SomeValue: typeof Core & import("./a.d.ts").Constructor<{
       ]
     );
   }
+
+  #[test]
+  fn test_dynamic_dependencies() {
+    let source = crate::parse_module(
+      crate::ParseParams::builder(
+        "file:///test.ts",
+        crate::SourceTextInfo::from_string(
+          r#"import "./static.ts";
+const a = await import("./a.ts");
+async function load(name: string) {
+  const b = await import(`./b.ts`);
+  const c = await import(`./c/${name}.ts`, { with: { type: "json" } });
+  return () => import(name);
+}
+const d = import.meta.resolve("./d.ts");
+"#
+          .to_string(),
+        ),
+      )
+      .media_type(crate::MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap();
+    let get_dependencies = |options: &DependencyAnalysisOptions| {
+      source
+        .dynamic_dependencies(options)
+        .into_iter()
+        .map(|dep| {
+          (
+            dep.kind,
+            source.text_for_range(&dep.range).to_string(),
+            dep.argument,
+            source.text_for_range(&dep.argument_range).to_string(),
+            dep.import_attributes,
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+    let mut expected = vec![
+      (
+        DynamicDependencyKind::Import,
+        r#"import("./a.ts")"#.to_string(),
+        DynamicArgument::String(JsWord::from("./a.ts")),
+        r#""./a.ts""#.to_string(),
+        ImportAttributes::None,
+      ),
+      (
+        DynamicDependencyKind::Import,
+        "import(`./b.ts`)".to_string(),
+        DynamicArgument::String(JsWord::from("./b.ts")),
+        "`./b.ts`".to_string(),
+        ImportAttributes::None,
+      ),
+      (
+        DynamicDependencyKind::Import,
+        r#"import(`./c/${name}.ts`, { with: { type: "json" } })"#.to_string(),
+        DynamicArgument::Template(vec![
+          DynamicTemplatePart::String(JsWord::from("./c/")),
+          DynamicTemplatePart::Expr,
+          DynamicTemplatePart::String(JsWord::from(".ts")),
+        ]),
+        "`./c/${name}.ts`".to_string(),
+        ImportAttributes::Known({
+          let mut map = HashMap::new();
+          map.insert(
+            "type".to_string(),
+            ImportAttribute::Known("json".to_string()),
+          );
+          map
+        }),
+      ),
+      (
+        DynamicDependencyKind::Import,
+        "import(name)".to_string(),
+        DynamicArgument::Expr,
+        "name".to_string(),
+        ImportAttributes::None,
+      ),
+    ];
+    assert_eq!(get_dependencies(&Default::default()), expected);
+
+    expected.push((
+      DynamicDependencyKind::ImportMetaResolve,
+      r#"import.meta.resolve("./d.ts")"#.to_string(),
+      DynamicArgument::String(JsWord::from("./d.ts")),
+      r#""./d.ts""#.to_string(),
+      ImportAttributes::None,
+    ));
+    assert_eq!(
+      get_dependencies(&DependencyAnalysisOptions {
+        include_import_meta_resolve: true,
+      }),
+      expected
+    );
+  }
 }
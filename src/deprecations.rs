@@ -0,0 +1,98 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+
+use crate::comments::MultiThreadedComments;
+use crate::swc::common::comments::CommentKind;
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+
+/// Deprecated syntax that is surfaced as a warning when
+/// `ParseParamsBuilder::lint_deprecations` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecatedSyntax {
+  /// HTML-like comment in a script (ex. `<!-- comment` or `--> comment`).
+  HtmlComment,
+  /// Use of `arguments.callee`.
+  ArgumentsCallee,
+}
+
+impl DeprecatedSyntax {
+  /// Message text for when the deprecated syntax is used.
+  pub fn msg(&self) -> Cow<str> {
+    match self {
+      DeprecatedSyntax::HtmlComment => Cow::Borrowed(
+        "HTML-like comments are deprecated and not allowed in modules. Use '//' instead.",
+      ),
+      DeprecatedSyntax::ArgumentsCallee => Cow::Borrowed(
+        "'arguments.callee' is deprecated and throws in strict mode. Use a named function instead.",
+      ),
+    }
+  }
+}
+
+/// Gets the ranges of any HTML-like comments, including the `<!--`
+/// or `-->` that starts them.
+pub(crate) fn get_html_comment_ranges(
+  comments: &MultiThreadedComments,
+  source: &SourceTextInfo,
+) -> Vec<SourceRange> {
+  let text = source.text_str();
+  let start_pos = source.range().start;
+  let mut ranges = comments
+    .iter_unstable()
+    .filter(|comment| comment.kind == CommentKind::Line)
+    .filter_map(|comment| {
+      let range = comment.range();
+      let start = range.start - start_pos;
+      let before = &text[..start];
+      let after = &text[start..];
+      // depending on how swc lexed the comment, its range may
+      // or may not include the characters that start it
+      let html_start_len = if after.starts_with("<!--") {
+        0
+      } else if before.ends_with('<') && after.starts_with("!--") {
+        1
+      } else if before.ends_with("-->") {
+        3
+      } else if after.starts_with("-->") {
+        0
+      } else {
+        return None;
+      };
+      Some(SourceRange::new(range.start - html_start_len, range.end))
+    })
+    .collect::<Vec<_>>();
+  ranges.sort_by_key(|range| range.start);
+  ranges
+}
+
+/// Gets the ranges of any `arguments.callee` member expressions.
+pub(crate) fn get_arguments_callee_ranges(
+  tokens: &[TokenAndSpan],
+  source: &SourceTextInfo,
+) -> Vec<SourceRange> {
+  let is_word = |token: &TokenAndSpan, text: &str| {
+    matches!(token.token, Token::Word(_))
+      && source.range_text(&token.range()) == text
+  };
+  tokens
+    .windows(3)
+    .enumerate()
+    .filter(|(i, window)| {
+      // ignore a property named arguments (ex. `fn.arguments.callee`)
+      let is_member_name = i
+        .checked_sub(1)
+        .and_then(|i| tokens.get(i))
+        .is_some_and(|prev| prev.token == Token::Dot);
+      !is_member_name
+        && is_word(&window[0], "arguments")
+        && window[1].token == Token::Dot
+        && is_word(&window[2], "callee")
+    })
+    .map(|(_, window)| SourceRange::new(window[0].start(), window[2].end()))
+    .collect()
+}
@@ -0,0 +1,219 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::parsed_source::sort_and_dedup_diagnostics;
+use crate::Diagnostic;
+use crate::DiagnosticSeverity;
+use crate::ParsedSource;
+
+/// Diagnostics from many sources grouped by specifier.
+///
+/// Diagnostics are sorted and exact duplicates are removed the same way
+/// as `ParsedSource::diagnostics_sorted`.
+#[derive(Debug, Default, Clone)]
+pub struct DiagnosticsCollection {
+  diagnostics: BTreeMap<String, Vec<Diagnostic>>,
+}
+
+impl DiagnosticsCollection {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a diagnostic to the collection.
+  pub fn add(&mut self, diagnostic: Diagnostic) {
+    self
+      .diagnostics
+      .entry(diagnostic.specifier().to_string())
+      .or_default()
+      .push(diagnostic);
+  }
+
+  /// Adds the non-fatal diagnostics of the parsed source.
+  pub fn add_parsed_source(&mut self, parsed_source: &ParsedSource) {
+    self.extend(parsed_source.diagnostics().iter().cloned());
+  }
+
+  /// Gets the sorted diagnostics of each specifier, ordered by specifier.
+  pub fn by_specifier(&self) -> BTreeMap<&str, Vec<&Diagnostic>> {
+    self
+      .diagnostics
+      .iter()
+      .map(|(specifier, diagnostics)| {
+        (
+          specifier.as_str(),
+          sort_and_dedup_diagnostics(diagnostics.iter().collect(), None),
+        )
+      })
+      .collect()
+  }
+
+  /// Gets all the diagnostics ordered by specifier then position.
+  pub fn all_sorted(&self) -> Vec<&Diagnostic> {
+    self.by_specifier().into_values().flatten().collect()
+  }
+
+  /// Gets the number of diagnostics with an error severity.
+  pub fn error_count(&self) -> usize {
+    self.count_with_severity(DiagnosticSeverity::Error)
+  }
+
+  /// Gets the number of diagnostics with a warning severity.
+  pub fn warning_count(&self) -> usize {
+    self.count_with_severity(DiagnosticSeverity::Warning)
+  }
+
+  fn count_with_severity(&self, severity: DiagnosticSeverity) -> usize {
+    self
+      .all_sorted()
+      .into_iter()
+      .filter(|d| d.severity == severity)
+      .count()
+  }
+
+  /// Gets if the collection has no diagnostics.
+  pub fn is_empty(&self) -> bool {
+    self.diagnostics.is_empty()
+  }
+
+  /// Gets a summary of the diagnostics (ex. "3 errors in 2 files").
+  pub fn summary(&self) -> String {
+    fn pluralize(count: usize, word: &str) -> String {
+      if count == 1 {
+        format!("1 {}", word)
+      } else {
+        format!("{} {}s", count, word)
+      }
+    }
+
+    let error_count = self.error_count();
+    let warning_count = self.warning_count();
+    let counts = match (error_count, warning_count) {
+      (0, 0) => return "No errors or warnings".to_string(),
+      (_, 0) => pluralize(error_count, "error"),
+      (0, _) => pluralize(warning_count, "warning"),
+      _ => format!(
+        "{} and {}",
+        pluralize(error_count, "error"),
+        pluralize(warning_count, "warning")
+      ),
+    };
+    format!(
+      "{} in {}",
+      counts,
+      pluralize(self.diagnostics.len(), "file")
+    )
+  }
+}
+
+impl Extend<Diagnostic> for DiagnosticsCollection {
+  fn extend<T: IntoIterator<Item = Diagnostic>>(&mut self, iter: T) {
+    for diagnostic in iter {
+      self.add(diagnostic);
+    }
+  }
+}
+
+impl fmt::Display for DiagnosticsCollection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for diagnostic in self.all_sorted() {
+      write!(
+        f,
+        "{}\n\n",
+        diagnostic.display_with_snippet(Default::default())
+      )?;
+    }
+    write!(f, "{}", self.summary())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::DiagnosticKind;
+  use crate::SourceRange;
+  use crate::SourceTextInfo;
+
+  #[test]
+  fn groups_and_sorts_diagnostics() {
+    let source = SourceTextInfo::from_string("#! a\n#! b".to_string());
+    let create = |specifier: &str, index: usize| {
+      let start = source.range().start + index;
+      Diagnostic::new(
+        specifier,
+        SourceRange::new(start, start + 2),
+        DiagnosticKind::MisplacedShebang,
+        source.clone(),
+      )
+    };
+    let mut collection = DiagnosticsCollection::new();
+    assert_eq!(collection.summary(), "No errors or warnings");
+    collection.extend([
+      create("file:///c.ts", 5),
+      create("file:///a.ts", 5),
+      create("file:///c.ts", 0),
+      // duplicate
+      create("file:///a.ts", 5),
+      create("file:///b.ts", 0).with_severity(DiagnosticSeverity::Warning),
+      create("file:///a.ts", 0),
+    ]);
+
+    let by_specifier = collection
+      .by_specifier()
+      .into_iter()
+      .map(|(specifier, diagnostics)| {
+        (
+          specifier,
+          diagnostics
+            .into_iter()
+            .map(|d| d.display_position().line_number)
+            .collect::<Vec<_>>(),
+        )
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      by_specifier,
+      vec![
+        ("file:///a.ts", vec![1, 2]),
+        ("file:///b.ts", vec![1]),
+        ("file:///c.ts", vec![1, 2]),
+      ]
+    );
+    let all = collection
+      .all_sorted()
+      .into_iter()
+      .map(|d| (d.specifier(), d.display_position().line_number))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      all,
+      vec![
+        ("file:///a.ts", 1),
+        ("file:///a.ts", 2),
+        ("file:///b.ts", 1),
+        ("file:///c.ts", 1),
+        ("file:///c.ts", 2),
+      ]
+    );
+    assert_eq!(collection.error_count(), 4);
+    assert_eq!(collection.warning_count(), 1);
+    assert_eq!(collection.summary(), "4 errors and 1 warning in 3 files");
+    let text = collection.to_string();
+    let first_specifier = collection.all_sorted()[0]
+      .display_specifier(&Default::default())
+      .into_owned();
+    assert!(
+      text.starts_with(&format!("{}:1:1: ", first_specifier)),
+      "{}",
+      text
+    );
+    assert!(
+      text.ends_with("\n\n4 errors and 1 warning in 3 files"),
+      "{}",
+      text
+    );
+  }
+}
@@ -0,0 +1,313 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fmt;
+
+use crate::SourceTextInfo;
+use crate::StartSourcePos;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+const BOM_CHAR: char = '\u{FEFF}';
+
+/// Encoding of source text bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+}
+
+/// Error decoding source text bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+  /// The bytes were not valid UTF-8.
+  InvalidUtf8 {
+    /// Byte index of the first invalid byte.
+    byte_index: usize,
+  },
+  /// The bytes were not valid UTF-16.
+  InvalidUtf16 {
+    encoding: TextEncoding,
+    /// Byte index of the first invalid code unit.
+    byte_index: usize,
+  },
+}
+
+impl std::error::Error for EncodingError {}
+
+impl fmt::Display for EncodingError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      EncodingError::InvalidUtf8 { byte_index } => write!(
+        f,
+        "Source text was not valid UTF-8. Invalid byte at index {}.",
+        byte_index
+      ),
+      EncodingError::InvalidUtf16 {
+        encoding,
+        byte_index,
+      } => write!(
+        f,
+        "Source text was not valid {}. Invalid code unit at byte index {}.",
+        match encoding {
+          TextEncoding::Utf8 => "UTF-8",
+          TextEncoding::Utf16Le => "UTF-16LE",
+          TextEncoding::Utf16Be => "UTF-16BE",
+        },
+        byte_index
+      ),
+    }
+  }
+}
+
+/// Source text decoded from bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+  /// Decoded text without the byte order mark.
+  pub text: String,
+  /// Encoding that was detected.
+  pub encoding: TextEncoding,
+  /// Whether a byte order mark was stripped.
+  pub had_bom: bool,
+  /// Length of the original bytes.
+  pub original_byte_len: usize,
+}
+
+impl DecodedText {
+  /// Creates a `SourceTextInfo` from the decoded text.
+  pub fn into_text_info(self) -> SourceTextInfo {
+    SourceTextInfo::from_string(self.text)
+  }
+}
+
+/// Creation of source text without a byte order mark.
+pub trait SourceTextInfoBom: Sized {
+  /// Creates the source text with any leading UTF-8 byte order mark
+  /// stripped, along with whether there was one.
+  ///
+  /// Positions are relative to the stripped text, so the first
+  /// character of the text is at `range().start` either way.
+  fn from_string_handling_bom(text: String) -> (Self, bool);
+}
+
+impl SourceTextInfoBom for SourceTextInfo {
+  fn from_string_handling_bom(mut text: String) -> (Self, bool) {
+    let had_bom = text.starts_with(BOM_CHAR);
+    if had_bom {
+      text.drain(..BOM_CHAR.len_utf8());
+    }
+    (SourceTextInfo::from_string(text), had_bom)
+  }
+}
+
+/// Creates source text that starts with a byte order mark.
+///
+/// `SourceTextInfo::new` strips a leading byte order mark, so this
+/// creates it with `SourceTextInfo::new_with_indent_width`, which doesn't.
+pub(crate) fn text_info_with_bom(text: &str) -> SourceTextInfo {
+  SourceTextInfo::new_with_indent_width(
+    StartSourcePos::START_SOURCE_POS.as_source_pos(),
+    format!("{}{}", BOM_CHAR, text).into(),
+    2,
+  )
+}
+
+/// Strips any leading byte order mark from the source text, copying
+/// the text only when there is one.
+pub(crate) fn strip_bom(text_info: SourceTextInfo) -> (SourceTextInfo, bool) {
+  match text_info.text_str().strip_prefix(BOM_CHAR) {
+    Some(text) => (SourceTextInfo::from_string(text.to_string()), true),
+    None => (text_info, false),
+  }
+}
+
+/// Decodes the provided bytes to text, detecting the encoding based on
+/// the byte order mark and defaulting to UTF-8 when there is none.
+///
+/// The byte order mark is stripped from the returned text.
+pub fn decode_text(bytes: Vec<u8>) -> Result<DecodedText, EncodingError> {
+  let original_byte_len = bytes.len();
+  if bytes.starts_with(UTF16_LE_BOM) {
+    decode_utf16(&bytes, TextEncoding::Utf16Le).map(|text| DecodedText {
+      text,
+      encoding: TextEncoding::Utf16Le,
+      had_bom: true,
+      original_byte_len,
+    })
+  } else if bytes.starts_with(UTF16_BE_BOM) {
+    decode_utf16(&bytes, TextEncoding::Utf16Be).map(|text| DecodedText {
+      text,
+      encoding: TextEncoding::Utf16Be,
+      had_bom: true,
+      original_byte_len,
+    })
+  } else {
+    let had_bom = bytes.starts_with(UTF8_BOM);
+    let mut bytes = bytes;
+    if had_bom {
+      bytes.drain(..UTF8_BOM.len());
+    }
+    let bom_len = original_byte_len - bytes.len();
+    String::from_utf8(bytes)
+      .map(|text| DecodedText {
+        text,
+        encoding: TextEncoding::Utf8,
+        had_bom,
+        original_byte_len,
+      })
+      .map_err(|err| EncodingError::InvalidUtf8 {
+        byte_index: bom_len + err.utf8_error().valid_up_to(),
+      })
+  }
+}
+
+fn decode_utf16(
+  bytes: &[u8],
+  encoding: TextEncoding,
+) -> Result<String, EncodingError> {
+  // skip the byte order mark
+  let bytes = &bytes[2..];
+  if bytes.len() % 2 != 0 {
+    return Err(EncodingError::InvalidUtf16 {
+      encoding,
+      byte_index: bytes.len() + 1,
+    });
+  }
+  let code_units = bytes.chunks_exact(2).map(|pair| match encoding {
+    TextEncoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+    TextEncoding::Utf16Le | TextEncoding::Utf8 => {
+      u16::from_le_bytes([pair[0], pair[1]])
+    }
+  });
+  let mut text = String::with_capacity(bytes.len() / 2);
+  let mut byte_index = 2;
+  for result in char::decode_utf16(code_units) {
+    match result {
+      Ok(c) => {
+        byte_index += c.len_utf16() * 2;
+        text.push(c);
+      }
+      Err(_) => {
+        return Err(EncodingError::InvalidUtf16 {
+          encoding,
+          byte_index,
+        })
+      }
+    }
+  }
+  Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn decodes_utf8_without_bom() {
+    let decoded = decode_text("const a = 'ü';".as_bytes().to_vec()).unwrap();
+    assert_eq!(decoded.text, "const a = 'ü';");
+    assert_eq!(decoded.encoding, TextEncoding::Utf8);
+    assert!(!decoded.had_bom);
+    assert_eq!(decoded.original_byte_len, 15);
+  }
+
+  #[test]
+  fn decodes_utf8_with_bom() {
+    let mut bytes = UTF8_BOM.to_vec();
+    bytes.extend("1 + 1".as_bytes());
+    let decoded = decode_text(bytes).unwrap();
+    assert_eq!(decoded.text, "1 + 1");
+    assert_eq!(decoded.encoding, TextEncoding::Utf8);
+    assert!(decoded.had_bom);
+    assert_eq!(decoded.original_byte_len, 8);
+    assert_eq!(decoded.into_text_info().text_str(), "1 + 1");
+  }
+
+  #[test]
+  fn from_string_handling_bom() {
+    let (text_info, had_bom) =
+      SourceTextInfo::from_string_handling_bom("\u{FEFF}let a;".to_string());
+    assert!(had_bom);
+    assert_eq!(text_info.text_str(), "let a;");
+    let (text_info_without_bom, had_bom) =
+      SourceTextInfo::from_string_handling_bom("let a;".to_string());
+    assert!(!had_bom);
+    assert_eq!(text_info.range(), text_info_without_bom.range());
+    let start = text_info.range().start.as_source_pos();
+    assert_eq!(
+      text_info.line_and_column_display(start),
+      text_info_without_bom.line_and_column_display(start)
+    );
+    // only a leading byte order mark is stripped
+    let (text_info, had_bom) =
+      SourceTextInfo::from_string_handling_bom("a\u{FEFF}".to_string());
+    assert!(!had_bom);
+    assert_eq!(text_info.text_str(), "a\u{FEFF}");
+
+    let (text_info, had_bom) = strip_bom(text_info_with_bom("a"));
+    assert!(had_bom);
+    assert_eq!(text_info.text_str(), "a");
+    let (text_info, had_bom) =
+      strip_bom(SourceTextInfo::from_string("\u{FEFF}a".to_string()));
+    // already stripped by `SourceTextInfo::new`
+    assert!(!had_bom);
+    assert_eq!(text_info.text_str(), "a");
+  }
+
+  #[test]
+  fn decodes_utf16_le() {
+    let mut bytes = UTF16_LE_BOM.to_vec();
+    for code_unit in "a = '😀';".encode_utf16() {
+      bytes.extend(code_unit.to_le_bytes());
+    }
+    let decoded = decode_text(bytes).unwrap();
+    assert_eq!(decoded.text, "a = '😀';");
+    assert_eq!(decoded.encoding, TextEncoding::Utf16Le);
+    assert!(decoded.had_bom);
+    assert_eq!(decoded.original_byte_len, 2 + 9 * 2);
+  }
+
+  #[test]
+  fn decodes_utf16_be() {
+    let mut bytes = UTF16_BE_BOM.to_vec();
+    for code_unit in "let b;".encode_utf16() {
+      bytes.extend(code_unit.to_be_bytes());
+    }
+    let decoded = decode_text(bytes).unwrap();
+    assert_eq!(decoded.text, "let b;");
+    assert_eq!(decoded.encoding, TextEncoding::Utf16Be);
+  }
+
+  #[test]
+  fn errors_for_invalid_utf8() {
+    let err = decode_text(vec![b'a', b'b', 0xFF, b'c']).unwrap_err();
+    assert_eq!(err, EncodingError::InvalidUtf8 { byte_index: 2 });
+    assert_eq!(
+      err.to_string(),
+      "Source text was not valid UTF-8. Invalid byte at index 2."
+    );
+
+    let mut bytes = UTF8_BOM.to_vec();
+    bytes.extend([b'a', 0xC0]);
+    let err = decode_text(bytes).unwrap_err();
+    assert_eq!(err, EncodingError::InvalidUtf8 { byte_index: 4 });
+  }
+
+  #[test]
+  fn errors_for_invalid_utf16() {
+    // unpaired surrogate
+    let mut bytes = UTF16_LE_BOM.to_vec();
+    bytes.extend(0x61u16.to_le_bytes());
+    bytes.extend(0xD800u16.to_le_bytes());
+    bytes.extend(0x61u16.to_le_bytes());
+    let err = decode_text(bytes).unwrap_err();
+    assert_eq!(
+      err,
+      EncodingError::InvalidUtf16 {
+        encoding: TextEncoding::Utf16Le,
+        byte_index: 4,
+      }
+    );
+  }
+}
@@ -0,0 +1,2757 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversion of a parsed program to ESTree shaped JSON for interop with
+//! JavaScript tooling (ex. AST explorers or prototyping lint rules).
+//!
+//! Nodes use the `type` and property names of ESTree, with TypeScript
+//! nodes using the names of TS-ESTree (ex. `TSTypeAnnotation`). Every
+//! node has `start` and `end` byte indexes in the text and, when
+//! `EstreeOptions::loc` is set, a `loc` with 1-indexed lines and
+//! 0-indexed UTF-16 columns like ESTree parsers.
+//!
+//! Properties defined by ESTree are always present and are `null` when
+//! absent. TypeScript specific properties of ESTree nodes (ex. the
+//! `typeAnnotation` of an `Identifier`) are only present when they have
+//! a value. Parenthesized expressions and types are represented by their
+//! inner node.
+//!
+//! Nodes without an ESTree equivalent, such as the nodes recovered from
+//! invalid syntax, are `{ "type": "Unsupported", "kind": "<swc name>" }`
+//! along with their position so that they can be skipped.
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::swc::ast::*;
+use crate::swc::parser::token::BinOpToken;
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::ParsedSource;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+use crate::SourceTextInfoUtf16;
+
+/// Options for `program_to_estree_json()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EstreeOptions {
+  /// Whether to include the line and column `loc` of each node.
+  pub loc: bool,
+}
+
+/// Converts the program of a parsed source to ESTree shaped JSON.
+///
+/// The source is lexed when its tokens were not captured during parsing
+/// in order to find the positions of nodes swc does not store.
+pub fn program_to_estree_json(
+  parsed_source: &ParsedSource,
+  options: EstreeOptions,
+) -> Value {
+  let tokens = parsed_source.tokens_or_lex();
+  let converter = Converter {
+    text_info: parsed_source.text_info(),
+    tokens: &tokens,
+    options,
+  };
+  converter.program(parsed_source.program_ref())
+}
+
+/// ESTree node being built.
+struct Node(Map<String, Value>);
+
+impl Node {
+  fn prop(mut self, key: &str, value: impl Into<Value>) -> Self {
+    self.0.insert(key.to_string(), value.into());
+    self
+  }
+
+  /// Sets a TypeScript specific property of an ESTree node when it has
+  /// a value.
+  fn ts_prop(self, key: &str, value: Option<Value>) -> Self {
+    match value {
+      Some(value) => self.prop(key, value),
+      None => self,
+    }
+  }
+
+  /// Sets a TypeScript specific flag of an ESTree node when it's true.
+  fn ts_flag(self, key: &str, value: bool) -> Self {
+    if value {
+      self.prop(key, true)
+    } else {
+      self
+    }
+  }
+}
+
+impl From<Node> for Value {
+  fn from(node: Node) -> Self {
+    Value::Object(node.0)
+  }
+}
+
+struct Converter<'a> {
+  text_info: &'a SourceTextInfo,
+  tokens: &'a [TokenAndSpan],
+  options: EstreeOptions,
+}
+
+impl<'a> Converter<'a> {
+  fn node(&self, node_type: &str, range: SourceRange) -> Node {
+    let byte_range = range.as_byte_range(self.text_info.range().start);
+    let mut map = Map::new();
+    map.insert("type".to_string(), node_type.into());
+    map.insert("start".to_string(), byte_range.start.into());
+    map.insert("end".to_string(), byte_range.end.into());
+    if self.options.loc {
+      map.insert(
+        "loc".to_string(),
+        json!({
+          "start": self.loc_position(range.start),
+          "end": self.loc_position(range.end),
+        }),
+      );
+    }
+    Node(map)
+  }
+
+  fn loc_position(&self, pos: SourcePos) -> Value {
+    let position = self.text_info.line_and_column_utf16(pos);
+    json!({
+      "line": position.line_index + 1,
+      "column": position.column_index,
+    })
+  }
+
+  fn unsupported(&self, kind: &str, range: SourceRange) -> Value {
+    self.node("Unsupported", range).prop("kind", kind).into()
+  }
+
+  fn text(&self, range: SourceRange) -> &'a str {
+    self.text_info.range_text(&range)
+  }
+
+  /// Gets the tokens that start within a range.
+  fn tokens_in(&self, range: SourceRange) -> &'a [TokenAndSpan] {
+    let start_index = self
+      .tokens
+      .partition_point(|token| token.range().start < range.start);
+    let end_index = self
+      .tokens
+      .partition_point(|token| token.range().start < range.end);
+    &self.tokens[start_index..std::cmp::max(start_index, end_index)]
+  }
+
+  /// Gets the start of the first matching token at or after a position.
+  fn find_token_start(
+    &self,
+    pos: SourcePos,
+    is_match: impl Fn(&Token) -> bool,
+  ) -> Option<SourcePos> {
+    let index = self
+      .tokens
+      .partition_point(|token| token.range().start < pos);
+    self.tokens[index..]
+      .iter()
+      .find(|token| is_match(&token.token))
+      .map(|token| token.range().start)
+  }
+
+  fn program(&self, program: &Program) -> Value {
+    let (source_type, body) = match program {
+      Program::Module(module) => ("module", self.module_items(&module.body)),
+      Program::Script(script) => ("script", self.stmts(&script.body, true)),
+    };
+    let range = self.text_info.range();
+    self
+      .node(
+        "Program",
+        SourceRange::new(range.start.as_source_pos(), range.end),
+      )
+      .prop("sourceType", source_type)
+      .prop("body", body)
+      .into()
+  }
+
+  fn module_items(&self, items: &[ModuleItem]) -> Vec<Value> {
+    let mut in_prologue = true;
+    items
+      .iter()
+      .map(|item| match item {
+        ModuleItem::ModuleDecl(decl) => {
+          in_prologue = false;
+          self.module_decl(decl)
+        }
+        ModuleItem::Stmt(stmt) => self.body_stmt(stmt, &mut in_prologue),
+      })
+      .collect()
+  }
+
+  fn stmts(&self, stmts: &[Stmt], allow_directives: bool) -> Vec<Value> {
+    let mut in_prologue = allow_directives;
+    stmts
+      .iter()
+      .map(|stmt| self.body_stmt(stmt, &mut in_prologue))
+      .collect()
+  }
+
+  /// Converts a statement of a program or function body, where the
+  /// string literal statements at the start are directives.
+  fn body_stmt(&self, stmt: &Stmt, in_prologue: &mut bool) -> Value {
+    if *in_prologue {
+      if let Stmt::Expr(expr_stmt) = stmt {
+        if let Expr::Lit(Lit::Str(str_lit)) = &*expr_stmt.expr {
+          let raw = self.text(str_lit.range());
+          return self
+            .node("ExpressionStatement", expr_stmt.range())
+            .prop("expression", self.str_lit(str_lit))
+            .prop("directive", &raw[1..raw.len() - 1])
+            .into();
+        }
+      }
+      *in_prologue = false;
+    }
+    self.stmt(stmt)
+  }
+
+  fn module_decl(&self, decl: &ModuleDecl) -> Value {
+    match decl {
+      ModuleDecl::Import(import) => self
+        .node("ImportDeclaration", import.range())
+        .prop(
+          "specifiers",
+          import
+            .specifiers
+            .iter()
+            .map(|specifier| self.import_specifier(specifier))
+            .collect::<Vec<_>>(),
+        )
+        .prop("source", self.str_lit(&import.src))
+        .prop("attributes", self.import_attributes(import.with.as_deref()))
+        .ts_prop("importKind", import.type_only.then(|| "type".into()))
+        .into(),
+      ModuleDecl::ExportDecl(export) => self
+        .node("ExportNamedDeclaration", export.range())
+        .prop("declaration", self.decl(&export.decl))
+        .prop("specifiers", Vec::<Value>::new())
+        .prop("source", Value::Null)
+        .prop("attributes", Vec::<Value>::new())
+        .ts_prop(
+          "exportKind",
+          matches!(export.decl, Decl::TsInterface(_) | Decl::TsTypeAlias(_))
+            .then(|| "type".into()),
+        )
+        .into(),
+      ModuleDecl::ExportNamed(named) => {
+        let export_kind = named.type_only.then(|| "type".into());
+        let attributes = self.import_attributes(named.with.as_deref());
+        if let (Some(src), [ExportSpecifier::Namespace(namespace)]) =
+          (&named.src, named.specifiers.as_slice())
+        {
+          return self
+            .node("ExportAllDeclaration", named.range())
+            .prop("exported", self.module_export_name(&namespace.name))
+            .prop("source", self.str_lit(src))
+            .prop("attributes", attributes)
+            .ts_prop("exportKind", export_kind)
+            .into();
+        }
+        self
+          .node("ExportNamedDeclaration", named.range())
+          .prop("declaration", Value::Null)
+          .prop(
+            "specifiers",
+            named
+              .specifiers
+              .iter()
+              .map(|specifier| self.export_specifier(specifier))
+              .collect::<Vec<_>>(),
+          )
+          .prop("source", named.src.as_deref().map(|src| self.str_lit(src)))
+          .prop("attributes", attributes)
+          .ts_prop("exportKind", export_kind)
+          .into()
+      }
+      ModuleDecl::ExportDefaultDecl(export) => {
+        let declaration: Value = match &export.decl {
+          DefaultDecl::Class(class_expr) => self
+            .class(
+              "ClassDeclaration",
+              class_expr.range(),
+              class_expr.ident.as_ref(),
+              &class_expr.class,
+            )
+            .into(),
+          DefaultDecl::Fn(fn_expr) => self
+            .function(
+              "FunctionDeclaration",
+              fn_expr.range(),
+              fn_expr.ident.as_ref(),
+              &fn_expr.function,
+            )
+            .into(),
+          DefaultDecl::TsInterfaceDecl(interface) => {
+            self.ts_interface(interface).into()
+          }
+        };
+        self
+          .node("ExportDefaultDeclaration", export.range())
+          .prop("declaration", declaration)
+          .into()
+      }
+      ModuleDecl::ExportDefaultExpr(export) => self
+        .node("ExportDefaultDeclaration", export.range())
+        .prop("declaration", self.expr(&export.expr))
+        .into(),
+      ModuleDecl::ExportAll(export) => self
+        .node("ExportAllDeclaration", export.range())
+        .prop("exported", Value::Null)
+        .prop("source", self.str_lit(&export.src))
+        .prop("attributes", self.import_attributes(export.with.as_deref()))
+        .ts_prop("exportKind", export.type_only.then(|| "type".into()))
+        .into(),
+      ModuleDecl::TsImportEquals(import) => {
+        let module_reference = match &import.module_ref {
+          TsModuleRef::TsEntityName(name) => self.entity_name(name),
+          TsModuleRef::TsExternalModuleRef(module_ref) => self
+            .node("TSExternalModuleReference", module_ref.range())
+            .prop("expression", self.str_lit(&module_ref.expr))
+            .into(),
+        };
+        self
+          .node("TSImportEqualsDeclaration", import.range())
+          .prop("id", self.ident(&import.id))
+          .prop("moduleReference", module_reference)
+          .prop(
+            "importKind",
+            if import.is_type_only { "type" } else { "value" },
+          )
+          .prop("isExport", import.is_export)
+          .into()
+      }
+      ModuleDecl::TsExportAssignment(export) => self
+        .node("TSExportAssignment", export.range())
+        .prop("expression", self.expr(&export.expr))
+        .into(),
+      ModuleDecl::TsNamespaceExport(export) => self
+        .node("TSNamespaceExportDeclaration", export.range())
+        .prop("id", self.ident(&export.id))
+        .into(),
+    }
+  }
+
+  fn import_specifier(&self, specifier: &ImportSpecifier) -> Value {
+    match specifier {
+      ImportSpecifier::Named(named) => {
+        let local = self.ident(&named.local);
+        let imported = match &named.imported {
+          Some(imported) => self.module_export_name(imported),
+          None => local.clone(),
+        };
+        self
+          .node("ImportSpecifier", named.range())
+          .prop("imported", imported)
+          .prop("local", local)
+          .ts_prop("importKind", named.is_type_only.then(|| "type".into()))
+          .into()
+      }
+      ImportSpecifier::Default(default) => self
+        .node("ImportDefaultSpecifier", default.range())
+        .prop("local", self.ident(&default.local))
+        .into(),
+      ImportSpecifier::Namespace(namespace) => self
+        .node("ImportNamespaceSpecifier", namespace.range())
+        .prop("local", self.ident(&namespace.local))
+        .into(),
+    }
+  }
+
+  fn export_specifier(&self, specifier: &ExportSpecifier) -> Value {
+    match specifier {
+      ExportSpecifier::Named(named) => {
+        let local = self.module_export_name(&named.orig);
+        let exported = match &named.exported {
+          Some(exported) => self.module_export_name(exported),
+          None => local.clone(),
+        };
+        self
+          .node("ExportSpecifier", named.range())
+          .prop("local", local)
+          .prop("exported", exported)
+          .ts_prop("exportKind", named.is_type_only.then(|| "type".into()))
+          .into()
+      }
+      // not in ESTree, so these use the names from Babel
+      ExportSpecifier::Namespace(namespace) => self
+        .node("ExportNamespaceSpecifier", namespace.range())
+        .prop("exported", self.module_export_name(&namespace.name))
+        .into(),
+      ExportSpecifier::Default(default) => self
+        .node("ExportDefaultSpecifier", default.exported.range())
+        .prop("exported", self.ident(&default.exported))
+        .into(),
+    }
+  }
+
+  fn module_export_name(&self, name: &ModuleExportName) -> Value {
+    match name {
+      ModuleExportName::Ident(ident) => self.ident(ident),
+      ModuleExportName::Str(str_lit) => self.str_lit(str_lit),
+    }
+  }
+
+  fn import_attributes(&self, maybe_with: Option<&ObjectLit>) -> Vec<Value> {
+    let Some(with) = maybe_with else {
+      return Vec::new();
+    };
+    with
+      .props
+      .iter()
+      .map(|prop| match prop {
+        PropOrSpread::Prop(prop) => match &**prop {
+          Prop::KeyValue(key_value) => self
+            .node(
+              "ImportAttribute",
+              SourceRange::new(key_value.key.start(), key_value.value.end()),
+            )
+            .prop("key", self.prop_name(&key_value.key).0)
+            .prop("value", self.expr(&key_value.value))
+            .into(),
+          _ => self.unsupported("ImportAttribute", prop.range()),
+        },
+        PropOrSpread::Spread(spread) => {
+          self.unsupported("SpreadElement", spread.range())
+        }
+      })
+      .collect()
+  }
+
+  fn stmt(&self, stmt: &Stmt) -> Value {
+    match stmt {
+      Stmt::Block(block) => self.block(block, false),
+      Stmt::Empty(empty) => self.node("EmptyStatement", empty.range()).into(),
+      Stmt::Debugger(debugger) => {
+        self.node("DebuggerStatement", debugger.range()).into()
+      }
+      Stmt::With(with) => self
+        .node("WithStatement", with.range())
+        .prop("object", self.expr(&with.obj))
+        .prop("body", self.stmt(&with.body))
+        .into(),
+      Stmt::Return(return_stmt) => self
+        .node("ReturnStatement", return_stmt.range())
+        .prop("argument", self.opt_expr(&return_stmt.arg))
+        .into(),
+      Stmt::Labeled(labeled) => self
+        .node("LabeledStatement", labeled.range())
+        .prop("label", self.ident(&labeled.label))
+        .prop("body", self.stmt(&labeled.body))
+        .into(),
+      Stmt::Break(break_stmt) => self
+        .node("BreakStatement", break_stmt.range())
+        .prop(
+          "label",
+          break_stmt.label.as_ref().map(|label| self.ident(label)),
+        )
+        .into(),
+      Stmt::Continue(continue_stmt) => self
+        .node("ContinueStatement", continue_stmt.range())
+        .prop(
+          "label",
+          continue_stmt.label.as_ref().map(|label| self.ident(label)),
+        )
+        .into(),
+      Stmt::If(if_stmt) => self
+        .node("IfStatement", if_stmt.range())
+        .prop("test", self.expr(&if_stmt.test))
+        .prop("consequent", self.stmt(&if_stmt.cons))
+        .prop(
+          "alternate",
+          if_stmt.alt.as_deref().map(|alt| self.stmt(alt)),
+        )
+        .into(),
+      Stmt::Switch(switch) => self
+        .node("SwitchStatement", switch.range())
+        .prop("discriminant", self.expr(&switch.discriminant))
+        .prop(
+          "cases",
+          switch
+            .cases
+            .iter()
+            .map(|case| {
+              self
+                .node("SwitchCase", case.range())
+                .prop("test", self.opt_expr(&case.test))
+                .prop("consequent", self.stmts(&case.cons, false))
+                .into()
+            })
+            .collect::<Vec<Value>>(),
+        )
+        .into(),
+      Stmt::Throw(throw) => self
+        .node("ThrowStatement", throw.range())
+        .prop("argument", self.expr(&throw.arg))
+        .into(),
+      Stmt::Try(try_stmt) => self
+        .node("TryStatement", try_stmt.range())
+        .prop("block", self.block(&try_stmt.block, false))
+        .prop(
+          "handler",
+          try_stmt.handler.as_ref().map(|handler| {
+            Value::from(
+              self
+                .node("CatchClause", handler.range())
+                .prop("param", handler.param.as_ref().map(|pat| self.pat(pat)))
+                .prop("body", self.block(&handler.body, false)),
+            )
+          }),
+        )
+        .prop(
+          "finalizer",
+          try_stmt
+            .finalizer
+            .as_ref()
+            .map(|finalizer| self.block(finalizer, false)),
+        )
+        .into(),
+      Stmt::While(while_stmt) => self
+        .node("WhileStatement", while_stmt.range())
+        .prop("test", self.expr(&while_stmt.test))
+        .prop("body", self.stmt(&while_stmt.body))
+        .into(),
+      Stmt::DoWhile(do_while) => self
+        .node("DoWhileStatement", do_while.range())
+        .prop("body", self.stmt(&do_while.body))
+        .prop("test", self.expr(&do_while.test))
+        .into(),
+      Stmt::For(for_stmt) => self
+        .node("ForStatement", for_stmt.range())
+        .prop(
+          "init",
+          for_stmt.init.as_ref().map(|init| match init {
+            VarDeclOrExpr::VarDecl(var_decl) => self.var_decl(var_decl),
+            VarDeclOrExpr::Expr(expr) => self.expr(expr),
+          }),
+        )
+        .prop("test", self.opt_expr(&for_stmt.test))
+        .prop("update", self.opt_expr(&for_stmt.update))
+        .prop("body", self.stmt(&for_stmt.body))
+        .into(),
+      Stmt::ForIn(for_in) => self
+        .node("ForInStatement", for_in.range())
+        .prop("left", self.for_head(&for_in.left))
+        .prop("right", self.expr(&for_in.right))
+        .prop("body", self.stmt(&for_in.body))
+        .into(),
+      Stmt::ForOf(for_of) => self
+        .node("ForOfStatement", for_of.range())
+        .prop("await", for_of.is_await)
+        .prop("left", self.for_head(&for_of.left))
+        .prop("right", self.expr(&for_of.right))
+        .prop("body", self.stmt(&for_of.body))
+        .into(),
+      Stmt::Decl(decl) => self.decl(decl),
+      Stmt::Expr(expr_stmt) => self
+        .node("ExpressionStatement", expr_stmt.range())
+        .prop("expression", self.expr(&expr_stmt.expr))
+        .into(),
+    }
+  }
+
+  fn block(&self, block: &BlockStmt, allow_directives: bool) -> Value {
+    self
+      .node("BlockStatement", block.range())
+      .prop("body", self.stmts(&block.stmts, allow_directives))
+      .into()
+  }
+
+  fn for_head(&self, head: &ForHead) -> Value {
+    match head {
+      ForHead::VarDecl(var_decl) => self.var_decl(var_decl),
+      ForHead::UsingDecl(using_decl) => self.using_decl(using_decl),
+      ForHead::Pat(pat) => self.pat(pat),
+    }
+  }
+
+  fn decl(&self, decl: &Decl) -> Value {
+    match decl {
+      Decl::Class(class_decl) => self
+        .class(
+          "ClassDeclaration",
+          class_decl.range(),
+          Some(&class_decl.ident),
+          &class_decl.class,
+        )
+        .ts_flag("declare", class_decl.declare)
+        .into(),
+      Decl::Fn(fn_decl) => self
+        .function(
+          "FunctionDeclaration",
+          fn_decl.range(),
+          Some(&fn_decl.ident),
+          &fn_decl.function,
+        )
+        .ts_flag("declare", fn_decl.declare)
+        .into(),
+      Decl::Var(var_decl) => self.var_decl(var_decl),
+      Decl::Using(using_decl) => self.using_decl(using_decl),
+      Decl::TsInterface(interface) => self.ts_interface(interface).into(),
+      Decl::TsTypeAlias(alias) => self
+        .node("TSTypeAliasDeclaration", alias.range())
+        .prop("id", self.ident(&alias.id))
+        .prop("typeAnnotation", self.ts_type(&alias.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&alias.type_params),
+        )
+        .prop("declare", alias.declare)
+        .into(),
+      Decl::TsEnum(ts_enum) => self
+        .node("TSEnumDeclaration", ts_enum.range())
+        .prop("id", self.ident(&ts_enum.id))
+        .prop(
+          "members",
+          ts_enum
+            .members
+            .iter()
+            .map(|member| {
+              let id = match &member.id {
+                TsEnumMemberId::Ident(ident) => self.ident(ident),
+                TsEnumMemberId::Str(str_lit) => self.str_lit(str_lit),
+              };
+              self
+                .node("TSEnumMember", member.range())
+                .prop("id", id)
+                .prop("initializer", self.opt_expr(&member.init))
+                .into()
+            })
+            .collect::<Vec<Value>>(),
+        )
+        .prop("const", ts_enum.is_const)
+        .prop("declare", ts_enum.declare)
+        .into(),
+      Decl::TsModule(module) => self.ts_module(module),
+    }
+  }
+
+  fn var_decl(&self, var_decl: &VarDecl) -> Value {
+    let kind = match var_decl.kind {
+      VarDeclKind::Var => "var",
+      VarDeclKind::Let => "let",
+      VarDeclKind::Const => "const",
+    };
+    self
+      .node("VariableDeclaration", var_decl.range())
+      .prop("declarations", self.var_declarators(&var_decl.decls))
+      .prop("kind", kind)
+      .ts_flag("declare", var_decl.declare)
+      .into()
+  }
+
+  fn using_decl(&self, using_decl: &UsingDecl) -> Value {
+    let kind = if using_decl.is_await {
+      "await using"
+    } else {
+      "using"
+    };
+    self
+      .node("VariableDeclaration", using_decl.range())
+      .prop("declarations", self.var_declarators(&using_decl.decls))
+      .prop("kind", kind)
+      .into()
+  }
+
+  fn var_declarators(&self, decls: &[VarDeclarator]) -> Vec<Value> {
+    decls
+      .iter()
+      .map(|decl| {
+        self
+          .node("VariableDeclarator", decl.range())
+          .prop("id", self.pat(&decl.name))
+          .prop("init", self.opt_expr(&decl.init))
+          .ts_flag("definite", decl.definite)
+          .into()
+      })
+      .collect()
+  }
+
+  fn function(
+    &self,
+    node_type: &str,
+    range: SourceRange,
+    id: Option<&Ident>,
+    function: &Function,
+  ) -> Node {
+    let params = function
+      .params
+      .iter()
+      .map(|param| self.param(param))
+      .collect();
+    self
+      .function_value(node_type, range, params, function.body.as_ref())
+      .prop("id", id.map(|id| self.ident(id)))
+      .prop("async", function.is_async)
+      .prop("generator", function.is_generator)
+      .ts_prop(
+        "typeParameters",
+        self.opt_type_param_decl(&function.type_params),
+      )
+      .ts_prop("returnType", self.opt_type_ann(&function.return_type))
+  }
+
+  /// Creates a function node, which uses the TS-ESTree type for
+  /// functions without a body (ex. overloads).
+  fn function_value(
+    &self,
+    node_type: &str,
+    range: SourceRange,
+    params: Vec<Value>,
+    body: Option<&BlockStmt>,
+  ) -> Node {
+    let node_type = match (node_type, body) {
+      (_, Some(_)) => node_type,
+      ("FunctionDeclaration", None) => "TSDeclareFunction",
+      (_, None) => "TSEmptyBodyFunctionExpression",
+    };
+    self
+      .node(node_type, range)
+      .prop("id", Value::Null)
+      .prop("params", params)
+      .prop("body", body.map(|body| self.block(body, true)))
+      .prop("async", false)
+      .prop("generator", false)
+      .prop("expression", false)
+  }
+
+  /// Gets the range of the function of a method, which starts at its
+  /// type parameters or parameters.
+  fn method_value_range(
+    &self,
+    key_end: SourcePos,
+    end: SourcePos,
+  ) -> SourceRange {
+    let start = self
+      .find_token_start(key_end, |token| {
+        matches!(token, Token::LParen | Token::BinOp(BinOpToken::Lt))
+      })
+      .filter(|start| *start < end)
+      .unwrap_or(key_end);
+    SourceRange::new(start, end)
+  }
+
+  fn param(&self, param: &Param) -> Value {
+    let mut value = self.pat(&param.pat);
+    if let (Some(decorators), Value::Object(map)) =
+      (self.decorators(&param.decorators), &mut value)
+    {
+      map.insert("decorators".to_string(), decorators);
+    }
+    value
+  }
+
+  fn decorators(&self, decorators: &[Decorator]) -> Option<Value> {
+    if decorators.is_empty() {
+      return None;
+    }
+    Some(
+      decorators
+        .iter()
+        .map(|decorator| {
+          self
+            .node("Decorator", decorator.range())
+            .prop("expression", self.expr(&decorator.expr))
+            .into()
+        })
+        .collect::<Vec<Value>>()
+        .into(),
+    )
+  }
+
+  fn class(
+    &self,
+    node_type: &str,
+    range: SourceRange,
+    id: Option<&Ident>,
+    class: &Class,
+  ) -> Node {
+    // the body starts at the first brace after the heading, which
+    // swc doesn't store
+    let heading_end = [
+      class.decorators.last().map(|decorator| decorator.end()),
+      id.map(|id| id.end()),
+      class.type_params.as_ref().map(|params| params.end()),
+      class
+        .super_class
+        .as_ref()
+        .map(|super_class| super_class.end()),
+      class.super_type_params.as_ref().map(|params| params.end()),
+      class.implements.last().map(|implements| implements.end()),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(class.start());
+    let body_start = self
+      .find_token_start(heading_end, |token| *token == Token::LBrace)
+      .filter(|start| *start < class.end())
+      .unwrap_or(heading_end);
+    let body = self
+      .node("ClassBody", SourceRange::new(body_start, class.end()))
+      .prop(
+        "body",
+        class
+          .body
+          .iter()
+          .filter_map(|member| self.class_member(member))
+          .collect::<Vec<_>>(),
+      );
+    let implements = (!class.implements.is_empty()).then(|| {
+      class
+        .implements
+        .iter()
+        .map(|implements| {
+          self.expr_with_type_args("TSClassImplements", implements)
+        })
+        .collect::<Vec<_>>()
+        .into()
+    });
+    self
+      .node(node_type, range)
+      .prop("id", id.map(|id| self.ident(id)))
+      .prop(
+        "superClass",
+        class.super_class.as_deref().map(|expr| self.expr(expr)),
+      )
+      .prop("body", body)
+      .ts_prop("decorators", self.decorators(&class.decorators))
+      .ts_flag("abstract", class.is_abstract)
+      .ts_prop(
+        "typeParameters",
+        self.opt_type_param_decl(&class.type_params),
+      )
+      .ts_prop(
+        "superTypeArguments",
+        self.opt_type_param_inst(&class.super_type_params),
+      )
+      .ts_prop("implements", implements)
+  }
+
+  fn class_member(&self, member: &ClassMember) -> Option<Value> {
+    let value = match member {
+      ClassMember::Constructor(constructor) => {
+        let params = constructor
+          .params
+          .iter()
+          .map(|param| match param {
+            ParamOrTsParamProp::Param(param) => self.param(param),
+            ParamOrTsParamProp::TsParamProp(prop) => self.ts_param_prop(prop),
+          })
+          .collect();
+        let value = self.function_value(
+          "FunctionExpression",
+          self.method_value_range(constructor.key.end(), constructor.end()),
+          params,
+          constructor.body.as_ref(),
+        );
+        self
+          .node("MethodDefinition", constructor.range())
+          .prop("key", self.prop_name(&constructor.key).0)
+          .prop("value", value)
+          .prop("kind", "constructor")
+          .prop("computed", false)
+          .prop("static", false)
+          .ts_prop("accessibility", accessibility(constructor.accessibility))
+          .ts_flag("optional", constructor.is_optional)
+      }
+      ClassMember::Method(method) => self
+        .method_definition(
+          method.range(),
+          self.prop_name(&method.key),
+          method.key.end(),
+          &method.function,
+          method.kind,
+          method.is_abstract,
+        )
+        .prop("static", method.is_static)
+        .ts_prop("accessibility", accessibility(method.accessibility))
+        .ts_flag("optional", method.is_optional)
+        .ts_flag("override", method.is_override),
+      ClassMember::PrivateMethod(method) => self
+        .method_definition(
+          method.range(),
+          (self.private_name(&method.key), false),
+          method.key.end(),
+          &method.function,
+          method.kind,
+          method.is_abstract,
+        )
+        .prop("static", method.is_static)
+        .ts_prop("accessibility", accessibility(method.accessibility))
+        .ts_flag("optional", method.is_optional)
+        .ts_flag("override", method.is_override),
+      ClassMember::ClassProp(prop) => self
+        .property_definition(
+          prop.range(),
+          self.prop_name(&prop.key),
+          &prop.value,
+          prop.is_abstract,
+        )
+        .prop("static", prop.is_static)
+        .ts_prop("decorators", self.decorators(&prop.decorators))
+        .ts_prop("typeAnnotation", self.opt_type_ann(&prop.type_ann))
+        .ts_prop("accessibility", accessibility(prop.accessibility))
+        .ts_flag("optional", prop.is_optional)
+        .ts_flag("override", prop.is_override)
+        .ts_flag("readonly", prop.readonly)
+        .ts_flag("declare", prop.declare)
+        .ts_flag("definite", prop.definite),
+      ClassMember::PrivateProp(prop) => self
+        .property_definition(
+          prop.range(),
+          (self.private_name(&prop.key), false),
+          &prop.value,
+          false,
+        )
+        .prop("static", prop.is_static)
+        .ts_prop("decorators", self.decorators(&prop.decorators))
+        .ts_prop("typeAnnotation", self.opt_type_ann(&prop.type_ann))
+        .ts_prop("accessibility", accessibility(prop.accessibility))
+        .ts_flag("optional", prop.is_optional)
+        .ts_flag("override", prop.is_override)
+        .ts_flag("readonly", prop.readonly)
+        .ts_flag("definite", prop.definite),
+      ClassMember::TsIndexSignature(signature) => {
+        return Some(self.ts_index_signature(signature));
+      }
+      ClassMember::Empty(_) => return None,
+      ClassMember::StaticBlock(static_block) => self
+        .node("StaticBlock", static_block.range())
+        .prop("body", self.stmts(&static_block.body.stmts, false)),
+      ClassMember::AutoAccessor(accessor) => {
+        let key = match &accessor.key {
+          Key::Private(name) => (self.private_name(name), false),
+          Key::Public(name) => self.prop_name(name),
+        };
+        self
+          .property_definition(accessor.range(), key, &accessor.value, false)
+          .prop("type", "AccessorProperty")
+          .prop("static", accessor.is_static)
+          .ts_prop("decorators", self.decorators(&accessor.decorators))
+          .ts_prop("typeAnnotation", self.opt_type_ann(&accessor.type_ann))
+      }
+    };
+    Some(value.into())
+  }
+
+  fn method_definition(
+    &self,
+    range: SourceRange,
+    (key, computed): (Value, bool),
+    key_end: SourcePos,
+    function: &Function,
+    kind: MethodKind,
+    is_abstract: bool,
+  ) -> Node {
+    let node_type = if is_abstract {
+      "TSAbstractMethodDefinition"
+    } else {
+      "MethodDefinition"
+    };
+    let kind = match kind {
+      MethodKind::Method => "method",
+      MethodKind::Getter => "get",
+      MethodKind::Setter => "set",
+    };
+    let value = self.function(
+      "FunctionExpression",
+      self.method_value_range(key_end, function.end()),
+      None,
+      function,
+    );
+    self
+      .node(node_type, range)
+      .prop("key", key)
+      .prop("value", value)
+      .prop("kind", kind)
+      .prop("computed", computed)
+      .ts_prop("decorators", self.decorators(&function.decorators))
+  }
+
+  fn property_definition(
+    &self,
+    range: SourceRange,
+    (key, computed): (Value, bool),
+    value: &Option<Box<Expr>>,
+    is_abstract: bool,
+  ) -> Node {
+    let node_type = if is_abstract {
+      "TSAbstractPropertyDefinition"
+    } else {
+      "PropertyDefinition"
+    };
+    self
+      .node(node_type, range)
+      .prop("key", key)
+      .prop("value", self.opt_expr(value))
+      .prop("computed", computed)
+  }
+
+  fn ts_param_prop(&self, prop: &TsParamProp) -> Value {
+    let parameter = match &prop.param {
+      TsParamPropParam::Ident(ident) => self.binding_ident(ident),
+      TsParamPropParam::Assign(assign) => self.assign_pat(assign),
+    };
+    self
+      .node("TSParameterProperty", prop.range())
+      .prop("parameter", parameter)
+      .prop("accessibility", accessibility(prop.accessibility))
+      .prop("readonly", prop.readonly)
+      .prop("override", prop.is_override)
+      .prop("decorators", self.decorators(&prop.decorators))
+      .into()
+  }
+
+  fn expr(&self, expr: &Expr) -> Value {
+    match expr {
+      Expr::This(this) => self.node("ThisExpression", this.range()).into(),
+      Expr::Array(array) => self
+        .node("ArrayExpression", array.range())
+        .prop(
+          "elements",
+          array
+            .elems
+            .iter()
+            .map(|elem| elem.as_ref().map(|elem| self.expr_or_spread(elem)))
+            .collect::<Vec<_>>(),
+        )
+        .into(),
+      Expr::Object(object) => self
+        .node("ObjectExpression", object.range())
+        .prop(
+          "properties",
+          object
+            .props
+            .iter()
+            .map(|prop| match prop {
+              PropOrSpread::Spread(spread) => self
+                .node("SpreadElement", spread.range())
+                .prop("argument", self.expr(&spread.expr))
+                .into(),
+              PropOrSpread::Prop(prop) => self.prop(prop),
+            })
+            .collect::<Vec<_>>(),
+        )
+        .into(),
+      Expr::Fn(fn_expr) => self
+        .function(
+          "FunctionExpression",
+          fn_expr.range(),
+          fn_expr.ident.as_ref(),
+          &fn_expr.function,
+        )
+        .into(),
+      Expr::Unary(unary) => self
+        .node("UnaryExpression", unary.range())
+        .prop("operator", unary.op.to_string())
+        .prop("prefix", true)
+        .prop("argument", self.expr(&unary.arg))
+        .into(),
+      Expr::Update(update) => self
+        .node("UpdateExpression", update.range())
+        .prop("operator", update.op.to_string())
+        .prop("prefix", update.prefix)
+        .prop("argument", self.expr(&update.arg))
+        .into(),
+      Expr::Bin(bin) => {
+        let node_type = match bin.op {
+          BinaryOp::LogicalOr
+          | BinaryOp::LogicalAnd
+          | BinaryOp::NullishCoalescing => "LogicalExpression",
+          _ => "BinaryExpression",
+        };
+        self
+          .node(node_type, bin.range())
+          .prop("operator", bin.op.to_string())
+          .prop("left", self.expr(&bin.left))
+          .prop("right", self.expr(&bin.right))
+          .into()
+      }
+      Expr::Assign(assign) => {
+        let left = match &assign.left {
+          PatOrExpr::Expr(expr) => self.expr(expr),
+          PatOrExpr::Pat(pat) => self.pat(pat),
+        };
+        self
+          .node("AssignmentExpression", assign.range())
+          .prop("operator", assign.op.to_string())
+          .prop("left", left)
+          .prop("right", self.expr(&assign.right))
+          .into()
+      }
+      Expr::Member(member) => self.member_expr(member, false),
+      Expr::SuperProp(super_prop) => {
+        let (property, computed) = match &super_prop.prop {
+          SuperProp::Ident(ident) => (self.ident(ident), false),
+          SuperProp::Computed(computed) => (self.expr(&computed.expr), true),
+        };
+        self
+          .node("MemberExpression", super_prop.range())
+          .prop("object", self.node("Super", super_prop.obj.range()))
+          .prop("property", property)
+          .prop("computed", computed)
+          .prop("optional", false)
+          .into()
+      }
+      Expr::Cond(cond) => self
+        .node("ConditionalExpression", cond.range())
+        .prop("test", self.expr(&cond.test))
+        .prop("consequent", self.expr(&cond.cons))
+        .prop("alternate", self.expr(&cond.alt))
+        .into(),
+      Expr::Call(call) => {
+        let callee: Value = match &call.callee {
+          Callee::Super(super_) => self.node("Super", super_.range()).into(),
+          Callee::Import(_) => {
+            return self
+              .node("ImportExpression", call.range())
+              .prop(
+                "source",
+                call.args.first().map(|arg| self.expr_or_spread(arg)),
+              )
+              .prop(
+                "options",
+                call.args.get(1).map(|arg| self.expr_or_spread(arg)),
+              )
+              .into();
+          }
+          Callee::Expr(expr) => self.expr(expr),
+        };
+        self
+          .node("CallExpression", call.range())
+          .prop("callee", callee)
+          .prop("arguments", self.args(&call.args))
+          .prop("optional", false)
+          .ts_prop("typeArguments", self.opt_type_param_inst(&call.type_args))
+          .into()
+      }
+      Expr::New(new) => self
+        .node("NewExpression", new.range())
+        .prop("callee", self.expr(&new.callee))
+        .prop(
+          "arguments",
+          new
+            .args
+            .as_ref()
+            .map(|args| self.args(args))
+            .unwrap_or_default(),
+        )
+        .ts_prop("typeArguments", self.opt_type_param_inst(&new.type_args))
+        .into(),
+      Expr::Seq(seq) => self
+        .node("SequenceExpression", seq.range())
+        .prop(
+          "expressions",
+          seq
+            .exprs
+            .iter()
+            .map(|expr| self.expr(expr))
+            .collect::<Vec<_>>(),
+        )
+        .into(),
+      Expr::Ident(ident) => self.ident(ident),
+      Expr::Lit(lit) => self.lit(lit),
+      Expr::Tpl(tpl) => self.template_literal(tpl),
+      Expr::TaggedTpl(tagged) => self
+        .node("TaggedTemplateExpression", tagged.range())
+        .prop("tag", self.expr(&tagged.tag))
+        .prop("quasi", self.template_literal(&tagged.tpl))
+        .into(),
+      Expr::Arrow(arrow) => {
+        let (body, is_expression) = match &*arrow.body {
+          BlockStmtOrExpr::BlockStmt(block) => (self.block(block, true), false),
+          BlockStmtOrExpr::Expr(expr) => (self.expr(expr), true),
+        };
+        self
+          .node("ArrowFunctionExpression", arrow.range())
+          .prop("id", Value::Null)
+          .prop(
+            "params",
+            arrow
+              .params
+              .iter()
+              .map(|pat| self.pat(pat))
+              .collect::<Vec<_>>(),
+          )
+          .prop("body", body)
+          .prop("async", arrow.is_async)
+          .prop("generator", false)
+          .prop("expression", is_expression)
+          .ts_prop(
+            "typeParameters",
+            self.opt_type_param_decl(&arrow.type_params),
+          )
+          .ts_prop("returnType", self.opt_type_ann(&arrow.return_type))
+          .into()
+      }
+      Expr::Class(class_expr) => self
+        .class(
+          "ClassExpression",
+          class_expr.range(),
+          class_expr.ident.as_ref(),
+          &class_expr.class,
+        )
+        .into(),
+      Expr::Yield(yield_expr) => self
+        .node("YieldExpression", yield_expr.range())
+        .prop("argument", self.opt_expr(&yield_expr.arg))
+        .prop("delegate", yield_expr.delegate)
+        .into(),
+      Expr::MetaProp(meta_prop) => {
+        let (meta, property) = match meta_prop.kind {
+          MetaPropKind::NewTarget => ("new", "target"),
+          MetaPropKind::ImportMeta => ("import", "meta"),
+        };
+        let range = meta_prop.range();
+        let tokens = self.tokens_in(range);
+        let identifier = |name: &str, token: Option<&TokenAndSpan>| {
+          let range = token.map(|token| token.range()).unwrap_or(range);
+          Value::from(self.node("Identifier", range).prop("name", name))
+        };
+        self
+          .node("MetaProperty", range)
+          .prop("meta", identifier(meta, tokens.first()))
+          .prop("property", identifier(property, tokens.last()))
+          .into()
+      }
+      Expr::Await(await_expr) => self
+        .node("AwaitExpression", await_expr.range())
+        .prop("argument", self.expr(&await_expr.arg))
+        .into(),
+      Expr::Paren(paren) => self.expr(&paren.expr),
+      Expr::JSXMember(member) => self.jsx_member_expr(member),
+      Expr::JSXNamespacedName(name) => self.jsx_namespaced_name(name),
+      Expr::JSXEmpty(empty) => {
+        self.node("JSXEmptyExpression", empty.range()).into()
+      }
+      Expr::JSXElement(element) => self.jsx_element(element),
+      Expr::JSXFragment(fragment) => self.jsx_fragment(fragment),
+      Expr::TsTypeAssertion(assertion) => self
+        .node("TSTypeAssertion", assertion.range())
+        .prop("typeAnnotation", self.ts_type(&assertion.type_ann))
+        .prop("expression", self.expr(&assertion.expr))
+        .into(),
+      Expr::TsConstAssertion(assertion) => {
+        let range = assertion.range();
+        let const_range = self
+          .tokens_in(range)
+          .last()
+          .map(|token| token.range())
+          .unwrap_or(range);
+        let const_ident =
+          self.node("Identifier", const_range).prop("name", "const");
+        self
+          .node("TSAsExpression", range)
+          .prop("expression", self.expr(&assertion.expr))
+          .prop(
+            "typeAnnotation",
+            self
+              .node("TSTypeReference", const_range)
+              .prop("typeName", const_ident)
+              .prop("typeArguments", Value::Null),
+          )
+          .into()
+      }
+      Expr::TsNonNull(non_null) => self
+        .node("TSNonNullExpression", non_null.range())
+        .prop("expression", self.expr(&non_null.expr))
+        .into(),
+      Expr::TsAs(as_expr) => self
+        .node("TSAsExpression", as_expr.range())
+        .prop("expression", self.expr(&as_expr.expr))
+        .prop("typeAnnotation", self.ts_type(&as_expr.type_ann))
+        .into(),
+      Expr::TsInstantiation(instantiation) => self
+        .node("TSInstantiationExpression", instantiation.range())
+        .prop("expression", self.expr(&instantiation.expr))
+        .prop(
+          "typeArguments",
+          self.type_param_inst(&instantiation.type_args),
+        )
+        .into(),
+      Expr::TsSatisfies(satisfies) => self
+        .node("TSSatisfiesExpression", satisfies.range())
+        .prop("expression", self.expr(&satisfies.expr))
+        .prop("typeAnnotation", self.ts_type(&satisfies.type_ann))
+        .into(),
+      Expr::PrivateName(name) => self.private_name(name),
+      Expr::OptChain(opt_chain) => self
+        .node("ChainExpression", opt_chain.range())
+        .prop("expression", self.chain_element(opt_chain))
+        .into(),
+      Expr::Invalid(invalid) => self.unsupported("Invalid", invalid.range()),
+    }
+  }
+
+  fn opt_expr(&self, expr: &Option<Box<Expr>>) -> Value {
+    expr.as_deref().map(|expr| self.expr(expr)).into()
+  }
+
+  fn member_expr(&self, member: &MemberExpr, optional: bool) -> Value {
+    let (property, computed) = match &member.prop {
+      MemberProp::Ident(ident) => (self.ident(ident), false),
+      MemberProp::PrivateName(name) => (self.private_name(name), false),
+      MemberProp::Computed(computed) => (self.expr(&computed.expr), true),
+    };
+    self
+      .node("MemberExpression", member.range())
+      .prop("object", self.chain_object(&member.obj))
+      .prop("property", property)
+      .prop("computed", computed)
+      .prop("optional", optional)
+      .into()
+  }
+
+  /// Converts the object or callee of a member or call, which continues
+  /// the optional chain it's in rather than starting a new one.
+  fn chain_object(&self, expr: &Expr) -> Value {
+    match expr {
+      Expr::OptChain(opt_chain) => self.chain_element(opt_chain),
+      _ => self.expr(expr),
+    }
+  }
+
+  fn chain_element(&self, opt_chain: &OptChainExpr) -> Value {
+    match &*opt_chain.base {
+      OptChainBase::Member(member) => {
+        self.member_expr(member, opt_chain.optional)
+      }
+      OptChainBase::Call(call) => self
+        .node("CallExpression", call.range())
+        .prop("callee", self.chain_object(&call.callee))
+        .prop("arguments", self.args(&call.args))
+        .prop("optional", opt_chain.optional)
+        .ts_prop("typeArguments", self.opt_type_param_inst(&call.type_args))
+        .into(),
+    }
+  }
+
+  fn args(&self, args: &[ExprOrSpread]) -> Vec<Value> {
+    args.iter().map(|arg| self.expr_or_spread(arg)).collect()
+  }
+
+  fn expr_or_spread(&self, expr_or_spread: &ExprOrSpread) -> Value {
+    match expr_or_spread.spread {
+      Some(_) => self
+        .node("SpreadElement", expr_or_spread.range())
+        .prop("argument", self.expr(&expr_or_spread.expr))
+        .into(),
+      None => self.expr(&expr_or_spread.expr),
+    }
+  }
+
+  fn prop(&self, prop: &Prop) -> Value {
+    match prop {
+      Prop::Shorthand(ident) => {
+        let key = self.ident(ident);
+        self.property(
+          ident.range(),
+          (key.clone(), false),
+          key,
+          "init",
+          false,
+          true,
+        )
+      }
+      Prop::KeyValue(key_value) => self.property(
+        SourceRange::new(key_value.key.start(), key_value.value.end()),
+        self.prop_name(&key_value.key),
+        self.expr(&key_value.value),
+        "init",
+        false,
+        false,
+      ),
+      Prop::Assign(assign) => {
+        // only valid when the object is a pattern (ex. `({ a = 1 } = b)`)
+        let range = SourceRange::new(assign.key.start(), assign.value.end());
+        let key = self.ident(&assign.key);
+        let value = self
+          .node("AssignmentPattern", range)
+          .prop("left", key.clone())
+          .prop("right", self.expr(&assign.value));
+        self.property(range, (key, false), value.into(), "init", false, true)
+      }
+      Prop::Getter(getter) => {
+        let value = self
+          .function_value(
+            "FunctionExpression",
+            self.method_value_range(getter.key.end(), getter.end()),
+            Vec::new(),
+            getter.body.as_ref(),
+          )
+          .ts_prop("returnType", self.opt_type_ann(&getter.type_ann));
+        self.property(
+          getter.range(),
+          self.prop_name(&getter.key),
+          value.into(),
+          "get",
+          false,
+          false,
+        )
+      }
+      Prop::Setter(setter) => {
+        let value = self.function_value(
+          "FunctionExpression",
+          self.method_value_range(setter.key.end(), setter.end()),
+          vec![self.pat(&setter.param)],
+          setter.body.as_ref(),
+        );
+        self.property(
+          setter.range(),
+          self.prop_name(&setter.key),
+          value.into(),
+          "set",
+          false,
+          false,
+        )
+      }
+      Prop::Method(method) => {
+        let range = SourceRange::new(method.key.start(), method.function.end());
+        let value = self.function(
+          "FunctionExpression",
+          self.method_value_range(method.key.end(), method.function.end()),
+          None,
+          &method.function,
+        );
+        self.property(
+          range,
+          self.prop_name(&method.key),
+          value.into(),
+          "init",
+          true,
+          false,
+        )
+      }
+    }
+  }
+
+  fn property(
+    &self,
+    range: SourceRange,
+    (key, computed): (Value, bool),
+    value: Value,
+    kind: &str,
+    method: bool,
+    shorthand: bool,
+  ) -> Value {
+    self
+      .node("Property", range)
+      .prop("key", key)
+      .prop("value", value)
+      .prop("kind", kind)
+      .prop("method", method)
+      .prop("shorthand", shorthand)
+      .prop("computed", computed)
+      .into()
+  }
+
+  /// Converts a property name, returning whether it's computed.
+  fn prop_name(&self, prop_name: &PropName) -> (Value, bool) {
+    match prop_name {
+      PropName::Ident(ident) => (self.ident(ident), false),
+      PropName::Str(str_lit) => (self.str_lit(str_lit), false),
+      PropName::Num(number) => (self.num_lit(number), false),
+      PropName::BigInt(big_int) => (self.big_int_lit(big_int), false),
+      PropName::Computed(computed) => (self.expr(&computed.expr), true),
+    }
+  }
+
+  fn ident(&self, ident: &Ident) -> Value {
+    self
+      .node("Identifier", ident.range())
+      .prop("name", ident.sym.to_string())
+      .ts_flag("optional", ident.optional)
+      .into()
+  }
+
+  fn private_name(&self, name: &PrivateName) -> Value {
+    self
+      .node("PrivateIdentifier", name.range())
+      .prop("name", name.id.sym.to_string())
+      .into()
+  }
+
+  fn lit(&self, lit: &Lit) -> Value {
+    match lit {
+      Lit::Str(str_lit) => self.str_lit(str_lit),
+      Lit::Bool(bool_lit) => self.literal(bool_lit.range(), bool_lit.value),
+      Lit::Null(null) => self.literal(null.range(), Value::Null),
+      Lit::Num(number) => self.num_lit(number),
+      Lit::BigInt(big_int) => self.big_int_lit(big_int),
+      Lit::Regex(regex) => self
+        .node("Literal", regex.range())
+        .prop("value", Value::Null)
+        .prop("raw", self.text(regex.range()))
+        .prop(
+          "regex",
+          json!({
+            "pattern": regex.exp.to_string(),
+            "flags": regex.flags.to_string(),
+          }),
+        )
+        .into(),
+      Lit::JSXText(text) => self.jsx_text(text),
+    }
+  }
+
+  fn literal(&self, range: SourceRange, value: impl Into<Value>) -> Value {
+    self
+      .node("Literal", range)
+      .prop("value", value)
+      .prop("raw", self.text(range))
+      .into()
+  }
+
+  fn str_lit(&self, str_lit: &Str) -> Value {
+    self.literal(str_lit.range(), str_lit.value.to_string())
+  }
+
+  fn num_lit(&self, number: &Number) -> Value {
+    self.literal(number.range(), number_value(number.value))
+  }
+
+  /// Converts a bigint, which has a `null` value because JSON can't
+  /// represent it, and its digits in `bigint` like ESTree.
+  fn big_int_lit(&self, big_int: &BigInt) -> Value {
+    let raw = self.text(big_int.range());
+    self
+      .node("Literal", big_int.range())
+      .prop("value", Value::Null)
+      .prop("raw", raw)
+      .prop("bigint", raw.trim_end_matches('n').replace('_', ""))
+      .into()
+  }
+
+  fn template_literal(&self, tpl: &Tpl) -> Value {
+    self
+      .node("TemplateLiteral", tpl.range())
+      .prop("quasis", self.template_elements(&tpl.quasis))
+      .prop(
+        "expressions",
+        tpl
+          .exprs
+          .iter()
+          .map(|expr| self.expr(expr))
+          .collect::<Vec<_>>(),
+      )
+      .into()
+  }
+
+  fn template_elements(&self, elements: &[TplElement]) -> Vec<Value> {
+    elements
+      .iter()
+      .map(|element| {
+        self
+          .node("TemplateElement", element.range())
+          .prop(
+            "value",
+            json!({
+              "raw": element.raw.to_string(),
+              "cooked": element.cooked.as_ref().map(|cooked| cooked.to_string()),
+            }),
+          )
+          .prop("tail", element.tail)
+          .into()
+      })
+      .collect()
+  }
+
+  fn pat(&self, pat: &Pat) -> Value {
+    match pat {
+      Pat::Ident(ident) => self.binding_ident(ident),
+      Pat::Array(array) => self.array_pat(array),
+      Pat::Rest(rest) => self.rest_pat(rest),
+      Pat::Object(object) => self.object_pat(object),
+      Pat::Assign(assign) => self.assign_pat(assign),
+      Pat::Invalid(invalid) => self.unsupported("Invalid", invalid.range()),
+      Pat::Expr(expr) => self.expr(expr),
+    }
+  }
+
+  fn binding_ident(&self, ident: &BindingIdent) -> Value {
+    // the type annotation is part of the identifier in TS-ESTree
+    let end = match &ident.type_ann {
+      Some(type_ann) => type_ann.end(),
+      None => ident.id.end(),
+    };
+    self
+      .node("Identifier", SourceRange::new(ident.id.start(), end))
+      .prop("name", ident.id.sym.to_string())
+      .ts_flag("optional", ident.id.optional)
+      .ts_prop("typeAnnotation", self.opt_type_ann(&ident.type_ann))
+      .into()
+  }
+
+  fn array_pat(&self, array: &ArrayPat) -> Value {
+    self
+      .node("ArrayPattern", array.range())
+      .prop(
+        "elements",
+        array
+          .elems
+          .iter()
+          .map(|elem| elem.as_ref().map(|elem| self.pat(elem)))
+          .collect::<Vec<_>>(),
+      )
+      .ts_flag("optional", array.optional)
+      .ts_prop("typeAnnotation", self.opt_type_ann(&array.type_ann))
+      .into()
+  }
+
+  fn rest_pat(&self, rest: &RestPat) -> Value {
+    self
+      .node("RestElement", rest.range())
+      .prop("argument", self.pat(&rest.arg))
+      .ts_prop("typeAnnotation", self.opt_type_ann(&rest.type_ann))
+      .into()
+  }
+
+  fn object_pat(&self, object: &ObjectPat) -> Value {
+    self
+      .node("ObjectPattern", object.range())
+      .prop(
+        "properties",
+        object
+          .props
+          .iter()
+          .map(|prop| self.object_pat_prop(prop))
+          .collect::<Vec<_>>(),
+      )
+      .ts_flag("optional", object.optional)
+      .ts_prop("typeAnnotation", self.opt_type_ann(&object.type_ann))
+      .into()
+  }
+
+  fn object_pat_prop(&self, prop: &ObjectPatProp) -> Value {
+    match prop {
+      ObjectPatProp::KeyValue(key_value) => self.property(
+        SourceRange::new(key_value.key.start(), key_value.value.end()),
+        self.prop_name(&key_value.key),
+        self.pat(&key_value.value),
+        "init",
+        false,
+        false,
+      ),
+      ObjectPatProp::Assign(assign) => {
+        let key = self.ident(&assign.key);
+        let value = match &assign.value {
+          Some(value) => self
+            .node("AssignmentPattern", assign.range())
+            .prop("left", key.clone())
+            .prop("right", self.expr(value))
+            .into(),
+          None => key.clone(),
+        };
+        self.property(assign.range(), (key, false), value, "init", false, true)
+      }
+      ObjectPatProp::Rest(rest) => self.rest_pat(rest),
+    }
+  }
+
+  fn assign_pat(&self, assign: &AssignPat) -> Value {
+    self
+      .node("AssignmentPattern", assign.range())
+      .prop("left", self.pat(&assign.left))
+      .prop("right", self.expr(&assign.right))
+      .into()
+  }
+
+  fn jsx_element(&self, element: &JSXElement) -> Value {
+    let opening = &element.opening;
+    let attributes = opening
+      .attrs
+      .iter()
+      .map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(attr) => {
+          let name = match &attr.name {
+            JSXAttrName::Ident(ident) => self.jsx_ident(ident),
+            JSXAttrName::JSXNamespacedName(name) => {
+              self.jsx_namespaced_name(name)
+            }
+          };
+          let value = attr.value.as_ref().map(|value| match value {
+            JSXAttrValue::Lit(lit) => self.lit(lit),
+            JSXAttrValue::JSXExprContainer(container) => {
+              self.jsx_expr_container(container)
+            }
+            JSXAttrValue::JSXElement(element) => self.jsx_element(element),
+            JSXAttrValue::JSXFragment(fragment) => self.jsx_fragment(fragment),
+          });
+          self
+            .node("JSXAttribute", attr.range())
+            .prop("name", name)
+            .prop("value", value)
+            .into()
+        }
+        JSXAttrOrSpread::SpreadElement(spread) => self
+          .node("JSXSpreadAttribute", spread.range())
+          .prop("argument", self.expr(&spread.expr))
+          .into(),
+      })
+      .collect::<Vec<Value>>();
+    let opening_element = self
+      .node("JSXOpeningElement", opening.range())
+      .prop("name", self.jsx_element_name(&opening.name))
+      .prop("attributes", attributes)
+      .prop("selfClosing", opening.self_closing)
+      .ts_prop(
+        "typeArguments",
+        self.opt_type_param_inst(&opening.type_args),
+      );
+    let closing_element = element.closing.as_ref().map(|closing| {
+      Value::from(
+        self
+          .node("JSXClosingElement", closing.range())
+          .prop("name", self.jsx_element_name(&closing.name)),
+      )
+    });
+    self
+      .node("JSXElement", element.range())
+      .prop("openingElement", opening_element)
+      .prop("closingElement", closing_element)
+      .prop("children", self.jsx_children(&element.children))
+      .into()
+  }
+
+  fn jsx_fragment(&self, fragment: &JSXFragment) -> Value {
+    self
+      .node("JSXFragment", fragment.range())
+      .prop(
+        "openingFragment",
+        self.node("JSXOpeningFragment", fragment.opening.range()),
+      )
+      .prop(
+        "closingFragment",
+        self.node("JSXClosingFragment", fragment.closing.range()),
+      )
+      .prop("children", self.jsx_children(&fragment.children))
+      .into()
+  }
+
+  fn jsx_children(&self, children: &[JSXElementChild]) -> Vec<Value> {
+    children
+      .iter()
+      .map(|child| match child {
+        JSXElementChild::JSXText(text) => self.jsx_text(text),
+        JSXElementChild::JSXExprContainer(container) => {
+          self.jsx_expr_container(container)
+        }
+        JSXElementChild::JSXSpreadChild(spread) => self
+          .node("JSXSpreadChild", spread.range())
+          .prop("expression", self.expr(&spread.expr))
+          .into(),
+        JSXElementChild::JSXElement(element) => self.jsx_element(element),
+        JSXElementChild::JSXFragment(fragment) => self.jsx_fragment(fragment),
+      })
+      .collect()
+  }
+
+  fn jsx_text(&self, text: &JSXText) -> Value {
+    self
+      .node("JSXText", text.range())
+      .prop("value", text.value.to_string())
+      .prop("raw", self.text(text.range()))
+      .into()
+  }
+
+  fn jsx_expr_container(&self, container: &JSXExprContainer) -> Value {
+    let expression: Value = match &container.expr {
+      JSXExpr::JSXEmptyExpr(empty) => {
+        self.node("JSXEmptyExpression", empty.range()).into()
+      }
+      JSXExpr::Expr(expr) => self.expr(expr),
+    };
+    self
+      .node("JSXExpressionContainer", container.range())
+      .prop("expression", expression)
+      .into()
+  }
+
+  fn jsx_element_name(&self, name: &JSXElementName) -> Value {
+    match name {
+      JSXElementName::Ident(ident) => self.jsx_ident(ident),
+      JSXElementName::JSXMemberExpr(member) => self.jsx_member_expr(member),
+      JSXElementName::JSXNamespacedName(name) => self.jsx_namespaced_name(name),
+    }
+  }
+
+  fn jsx_member_expr(&self, member: &JSXMemberExpr) -> Value {
+    let object = match &member.obj {
+      JSXObject::JSXMemberExpr(member) => self.jsx_member_expr(member),
+      JSXObject::Ident(ident) => self.jsx_ident(ident),
+    };
+    self
+      .node("JSXMemberExpression", member.range())
+      .prop("object", object)
+      .prop("property", self.jsx_ident(&member.prop))
+      .into()
+  }
+
+  fn jsx_namespaced_name(&self, name: &JSXNamespacedName) -> Value {
+    self
+      .node("JSXNamespacedName", name.range())
+      .prop("namespace", self.jsx_ident(&name.ns))
+      .prop("name", self.jsx_ident(&name.name))
+      .into()
+  }
+
+  fn jsx_ident(&self, ident: &Ident) -> Value {
+    self
+      .node("JSXIdentifier", ident.range())
+      .prop("name", ident.sym.to_string())
+      .into()
+  }
+
+  fn ts_interface(&self, interface: &TsInterfaceDecl) -> Node {
+    self
+      .node("TSInterfaceDeclaration", interface.range())
+      .prop("id", self.ident(&interface.id))
+      .prop(
+        "body",
+        self.node("TSInterfaceBody", interface.body.range()).prop(
+          "body",
+          interface
+            .body
+            .body
+            .iter()
+            .map(|element| self.ts_type_element(element))
+            .collect::<Vec<_>>(),
+        ),
+      )
+      .prop(
+        "extends",
+        interface
+          .extends
+          .iter()
+          .map(|extends| {
+            self.expr_with_type_args("TSInterfaceHeritage", extends)
+          })
+          .collect::<Vec<_>>(),
+      )
+      .prop(
+        "typeParameters",
+        self.opt_type_param_decl(&interface.type_params),
+      )
+      .prop("declare", interface.declare)
+  }
+
+  fn expr_with_type_args(
+    &self,
+    node_type: &str,
+    expr: &TsExprWithTypeArgs,
+  ) -> Value {
+    self
+      .node(node_type, expr.range())
+      .prop("expression", self.expr(&expr.expr))
+      .prop("typeArguments", self.opt_type_param_inst(&expr.type_args))
+      .into()
+  }
+
+  fn ts_module(&self, module: &TsModuleDecl) -> Value {
+    let (id, id_start) = match &module.id {
+      TsModuleName::Ident(ident) => (self.ident(ident), ident.start()),
+      TsModuleName::Str(str_lit) => (self.str_lit(str_lit), str_lit.start()),
+    };
+    let kind = if module.global {
+      "global"
+    } else if matches!(module.id, TsModuleName::Str(_))
+      || self
+        .text(SourceRange::new(module.start(), id_start))
+        .contains("module")
+    {
+      "module"
+    } else {
+      "namespace"
+    };
+    self
+      .node("TSModuleDeclaration", module.range())
+      .prop("id", id)
+      .prop(
+        "body",
+        module
+          .body
+          .as_ref()
+          .map(|body| self.ts_namespace_body(body)),
+      )
+      .prop("kind", kind)
+      .prop("declare", module.declare)
+      .prop("global", module.global)
+      .into()
+  }
+
+  /// Converts the body of a namespace, where the body of a nested
+  /// namespace (ex. `namespace a.b {}`) is another declaration.
+  fn ts_namespace_body(&self, body: &TsNamespaceBody) -> Value {
+    match body {
+      TsNamespaceBody::TsModuleBlock(block) => self
+        .node("TSModuleBlock", block.range())
+        .prop("body", self.module_items(&block.body))
+        .into(),
+      TsNamespaceBody::TsNamespaceDecl(decl) => self
+        .node("TSModuleDeclaration", decl.range())
+        .prop("id", self.ident(&decl.id))
+        .prop("body", self.ts_namespace_body(&decl.body))
+        .prop("kind", "namespace")
+        .prop("declare", decl.declare)
+        .prop("global", decl.global)
+        .into(),
+    }
+  }
+
+  fn ts_type_element(&self, element: &TsTypeElement) -> Value {
+    match element {
+      TsTypeElement::TsCallSignatureDecl(signature) => self
+        .node("TSCallSignatureDeclaration", signature.range())
+        .prop("params", self.ts_fn_params(&signature.params))
+        .prop("returnType", self.opt_type_ann(&signature.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&signature.type_params),
+        )
+        .into(),
+      TsTypeElement::TsConstructSignatureDecl(signature) => self
+        .node("TSConstructSignatureDeclaration", signature.range())
+        .prop("params", self.ts_fn_params(&signature.params))
+        .prop("returnType", self.opt_type_ann(&signature.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&signature.type_params),
+        )
+        .into(),
+      TsTypeElement::TsPropertySignature(signature) => self
+        .node("TSPropertySignature", signature.range())
+        .prop("key", self.expr(&signature.key))
+        .prop("computed", signature.computed)
+        .prop("optional", signature.optional)
+        .prop("readonly", signature.readonly)
+        .prop("typeAnnotation", self.opt_type_ann(&signature.type_ann))
+        .into(),
+      TsTypeElement::TsGetterSignature(signature) => self
+        .node("TSMethodSignature", signature.range())
+        .prop("key", self.expr(&signature.key))
+        .prop("kind", "get")
+        .prop("computed", signature.computed)
+        .prop("optional", signature.optional)
+        .prop("params", Vec::<Value>::new())
+        .prop("returnType", self.opt_type_ann(&signature.type_ann))
+        .prop("typeParameters", Value::Null)
+        .into(),
+      TsTypeElement::TsSetterSignature(signature) => self
+        .node("TSMethodSignature", signature.range())
+        .prop("key", self.expr(&signature.key))
+        .prop("kind", "set")
+        .prop("computed", signature.computed)
+        .prop("optional", signature.optional)
+        .prop("params", vec![self.ts_fn_param(&signature.param)])
+        .prop("returnType", Value::Null)
+        .prop("typeParameters", Value::Null)
+        .into(),
+      TsTypeElement::TsMethodSignature(signature) => self
+        .node("TSMethodSignature", signature.range())
+        .prop("key", self.expr(&signature.key))
+        .prop("kind", "method")
+        .prop("computed", signature.computed)
+        .prop("optional", signature.optional)
+        .prop("params", self.ts_fn_params(&signature.params))
+        .prop("returnType", self.opt_type_ann(&signature.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&signature.type_params),
+        )
+        .into(),
+      TsTypeElement::TsIndexSignature(signature) => {
+        self.ts_index_signature(signature)
+      }
+    }
+  }
+
+  fn ts_index_signature(&self, signature: &TsIndexSignature) -> Value {
+    self
+      .node("TSIndexSignature", signature.range())
+      .prop("parameters", self.ts_fn_params(&signature.params))
+      .prop("typeAnnotation", self.opt_type_ann(&signature.type_ann))
+      .prop("readonly", signature.readonly)
+      .prop("static", signature.is_static)
+      .into()
+  }
+
+  fn ts_fn_params(&self, params: &[TsFnParam]) -> Vec<Value> {
+    params.iter().map(|param| self.ts_fn_param(param)).collect()
+  }
+
+  fn ts_fn_param(&self, param: &TsFnParam) -> Value {
+    match param {
+      TsFnParam::Ident(ident) => self.binding_ident(ident),
+      TsFnParam::Array(array) => self.array_pat(array),
+      TsFnParam::Rest(rest) => self.rest_pat(rest),
+      TsFnParam::Object(object) => self.object_pat(object),
+    }
+  }
+
+  fn ts_type(&self, ts_type: &TsType) -> Value {
+    match ts_type {
+      TsType::TsKeywordType(keyword) => self
+        .node(keyword_type_name(keyword.kind), keyword.range())
+        .into(),
+      TsType::TsThisType(this) => self.node("TSThisType", this.range()).into(),
+      TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsFnType(
+        fn_type,
+      )) => self
+        .node("TSFunctionType", fn_type.range())
+        .prop("params", self.ts_fn_params(&fn_type.params))
+        .prop("returnType", self.type_ann(&fn_type.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&fn_type.type_params),
+        )
+        .into(),
+      TsType::TsFnOrConstructorType(
+        TsFnOrConstructorType::TsConstructorType(constructor_type),
+      ) => self
+        .node("TSConstructorType", constructor_type.range())
+        .prop("params", self.ts_fn_params(&constructor_type.params))
+        .prop("returnType", self.type_ann(&constructor_type.type_ann))
+        .prop(
+          "typeParameters",
+          self.opt_type_param_decl(&constructor_type.type_params),
+        )
+        .prop("abstract", constructor_type.is_abstract)
+        .into(),
+      TsType::TsTypeRef(type_ref) => self
+        .node("TSTypeReference", type_ref.range())
+        .prop("typeName", self.entity_name(&type_ref.type_name))
+        .prop(
+          "typeArguments",
+          self.opt_type_param_inst(&type_ref.type_params),
+        )
+        .into(),
+      TsType::TsTypeQuery(query) => {
+        let expr_name = match &query.expr_name {
+          TsTypeQueryExpr::TsEntityName(name) => self.entity_name(name),
+          TsTypeQueryExpr::Import(import) => self.ts_import_type(import),
+        };
+        self
+          .node("TSTypeQuery", query.range())
+          .prop("exprName", expr_name)
+          .prop("typeArguments", self.opt_type_param_inst(&query.type_args))
+          .into()
+      }
+      TsType::TsTypeLit(type_lit) => self
+        .node("TSTypeLiteral", type_lit.range())
+        .prop(
+          "members",
+          type_lit
+            .members
+            .iter()
+            .map(|member| self.ts_type_element(member))
+            .collect::<Vec<_>>(),
+        )
+        .into(),
+      TsType::TsArrayType(array) => self
+        .node("TSArrayType", array.range())
+        .prop("elementType", self.ts_type(&array.elem_type))
+        .into(),
+      TsType::TsTupleType(tuple) => self
+        .node("TSTupleType", tuple.range())
+        .prop(
+          "elementTypes",
+          tuple
+            .elem_types
+            .iter()
+            .map(|element| match &element.label {
+              Some(label) => self
+                .node("TSNamedTupleMember", element.range())
+                .prop("label", self.pat(label))
+                .prop("elementType", self.ts_type(&element.ty))
+                .prop("optional", false)
+                .into(),
+              None => self.ts_type(&element.ty),
+            })
+            .collect::<Vec<_>>(),
+        )
+        .into(),
+      TsType::TsOptionalType(optional) => self
+        .node("TSOptionalType", optional.range())
+        .prop("typeAnnotation", self.ts_type(&optional.type_ann))
+        .into(),
+      TsType::TsRestType(rest) => self
+        .node("TSRestType", rest.range())
+        .prop("typeAnnotation", self.ts_type(&rest.type_ann))
+        .into(),
+      TsType::TsUnionOrIntersectionType(
+        TsUnionOrIntersectionType::TsUnionType(union),
+      ) => self
+        .node("TSUnionType", union.range())
+        .prop("types", self.ts_types(&union.types))
+        .into(),
+      TsType::TsUnionOrIntersectionType(
+        TsUnionOrIntersectionType::TsIntersectionType(intersection),
+      ) => self
+        .node("TSIntersectionType", intersection.range())
+        .prop("types", self.ts_types(&intersection.types))
+        .into(),
+      TsType::TsConditionalType(conditional) => self
+        .node("TSConditionalType", conditional.range())
+        .prop("checkType", self.ts_type(&conditional.check_type))
+        .prop("extendsType", self.ts_type(&conditional.extends_type))
+        .prop("trueType", self.ts_type(&conditional.true_type))
+        .prop("falseType", self.ts_type(&conditional.false_type))
+        .into(),
+      TsType::TsInferType(infer) => self
+        .node("TSInferType", infer.range())
+        .prop("typeParameter", self.type_param(&infer.type_param))
+        .into(),
+      TsType::TsParenthesizedType(paren) => self.ts_type(&paren.type_ann),
+      TsType::TsTypeOperator(operator) => {
+        let operator_text = match operator.op {
+          TsTypeOperatorOp::KeyOf => "keyof",
+          TsTypeOperatorOp::Unique => "unique",
+          TsTypeOperatorOp::ReadOnly => "readonly",
+        };
+        self
+          .node("TSTypeOperator", operator.range())
+          .prop("operator", operator_text)
+          .prop("typeAnnotation", self.ts_type(&operator.type_ann))
+          .into()
+      }
+      TsType::TsIndexedAccessType(indexed) => self
+        .node("TSIndexedAccessType", indexed.range())
+        .prop("objectType", self.ts_type(&indexed.obj_type))
+        .prop("indexType", self.ts_type(&indexed.index_type))
+        .into(),
+      TsType::TsMappedType(mapped) => self
+        .node("TSMappedType", mapped.range())
+        .prop("typeParameter", self.type_param(&mapped.type_param))
+        .prop(
+          "nameType",
+          mapped.name_type.as_deref().map(|name| self.ts_type(name)),
+        )
+        .prop(
+          "typeAnnotation",
+          mapped
+            .type_ann
+            .as_deref()
+            .map(|ts_type| self.ts_type(ts_type)),
+        )
+        .prop("optional", true_plus_minus(mapped.optional))
+        .prop("readonly", true_plus_minus(mapped.readonly))
+        .into(),
+      TsType::TsLitType(lit_type) => {
+        let literal = match &lit_type.lit {
+          TsLit::Tpl(tpl) => {
+            return self
+              .node("TSTemplateLiteralType", lit_type.range())
+              .prop("quasis", self.template_elements(&tpl.quasis))
+              .prop("types", self.ts_types(&tpl.types))
+              .into();
+          }
+          TsLit::Number(number) => self.num_lit(number),
+          TsLit::Str(str_lit) => self.str_lit(str_lit),
+          TsLit::Bool(bool_lit) => {
+            self.literal(bool_lit.range(), bool_lit.value)
+          }
+          TsLit::BigInt(big_int) => self.big_int_lit(big_int),
+        };
+        self
+          .node("TSLiteralType", lit_type.range())
+          .prop("literal", literal)
+          .into()
+      }
+      TsType::TsTypePredicate(predicate) => {
+        let parameter_name: Value = match &predicate.param_name {
+          TsThisTypeOrIdent::TsThisType(this) => {
+            self.node("TSThisType", this.range()).into()
+          }
+          TsThisTypeOrIdent::Ident(ident) => self.ident(ident),
+        };
+        self
+          .node("TSTypePredicate", predicate.range())
+          .prop("asserts", predicate.asserts)
+          .prop("parameterName", parameter_name)
+          .prop("typeAnnotation", self.opt_type_ann(&predicate.type_ann))
+          .into()
+      }
+      TsType::TsImportType(import) => self.ts_import_type(import),
+    }
+  }
+
+  fn ts_types(&self, types: &[Box<TsType>]) -> Vec<Value> {
+    types.iter().map(|ts_type| self.ts_type(ts_type)).collect()
+  }
+
+  fn ts_import_type(&self, import: &TsImportType) -> Value {
+    self
+      .node("TSImportType", import.range())
+      .prop(
+        "argument",
+        self
+          .node("TSLiteralType", import.arg.range())
+          .prop("literal", self.str_lit(&import.arg)),
+      )
+      .prop(
+        "qualifier",
+        import.qualifier.as_ref().map(|name| self.entity_name(name)),
+      )
+      .prop("typeArguments", self.opt_type_param_inst(&import.type_args))
+      .into()
+  }
+
+  fn entity_name(&self, name: &TsEntityName) -> Value {
+    match name {
+      TsEntityName::Ident(ident) => self.ident(ident),
+      TsEntityName::TsQualifiedName(qualified) => self
+        .node(
+          "TSQualifiedName",
+          SourceRange::new(qualified.left.start(), qualified.right.end()),
+        )
+        .prop("left", self.entity_name(&qualified.left))
+        .prop("right", self.ident(&qualified.right))
+        .into(),
+    }
+  }
+
+  fn type_ann(&self, type_ann: &TsTypeAnn) -> Value {
+    self
+      .node("TSTypeAnnotation", type_ann.range())
+      .prop("typeAnnotation", self.ts_type(&type_ann.type_ann))
+      .into()
+  }
+
+  fn opt_type_ann(&self, type_ann: &Option<Box<TsTypeAnn>>) -> Option<Value> {
+    type_ann.as_deref().map(|type_ann| self.type_ann(type_ann))
+  }
+
+  fn opt_type_param_decl(
+    &self,
+    type_params: &Option<Box<TsTypeParamDecl>>,
+  ) -> Option<Value> {
+    type_params.as_deref().map(|type_params| {
+      self
+        .node("TSTypeParameterDeclaration", type_params.range())
+        .prop(
+          "params",
+          type_params
+            .params
+            .iter()
+            .map(|param| self.type_param(param))
+            .collect::<Vec<_>>(),
+        )
+        .into()
+    })
+  }
+
+  fn type_param(&self, param: &TsTypeParam) -> Value {
+    self
+      .node("TSTypeParameter", param.range())
+      .prop("name", self.ident(&param.name))
+      .prop(
+        "constraint",
+        param
+          .constraint
+          .as_deref()
+          .map(|ts_type| self.ts_type(ts_type)),
+      )
+      .prop(
+        "default",
+        param
+          .default
+          .as_deref()
+          .map(|ts_type| self.ts_type(ts_type)),
+      )
+      .prop("in", param.is_in)
+      .prop("out", param.is_out)
+      .prop("const", param.is_const)
+      .into()
+  }
+
+  fn opt_type_param_inst(
+    &self,
+    type_args: &Option<Box<TsTypeParamInstantiation>>,
+  ) -> Option<Value> {
+    type_args
+      .as_deref()
+      .map(|type_args| self.type_param_inst(type_args))
+  }
+
+  fn type_param_inst(&self, type_args: &TsTypeParamInstantiation) -> Value {
+    self
+      .node("TSTypeParameterInstantiation", type_args.range())
+      .prop("params", self.ts_types(&type_args.params))
+      .into()
+  }
+}
+
+/// Gets the JSON value of a number, using an integer when it has no
+/// fractional part so that `1` isn't `1.0`.
+fn number_value(value: f64) -> Value {
+  // the largest integer that can be exactly represented
+  const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+  if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER {
+    (value as i64).into()
+  } else {
+    // NaN and infinity are null
+    serde_json::Number::from_f64(value)
+      .map(Value::Number)
+      .unwrap_or(Value::Null)
+  }
+}
+
+fn accessibility(accessibility: Option<Accessibility>) -> Option<Value> {
+  accessibility.map(|accessibility| {
+    match accessibility {
+      Accessibility::Public => "public",
+      Accessibility::Protected => "protected",
+      Accessibility::Private => "private",
+    }
+    .into()
+  })
+}
+
+fn true_plus_minus(value: Option<TruePlusMinus>) -> Value {
+  match value {
+    None => false.into(),
+    Some(TruePlusMinus::True) => true.into(),
+    Some(TruePlusMinus::Plus) => "+".into(),
+    Some(TruePlusMinus::Minus) => "-".into(),
+  }
+}
+
+fn keyword_type_name(kind: TsKeywordTypeKind) -> &'static str {
+  match kind {
+    TsKeywordTypeKind::TsAnyKeyword => "TSAnyKeyword",
+    TsKeywordTypeKind::TsUnknownKeyword => "TSUnknownKeyword",
+    TsKeywordTypeKind::TsNumberKeyword => "TSNumberKeyword",
+    TsKeywordTypeKind::TsObjectKeyword => "TSObjectKeyword",
+    TsKeywordTypeKind::TsBooleanKeyword => "TSBooleanKeyword",
+    TsKeywordTypeKind::TsBigIntKeyword => "TSBigIntKeyword",
+    TsKeywordTypeKind::TsStringKeyword => "TSStringKeyword",
+    TsKeywordTypeKind::TsSymbolKeyword => "TSSymbolKeyword",
+    TsKeywordTypeKind::TsVoidKeyword => "TSVoidKeyword",
+    TsKeywordTypeKind::TsUndefinedKeyword => "TSUndefinedKeyword",
+    TsKeywordTypeKind::TsNullKeyword => "TSNullKeyword",
+    TsKeywordTypeKind::TsNeverKeyword => "TSNeverKeyword",
+    TsKeywordTypeKind::TsIntrinsicKeyword => "TSIntrinsicKeyword",
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+
+  use super::*;
+
+  fn parse(text: &str, media_type: MediaType) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(media_type)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  /// Gets the ESTree of the statements of a module without positions.
+  fn body_shape(text: &str, media_type: MediaType) -> Value {
+    let mut value = program_to_estree_json(
+      &parse(text, media_type),
+      EstreeOptions::default(),
+    );
+    strip_positions(&mut value);
+    value["body"].take()
+  }
+
+  fn strip_positions(value: &mut Value) {
+    match value {
+      Value::Object(map) => {
+        map.remove("start");
+        map.remove("end");
+        map.values_mut().for_each(strip_positions);
+      }
+      Value::Array(values) => values.iter_mut().for_each(strip_positions),
+      _ => {}
+    }
+  }
+
+  #[test]
+  fn positions_and_loc() {
+    let parsed_source = parse("let a = 'é';\nfoo?.(a);", MediaType::JavaScript);
+    let value =
+      program_to_estree_json(&parsed_source, EstreeOptions { loc: true });
+    assert_eq!(value["type"], "Program");
+    assert_eq!(value["sourceType"], "module");
+    assert_eq!(value["start"], 0);
+    assert_eq!(value["end"], 23);
+    let var_decl = &value["body"][0];
+    assert_eq!(var_decl["type"], "VariableDeclaration");
+    assert_eq!(var_decl["start"], 0);
+    // byte index after the two byte character
+    assert_eq!(var_decl["end"], 13);
+    // UTF-16 column after the character
+    assert_eq!(
+      var_decl["loc"],
+      json!({
+        "start": { "line": 1, "column": 0 },
+        "end": { "line": 1, "column": 12 },
+      })
+    );
+    let expr_stmt = &value["body"][1];
+    assert_eq!(expr_stmt["type"], "ExpressionStatement");
+    assert_eq!(expr_stmt["start"], 14);
+    assert_eq!(expr_stmt["end"], 23);
+    assert_eq!(
+      expr_stmt["loc"],
+      json!({
+        "start": { "line": 2, "column": 0 },
+        "end": { "line": 2, "column": 9 },
+      })
+    );
+    let chain = &expr_stmt["expression"];
+    assert_eq!(chain["type"], "ChainExpression");
+    assert_eq!(chain["start"], 14);
+    assert_eq!(chain["end"], 22);
+    assert_eq!(chain["expression"]["type"], "CallExpression");
+    assert_eq!(chain["expression"]["optional"], true);
+
+    // loc is excluded by default
+    let value =
+      program_to_estree_json(&parsed_source, EstreeOptions::default());
+    assert_eq!(value["body"][0].get("loc"), None);
+  }
+
+  #[test]
+  fn classes() {
+    let text =
+      "class A extends B { #x = 1; static get y() { return this.#x; } }";
+    let value = program_to_estree_json(
+      &parse(text, MediaType::JavaScript),
+      EstreeOptions::default(),
+    );
+    let class_body = &value["body"][0]["body"];
+    assert_eq!(class_body["start"], text.find('{').unwrap());
+    assert_eq!(class_body["end"], text.len());
+    assert_eq!(
+      class_body["body"][1]["value"]["start"],
+      text.find("()").unwrap()
+    );
+
+    assert_eq!(
+      body_shape(text, MediaType::JavaScript),
+      json!([{
+        "type": "ClassDeclaration",
+        "id": { "type": "Identifier", "name": "A" },
+        "superClass": { "type": "Identifier", "name": "B" },
+        "body": {
+          "type": "ClassBody",
+          "body": [{
+            "type": "PropertyDefinition",
+            "key": { "type": "PrivateIdentifier", "name": "x" },
+            "value": { "type": "Literal", "value": 1, "raw": "1" },
+            "computed": false,
+            "static": false,
+          }, {
+            "type": "MethodDefinition",
+            "key": { "type": "Identifier", "name": "y" },
+            "value": {
+              "type": "FunctionExpression",
+              "id": null,
+              "params": [],
+              "body": {
+                "type": "BlockStatement",
+                "body": [{
+                  "type": "ReturnStatement",
+                  "argument": {
+                    "type": "MemberExpression",
+                    "object": { "type": "ThisExpression" },
+                    "property": { "type": "PrivateIdentifier", "name": "x" },
+                    "computed": false,
+                    "optional": false,
+                  },
+                }],
+              },
+              "async": false,
+              "generator": false,
+              "expression": false,
+            },
+            "kind": "get",
+            "computed": false,
+            "static": true,
+          }],
+        },
+      }])
+    );
+  }
+
+  #[test]
+  fn optional_chaining() {
+    assert_eq!(
+      body_shape("a?.b.c; a?.[0]?.(1);", MediaType::JavaScript),
+      json!([{
+        "type": "ExpressionStatement",
+        "expression": {
+          "type": "ChainExpression",
+          "expression": {
+            "type": "MemberExpression",
+            "object": {
+              "type": "MemberExpression",
+              "object": { "type": "Identifier", "name": "a" },
+              "property": { "type": "Identifier", "name": "b" },
+              "computed": false,
+              "optional": true,
+            },
+            "property": { "type": "Identifier", "name": "c" },
+            "computed": false,
+            "optional": false,
+          },
+        },
+      }, {
+        "type": "ExpressionStatement",
+        "expression": {
+          "type": "ChainExpression",
+          "expression": {
+            "type": "CallExpression",
+            "callee": {
+              "type": "MemberExpression",
+              "object": { "type": "Identifier", "name": "a" },
+              "property": { "type": "Literal", "value": 0, "raw": "0" },
+              "computed": true,
+              "optional": true,
+            },
+            "arguments": [{ "type": "Literal", "value": 1, "raw": "1" }],
+            "optional": true,
+          },
+        },
+      }])
+    );
+  }
+
+  #[test]
+  fn jsx() {
+    let name = json!({
+      "type": "JSXMemberExpression",
+      "object": { "type": "JSXIdentifier", "name": "A" },
+      "property": { "type": "JSXIdentifier", "name": "B" },
+    });
+    assert_eq!(
+      body_shape(
+        "<A.B x=\"1\" {...y}>{z}t</A.B>;\n<>{/* c */}</>;",
+        MediaType::Tsx
+      ),
+      json!([{
+        "type": "ExpressionStatement",
+        "expression": {
+          "type": "JSXElement",
+          "openingElement": {
+            "type": "JSXOpeningElement",
+            "name": name,
+            "attributes": [{
+              "type": "JSXAttribute",
+              "name": { "type": "JSXIdentifier", "name": "x" },
+              "value": { "type": "Literal", "value": "1", "raw": "\"1\"" },
+            }, {
+              "type": "JSXSpreadAttribute",
+              "argument": { "type": "Identifier", "name": "y" },
+            }],
+            "selfClosing": false,
+          },
+          "closingElement": { "type": "JSXClosingElement", "name": name },
+          "children": [{
+            "type": "JSXExpressionContainer",
+            "expression": { "type": "Identifier", "name": "z" },
+          }, {
+            "type": "JSXText",
+            "value": "t",
+            "raw": "t",
+          }],
+        },
+      }, {
+        "type": "ExpressionStatement",
+        "expression": {
+          "type": "JSXFragment",
+          "openingFragment": { "type": "JSXOpeningFragment" },
+          "closingFragment": { "type": "JSXClosingFragment" },
+          "children": [{
+            "type": "JSXExpressionContainer",
+            "expression": { "type": "JSXEmptyExpression" },
+          }],
+        },
+      }])
+    );
+  }
+
+  #[test]
+  fn typescript() {
+    let type_ref = json!({
+      "type": "TSTypeReference",
+      "typeName": { "type": "Identifier", "name": "T" },
+      "typeArguments": null,
+    });
+    let number_type_ann = json!({
+      "type": "TSTypeAnnotation",
+      "typeAnnotation": { "type": "TSNumberKeyword" },
+    });
+    assert_eq!(
+      body_shape(
+        concat!(
+          "interface I { a?: string; m(x: number): void; }\n",
+          "type U = keyof T | T[];\n",
+          "let x: number = 1 as const;",
+        ),
+        MediaType::TypeScript
+      ),
+      json!([{
+        "type": "TSInterfaceDeclaration",
+        "id": { "type": "Identifier", "name": "I" },
+        "body": {
+          "type": "TSInterfaceBody",
+          "body": [{
+            "type": "TSPropertySignature",
+            "key": { "type": "Identifier", "name": "a" },
+            "computed": false,
+            "optional": true,
+            "readonly": false,
+            "typeAnnotation": {
+              "type": "TSTypeAnnotation",
+              "typeAnnotation": { "type": "TSStringKeyword" },
+            },
+          }, {
+            "type": "TSMethodSignature",
+            "key": { "type": "Identifier", "name": "m" },
+            "kind": "method",
+            "computed": false,
+            "optional": false,
+            "params": [{
+              "type": "Identifier",
+              "name": "x",
+              "typeAnnotation": number_type_ann,
+            }],
+            "returnType": {
+              "type": "TSTypeAnnotation",
+              "typeAnnotation": { "type": "TSVoidKeyword" },
+            },
+            "typeParameters": null,
+          }],
+        },
+        "extends": [],
+        "typeParameters": null,
+        "declare": false,
+      }, {
+        "type": "TSTypeAliasDeclaration",
+        "id": { "type": "Identifier", "name": "U" },
+        "typeAnnotation": {
+          "type": "TSUnionType",
+          "types": [{
+            "type": "TSTypeOperator",
+            "operator": "keyof",
+            "typeAnnotation": type_ref,
+          }, {
+            "type": "TSArrayType",
+            "elementType": type_ref,
+          }],
+        },
+        "typeParameters": null,
+        "declare": false,
+      }, {
+        "type": "VariableDeclaration",
+        "declarations": [{
+          "type": "VariableDeclarator",
+          "id": {
+            "type": "Identifier",
+            "name": "x",
+            "typeAnnotation": number_type_ann,
+          },
+          "init": {
+            "type": "TSAsExpression",
+            "expression": { "type": "Literal", "value": 1, "raw": "1" },
+            "typeAnnotation": {
+              "type": "TSTypeReference",
+              "typeName": { "type": "Identifier", "name": "const" },
+              "typeArguments": null,
+            },
+          },
+        }],
+        "kind": "let",
+      }])
+    );
+  }
+
+  #[test]
+  fn imports_and_exports() {
+    let c = json!({ "type": "Identifier", "name": "c" });
+    assert_eq!(
+      body_shape(
+        concat!(
+          "import a, { b as c } from './d.ts';\n",
+          "export * as ns from './e.ts';\n",
+          "export { c };",
+        ),
+        MediaType::TypeScript
+      ),
+      json!([{
+        "type": "ImportDeclaration",
+        "specifiers": [{
+          "type": "ImportDefaultSpecifier",
+          "local": { "type": "Identifier", "name": "a" },
+        }, {
+          "type": "ImportSpecifier",
+          "imported": { "type": "Identifier", "name": "b" },
+          "local": c,
+        }],
+        "source": { "type": "Literal", "value": "./d.ts", "raw": "'./d.ts'" },
+        "attributes": [],
+      }, {
+        "type": "ExportAllDeclaration",
+        "exported": { "type": "Identifier", "name": "ns" },
+        "source": { "type": "Literal", "value": "./e.ts", "raw": "'./e.ts'" },
+        "attributes": [],
+      }, {
+        "type": "ExportNamedDeclaration",
+        "declaration": null,
+        "specifiers": [{
+          "type": "ExportSpecifier",
+          "local": c,
+          "exported": c,
+        }],
+        "source": null,
+        "attributes": [],
+      }])
+    );
+  }
+
+  #[test]
+  fn unsupported_fallback() {
+    let parsed_source = parse("value;", MediaType::JavaScript);
+    let tokens = parsed_source.tokens_or_lex();
+    let converter = Converter {
+      text_info: parsed_source.text_info(),
+      tokens: &tokens,
+      options: EstreeOptions::default(),
+    };
+    let ident = parsed_source.module().body[0]
+      .as_stmt()
+      .and_then(|stmt| stmt.as_expr())
+      .and_then(|expr_stmt| expr_stmt.expr.as_ident())
+      .unwrap();
+    let expr = Expr::Invalid(Invalid { span: ident.span });
+    assert_eq!(
+      converter.expr(&expr),
+      json!({ "type": "Unsupported", "kind": "Invalid", "start": 0, "end": 5 })
+    );
+  }
+}
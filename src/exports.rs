@@ -0,0 +1,481 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::swc::ast::*;
+use crate::swc::atoms::Atom;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+
+/// Kind of export found by `ParsedSource::exports()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+  /// ex. `export const a = 1;` or `export { a as b };`
+  Named,
+  /// ex. `export default a;` or `export { a as default };`
+  Default,
+  /// ex. `export * as ns from "./x.ts";`
+  Namespace,
+  /// ex. `export * from "./x.ts";`, which exports the names of
+  /// another module.
+  All,
+  /// ex. `export = a;` in TypeScript.
+  Equals,
+}
+
+/// An export found by `ParsedSource::exports()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportInfo {
+  /// Exported name, which is `None` for `export * from` and `export =`.
+  pub name: Option<Atom>,
+  pub kind: ExportKind,
+  /// Whether the export is only a type (ex. `export type { T }` or
+  /// `export interface T {}`).
+  pub is_type_only: bool,
+  /// Name in this module or in the re-exported module when it's
+  /// different from the exported name (ex. `a` in `export { a as b }`).
+  pub local_name: Option<Atom>,
+  /// Specifier of the module for re-exports.
+  pub specifier: Option<Atom>,
+  /// Range of the declaration for exported declarations. Otherwise, the
+  /// range of the export specifier or statement.
+  pub range: SourceRange,
+}
+
+impl ParsedSource {
+  /// Gets the exports of the module in source order.
+  ///
+  /// This is a static analysis of the export declarations, so it doesn't
+  /// include the names of modules re-exported with `export * from`.
+  pub fn exports(&self) -> Vec<ExportInfo> {
+    match self.program_ref() {
+      Program::Module(module) => module_exports(module),
+      Program::Script(_) => Vec::new(),
+    }
+  }
+}
+
+fn module_exports(module: &Module) -> Vec<ExportInfo> {
+  let mut exports = Vec::new();
+  for item in &module.body {
+    let ModuleItem::ModuleDecl(module_decl) = item else {
+      continue;
+    };
+    match module_decl {
+      ModuleDecl::ExportDecl(export_decl) => {
+        add_decl_exports(&mut exports, &export_decl.decl);
+      }
+      ModuleDecl::ExportNamed(named_export) => {
+        add_named_exports(&mut exports, named_export);
+      }
+      ModuleDecl::ExportDefaultDecl(default_decl) => {
+        let (local_name, is_type_only) = match &default_decl.decl {
+          DefaultDecl::Class(class_expr) => (class_expr.ident.as_ref(), false),
+          DefaultDecl::Fn(fn_expr) => (fn_expr.ident.as_ref(), false),
+          DefaultDecl::TsInterfaceDecl(decl) => (Some(&decl.id), true),
+        };
+        exports.push(ExportInfo {
+          name: Some("default".into()),
+          kind: ExportKind::Default,
+          is_type_only,
+          local_name: local_name.map(|ident| ident.sym.clone()),
+          specifier: None,
+          range: default_decl.decl.range(),
+        });
+      }
+      ModuleDecl::ExportDefaultExpr(default_expr) => {
+        exports.push(ExportInfo {
+          name: Some("default".into()),
+          kind: ExportKind::Default,
+          is_type_only: false,
+          local_name: expr_ident_name(&default_expr.expr),
+          specifier: None,
+          range: default_expr.range(),
+        });
+      }
+      ModuleDecl::ExportAll(export_all) => {
+        exports.push(ExportInfo {
+          name: None,
+          kind: ExportKind::All,
+          is_type_only: export_all.type_only,
+          local_name: None,
+          specifier: Some(export_all.src.value.clone()),
+          range: export_all.range(),
+        });
+      }
+      ModuleDecl::TsExportAssignment(export_assignment) => {
+        exports.push(ExportInfo {
+          name: None,
+          kind: ExportKind::Equals,
+          is_type_only: false,
+          local_name: expr_ident_name(&export_assignment.expr),
+          specifier: None,
+          range: export_assignment.range(),
+        });
+      }
+      ModuleDecl::TsImportEquals(import_equals) if import_equals.is_export => {
+        exports.push(ExportInfo {
+          name: Some(import_equals.id.sym.clone()),
+          kind: ExportKind::Named,
+          is_type_only: import_equals.is_type_only,
+          local_name: None,
+          specifier: match &import_equals.module_ref {
+            TsModuleRef::TsExternalModuleRef(module_ref) => {
+              Some(module_ref.expr.value.clone())
+            }
+            TsModuleRef::TsEntityName(_) => None,
+          },
+          range: import_equals.range(),
+        });
+      }
+      // `export as namespace` declares a global rather than an export
+      ModuleDecl::TsNamespaceExport(_)
+      | ModuleDecl::TsImportEquals(_)
+      | ModuleDecl::Import(_) => {}
+    }
+  }
+  exports
+}
+
+fn add_decl_exports(exports: &mut Vec<ExportInfo>, decl: &Decl) {
+  let mut add = |ident: &Ident, is_type_only: bool, range: SourceRange| {
+    exports.push(ExportInfo {
+      name: Some(ident.sym.clone()),
+      kind: ExportKind::Named,
+      is_type_only,
+      local_name: None,
+      specifier: None,
+      range,
+    });
+  };
+  match decl {
+    Decl::Class(class_decl) => add(&class_decl.ident, false, decl.range()),
+    Decl::Fn(fn_decl) => add(&fn_decl.ident, false, decl.range()),
+    Decl::Var(var_decl) => {
+      for declarator in &var_decl.decls {
+        let mut idents = Vec::new();
+        collect_pat_idents(&declarator.name, &mut idents);
+        for ident in idents {
+          add(ident, false, declarator.range());
+        }
+      }
+    }
+    Decl::TsInterface(interface_decl) => {
+      add(&interface_decl.id, true, decl.range())
+    }
+    Decl::TsTypeAlias(type_alias_decl) => {
+      add(&type_alias_decl.id, true, decl.range())
+    }
+    Decl::TsEnum(enum_decl) => add(&enum_decl.id, false, decl.range()),
+    Decl::TsModule(module_decl) => match &module_decl.id {
+      TsModuleName::Ident(ident) => add(ident, false, decl.range()),
+      TsModuleName::Str(_) => {}
+    },
+    // using declarations can't be exported
+    Decl::Using(_) => {}
+  }
+}
+
+fn add_named_exports(
+  exports: &mut Vec<ExportInfo>,
+  named_export: &NamedExport,
+) {
+  let specifier = named_export.src.as_ref().map(|src| src.value.clone());
+  for export_specifier in &named_export.specifiers {
+    let range = export_specifier.range();
+    match export_specifier {
+      ExportSpecifier::Named(named) => {
+        let local_name = module_export_name(&named.orig);
+        let name = named
+          .exported
+          .as_ref()
+          .map(module_export_name)
+          .unwrap_or_else(|| local_name.clone());
+        exports.push(ExportInfo {
+          kind: if &*name == "default" {
+            ExportKind::Default
+          } else {
+            ExportKind::Named
+          },
+          is_type_only: named_export.type_only || named.is_type_only,
+          local_name: (local_name != name).then_some(local_name),
+          name: Some(name),
+          specifier: specifier.clone(),
+          range,
+        });
+      }
+      ExportSpecifier::Namespace(namespace) => {
+        exports.push(ExportInfo {
+          name: Some(module_export_name(&namespace.name)),
+          kind: ExportKind::Namespace,
+          is_type_only: named_export.type_only,
+          local_name: None,
+          specifier: specifier.clone(),
+          range,
+        });
+      }
+      // ex. `export a from "./x.ts";` from the export default from proposal
+      ExportSpecifier::Default(default) => {
+        exports.push(ExportInfo {
+          name: Some(default.exported.sym.clone()),
+          kind: ExportKind::Named,
+          is_type_only: named_export.type_only,
+          local_name: Some("default".into()),
+          specifier: specifier.clone(),
+          range,
+        });
+      }
+    }
+  }
+}
+
+fn module_export_name(name: &ModuleExportName) -> Atom {
+  match name {
+    ModuleExportName::Ident(ident) => ident.sym.clone(),
+    ModuleExportName::Str(string) => string.value.clone(),
+  }
+}
+
+fn expr_ident_name(expr: &Expr) -> Option<Atom> {
+  match expr {
+    Expr::Ident(ident) => Some(ident.sym.clone()),
+    _ => None,
+  }
+}
+
+fn collect_pat_idents<'a>(pat: &'a Pat, idents: &mut Vec<&'a Ident>) {
+  match pat {
+    Pat::Ident(binding_ident) => idents.push(&binding_ident.id),
+    Pat::Array(array_pat) => {
+      for elem in array_pat.elems.iter().flatten() {
+        collect_pat_idents(elem, idents);
+      }
+    }
+    Pat::Object(object_pat) => {
+      for prop in &object_pat.props {
+        match prop {
+          ObjectPatProp::KeyValue(key_value) => {
+            collect_pat_idents(&key_value.value, idents);
+          }
+          ObjectPatProp::Assign(assign) => idents.push(&assign.key),
+          ObjectPatProp::Rest(rest) => collect_pat_idents(&rest.arg, idents),
+        }
+      }
+    }
+    Pat::Rest(rest_pat) => collect_pat_idents(&rest_pat.arg, idents),
+    Pat::Assign(assign_pat) => collect_pat_idents(&assign_pat.left, idents),
+    Pat::Invalid(_) | Pat::Expr(_) => {}
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[derive(Debug, PartialEq)]
+  struct Export {
+    name: Option<String>,
+    kind: ExportKind,
+    is_type_only: bool,
+    local_name: Option<String>,
+    specifier: Option<String>,
+    text: String,
+  }
+
+  fn named(name: &str, text: &str) -> Export {
+    Export {
+      name: Some(name.to_string()),
+      kind: ExportKind::Named,
+      is_type_only: false,
+      local_name: None,
+      specifier: None,
+      text: text.to_string(),
+    }
+  }
+
+  fn get_exports(text: &str) -> Vec<Export> {
+    let parsed_source = parse(text);
+    let to_string = |atom: Option<Atom>| atom.map(|atom| atom.to_string());
+    parsed_source
+      .exports()
+      .into_iter()
+      .map(|export| Export {
+        name: to_string(export.name),
+        kind: export.kind,
+        is_type_only: export.is_type_only,
+        local_name: to_string(export.local_name),
+        specifier: to_string(export.specifier),
+        text: parsed_source.text_for_range(&export.range).to_string(),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn declarations() {
+    assert_eq!(
+      get_exports(concat!(
+        "export const a = 1, { b, c: [d] } = e;\n",
+        "export function f() {}\n",
+        "export class G {}\n",
+        "export enum H {}\n",
+        "export namespace I {}\n",
+        "export interface J {}\n",
+        "export type K = string\n",
+      )),
+      vec![
+        named("a", "a = 1"),
+        named("b", "{ b, c: [d] } = e"),
+        named("d", "{ b, c: [d] } = e"),
+        named("f", "function f() {}"),
+        named("G", "class G {}"),
+        named("H", "enum H {}"),
+        named("I", "namespace I {}"),
+        Export {
+          is_type_only: true,
+          ..named("J", "interface J {}")
+        },
+        Export {
+          is_type_only: true,
+          ..named("K", "type K = string")
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn specifiers() {
+    assert_eq!(
+      get_exports(concat!(
+        "const a = 1, c = 2;\n",
+        "type T = string;\n",
+        "export { a as b, c };\n",
+        "export { c as default };\n",
+        "export type { T };\n",
+        "export { type T as U } from './y.ts';\n",
+        "export * as ns from './x.ts';\n",
+        "export * from './z.ts'\n",
+        "export type * from './types.ts'\n",
+      )),
+      vec![
+        Export {
+          local_name: Some("a".to_string()),
+          ..named("b", "a as b")
+        },
+        named("c", "c"),
+        Export {
+          kind: ExportKind::Default,
+          local_name: Some("c".to_string()),
+          ..named("default", "c as default")
+        },
+        Export {
+          is_type_only: true,
+          ..named("T", "T")
+        },
+        Export {
+          is_type_only: true,
+          local_name: Some("T".to_string()),
+          specifier: Some("./y.ts".to_string()),
+          ..named("U", "type T as U")
+        },
+        Export {
+          kind: ExportKind::Namespace,
+          specifier: Some("./x.ts".to_string()),
+          ..named("ns", "* as ns")
+        },
+        Export {
+          name: None,
+          kind: ExportKind::All,
+          specifier: Some("./z.ts".to_string()),
+          ..named("", "export * from './z.ts'")
+        },
+        Export {
+          name: None,
+          kind: ExportKind::All,
+          is_type_only: true,
+          specifier: Some("./types.ts".to_string()),
+          ..named("", "export type * from './types.ts'")
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn default_exports() {
+    let default = |local_name: Option<&str>, text| Export {
+      kind: ExportKind::Default,
+      local_name: local_name.map(|name| name.to_string()),
+      ..named("default", text)
+    };
+    assert_eq!(
+      get_exports("export default function name() {}"),
+      vec![default(Some("name"), "function name() {}")]
+    );
+    assert_eq!(
+      get_exports("export default class {}"),
+      vec![default(None, "class {}")]
+    );
+    assert_eq!(
+      get_exports("export default interface A {}"),
+      vec![Export {
+        is_type_only: true,
+        ..default(Some("A"), "interface A {}")
+      }]
+    );
+    assert_eq!(
+      get_exports("const a = 1;\nexport default a"),
+      vec![default(Some("a"), "export default a")]
+    );
+  }
+
+  #[test]
+  fn export_equals() {
+    assert_eq!(
+      get_exports("const a = 1;\nexport = a"),
+      vec![Export {
+        name: None,
+        kind: ExportKind::Equals,
+        local_name: Some("a".to_string()),
+        ..named("", "export = a")
+      }]
+    );
+    assert_eq!(
+      get_exports("export import a = require('./a.ts')"),
+      vec![Export {
+        specifier: Some("./a.ts".to_string()),
+        ..named("a", "export import a = require('./a.ts')")
+      }]
+    );
+  }
+
+  #[test]
+  fn script() {
+    let parsed_source = crate::parse_script(
+      ParseParams::builder(
+        "file:///script.js",
+        SourceTextInfo::from_string("var a = 1;".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.exports(), Vec::new());
+  }
+}
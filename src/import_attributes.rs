@@ -0,0 +1,141 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::swc::ast::ModuleDecl;
+use crate::swc::ast::ModuleItem;
+use crate::swc::ast::Program;
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+
+/// How to handle the legacy `assert` keyword for import attributes
+/// (ex. `import data from "./data.json" assert { type: "json" };`).
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAttributesMode {
+  /// Allow both the `with` and `assert` keywords.
+  #[default]
+  AllowBoth,
+  /// Only allow the `with` keyword. Using the `assert` keyword
+  /// causes an error.
+  WithOnly,
+  /// Allow both keywords, but surface a non-fatal diagnostic when
+  /// using the `assert` keyword.
+  AssertDeprecatedWarning,
+}
+
+/// Gets the ranges of any `assert` keywords used for import attributes
+/// in static imports and exports, and in the options provided to
+/// dynamic imports (ex. `import("./data.json", { assert: { ... } })`).
+pub(crate) fn get_import_assert_ranges(
+  program: &Program,
+  tokens: &[TokenAndSpan],
+  source: &SourceTextInfo,
+) -> Vec<SourceRange> {
+  let is_word = |token: &TokenAndSpan, text: &str| {
+    matches!(token.token, Token::Word(_))
+      && source.range_text(&token.range()) == text
+  };
+  let is_assert_key = |token: &TokenAndSpan| {
+    matches!(token.token, Token::Word(_) | Token::Str { .. })
+      && source
+        .range_text(&token.range())
+        .trim_matches(|c| c == '"' || c == '\'')
+        == "assert"
+  };
+  let static_src_ends = get_static_src_ends_with_attributes(program);
+  let mut ranges = Vec::new();
+  for (i, token) in tokens.iter().enumerate() {
+    if matches!(token.token, Token::Str { .. })
+      && static_src_ends.contains(&token.end())
+    {
+      if let Some(next) = tokens.get(i + 1) {
+        if is_word(next, "assert") {
+          ranges.push(next.range());
+        }
+      }
+    } else if is_word(token, "import")
+      && tokens.get(i + 1).map(|t| &t.token) == Some(&Token::LParen)
+    {
+      if let Some(key) =
+        get_dynamic_import_options_assert_key(&tokens[i + 2..], is_assert_key)
+      {
+        ranges.push(key.range());
+      }
+    }
+  }
+  ranges
+}
+
+/// Gets the end positions of the specifiers of the top level imports
+/// and re-exports that have import attributes.
+fn get_static_src_ends_with_attributes(
+  program: &Program,
+) -> HashSet<SourcePos> {
+  let Program::Module(module) = program else {
+    return HashSet::new();
+  };
+  module
+    .body
+    .iter()
+    .filter_map(|item| match item {
+      ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) => {
+        decl.with.as_ref().map(|_| decl.src.end())
+      }
+      ModuleItem::ModuleDecl(ModuleDecl::ExportAll(decl)) => {
+        decl.with.as_ref().map(|_| decl.src.end())
+      }
+      ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(decl)) => decl
+        .with
+        .as_ref()
+        .and(decl.src.as_ref())
+        .map(|src| src.end()),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Gets the `assert` key of the options object provided as the second
+/// argument to a dynamic import. The tokens start after the `(`.
+fn get_dynamic_import_options_assert_key(
+  tokens: &[TokenAndSpan],
+  is_assert_key: impl Fn(&TokenAndSpan) -> bool,
+) -> Option<&TokenAndSpan> {
+  let mut depth = 0;
+  for (i, token) in tokens.iter().enumerate() {
+    match token.token {
+      Token::LParen | Token::LBracket | Token::LBrace | Token::DollarLBrace => {
+        depth += 1
+      }
+      Token::RParen | Token::RBracket | Token::RBrace => {
+        if depth == 0 {
+          return None;
+        }
+        depth -= 1;
+      }
+      Token::Comma if depth == 0 => {
+        return match tokens.get(i + 1..i + 4) {
+          Some([open_brace, key, colon])
+            if open_brace.token == Token::LBrace
+              && is_assert_key(key)
+              && colon.token == Token::Colon =>
+          {
+            Some(key)
+          }
+          _ => None,
+        };
+      }
+      _ => {}
+    }
+  }
+  None
+}
@@ -0,0 +1,314 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::comments::MultiThreadedComments;
+use crate::swc::ast::ArrayLit;
+use crate::swc::ast::Expr;
+use crate::swc::ast::Lit;
+use crate::swc::ast::ObjectLit;
+use crate::swc::ast::Prop;
+use crate::swc::ast::PropName;
+use crate::swc::ast::PropOrSpread;
+use crate::swc::ast::UnaryOp;
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::Diagnostic;
+use crate::DiagnosticKind;
+use crate::DiagnosticSeverity;
+use crate::RelatedInformation;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+
+/// Kind of JSON diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonDiagnosticKind {
+  /// A comment, which JSON does not support.
+  Comment,
+  /// A trailing comma in an object or array, which JSON does not support.
+  TrailingComma,
+  /// A key that appears more than once in the same object.
+  ///
+  /// This is a non-fatal diagnostic because the last value wins.
+  DuplicateKey {
+    /// Value of the key.
+    key: String,
+  },
+  /// Syntax that is valid JavaScript, but not valid JSON (ex. single
+  /// quoted strings or unquoted keys).
+  UnsupportedSyntax,
+}
+
+impl JsonDiagnosticKind {
+  /// Message text of the JSON diagnostic kind.
+  pub fn msg(&self) -> Cow<str> {
+    match self {
+      JsonDiagnosticKind::Comment => {
+        Cow::Borrowed("Comments are not allowed in JSON")
+      }
+      JsonDiagnosticKind::TrailingComma => {
+        Cow::Borrowed("Trailing commas are not allowed in JSON")
+      }
+      JsonDiagnosticKind::DuplicateKey { key } => {
+        Cow::Owned(format!("Duplicate key '{}' in JSON object", key))
+      }
+      JsonDiagnosticKind::UnsupportedSyntax => {
+        Cow::Borrowed("Syntax is not allowed in JSON")
+      }
+    }
+  }
+
+  /// Gets if the diagnostic prevents the source from being used as JSON.
+  pub fn is_fatal(&self) -> bool {
+    !matches!(self, JsonDiagnosticKind::DuplicateKey { .. })
+  }
+}
+
+/// Gets the diagnostics for anything in the parsed expression that is
+/// not allowed in JSON, sorted by position.
+pub(crate) fn get_json_diagnostics(
+  specifier: &str,
+  source: &SourceTextInfo,
+  expr: &Expr,
+  comments: &MultiThreadedComments,
+  tokens: &[TokenAndSpan],
+) -> Vec<Diagnostic> {
+  let mut collector = JsonDiagnosticsCollector {
+    specifier,
+    source,
+    diagnostics: Vec::new(),
+  };
+  for comment in comments.iter_unstable() {
+    collector.add(comment.range(), JsonDiagnosticKind::Comment);
+  }
+  for window in tokens.windows(2) {
+    if window[0].token == Token::Comma
+      && matches!(window[1].token, Token::RBrace | Token::RBracket)
+    {
+      collector.add(window[0].range(), JsonDiagnosticKind::TrailingComma);
+    }
+  }
+  collector.visit_expr(expr);
+  let mut diagnostics = collector.diagnostics;
+  diagnostics.sort_by_key(|d| d.range.start);
+  diagnostics
+}
+
+struct JsonDiagnosticsCollector<'a> {
+  specifier: &'a str,
+  source: &'a SourceTextInfo,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> JsonDiagnosticsCollector<'a> {
+  fn add(&mut self, range: SourceRange, kind: JsonDiagnosticKind) {
+    let diagnostic = self.create(range, kind);
+    self.diagnostics.push(diagnostic);
+  }
+
+  fn create(&self, range: SourceRange, kind: JsonDiagnosticKind) -> Diagnostic {
+    let severity = if kind.is_fatal() {
+      DiagnosticSeverity::Error
+    } else {
+      DiagnosticSeverity::Warning
+    };
+    Diagnostic::new(
+      self.specifier,
+      range,
+      DiagnosticKind::Json(kind),
+      self.source.clone(),
+    )
+    .with_severity(severity)
+  }
+
+  fn visit_expr(&mut self, expr: &Expr) {
+    let is_valid = match expr {
+      Expr::Object(obj) => {
+        self.visit_object(obj);
+        true
+      }
+      Expr::Array(array) => self.visit_array(array),
+      Expr::Lit(Lit::Str(_)) => {
+        is_json_string(self.source.range_text(&expr.range()))
+      }
+      Expr::Lit(Lit::Num(_)) => {
+        is_json_number(self.source.range_text(&expr.range()))
+      }
+      Expr::Lit(Lit::Bool(_) | Lit::Null(_)) => true,
+      Expr::Unary(unary) => {
+        unary.op == UnaryOp::Minus
+          && matches!(&*unary.arg, Expr::Lit(Lit::Num(_)))
+          && is_json_number(self.source.range_text(&expr.range()))
+      }
+      _ => false,
+    };
+    if !is_valid {
+      self.add(expr.range(), JsonDiagnosticKind::UnsupportedSyntax);
+    }
+  }
+
+  fn visit_object(&mut self, obj: &ObjectLit) {
+    let mut keys = HashMap::with_capacity(obj.props.len());
+    for prop in &obj.props {
+      let key_value = match prop {
+        PropOrSpread::Prop(prop) => match &**prop {
+          Prop::KeyValue(key_value) => key_value,
+          _ => {
+            self.add(prop.range(), JsonDiagnosticKind::UnsupportedSyntax);
+            continue;
+          }
+        },
+        PropOrSpread::Spread(spread) => {
+          self.add(spread.range(), JsonDiagnosticKind::UnsupportedSyntax);
+          continue;
+        }
+      };
+      match &key_value.key {
+        PropName::Str(key)
+          if is_json_string(self.source.range_text(&key.range())) =>
+        {
+          match keys.get(&*key.value) {
+            Some(first_range) => {
+              let diagnostic = self
+                .create(
+                  key.range(),
+                  JsonDiagnosticKind::DuplicateKey {
+                    key: key.value.to_string(),
+                  },
+                )
+                .with_related(RelatedInformation {
+                  message: "First declared here".to_string(),
+                  range: *first_range,
+                });
+              self.diagnostics.push(diagnostic);
+            }
+            None => {
+              keys.insert(&*key.value, key.range());
+            }
+          }
+        }
+        key => {
+          self.add(key.range(), JsonDiagnosticKind::UnsupportedSyntax);
+        }
+      }
+      self.visit_expr(&key_value.value);
+    }
+  }
+
+  /// Visits the elements of the array, returning `false` when the
+  /// array itself is not valid JSON (ex. it has holes).
+  fn visit_array(&mut self, array: &ArrayLit) -> bool {
+    let mut is_valid = true;
+    for elem in &array.elems {
+      match elem {
+        Some(elem) => match elem.spread {
+          Some(_) => {
+            self.add(elem.range(), JsonDiagnosticKind::UnsupportedSyntax)
+          }
+          None => self.visit_expr(&elem.expr),
+        },
+        None => is_valid = false,
+      }
+    }
+    is_valid
+  }
+}
+
+/// Gets if the text is a double quoted string that only uses
+/// escapes and characters allowed by JSON.
+fn is_json_string(text: &str) -> bool {
+  let Some(inner) = text
+    .strip_prefix('"')
+    .and_then(|text| text.strip_suffix('"'))
+  else {
+    return false;
+  };
+  let mut chars = inner.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') => {}
+        Some('u') => {
+          for _ in 0..4 {
+            if !chars.next().is_some_and(|c| c.is_ascii_hexdigit()) {
+              return false;
+            }
+          }
+        }
+        _ => return false,
+      },
+      '\u{0}'..='\u{1F}' => return false,
+      _ => {}
+    }
+  }
+  true
+}
+
+/// Gets if the text is a number as described by the JSON grammar
+/// (ex. no leading zeros, hex literals, or numeric separators).
+fn is_json_number(text: &str) -> bool {
+  fn skip_digits(bytes: &[u8], index: &mut usize) -> usize {
+    let start = *index;
+    while bytes.get(*index).is_some_and(|b| b.is_ascii_digit()) {
+      *index += 1;
+    }
+    *index - start
+  }
+
+  let bytes = text.as_bytes();
+  let mut index = 0;
+  if bytes.first() == Some(&b'-') {
+    index += 1;
+  }
+  match bytes.get(index) {
+    Some(b'0') => index += 1,
+    Some(b'1'..=b'9') => {
+      skip_digits(bytes, &mut index);
+    }
+    _ => return false,
+  }
+  if bytes.get(index) == Some(&b'.') {
+    index += 1;
+    if skip_digits(bytes, &mut index) == 0 {
+      return false;
+    }
+  }
+  if matches!(bytes.get(index), Some(b'e' | b'E')) {
+    index += 1;
+    if matches!(bytes.get(index), Some(b'+' | b'-')) {
+      index += 1;
+    }
+    if skip_digits(bytes, &mut index) == 0 {
+      return false;
+    }
+  }
+  index == bytes.len()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn json_strings() {
+    assert!(is_json_string(r#""""#));
+    assert!(is_json_string(r#""a\"b\\c\/\b\f\n\r\t\u00af""#));
+    assert!(!is_json_string("'a'"));
+    assert!(!is_json_string(r#""\x41""#));
+    assert!(!is_json_string(r#""\u00a""#));
+    assert!(!is_json_string(r#""\'""#));
+    assert!(!is_json_string("\"\t\""));
+  }
+
+  #[test]
+  fn json_numbers() {
+    for text in ["0", "-0", "1", "-12", "1.5", "0.5e10", "1E-5", "2e+3"] {
+      assert!(is_json_number(text), "{}", text);
+    }
+    for text in ["01", ".5", "5.", "0x10", "1_000", "- 1", "1e", "+1"] {
+      assert!(!is_json_number(text), "{}", text);
+    }
+  }
+}
@@ -3,6 +3,7 @@
 use std::rc::Rc;
 
 use crate::get_syntax;
+use crate::swc::ast::EsVersion;
 use crate::swc::atoms::Atom;
 use crate::swc::common::comments::Comment;
 use crate::swc::common::comments::CommentKind;
@@ -10,8 +11,11 @@ use crate::swc::common::comments::SingleThreadedComments;
 use crate::swc::common::input::StringInput;
 use crate::swc::parser::lexer::Lexer;
 use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::swc::parser::Syntax;
 use crate::MediaType;
 use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
 use crate::StartSourcePos;
 use crate::ES_VERSION;
 
@@ -65,6 +69,20 @@ pub fn lex(source: &str, media_type: MediaType) -> Vec<LexedItem> {
   tokens
 }
 
+/// Lexes the source text with the provided syntax and Ecmascript version.
+///
+/// Without a parser, the lexer can't tell a regular expression from a
+/// division or where a template literal ends, so the tokens may differ
+/// from the ones captured when parsing. Use `capture_tokens` when they
+/// need to be the same.
+pub(crate) fn lex_tokens(
+  text_info: &SourceTextInfo,
+  syntax: Syntax,
+  es_version: EsVersion,
+) -> Vec<TokenAndSpan> {
+  Lexer::new(syntax, es_version, text_info.as_string_input(), None).collect()
+}
+
 fn flatten_comments(
   comments: SingleThreadedComments,
 ) -> impl Iterator<Item = Comment> {
@@ -3,39 +3,142 @@
 #![deny(clippy::disallowed_methods)]
 #![deny(clippy::disallowed_types)]
 
+#[cfg(feature = "cache")]
+mod cache;
+mod char_index;
 #[cfg(feature = "cjs")]
 mod cjs_parse;
+#[cfg(feature = "cjs")]
+mod cjs_patterns;
 mod comments;
 #[cfg(feature = "dep_analysis")]
 pub mod dep;
+mod deprecations;
+mod diagnostics_collection;
+mod encoding;
+#[cfg(feature = "estree")]
+pub mod estree;
+mod exports;
+mod import_attributes;
+mod json;
 mod lexing;
+mod line_endings;
+mod lines;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+mod media_type;
+#[cfg(feature = "visit")]
+mod memory_usage;
+#[cfg(feature = "miette")]
+mod miette_report;
+mod parsed_expr;
 mod parsed_source;
+mod parsed_ts_type;
 mod parsing;
+#[cfg(feature = "visit")]
+mod program_diff;
+mod proposals;
+mod range_text;
+#[cfg(feature = "sarif")]
+pub mod sarif;
 #[cfg(feature = "scopes")]
 mod scopes;
+pub mod serialization;
+mod suppression;
 mod text_changes;
+mod text_diff;
+mod text_edits;
+mod text_info_map;
+mod text_provider;
+#[cfg(feature = "visit")]
+mod top_level_await;
 #[cfg(feature = "transpiling")]
 mod transpiling;
+mod triple_slash;
+mod ts_directives;
 mod types;
+#[cfg(feature = "visit")]
+mod unresolved_identifiers;
+mod utf16;
 
 #[cfg(feature = "view")]
 pub use dprint_swc_ext::view;
 
 pub use dprint_swc_ext::common::*;
 
+#[cfg(feature = "cache")]
+pub use cache::CacheError;
+pub use char_index::CharIndexMap;
+pub use char_index::SourceTextInfoCharIndex;
 #[cfg(feature = "cjs")]
 pub use cjs_parse::*;
+#[cfg(feature = "cjs")]
+pub use cjs_patterns::*;
 pub use comments::*;
 pub use deno_media_type::*;
+pub use deprecations::DeprecatedSyntax;
+pub use diagnostics_collection::*;
+pub use encoding::*;
+pub use exports::ExportInfo;
+pub use exports::ExportKind;
+pub use import_attributes::ImportAttributesMode;
+pub use json::JsonDiagnosticKind;
 pub use lexing::*;
+pub use line_endings::LineEndingMap;
+pub use line_endings::SourceTextInfoLineEndings;
+pub use lines::Indentation;
+pub use lines::SourceTextInfoLines;
+pub use media_type::detect_media_type_from_content;
+pub use media_type::is_declaration_media_type;
+pub use media_type::is_emittable_media_type;
+pub use media_type::is_javascript_like_media_type;
+pub use media_type::is_jsx_media_type;
+pub use media_type::is_typed_media_type;
+pub use media_type::media_type_content_type;
+pub use media_type::media_type_extension;
+pub use media_type::media_type_for_output;
+pub use media_type::media_type_from_content_type;
+pub use media_type::media_type_from_name;
+pub use media_type::media_type_from_path;
+pub use media_type::media_type_from_path_with_package_type;
+pub use media_type::media_type_from_specifier;
+#[cfg(feature = "module_specifier")]
+pub use media_type::media_type_from_specifier_and_headers;
+pub use media_type::media_type_name;
+pub use media_type::try_media_type_from_name;
+pub use media_type::MediaTypeResolver;
+pub use media_type::PackageJsonType;
+pub use media_type::RegisterExtensionError;
+#[cfg(feature = "visit")]
+pub use memory_usage::MemoryUsage;
+#[cfg(feature = "miette")]
+pub use miette_report::*;
+pub use parsed_expr::*;
 pub use parsed_source::*;
+pub use parsed_ts_type::*;
 pub use parsing::*;
+#[cfg(feature = "visit")]
+pub use program_diff::diff_programs;
+#[cfg(feature = "visit")]
+pub use program_diff::ProgramDiff;
+pub use proposals::SyntaxProposal;
+pub use range_text::SourceTextInfoRangeText;
 #[cfg(feature = "scopes")]
 pub use scopes::*;
+pub use suppression::*;
 pub use text_changes::*;
+pub use text_diff::diff_texts;
+pub use text_edits::*;
+pub use text_info_map::*;
+pub use text_provider::*;
 #[cfg(feature = "transpiling")]
 pub use transpiling::*;
+pub use triple_slash::*;
+pub use ts_directives::*;
 pub use types::*;
+#[cfg(feature = "visit")]
+pub use unresolved_identifiers::UnresolvedRef;
+pub use utf16::SourceTextInfoUtf16;
 
 #[cfg(feature = "module_specifier")]
 pub type ModuleSpecifier = url::Url;
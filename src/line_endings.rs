@@ -0,0 +1,151 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::ops::Range;
+
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+
+/// Creation of source text with normalized line endings.
+pub trait SourceTextInfoLineEndings: Sized {
+  /// Creates the source text with its `\r\n` line endings normalized to
+  /// `\n`, along with a map for converting positions in the normalized
+  /// text back to byte indexes in the original text.
+  ///
+  /// Files with mixed line endings are supported. Lone `\r` characters
+  /// are left as-is.
+  fn new_normalized(text: String) -> (Self, LineEndingMap);
+}
+
+impl SourceTextInfoLineEndings for SourceTextInfo {
+  fn new_normalized(text: String) -> (Self, LineEndingMap) {
+    let mut removed_cr_indexes = Vec::new();
+    let normalized_text = if text.contains("\r\n") {
+      let mut normalized_text = String::with_capacity(text.len());
+      let mut last_index = 0;
+      for (index, _) in text.match_indices("\r\n") {
+        normalized_text.push_str(&text[last_index..index]);
+        removed_cr_indexes.push(normalized_text.len());
+        last_index = index + 1;
+      }
+      normalized_text.push_str(&text[last_index..]);
+      normalized_text
+    } else {
+      text
+    };
+    let text_info = SourceTextInfo::from_string(normalized_text);
+    let map = LineEndingMap {
+      start_pos: text_info.range().start.as_source_pos(),
+      removed_cr_indexes,
+    };
+    (text_info, map)
+  }
+}
+
+/// Maps positions in source text created with
+/// `SourceTextInfoLineEndings::new_normalized` back to byte indexes in
+/// the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineEndingMap {
+  start_pos: SourcePos,
+  /// Byte indexes in the normalized text of the `\n` characters that
+  /// had a `\r` removed before them.
+  removed_cr_indexes: Vec<usize>,
+}
+
+impl LineEndingMap {
+  /// Gets if any line endings were normalized.
+  pub fn has_changes(&self) -> bool {
+    !self.removed_cr_indexes.is_empty()
+  }
+
+  /// Gets the byte index in the original text of a position in the
+  /// normalized text.
+  ///
+  /// A position before a normalized line ending maps to before its `\r`.
+  pub fn original_byte_index(&self, pos: SourcePos) -> usize {
+    let index = pos - self.start_pos;
+    let removed_count = self
+      .removed_cr_indexes
+      .partition_point(|removed_index| *removed_index < index);
+    index + removed_count
+  }
+
+  /// Gets the byte range in the original text of a range in the
+  /// normalized text (ex. for creating a `TextChange` for the original
+  /// text).
+  pub fn original_byte_range(&self, range: SourceRange) -> Range<usize> {
+    self.original_byte_index(range.start)..self.original_byte_index(range.end)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn range_of(text_info: &SourceTextInfo, text: &str) -> SourceRange {
+    let start = text_info.range().start;
+    let index = text_info.text_str().find(text).unwrap();
+    SourceRange::new(start + index, start + index + text.len())
+  }
+
+  #[test]
+  fn crlf_throughout() {
+    let text = "const a = 1;\r\nconst b = 2;\r\n\r\nconst c = 3;\r\n";
+    let (text_info, map) = SourceTextInfo::new_normalized(text.to_string());
+    assert_eq!(
+      text_info.text_str(),
+      "const a = 1;\nconst b = 2;\n\nconst c = 3;\n"
+    );
+    assert!(map.has_changes());
+    for (search_text, expected_index) in
+      [("const a", 0), ("const b", 14), ("const c", 30)]
+    {
+      let range = range_of(&text_info, search_text);
+      assert_eq!(
+        map.original_byte_range(range),
+        expected_index..expected_index + 7
+      );
+      assert_eq!(&text[map.original_byte_range(range)], search_text);
+    }
+    assert_eq!(map.original_byte_index(text_info.range().end), text.len());
+  }
+
+  #[test]
+  fn mixed_line_endings() {
+    let text = "a\nb\r\nc\rd\r\ne";
+    let (text_info, map) = SourceTextInfo::new_normalized(text.to_string());
+    assert_eq!(text_info.text_str(), "a\nb\nc\rd\ne");
+    for search_text in ["a", "b", "c", "d", "e", "c\rd"] {
+      let range = range_of(&text_info, search_text);
+      assert_eq!(&text[map.original_byte_range(range)], search_text);
+    }
+  }
+
+  #[test]
+  fn range_straddling_line_boundary() {
+    let text = "let a = [\r\n  1,\r\n];\r\n";
+    let (text_info, map) = SourceTextInfo::new_normalized(text.to_string());
+    let range = range_of(&text_info, "[\n  1,\n]");
+    assert_eq!(&text[map.original_byte_range(range)], "[\r\n  1,\r\n]");
+    // the end of a line maps to before the `\r`
+    let line_end = text_info.line_end(0);
+    assert_eq!(map.original_byte_index(line_end), text.find('\r').unwrap());
+    assert_eq!(
+      map.original_byte_index(text_info.line_start(1)),
+      text.find("  1").unwrap()
+    );
+  }
+
+  #[test]
+  fn no_changes() {
+    let text = "let a;\nlet b;";
+    let (text_info, map) = SourceTextInfo::new_normalized(text.to_string());
+    assert_eq!(text_info.text_str(), text);
+    assert!(!map.has_changes());
+    let range = range_of(&text_info, "let b;");
+    assert_eq!(map.original_byte_range(range), 7..13);
+  }
+}
@@ -0,0 +1,535 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::LineAndColumnIndex;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+
+/// Access to the lines of the source text.
+///
+/// The number of lines is provided by `SourceTextInfo::lines_count()`.
+pub trait SourceTextInfoLines {
+  /// Gets the range of a 0-indexed line, excluding its line break
+  /// (`\n` or `\r\n`).
+  ///
+  /// # Panics
+  ///
+  /// Panics when the line index is out of bounds.
+  fn line_range(&self, line_index: usize) -> SourceRange;
+
+  /// Gets the text of a 0-indexed line, excluding its line break
+  /// (`\n` or `\r\n`).
+  ///
+  /// # Panics
+  ///
+  /// Panics when the line index is out of bounds.
+  fn line_text(&self, line_index: usize) -> &str;
+
+  /// Gets the range of a 0-indexed line, including its line break
+  /// when it has one.
+  ///
+  /// # Panics
+  ///
+  /// Panics when the line index is out of bounds.
+  fn range_of_line(&self, line_index: usize) -> SourceRange;
+
+  /// Expands the range to start at the start of its first line and end
+  /// after the line break of its last line (ex. for deleting a
+  /// statement along with its indentation).
+  ///
+  /// A non-empty range that already ends after a line break is not
+  /// expanded to the following line.
+  fn expand_to_line_boundaries(&self, range: SourceRange) -> SourceRange;
+
+  /// Shrinks the range to exclude any leading and trailing whitespace.
+  fn trim_range(&self, range: SourceRange) -> SourceRange;
+
+  /// Gets the 0-indexed line and column of a position or `None` when
+  /// the position is outside the text or within a character.
+  fn try_line_and_column_index(
+    &self,
+    pos: SourcePos,
+  ) -> Option<LineAndColumnIndex>;
+
+  /// Gets the 0-indexed line and column of a position, clamping it to
+  /// the start or end of the text and moving a position within a
+  /// character to its start.
+  ///
+  /// This is useful for positions that may be stale (ex. from analysis
+  /// done before an edit).
+  fn clamped_line_and_column(&self, pos: SourcePos) -> LineAndColumnIndex;
+
+  /// Gets the leading spaces and tabs of a 0-indexed line. For a line
+  /// that only contains whitespace, this is the entire line.
+  ///
+  /// # Panics
+  ///
+  /// Panics when the line index is out of bounds.
+  fn line_indent_text(&self, line_index: usize) -> &str;
+
+  /// Gets the column width of the indentation of a 0-indexed line,
+  /// where a tab advances to the next multiple of the tab width.
+  ///
+  /// # Panics
+  ///
+  /// Panics when the line index is out of bounds.
+  fn indent_width(&self, line_index: usize, tab_width: usize) -> usize;
+
+  /// Gets the 1-indexed column a position is displayed at when tabs
+  /// advance to the next multiple of the tab width, as they do in
+  /// terminals and editors.
+  ///
+  /// Positions outside the text are clamped to the start or end of the
+  /// text and positions within a character are moved to its start.
+  fn display_column(&self, pos: SourcePos, tab_width: usize) -> usize;
+
+  /// Detects whether the text is predominantly indented with tabs or
+  /// spaces by sampling its lines, returning `None` when no indented
+  /// lines are found.
+  fn detect_indentation(&self) -> Option<Indentation>;
+}
+
+/// Indentation used by source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indentation {
+  Tabs,
+  /// Spaces with the number of spaces per indentation level.
+  Spaces(usize),
+}
+
+/// Maximum number of lines sampled when detecting indentation.
+const MAX_INDENTATION_SAMPLE_LINES: usize = 1_000;
+/// Maximum number of spaces considered to be a single indentation level.
+const MAX_INDENTATION_SPACES: usize = 8;
+
+impl SourceTextInfoLines for SourceTextInfo {
+  fn line_range(&self, line_index: usize) -> SourceRange {
+    let lines_count = self.lines_count();
+    assert!(
+      line_index < lines_count,
+      "Line index {} was out of bounds for text with {} lines.",
+      line_index,
+      lines_count,
+    );
+    SourceRange::new(self.line_start(line_index), self.line_end(line_index))
+  }
+
+  fn line_text(&self, line_index: usize) -> &str {
+    self.range_text(&self.line_range(line_index))
+  }
+
+  fn range_of_line(&self, line_index: usize) -> SourceRange {
+    let line_range = self.line_range(line_index);
+    if line_index + 1 < self.lines_count() {
+      SourceRange::new(line_range.start, self.line_start(line_index + 1))
+    } else {
+      line_range
+    }
+  }
+
+  fn expand_to_line_boundaries(&self, range: SourceRange) -> SourceRange {
+    let start = self.line_start(self.line_index(range.start));
+    let end_line_index = self.line_index(range.end);
+    let end = if range.end > range.start
+      && self.line_start(end_line_index) == range.end
+    {
+      range.end
+    } else {
+      self.range_of_line(end_line_index).end
+    };
+    SourceRange::new(start, end)
+  }
+
+  fn trim_range(&self, range: SourceRange) -> SourceRange {
+    let text = self.range_text(&range);
+    let start = range.start + (text.len() - text.trim_start().len());
+    SourceRange::new(
+      start,
+      std::cmp::max(start, range.start + text.trim_end().len()),
+    )
+  }
+
+  fn try_line_and_column_index(
+    &self,
+    pos: SourcePos,
+  ) -> Option<LineAndColumnIndex> {
+    if clamp_pos(self, pos) == pos {
+      Some(self.line_and_column_index(pos))
+    } else {
+      None
+    }
+  }
+
+  fn clamped_line_and_column(&self, pos: SourcePos) -> LineAndColumnIndex {
+    self.line_and_column_index(clamp_pos(self, pos))
+  }
+
+  fn line_indent_text(&self, line_index: usize) -> &str {
+    let line_text = self.line_text(line_index);
+    let content_len = line_text.trim_start_matches([' ', '\t']).len();
+    &line_text[..line_text.len() - content_len]
+  }
+
+  fn indent_width(&self, line_index: usize, tab_width: usize) -> usize {
+    get_display_width(self.line_indent_text(line_index), tab_width)
+  }
+
+  fn display_column(&self, pos: SourcePos, tab_width: usize) -> usize {
+    let pos = clamp_pos(self, pos);
+    let line_start = self.line_start(self.line_index(pos));
+    let column_text = self.range_text(&SourceRange::new(line_start, pos));
+    get_display_width(column_text, tab_width) + 1
+  }
+
+  fn detect_indentation(&self) -> Option<Indentation> {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    // counts of the change in spaces between consecutive lines,
+    // indexed by the number of spaces
+    let mut space_deltas = [0; MAX_INDENTATION_SPACES + 1];
+    let mut min_spaces = usize::MAX;
+    let mut previous_spaces = 0;
+    let lines_count =
+      std::cmp::min(self.lines_count(), MAX_INDENTATION_SAMPLE_LINES);
+    for line_index in 0..lines_count {
+      let indent_text = self.line_indent_text(line_index);
+      if indent_text.len() == self.line_text(line_index).len() {
+        // blank or whitespace only line
+        continue;
+      }
+      if indent_text.starts_with('\t') {
+        tab_lines += 1;
+        continue;
+      }
+      let spaces =
+        indent_text.len() - indent_text.trim_start_matches(' ').len();
+      if spaces > 0 {
+        space_lines += 1;
+        min_spaces = std::cmp::min(min_spaces, spaces);
+      }
+      let delta = spaces.abs_diff(previous_spaces);
+      if delta > 0 && delta <= MAX_INDENTATION_SPACES {
+        space_deltas[delta] += 1;
+      }
+      previous_spaces = spaces;
+    }
+
+    if tab_lines == 0 && space_lines == 0 {
+      None
+    } else if tab_lines > space_lines {
+      Some(Indentation::Tabs)
+    } else {
+      let most_common_delta = (1..=MAX_INDENTATION_SPACES)
+        .filter(|delta| space_deltas[*delta] > 0)
+        // prefer the smaller width on ties
+        .max_by_key(|delta| (space_deltas[*delta], std::cmp::Reverse(*delta)));
+      Some(Indentation::Spaces(most_common_delta.unwrap_or(min_spaces)))
+    }
+  }
+}
+
+/// Gets the number of columns the text is displayed in when starting
+/// at the start of a line, where a tab advances to the next multiple of
+/// the tab width and every other character is one column.
+pub(crate) fn get_display_width(text: &str, tab_width: usize) -> usize {
+  text
+    .chars()
+    .fold(0, |width, c| width + get_char_width(c, width, tab_width))
+}
+
+/// Gets the number of columns a character is displayed in when at the
+/// provided column.
+pub(crate) fn get_char_width(
+  c: char,
+  column: usize,
+  tab_width: usize,
+) -> usize {
+  match c {
+    '\t' => tab_width - column.checked_rem(tab_width).unwrap_or(0),
+    _ => 1,
+  }
+}
+
+/// Clamps the position to the text, moving a position within a
+/// character to its start.
+pub(crate) fn clamp_pos(source: &SourceTextInfo, pos: SourcePos) -> SourcePos {
+  let text_range = source.range();
+  let text = source.text_str();
+  let mut byte_index = if pos < text_range.start {
+    0
+  } else {
+    std::cmp::min(pos - text_range.start, text.len())
+  };
+  while !text.is_char_boundary(byte_index) {
+    byte_index -= 1;
+  }
+  text_range.start + byte_index
+}
+
+/// Clamps both ends of the range to the text.
+pub(crate) fn clamp_range(
+  source: &SourceTextInfo,
+  range: SourceRange,
+) -> SourceRange {
+  let start = clamp_pos(source, range.start);
+  SourceRange::new(start, std::cmp::max(start, clamp_pos(source, range.end)))
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn get_lines(text: &str) -> Vec<String> {
+    let source = SourceTextInfo::from_string(text.to_string());
+    (0..source.lines_count())
+      .map(|line_index| {
+        let text = source.line_text(line_index);
+        assert_eq!(source.range_text(&source.line_range(line_index)), text);
+        text.to_string()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn line_text_lf() {
+    assert_eq!(get_lines("a\nbc\n\nd\n"), vec!["a", "bc", "", "d", ""]);
+  }
+
+  #[test]
+  fn line_text_crlf() {
+    assert_eq!(
+      get_lines("a\r\nbc\r\n\r\nd\r\n"),
+      vec!["a", "bc", "", "d", ""]
+    );
+  }
+
+  #[test]
+  fn line_text_no_final_newline() {
+    assert_eq!(get_lines("a\nb"), vec!["a", "b"]);
+    assert_eq!(get_lines("a\r\nb"), vec!["a", "b"]);
+  }
+
+  #[test]
+  fn line_text_empty_file() {
+    assert_eq!(get_lines(""), vec![""]);
+  }
+
+  #[test]
+  fn line_range() {
+    let source = SourceTextInfo::from_string("a\r\nbc".to_string());
+    let start = source.range().start.as_source_pos();
+    assert_eq!(source.line_range(0), SourceRange::new(start, start + 1));
+    assert_eq!(source.line_range(1), SourceRange::new(start + 3, start + 5));
+  }
+
+  #[test]
+  fn range_of_line() {
+    let source = SourceTextInfo::from_string("a\r\nbc\n\nd".to_string());
+    let lines = (0..source.lines_count())
+      .map(|line_index| source.range_text(&source.range_of_line(line_index)))
+      .collect::<Vec<_>>();
+    assert_eq!(lines, vec!["a\r\n", "bc\n", "\n", "d"]);
+  }
+
+  #[test]
+  fn expand_to_line_boundaries() {
+    let text = "function f() {\n  const a = 1;\n  g(a);\n}";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let range_of = |search_text: &str| {
+      let start = source.range().start + text.find(search_text).unwrap();
+      SourceRange::new(start, start + search_text.len())
+    };
+    let expanded_text = |range: SourceRange| {
+      source.range_text(&source.expand_to_line_boundaries(range))
+    };
+    // statement with leading indentation
+    assert_eq!(expanded_text(range_of("const a = 1;")), "  const a = 1;\n");
+    // spanning lines
+    assert_eq!(
+      expanded_text(range_of("a = 1;\n  g")),
+      "  const a = 1;\n  g(a);\n"
+    );
+    // ending exactly at a newline
+    assert_eq!(expanded_text(range_of("g(a);\n")), "  g(a);\n");
+    // last line without a trailing newline
+    assert_eq!(expanded_text(range_of("}")), "}");
+    // empty range
+    let pos = range_of("g(a)").start;
+    assert_eq!(expanded_text(SourceRange::new(pos, pos)), "  g(a);\n");
+    let line_start = source.line_start(2);
+    assert_eq!(
+      expanded_text(SourceRange::new(line_start, line_start)),
+      "  g(a);\n"
+    );
+  }
+
+  #[test]
+  fn trim_range() {
+    let text = "if (a) {\n  b();\n}\n\n";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start.as_source_pos();
+    let trimmed_text = |range: SourceRange| {
+      source.range_text(&source.trim_range(range)).to_string()
+    };
+    // expanded statement with leading indentation
+    let statement_range = source
+      .expand_to_line_boundaries(SourceRange::new(start + 11, start + 15));
+    assert_eq!(trimmed_text(statement_range), "b();");
+    assert_eq!(
+      trimmed_text(SourceRange::new(start, source.range().end)),
+      "if (a) {\n  b();\n}"
+    );
+    // only whitespace
+    let range = SourceRange::new(start + 8, start + 11);
+    assert_eq!(
+      source.trim_range(range),
+      SourceRange::new(start + 11, start + 11)
+    );
+    assert_eq!(
+      source.trim_range(source.line_range(3)),
+      source.line_range(3)
+    );
+  }
+
+  #[test]
+  fn checked_and_clamped_line_and_column() {
+    let text = "a\né≥";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    let end = source.range().end;
+    for byte_index in [0, 2, 4, text.len()] {
+      let pos = start + byte_index;
+      assert_eq!(
+        source.try_line_and_column_index(pos),
+        Some(source.line_and_column_index(pos))
+      );
+      assert_eq!(
+        source.clamped_line_and_column(pos),
+        source.line_and_column_index(pos)
+      );
+    }
+
+    // one past the end of the file
+    assert_eq!(source.try_line_and_column_index(end + 1), None);
+    assert_eq!(
+      source.clamped_line_and_column(end + 1),
+      source.line_and_column_index(end)
+    );
+    // in the middle of a multi-byte character
+    for byte_index in [3, 5, 6] {
+      assert_eq!(source.try_line_and_column_index(start + byte_index), None);
+    }
+    assert_eq!(
+      source.clamped_line_and_column(start + 3),
+      source.line_and_column_index(start + 2)
+    );
+    assert_eq!(
+      source.clamped_line_and_column(start + 6),
+      source.line_and_column_index(start + 4)
+    );
+    assert_eq!(
+      clamp_range(&source, SourceRange::new(start + 3, end + 10)),
+      SourceRange::new(start + 2, end)
+    );
+  }
+
+  #[test]
+  fn line_indent_text() {
+    let source = SourceTextInfo::from_string(
+      "a\n  b\n\t\tc\n\n   \n \t d\r\n\t".to_string(),
+    );
+    let indents = (0..source.lines_count())
+      .map(|line_index| source.line_indent_text(line_index))
+      .collect::<Vec<_>>();
+    assert_eq!(indents, vec!["", "  ", "\t\t", "", "   ", " \t ", "\t"]);
+  }
+
+  #[test]
+  fn indent_width() {
+    let source =
+      SourceTextInfo::from_string("  a\n\ta\n \ta\n\t  a\n   \n".to_string());
+    let widths = |tab_width: usize| {
+      (0..source.lines_count())
+        .map(|line_index| source.indent_width(line_index, tab_width))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(widths(4), vec![2, 4, 4, 6, 3, 0]);
+    assert_eq!(widths(2), vec![2, 2, 2, 4, 3, 0]);
+    assert_eq!(widths(0), vec![2, 0, 1, 2, 3, 0]);
+  }
+
+  #[test]
+  fn display_column() {
+    let text = "\tconst a = 1;\n\t  b();\nab\tc;\n  \t\td;";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let display_column = |search_text: &str, tab_width: usize| {
+      let pos = source.range().start + text.find(search_text).unwrap();
+      source.display_column(pos, tab_width)
+    };
+    // indented by a tab
+    assert_eq!(display_column("const", 4), 5);
+    assert_eq!(display_column("const", 8), 9);
+    assert_eq!(display_column("a = 1", 4), 11);
+    // tab then spaces
+    assert_eq!(display_column("b()", 4), 7);
+    assert_eq!(display_column("b()", 8), 11);
+    // tab mid-line after text
+    assert_eq!(display_column("c;", 4), 5);
+    assert_eq!(display_column("c;", 2), 5);
+    // spaces then tabs, where the first tab only advances two columns
+    assert_eq!(display_column("d;", 4), 9);
+    // a tab width of 0 ignores tabs
+    assert_eq!(display_column("d;", 0), 3);
+    // a tab width of 1 counts a tab as a single character
+    let pos = source.range().start + text.find("c;").unwrap();
+    assert_eq!(
+      source.line_and_column_index(pos).column_index + 1,
+      source.display_column(pos, 1)
+    );
+    assert_eq!(source.display_column(source.range().end + 1, 4), 11);
+  }
+
+  #[test]
+  fn detect_indentation() {
+    let detect = |text: &str| {
+      SourceTextInfo::from_string(text.to_string()).detect_indentation()
+    };
+    assert_eq!(detect(""), None);
+    assert_eq!(detect("a;\n\nb;\n    \n"), None);
+    assert_eq!(
+      detect("if (a) {\n\tb();\n\tif (c) {\n\t\td();\n\t}\n}\n"),
+      Some(Indentation::Tabs)
+    );
+    assert_eq!(
+      detect("if (a) {\n  b();\n\n  if (c) {\n    d();\n  }\n}\n"),
+      Some(Indentation::Spaces(2))
+    );
+    assert_eq!(
+      detect("class A {\n    a() {\n        b();\n    }\n}\n"),
+      Some(Indentation::Spaces(4))
+    );
+    // continuation lines do not affect the width
+    assert_eq!(
+      detect("f(\n  a,\n  b,\n);\nconst x = [\n  1,\n     2,\n];\n"),
+      Some(Indentation::Spaces(2))
+    );
+    // mixed tabs and spaces uses the most common
+    assert_eq!(detect("{\n\ta;\n\tb;\n  c;\n}\n"), Some(Indentation::Tabs));
+    assert_eq!(
+      detect("{\n  a;\n  {\n    b;\n  }\n\tc;\n}\n"),
+      Some(Indentation::Spaces(2))
+    );
+  }
+
+  #[test]
+  #[should_panic(
+    expected = "Line index 2 was out of bounds for text with 2 lines."
+  )]
+  fn line_text_out_of_bounds() {
+    let source = SourceTextInfo::from_string("a\nb".to_string());
+    // call the trait method because `SourceTextInfo` has its own `line_text`
+    SourceTextInfoLines::line_text(&source, 2);
+  }
+}
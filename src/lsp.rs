@@ -0,0 +1,395 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Types that mirror the diagnostic and document synchronization types of
+//! the language server protocol in order to avoid depending on a specific
+//! version of `lsp-types`.
+//!
+//! These serialize to the same shape as the protocol's types.
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::SourcePos;
+use crate::SourceTextInfo;
+use crate::SourceTextInfoUtf16;
+
+/// Value of the `source` property of diagnostics converted by this crate.
+pub const DIAGNOSTIC_SOURCE: &str = "deno-ast";
+
+/// Zero-indexed position where the character is in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+  pub line: u32,
+  pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Range {
+  pub start: Position,
+  pub end: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DiagnosticSeverity(u32);
+
+impl DiagnosticSeverity {
+  pub const ERROR: DiagnosticSeverity = DiagnosticSeverity(1);
+  pub const WARNING: DiagnosticSeverity = DiagnosticSeverity(2);
+  pub const INFORMATION: DiagnosticSeverity = DiagnosticSeverity(3);
+  pub const HINT: DiagnosticSeverity = DiagnosticSeverity(4);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+  pub range: Range,
+  pub severity: DiagnosticSeverity,
+  pub code: String,
+  pub source: String,
+  pub message: String,
+}
+
+/// Change to the text of a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentChangeEvent {
+  /// Range of the text that's replaced or `None` when the change
+  /// replaces the entire text.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub range: Option<Range>,
+  pub text: String,
+}
+
+/// Error converting a position of a content change to a position in
+/// the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+  /// The line was past the last line of the text.
+  LineOutOfRange(Position),
+  /// The character was between the two code units of a surrogate pair.
+  WithinSurrogatePair(Position),
+  /// The start of the range was after its end.
+  InvertedRange(Range),
+}
+
+impl std::error::Error for PositionError {}
+
+impl fmt::Display for PositionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PositionError::LineOutOfRange(position) => write!(
+        f,
+        "Line {} was past the last line of the text.",
+        position.line
+      ),
+      PositionError::WithinSurrogatePair(position) => write!(
+        f,
+        "Character {} on line {} was within a surrogate pair.",
+        position.character, position.line
+      ),
+      PositionError::InvertedRange(range) => write!(
+        f,
+        "Range start {}:{} was after its end {}:{}.",
+        range.start.line,
+        range.start.character,
+        range.end.line,
+        range.end.character
+      ),
+    }
+  }
+}
+
+/// Applying the content changes a language server receives for a
+/// document.
+pub trait SourceTextInfoLsp {
+  /// Applies the changes in order, returning the new text.
+  ///
+  /// The range of each change is relative to the text after applying
+  /// the previous changes. As in the protocol, a character past the end
+  /// of a line is clamped to the end of the line, excluding its line
+  /// break.
+  fn apply_lsp_changes(
+    &self,
+    changes: &[TextDocumentContentChangeEvent],
+  ) -> Result<SourceTextInfo, PositionError>;
+}
+
+impl SourceTextInfoLsp for SourceTextInfo {
+  fn apply_lsp_changes(
+    &self,
+    changes: &[TextDocumentContentChangeEvent],
+  ) -> Result<SourceTextInfo, PositionError> {
+    // changes before a change of the entire text have no effect
+    let (mut text, changes) =
+      match changes.iter().rposition(|change| change.range.is_none()) {
+        Some(index) => (changes[index].text.clone(), &changes[index + 1..]),
+        None if changes.is_empty() => return Ok(self.clone()),
+        None => (self.text_str().to_string(), changes),
+      };
+    for change in changes {
+      let range = change.range.unwrap();
+      let start = get_byte_index(&text, range.start)?;
+      let end = get_byte_index(&text, range.end)?;
+      if start > end {
+        return Err(PositionError::InvertedRange(range));
+      }
+      text.replace_range(start..end, &change.text);
+    }
+    Ok(SourceTextInfo::from_string(text))
+  }
+}
+
+/// Gets the byte index of a position in UTF-16 code units.
+fn get_byte_index(
+  text: &str,
+  position: Position,
+) -> Result<usize, PositionError> {
+  let mut line_start = 0;
+  for _ in 0..position.line {
+    match text[line_start..].find('\n') {
+      Some(index) => line_start += index + 1,
+      None => return Err(PositionError::LineOutOfRange(position)),
+    }
+  }
+  let line_text = match text[line_start..].find('\n') {
+    Some(index) => &text[line_start..line_start + index],
+    None => &text[line_start..],
+  };
+  let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+  let mut utf16_index = 0;
+  for (byte_index, c) in line_text.char_indices() {
+    if utf16_index == position.character as usize {
+      return Ok(line_start + byte_index);
+    }
+    utf16_index += c.len_utf16();
+    if utf16_index > position.character as usize {
+      return Err(PositionError::WithinSurrogatePair(position));
+    }
+  }
+  Ok(line_start + line_text.len())
+}
+
+pub(crate) fn to_lsp_diagnostic(
+  diagnostic: &crate::Diagnostic,
+  source: &SourceTextInfo,
+) -> Diagnostic {
+  Diagnostic {
+    range: Range {
+      start: get_position(source, diagnostic.range.start),
+      end: get_position(source, diagnostic.range.end),
+    },
+    severity: match diagnostic.severity {
+      crate::DiagnosticSeverity::Error => DiagnosticSeverity::ERROR,
+      crate::DiagnosticSeverity::Warning => DiagnosticSeverity::WARNING,
+      crate::DiagnosticSeverity::Info => DiagnosticSeverity::INFORMATION,
+    },
+    code: diagnostic.code().as_str().to_string(),
+    source: DIAGNOSTIC_SOURCE.to_string(),
+    message: diagnostic.message().into_owned(),
+  }
+}
+
+/// Gets the position in UTF-16 code units, clamping it to the text.
+fn get_position(source: &SourceTextInfo, pos: SourcePos) -> Position {
+  let position = source.line_and_column_utf16(pos);
+  Position {
+    line: position.line_index as u32,
+    character: position.column_index as u32,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::DiagnosticKind;
+  use crate::SourceRange;
+
+  fn get_lsp_range(text: &str, start: usize, end: usize) -> Range {
+    let source = SourceTextInfo::from_string(text.to_string());
+    let text_start = source.range().start;
+    crate::Diagnostic::new(
+      "file:///mod.ts",
+      SourceRange::new(text_start + start, text_start + end),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    )
+    .to_lsp()
+    .range
+  }
+
+  fn position(line: u32, character: u32) -> Position {
+    Position { line, character }
+  }
+
+  fn change(
+    start: (u32, u32),
+    end: (u32, u32),
+    text: &str,
+  ) -> TextDocumentContentChangeEvent {
+    TextDocumentContentChangeEvent {
+      range: Some(Range {
+        start: position(start.0, start.1),
+        end: position(end.0, end.1),
+      }),
+      text: text.to_string(),
+    }
+  }
+
+  #[test]
+  fn applies_content_changes() {
+    let source = SourceTextInfo::from_string(
+      "const a = 1;\r\nconsole.log(a);\r\n".to_string(),
+    );
+    // recorded from typing in an editor
+    let changes = [
+      change((0, 10), (0, 11), "'😀'"),
+      // after the surrogate pair of the first emoji
+      change((0, 13), (0, 13), "👍"),
+      // replaces the line break
+      change((0, 17), (1, 0), "\n"),
+      // past the end of the line
+      change((1, 100), (1, 100), " // log"),
+      change((2, 0), (2, 0), "// done"),
+    ];
+    let new_source = source.apply_lsp_changes(&changes).unwrap();
+    assert_eq!(
+      new_source.text_str(),
+      "const a = '😀👍';\nconsole.log(a); // log\r\n// done"
+    );
+    // changes are applied in order
+    let new_source = source
+      .apply_lsp_changes(&[
+        change((0, 0), (0, 5), "let"),
+        change((0, 0), (0, 3), "var"),
+      ])
+      .unwrap();
+    assert_eq!(new_source.text_str(), "var a = 1;\r\nconsole.log(a);\r\n");
+  }
+
+  #[test]
+  fn applies_full_content_changes() {
+    let source = SourceTextInfo::from_string("let a;".to_string());
+    let changes = [
+      change((5, 0), (5, 0), "ignored"),
+      TextDocumentContentChangeEvent {
+        range: None,
+        text: "x;".to_string(),
+      },
+      change((0, 1), (0, 1), "y"),
+    ];
+    let new_source = source.apply_lsp_changes(&changes).unwrap();
+    assert_eq!(new_source.text_str(), "xy;");
+    assert_eq!(source.apply_lsp_changes(&[]).unwrap().text_str(), "let a;");
+    assert_eq!(
+      serde_json::to_value(&changes[1]).unwrap(),
+      serde_json::json!({ "text": "x;" })
+    );
+  }
+
+  #[test]
+  fn rejects_invalid_content_changes() {
+    let source = SourceTextInfo::from_string("'😀';\n".to_string());
+    assert_eq!(
+      source
+        .apply_lsp_changes(&[change((0, 2), (0, 2), "")])
+        .unwrap_err(),
+      PositionError::WithinSurrogatePair(position(0, 2))
+    );
+    assert_eq!(
+      source
+        .apply_lsp_changes(&[change((2, 0), (2, 0), "")])
+        .unwrap_err(),
+      PositionError::LineOutOfRange(position(2, 0))
+    );
+    let range = Range {
+      start: position(0, 3),
+      end: position(0, 1),
+    };
+    assert_eq!(
+      source
+        .apply_lsp_changes(&[TextDocumentContentChangeEvent {
+          range: Some(range),
+          text: String::new(),
+        }])
+        .unwrap_err(),
+      PositionError::InvertedRange(range)
+    );
+  }
+
+  #[test]
+  fn utf16_after_emoji() {
+    // the emoji with a skin tone modifier is 4 UTF-16 code units
+    let text = "let a;\nlet b = \"👍🏽\"; #!c;";
+    let start = text.find("#!").unwrap();
+    assert_eq!(
+      get_lsp_range(text, start, start + 2),
+      Range {
+        start: position(1, 16),
+        end: position(1, 18),
+      }
+    );
+  }
+
+  #[test]
+  fn utf16_after_surrogate_pair() {
+    // 𝒳 requires a surrogate pair while ≥ is a single code unit
+    let text = "𝒳 ≥ #!";
+    let start = text.find("#!").unwrap();
+    assert_eq!(
+      get_lsp_range(text, start, start + 2),
+      Range {
+        start: position(0, 5),
+        end: position(0, 7),
+      }
+    );
+  }
+
+  #[test]
+  fn clamps_to_document() {
+    let text = "let a;\n#!";
+    assert_eq!(
+      get_lsp_range(text, 7, text.len() + 10),
+      Range {
+        start: position(1, 0),
+        end: position(1, 2),
+      }
+    );
+  }
+
+  #[test]
+  fn maps_severity_and_code() {
+    let source = SourceTextInfo::from_string("#!".to_string());
+    let range = source.range();
+    let diagnostic = crate::Diagnostic::new(
+      "file:///mod.ts",
+      SourceRange::new(range.start.as_source_pos(), range.end),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    )
+    .with_severity(crate::DiagnosticSeverity::Warning)
+    .to_lsp();
+    assert_eq!(diagnostic.severity, DiagnosticSeverity::WARNING);
+    assert_eq!(diagnostic.code, "misplaced-shebang");
+    assert_eq!(diagnostic.source, "deno-ast");
+    assert_eq!(
+      serde_json::to_value(&diagnostic).unwrap(),
+      serde_json::json!({
+        "range": {
+          "start": { "line": 0, "character": 0 },
+          "end": { "line": 0, "character": 2 },
+        },
+        "severity": 2,
+        "code": "misplaced-shebang",
+        "source": "deno-ast",
+        "message": "A shebang (#!) is only allowed at the start of a file",
+      })
+    );
+  }
+}
@@ -0,0 +1,1528 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::get_syntax;
+use crate::lexing::lex_tokens;
+use crate::parse_json;
+use crate::swc::ast::AssignOp;
+use crate::swc::parser::token::BinOpToken;
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::MediaType;
+use crate::ParseParams;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+use crate::ES_VERSION;
+
+/// Every media type along with its stable name, which is checked to
+/// match `media_type_name()` by the tests.
+const MEDIA_TYPE_NAMES: [(MediaType, &str); 16] = [
+  (MediaType::JavaScript, "javascript"),
+  (MediaType::Jsx, "jsx"),
+  (MediaType::Mjs, "mjs"),
+  (MediaType::Cjs, "cjs"),
+  (MediaType::TypeScript, "typescript"),
+  (MediaType::Mts, "mts"),
+  (MediaType::Cts, "cts"),
+  (MediaType::Dts, "dts"),
+  (MediaType::Dmts, "dmts"),
+  (MediaType::Dcts, "dcts"),
+  (MediaType::Tsx, "tsx"),
+  (MediaType::Json, "json"),
+  (MediaType::Wasm, "wasm"),
+  (MediaType::TsBuildInfo, "tsbuildinfo"),
+  (MediaType::SourceMap, "sourcemap"),
+  (MediaType::Unknown, "unknown"),
+];
+
+/// Gets the stable lowercase name of a media type (ex. `"typescript"`)
+/// for persisting it (ex. in a cache).
+pub fn media_type_name(media_type: MediaType) -> &'static str {
+  match media_type {
+    MediaType::JavaScript => "javascript",
+    MediaType::Jsx => "jsx",
+    MediaType::Mjs => "mjs",
+    MediaType::Cjs => "cjs",
+    MediaType::TypeScript => "typescript",
+    MediaType::Mts => "mts",
+    MediaType::Cts => "cts",
+    MediaType::Dts => "dts",
+    MediaType::Dmts => "dmts",
+    MediaType::Dcts => "dcts",
+    MediaType::Tsx => "tsx",
+    MediaType::Json => "json",
+    MediaType::Wasm => "wasm",
+    MediaType::TsBuildInfo => "tsbuildinfo",
+    MediaType::SourceMap => "sourcemap",
+    MediaType::Unknown => "unknown",
+  }
+}
+
+/// Gets the media type of a name from `media_type_name()` or `None`
+/// when the name is not recognized.
+pub fn try_media_type_from_name(name: &str) -> Option<MediaType> {
+  MEDIA_TYPE_NAMES
+    .iter()
+    .find(|(_, media_type_name)| *media_type_name == name)
+    .map(|(media_type, _)| *media_type)
+}
+
+/// Gets the media type of a name from `media_type_name()`.
+///
+/// Names that are not recognized (ex. from a newer version of this
+/// crate) are `MediaType::Unknown`.
+pub fn media_type_from_name(name: &str) -> MediaType {
+  try_media_type_from_name(name).unwrap_or(MediaType::Unknown)
+}
+
+/// Gets if the media type is a TypeScript declaration file (`.d.ts`,
+/// `.d.mts`, or `.d.cts`).
+pub fn is_declaration_media_type(media_type: MediaType) -> bool {
+  match media_type {
+    MediaType::Dts | MediaType::Dmts | MediaType::Dcts => true,
+    MediaType::JavaScript
+    | MediaType::Jsx
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Tsx
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => false,
+  }
+}
+
+/// Gets if the media type is parsed with JSX (`.jsx` or `.tsx`).
+pub fn is_jsx_media_type(media_type: MediaType) -> bool {
+  match media_type {
+    MediaType::Jsx | MediaType::Tsx => true,
+    MediaType::JavaScript
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => false,
+  }
+}
+
+/// Gets if the media type is TypeScript, including declaration files.
+pub fn is_typed_media_type(media_type: MediaType) -> bool {
+  match media_type {
+    MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Tsx => true,
+    MediaType::JavaScript
+    | MediaType::Jsx
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => false,
+  }
+}
+
+/// Gets if the media type is JavaScript, including JSX.
+pub fn is_javascript_like_media_type(media_type: MediaType) -> bool {
+  match media_type {
+    MediaType::JavaScript
+    | MediaType::Jsx
+    | MediaType::Mjs
+    | MediaType::Cjs => true,
+    MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Tsx
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => false,
+  }
+}
+
+/// Gets if the media type needs to be transpiled before it can be run,
+/// which is when `media_type_for_output()` differs from it.
+///
+/// Declaration files are not emittable because they have no runtime
+/// code.
+pub fn is_emittable_media_type(media_type: MediaType) -> bool {
+  match media_type {
+    MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Tsx
+    | MediaType::Jsx => true,
+    MediaType::JavaScript
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => false,
+  }
+}
+
+/// Gets the canonical file extension of a media type (ex. `".d.mts"`).
+///
+/// Unknown media types are `".js"`, which is how TypeScript treats
+/// them.
+pub fn media_type_extension(media_type: MediaType) -> &'static str {
+  match media_type {
+    MediaType::JavaScript => ".js",
+    MediaType::Jsx => ".jsx",
+    MediaType::Mjs => ".mjs",
+    MediaType::Cjs => ".cjs",
+    MediaType::TypeScript => ".ts",
+    MediaType::Mts => ".mts",
+    MediaType::Cts => ".cts",
+    MediaType::Dts => ".d.ts",
+    MediaType::Dmts => ".d.mts",
+    MediaType::Dcts => ".d.cts",
+    MediaType::Tsx => ".tsx",
+    MediaType::Json => ".json",
+    MediaType::Wasm => ".wasm",
+    MediaType::TsBuildInfo => ".tsbuildinfo",
+    MediaType::SourceMap => ".map",
+    MediaType::Unknown => ".js",
+  }
+}
+
+/// Gets the canonical `Content-Type` header value of a media type.
+///
+/// `media_type_from_content_type()` maps these back to the media type
+/// when the specifier has the media type's extension.
+pub fn media_type_content_type(media_type: MediaType) -> &'static str {
+  match media_type {
+    MediaType::JavaScript | MediaType::Mjs | MediaType::Cjs => {
+      "text/javascript"
+    }
+    MediaType::Jsx => "text/jsx",
+    MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts => "application/typescript",
+    MediaType::Tsx => "text/tsx",
+    MediaType::Json | MediaType::TsBuildInfo | MediaType::SourceMap => {
+      "application/json"
+    }
+    MediaType::Wasm => "application/wasm",
+    MediaType::Unknown => "application/octet-stream",
+  }
+}
+
+/// Gets the media type of the output of transpiling a media type (ex.
+/// `MediaType::Mts` is emitted as `MediaType::Mjs`).
+///
+/// Declaration files and media types that are not transpiled are
+/// unchanged.
+pub fn media_type_for_output(media_type: MediaType) -> MediaType {
+  match media_type {
+    MediaType::TypeScript | MediaType::Tsx | MediaType::Jsx => {
+      MediaType::JavaScript
+    }
+    MediaType::Mts => MediaType::Mjs,
+    MediaType::Cts => MediaType::Cjs,
+    MediaType::JavaScript
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Json
+    | MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => media_type,
+  }
+}
+
+/// Gets the media type from the extension of a path.
+///
+/// The algorithm is:
+///
+/// 1. Take the file name after the last `/` or `\` so that Windows
+///    paths work on every platform.
+/// 2. Take the final extension after the last `.`, ignoring case. A
+///    leading dot is part of the name rather than an extension (ex. a
+///    file named `.ts`), except for `.tsbuildinfo`. An empty extension
+///    (ex. `a.`) or an unrecognized one (ex. `a.ts.old`) is
+///    `MediaType::Unknown`.
+/// 3. Only when the final extension is `.ts`, `.mts`, or `.cts`, check
+///    whether the rest of the name ends with `.d` for a declaration
+///    file (ex. `a.test.d.mts`). Names like `a.d.ts.map` are decided by
+///    their final extension alone.
+pub fn media_type_from_path(path: &Path) -> MediaType {
+  media_type_from_path_str(&path.to_string_lossy())
+}
+
+fn media_type_from_path_str(path: &str) -> MediaType {
+  let file_name = get_file_name(path);
+  let Some((stem, extension)) = split_extension(file_name) else {
+    return if file_name.eq_ignore_ascii_case(".tsbuildinfo") {
+      MediaType::TsBuildInfo
+    } else {
+      MediaType::Unknown
+    };
+  };
+  let is_declaration =
+    || stem.len() > 2 && stem[stem.len() - 2..].eq_ignore_ascii_case(".d");
+  match extension.to_ascii_lowercase().as_str() {
+    "ts" if is_declaration() => MediaType::Dts,
+    "mts" if is_declaration() => MediaType::Dmts,
+    "cts" if is_declaration() => MediaType::Dcts,
+    "ts" => MediaType::TypeScript,
+    "mts" => MediaType::Mts,
+    "cts" => MediaType::Cts,
+    "tsx" => MediaType::Tsx,
+    "js" => MediaType::JavaScript,
+    "jsx" => MediaType::Jsx,
+    "mjs" => MediaType::Mjs,
+    "cjs" => MediaType::Cjs,
+    "json" => MediaType::Json,
+    "wasm" => MediaType::Wasm,
+    "tsbuildinfo" => MediaType::TsBuildInfo,
+    "map" => MediaType::SourceMap,
+    _ => MediaType::Unknown,
+  }
+}
+
+/// Gets the file name after the last `/` or `\` of a path.
+fn get_file_name(path: &str) -> &str {
+  match path.rfind(['/', '\\']) {
+    Some(index) => &path[index + 1..],
+    None => path,
+  }
+}
+
+/// Splits a file name into its stem and final extension, where a
+/// leading dot is part of the name rather than an extension.
+fn split_extension(file_name: &str) -> Option<(&str, &str)> {
+  match file_name.rfind('.') {
+    Some(0) | None => None,
+    Some(index) => Some((&file_name[..index], &file_name[index + 1..])),
+  }
+}
+
+/// Resolves media types using extensions registered by the embedder
+/// (ex. `.mjsx`) before falling back to the built-in detection of
+/// `media_type_from_specifier()` and `media_type_from_path()`.
+///
+/// Registered extensions are matched against the final extension of a
+/// path, ignoring case.
+#[derive(Debug, Default, Clone)]
+pub struct MediaTypeResolver {
+  extensions: HashMap<String, MediaType>,
+}
+
+impl MediaTypeResolver {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers the media type of an extension, with or without its
+  /// leading dot (ex. `".mjsx"` or `"mjsx"`).
+  ///
+  /// Extensions the built-in detection recognizes (ex. `.ts`) are
+  /// rejected unless `allow_built_in_override` is true, in which case
+  /// declaration files with that extension (ex. `.d.ts`) are overridden
+  /// as well.
+  pub fn register_extension(
+    &mut self,
+    extension: &str,
+    media_type: MediaType,
+    allow_built_in_override: bool,
+  ) -> Result<(), RegisterExtensionError> {
+    let extension = extension
+      .strip_prefix('.')
+      .unwrap_or(extension)
+      .to_ascii_lowercase();
+    if extension.is_empty() || extension.contains(['.', '/', '\\']) {
+      return Err(RegisterExtensionError::Invalid(extension));
+    }
+    if !allow_built_in_override {
+      let built_in_media_type =
+        media_type_from_specifier(&format!("file.{}", extension));
+      if built_in_media_type != MediaType::Unknown {
+        return Err(RegisterExtensionError::BuiltIn {
+          extension,
+          media_type: built_in_media_type,
+        });
+      }
+    }
+    self.extensions.insert(extension, media_type);
+    Ok(())
+  }
+
+  /// Gets the media type of a specifier, the same as
+  /// `media_type_from_specifier()` except for registered extensions.
+  pub fn resolve_specifier(&self, specifier: &str) -> MediaType {
+    if get_data_url_mime_type(specifier).is_none() {
+      if let Some(inner_specifier) = strip_scheme(specifier, "blob") {
+        return self.resolve_specifier(inner_specifier);
+      }
+      if let Some(media_type) =
+        self.get_registered(&get_specifier_path(specifier))
+      {
+        return media_type;
+      }
+    }
+    media_type_from_specifier(specifier)
+  }
+
+  /// Gets the media type of a path, the same as `media_type_from_path()`
+  /// except for registered extensions.
+  pub fn resolve_path(&self, path: &Path) -> MediaType {
+    let path = path.to_string_lossy();
+    self
+      .get_registered(&path)
+      .unwrap_or_else(|| media_type_from_path_str(&path))
+  }
+
+  fn get_registered(&self, path: &str) -> Option<MediaType> {
+    if self.extensions.is_empty() {
+      return None;
+    }
+    let (_, extension) = split_extension(get_file_name(path))?;
+    self
+      .extensions
+      .get(&extension.to_ascii_lowercase())
+      .copied()
+  }
+}
+
+/// Error registering an extension with a `MediaTypeResolver`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterExtensionError {
+  /// The extension was empty or contained a dot or path separator.
+  Invalid(String),
+  /// The extension is recognized by the built-in detection and
+  /// overriding it was not allowed.
+  BuiltIn {
+    extension: String,
+    media_type: MediaType,
+  },
+}
+
+impl std::error::Error for RegisterExtensionError {}
+
+impl fmt::Display for RegisterExtensionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RegisterExtensionError::Invalid(extension) => {
+        write!(f, "Invalid extension '{}'.", extension)
+      }
+      RegisterExtensionError::BuiltIn {
+        extension,
+        media_type,
+      } => write!(
+        f,
+        "Extension '.{}' is built in as '{}' and overriding it was not allowed.",
+        extension,
+        media_type_name(*media_type)
+      ),
+    }
+  }
+}
+
+/// Module system of the `.js` and `.ts` files in a package, from the
+/// `"type"` field of the nearest `package.json`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PackageJsonType {
+  /// `"type": "module"`
+  Module,
+  /// `"type": "commonjs"`
+  CommonJs,
+  /// No `package.json` or no `"type"` field, so the module system is
+  /// decided by the embedder.
+  #[default]
+  None,
+}
+
+/// Gets the media type of a path, using the `"type"` field of the
+/// nearest `package.json` to decide whether `.js`, `.ts`, and `.d.ts`
+/// files are ES modules or CommonJS (ex. a `.js` file is
+/// `MediaType::Mjs` in a `"type": "module"` package).
+///
+/// Explicit extensions like `.mjs` or `.cts` are used as-is. This does
+/// not access the file system, so the package type must be resolved by
+/// the caller.
+pub fn media_type_from_path_with_package_type(
+  path: &Path,
+  package_type: PackageJsonType,
+) -> MediaType {
+  let media_type = media_type_from_path(path);
+  match package_type {
+    PackageJsonType::Module => match media_type {
+      MediaType::JavaScript => MediaType::Mjs,
+      MediaType::TypeScript => MediaType::Mts,
+      MediaType::Dts => MediaType::Dmts,
+      media_type => media_type,
+    },
+    PackageJsonType::CommonJs => match media_type {
+      MediaType::JavaScript => MediaType::Cjs,
+      MediaType::TypeScript => MediaType::Cts,
+      MediaType::Dts => MediaType::Dcts,
+      media_type => media_type,
+    },
+    PackageJsonType::None => media_type,
+  }
+}
+
+/// Gets the media type of a module from the `Content-Type` header of the
+/// response it was loaded from (ex. `application/typescript;
+/// charset=utf-8`).
+///
+/// Parameters, case, and surrounding whitespace are ignored. The
+/// extension of the specifier is used to refine the JavaScript and
+/// TypeScript types (ex. `.d.ts` or `.mjs`) and to detect the media
+/// type when the content type is generic (`text/plain` or
+/// `application/octet-stream`). Unknown content types are
+/// `MediaType::Unknown`.
+pub fn media_type_from_content_type(
+  specifier: &str,
+  content_type: &str,
+) -> MediaType {
+  let mime_type = content_type
+    .split(';')
+    .next()
+    .unwrap_or_default()
+    .trim()
+    .to_ascii_lowercase();
+  match mime_type.as_str() {
+    "application/typescript"
+    | "text/typescript"
+    | "application/x-typescript"
+    | "video/vnd.dlna.mpeg-tts"
+    | "video/mp2t" => match media_type_from_specifier(specifier) {
+      media_type @ (MediaType::Mts
+      | MediaType::Cts
+      | MediaType::Dts
+      | MediaType::Dmts
+      | MediaType::Dcts
+      | MediaType::Tsx) => media_type,
+      _ => MediaType::TypeScript,
+    },
+    "application/javascript"
+    | "text/javascript"
+    | "application/ecmascript"
+    | "text/ecmascript"
+    | "application/x-javascript"
+    | "application/node" => match media_type_from_specifier(specifier) {
+      media_type @ (MediaType::Mjs | MediaType::Cjs | MediaType::Jsx) => {
+        media_type
+      }
+      _ => MediaType::JavaScript,
+    },
+    "text/jsx" => MediaType::Jsx,
+    "text/tsx" => MediaType::Tsx,
+    "application/json" | "text/json" | "application/jsonc"
+    | "application/json5" => MediaType::Json,
+    "application/wasm" => MediaType::Wasm,
+    "text/plain" | "application/octet-stream" => {
+      media_type_from_specifier(specifier)
+    }
+    _ => MediaType::Unknown,
+  }
+}
+
+/// Gets the media type of a module from its specifier and the headers
+/// of the response it was loaded from, using the same precedence
+/// everywhere:
+///
+/// 1. Data URLs use the MIME type embedded in them.
+/// 2. The `Content-Type` header (the name is case-insensitive) is used
+///    when present, unless it's generic (`text/plain` or
+///    `application/octet-stream`). See `media_type_from_content_type()`.
+/// 3. Otherwise, the extension of the specifier is used.
+#[cfg(feature = "module_specifier")]
+pub fn media_type_from_specifier_and_headers(
+  specifier: &crate::ModuleSpecifier,
+  maybe_headers: Option<&std::collections::HashMap<String, String>>,
+) -> MediaType {
+  if specifier.scheme() == "data" {
+    return media_type_from_specifier(specifier.as_str());
+  }
+  let maybe_content_type = maybe_headers.and_then(|headers| {
+    headers
+      .iter()
+      .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+      .map(|(_, value)| value.as_str())
+  });
+  match maybe_content_type {
+    Some(content_type) => {
+      media_type_from_content_type(specifier.as_str(), content_type)
+    }
+    None => media_type_from_specifier(specifier.as_str()),
+  }
+}
+
+/// Gets the media type of a module from its specifier and optional
+/// `Content-Type` header, falling back to inspecting its text when
+/// neither determine the media type (ex. a URL without an extension
+/// served as `text/plain`).
+///
+/// The text heuristics are intentionally conservative, so anything
+/// not matching them is `MediaType::JavaScript`:
+///
+/// - Text starting with `{` or `[` that parses as JSON is
+///   `MediaType::Json`.
+/// - Text with syntax that's only valid in TypeScript is
+///   `MediaType::TypeScript`. This is a triple-slash
+///   `<reference types="..." />` directive, an `interface`, `type`,
+///   `enum`, or `declare` statement, a type annotation on a variable
+///   or parameter, or a primitive return type annotation.
+/// - Text with JSX elements is `MediaType::Jsx`, or `MediaType::Tsx`
+///   when it also has TypeScript syntax.
+pub fn detect_media_type_from_content(
+  specifier: &str,
+  maybe_content_type: Option<&str>,
+  text: &str,
+) -> MediaType {
+  let media_type = match maybe_content_type {
+    Some(content_type) => media_type_from_content_type(specifier, content_type),
+    None => media_type_from_specifier(specifier),
+  };
+  if media_type != MediaType::Unknown {
+    return media_type;
+  }
+
+  let text_info = SourceTextInfo::from_string(text.to_string());
+  let content = text.trim_start_matches('\u{FEFF}').trim_start();
+  if content.starts_with(['{', '[']) {
+    let params = ParseParams::builder(specifier, text_info.clone())
+      .media_type(MediaType::Json)
+      .strip_bom(true)
+      .build();
+    if parse_json(params).is_ok() {
+      return MediaType::Json;
+    }
+  }
+  let tokens = lex_tokens(&text_info, get_syntax(MediaType::Tsx), ES_VERSION);
+  let has_jsx = tokens.iter().any(|token| token.token == Token::JSXTagStart);
+  let is_typescript = has_types_reference(text_info.text_str())
+    || has_typescript_syntax(&tokens, &text_info);
+  match (is_typescript, has_jsx) {
+    (true, true) => MediaType::Tsx,
+    (true, false) => MediaType::TypeScript,
+    (false, true) => MediaType::Jsx,
+    (false, false) => MediaType::JavaScript,
+  }
+}
+
+/// Gets if a triple-slash directive at the start of the text, after any
+/// shebang, references types (ex. `/// <reference types="node" />`).
+fn has_types_reference(text: &str) -> bool {
+  text
+    .trim_start_matches('\u{FEFF}')
+    .lines()
+    .map(|line| line.trim())
+    .take_while(|line| {
+      line.is_empty() || line.starts_with("//") || line.starts_with("#!")
+    })
+    .filter_map(|line| line.strip_prefix("///"))
+    .any(|line| line.trim_start().starts_with("<reference types="))
+}
+
+/// Gets if the tokens contain syntax that's only valid in TypeScript.
+fn has_typescript_syntax(
+  tokens: &[TokenAndSpan],
+  text_info: &SourceTextInfo,
+) -> bool {
+  let get_token = |index: Option<usize>| {
+    index
+      .and_then(|index| tokens.get(index))
+      .map(|token| &token.token)
+  };
+  // text of an identifier or keyword, otherwise empty
+  let get_word =
+    |index: Option<usize>| match index.and_then(|index| tokens.get(index)) {
+      Some(token) if matches!(token.token, Token::Word(_)) => {
+        text_info.range_text(&token.range())
+      }
+      _ => "",
+    };
+  // whether each open paren, bracket, or brace is a paren
+  let mut open_parens = Vec::new();
+  for (i, current) in tokens.iter().enumerate() {
+    let prev = i.checked_sub(1);
+    let next = Some(i + 1);
+    match &current.token {
+      Token::LParen => open_parens.push(true),
+      Token::LBracket | Token::LBrace | Token::DollarLBrace => {
+        open_parens.push(false)
+      }
+      Token::RParen | Token::RBracket | Token::RBrace => {
+        open_parens.pop();
+      }
+      _ => {}
+    }
+    match &current.token {
+      // ex. `function f(a: string, b?: number)`
+      Token::Colon if open_parens.last() == Some(&true) => {
+        let name_index = if get_token(prev) == Some(&Token::QuestionMark) {
+          prev.and_then(|prev| prev.checked_sub(1))
+        } else {
+          prev
+        };
+        let is_param_name = !get_word(name_index).is_empty()
+          && matches!(
+            get_token(name_index.and_then(|index| index.checked_sub(1))),
+            Some(Token::LParen | Token::Comma)
+          );
+        if is_param_name {
+          return true;
+        }
+      }
+      // ex. `function f(): string {` or `(): void =>`
+      Token::RParen
+        if get_token(next) == Some(&Token::Colon)
+          && is_primitive_type(get_word(Some(i + 2)))
+          && matches!(
+            get_token(Some(i + 3)),
+            Some(Token::LBrace | Token::Arrow)
+          ) =>
+      {
+        return true;
+      }
+      Token::Word(_) => {
+        let is_statement_start = prev.is_none()
+          || current.had_line_break
+          || matches!(get_token(prev), Some(Token::Semi | Token::RBrace))
+          || matches!(get_word(prev), "export" | "declare");
+        let has_name = !get_word(next).is_empty()
+          && tokens.get(i + 1).is_some_and(|next| !next.had_line_break);
+        let after_name = get_token(Some(i + 2));
+        let is_typescript = match get_word(Some(i)) {
+          // ex. `const a: string = ""`
+          "let" | "const" | "var" => {
+            has_name && after_name == Some(&Token::Colon)
+          }
+          "interface" => {
+            is_statement_start
+              && has_name
+              && (matches!(
+                after_name,
+                Some(Token::LBrace | Token::BinOp(BinOpToken::Lt))
+              ) || get_word(Some(i + 2)) == "extends")
+          }
+          "enum" => {
+            is_statement_start && has_name && after_name == Some(&Token::LBrace)
+          }
+          "type" => {
+            is_statement_start
+              && has_name
+              && matches!(
+                after_name,
+                Some(
+                  Token::AssignOp(AssignOp::Assign)
+                    | Token::BinOp(BinOpToken::Lt)
+                )
+              )
+          }
+          "declare" => {
+            is_statement_start
+              && has_name
+              && matches!(
+                get_word(next),
+                "const"
+                  | "let"
+                  | "var"
+                  | "function"
+                  | "class"
+                  | "module"
+                  | "namespace"
+                  | "global"
+              )
+          }
+          _ => false,
+        };
+        if is_typescript {
+          return true;
+        }
+      }
+      _ => {}
+    }
+  }
+  false
+}
+
+fn is_primitive_type(text: &str) -> bool {
+  matches!(
+    text,
+    "string" | "number" | "boolean" | "void" | "any" | "unknown" | "never"
+  )
+}
+
+/// Gets the media type from the extension of the path of a specifier.
+///
+/// JSONC and JSON5 (`.jsonc` and `.json5`) are `MediaType::Json`. Data
+/// URLs use their MIME type (ex. `data:application/typescript,...`),
+/// which defaults to `text/plain` and so `MediaType::Unknown`. Blob
+/// URLs use the extension of the URL they wrap, if any.
+pub fn media_type_from_specifier(specifier: &str) -> MediaType {
+  if let Some(mime_type) = get_data_url_mime_type(specifier) {
+    // data urls have no extension to fall back to
+    return media_type_from_content_type("", mime_type);
+  }
+  if let Some(inner_specifier) = strip_scheme(specifier, "blob") {
+    return media_type_from_specifier(inner_specifier);
+  }
+  if is_jsonc_specifier(specifier) {
+    return MediaType::Json;
+  }
+  media_type_from_path_str(&get_specifier_path(specifier))
+}
+
+/// Gets the MIME type of a `data:<mime>[;base64],<data>` URL.
+///
+/// The MIME type ends at the first comma, so percent-encoded commas in
+/// the data are not mistaken for it.
+fn get_data_url_mime_type(specifier: &str) -> Option<&str> {
+  let rest = strip_scheme(specifier, "data")?;
+  let mime_type = match rest.split_once(',') {
+    Some((mime_type, _)) => mime_type,
+    None => rest,
+  };
+  let mime_type = mime_type.trim();
+  if mime_type.is_empty() || mime_type.starts_with(';') {
+    Some("text/plain")
+  } else {
+    Some(mime_type)
+  }
+}
+
+/// Gets the rest of a specifier after a scheme, which is matched
+/// case-insensitively.
+fn strip_scheme<'a>(specifier: &'a str, scheme: &str) -> Option<&'a str> {
+  let (specifier_scheme, rest) = specifier.split_once(':')?;
+  specifier_scheme
+    .eq_ignore_ascii_case(scheme)
+    .then_some(rest)
+}
+
+/// Gets if the path of a specifier has a JSONC or JSON5 extension.
+pub(crate) fn is_jsonc_specifier(specifier: &str) -> bool {
+  let path = get_specifier_path(specifier);
+  let extension = Path::new(&*path)
+    .extension()
+    .and_then(|extension| extension.to_str());
+  matches!(extension, Some(extension) if extension.eq_ignore_ascii_case("jsonc")
+    || extension.eq_ignore_ascii_case("json5"))
+}
+
+/// Gets the path of a specifier for detecting its extension.
+///
+/// The query string and fragment of URLs are ignored and the path is
+/// percent-decoded. Data URLs are left as-is because their media type
+/// comes from their content type rather than a path.
+fn get_specifier_path(specifier: &str) -> Cow<str> {
+  let has_url_scheme = match specifier.split_once(':') {
+    // a single character scheme is a Windows drive letter
+    Some((scheme, _)) => {
+      scheme.len() > 1
+        && scheme != "data"
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    }
+    None => false,
+  };
+  if !has_url_scheme {
+    return Cow::Borrowed(specifier);
+  }
+  let path = match specifier.find(['?', '#']) {
+    Some(index) => &specifier[..index],
+    None => specifier,
+  };
+  match crate::types::percent_decode(path) {
+    Some(path) => Cow::Owned(path),
+    None => Cow::Borrowed(path),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn round_trips_media_type_names() {
+    for (media_type, name) in MEDIA_TYPE_NAMES {
+      assert_eq!(media_type_name(media_type), name);
+      assert_eq!(media_type_from_name(name), media_type);
+      assert_eq!(try_media_type_from_name(name), Some(media_type));
+    }
+  }
+
+  #[test]
+  fn unrecognized_media_type_name() {
+    for name in ["typescript6", "TypeScript", ""] {
+      assert_eq!(try_media_type_from_name(name), None);
+      assert_eq!(media_type_from_name(name), MediaType::Unknown);
+    }
+  }
+
+  #[test]
+  fn media_type_from_paths() {
+    for (path, media_type) in [
+      ("a.ts", MediaType::TypeScript),
+      ("a.d.ts", MediaType::Dts),
+      ("a.d.mts", MediaType::Dmts),
+      ("a.d.cts", MediaType::Dcts),
+      ("a.test.d.ts", MediaType::Dts),
+      ("a.D.TS", MediaType::Dts),
+      ("Foo.TS", MediaType::TypeScript),
+      ("a.MJS", MediaType::Mjs),
+      ("a.d.ts.map", MediaType::SourceMap),
+      ("a.d.ts.bak", MediaType::Unknown),
+      ("a.ts.old", MediaType::Unknown),
+      ("a.d.js", MediaType::JavaScript),
+      ("a.d", MediaType::Unknown),
+      ("a.", MediaType::Unknown),
+      ("a", MediaType::Unknown),
+      (".ts", MediaType::Unknown),
+      (".d.ts", MediaType::TypeScript),
+      (".tsbuildinfo", MediaType::TsBuildInfo),
+      ("tsconfig.tsbuildinfo", MediaType::TsBuildInfo),
+      ("/dir.d/a.ts", MediaType::TypeScript),
+      ("/dir.ts/a", MediaType::Unknown),
+      ("C:\\dir\\a.d.ts", MediaType::Dts),
+      ("C:\\dir.js\\a", MediaType::Unknown),
+    ] {
+      assert_eq!(
+        media_type_from_path(Path::new(path)),
+        media_type,
+        "{}",
+        path
+      );
+    }
+    assert_eq!(
+      media_type_from_specifier("https://deno.land/x/Mod.D.MTS?v=1"),
+      MediaType::Dmts
+    );
+  }
+
+  #[test]
+  fn resolves_registered_extensions() {
+    let mut resolver = MediaTypeResolver::new();
+    resolver
+      .register_extension(".mjsx", MediaType::Jsx, false)
+      .unwrap();
+    resolver
+      .register_extension("VUE", MediaType::TypeScript, false)
+      .unwrap();
+    assert_eq!(
+      resolver.resolve_path(Path::new("/dir/mod.mjsx")),
+      MediaType::Jsx
+    );
+    assert_eq!(
+      resolver.resolve_specifier("https://example.com/App.Vue?v=1"),
+      MediaType::TypeScript
+    );
+    assert_eq!(
+      resolver.resolve_specifier("blob:https://example.com/mod.mjsx"),
+      MediaType::Jsx
+    );
+    // everything else uses the built-in detection
+    assert_eq!(
+      resolver.resolve_path(Path::new("/dir/mod.d.ts")),
+      MediaType::Dts
+    );
+    assert_eq!(
+      resolver.resolve_specifier("file:///mod.jsonc"),
+      MediaType::Json
+    );
+    assert_eq!(
+      resolver.resolve_specifier("data:text/javascript,a.mjsx"),
+      MediaType::JavaScript
+    );
+    assert_eq!(
+      resolver.resolve_path(Path::new("/dir/mod.svelte")),
+      MediaType::Unknown
+    );
+    assert_eq!(
+      media_type_from_path(Path::new("/dir/mod.mjsx")),
+      MediaType::Unknown
+    );
+
+    // built-ins win by default
+    for extension in ["ts", ".JS", "jsonc", "tsbuildinfo"] {
+      let err = resolver
+        .register_extension(extension, MediaType::Tsx, false)
+        .unwrap_err();
+      assert!(
+        matches!(err, RegisterExtensionError::BuiltIn { .. }),
+        "{}",
+        extension
+      );
+    }
+    assert_eq!(
+      resolver
+        .register_extension(".ts", MediaType::Tsx, false)
+        .unwrap_err()
+        .to_string(),
+      "Extension '.ts' is built in as 'typescript' and overriding it was not allowed."
+    );
+    assert_eq!(
+      resolver.resolve_path(Path::new("/dir/mod.ts")),
+      MediaType::TypeScript
+    );
+    resolver
+      .register_extension(".ts", MediaType::Tsx, true)
+      .unwrap();
+    assert_eq!(
+      resolver.resolve_path(Path::new("/dir/mod.ts")),
+      MediaType::Tsx
+    );
+
+    for extension in ["", ".", "d.vue", "a/b"] {
+      assert!(
+        matches!(
+          resolver.register_extension(extension, MediaType::Jsx, false),
+          Err(RegisterExtensionError::Invalid(_))
+        ),
+        "{}",
+        extension
+      );
+    }
+  }
+
+  #[test]
+  fn media_type_from_package_type() {
+    // (path, module, commonjs, none)
+    let cases = [
+      (
+        "mod.js",
+        MediaType::Mjs,
+        MediaType::Cjs,
+        MediaType::JavaScript,
+      ),
+      (
+        "mod.ts",
+        MediaType::Mts,
+        MediaType::Cts,
+        MediaType::TypeScript,
+      ),
+      ("mod.d.ts", MediaType::Dmts, MediaType::Dcts, MediaType::Dts),
+      ("mod.mjs", MediaType::Mjs, MediaType::Mjs, MediaType::Mjs),
+      ("mod.cjs", MediaType::Cjs, MediaType::Cjs, MediaType::Cjs),
+      ("mod.mts", MediaType::Mts, MediaType::Mts, MediaType::Mts),
+      ("mod.cts", MediaType::Cts, MediaType::Cts, MediaType::Cts),
+      (
+        "mod.d.mts",
+        MediaType::Dmts,
+        MediaType::Dmts,
+        MediaType::Dmts,
+      ),
+      (
+        "mod.d.cts",
+        MediaType::Dcts,
+        MediaType::Dcts,
+        MediaType::Dcts,
+      ),
+      ("mod.jsx", MediaType::Jsx, MediaType::Jsx, MediaType::Jsx),
+      ("mod.tsx", MediaType::Tsx, MediaType::Tsx, MediaType::Tsx),
+      (
+        "package.json",
+        MediaType::Json,
+        MediaType::Json,
+        MediaType::Json,
+      ),
+      (
+        "mod.wasm",
+        MediaType::Wasm,
+        MediaType::Wasm,
+        MediaType::Wasm,
+      ),
+      (
+        "mod",
+        MediaType::Unknown,
+        MediaType::Unknown,
+        MediaType::Unknown,
+      ),
+    ];
+    for (path, module, commonjs, none) in cases {
+      let path = Path::new("/node_modules/package").join(path);
+      for (package_type, expected) in [
+        (PackageJsonType::Module, module),
+        (PackageJsonType::CommonJs, commonjs),
+        (PackageJsonType::None, none),
+      ] {
+        assert_eq!(
+          media_type_from_path_with_package_type(&path, package_type),
+          expected,
+          "{} {:?}",
+          path.display(),
+          package_type
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn media_type_predicates() {
+    // (media type, declaration, jsx, typed, javascript-like, emittable)
+    let cases = [
+      (MediaType::JavaScript, false, false, false, true, false),
+      (MediaType::Jsx, false, true, false, true, true),
+      (MediaType::Mjs, false, false, false, true, false),
+      (MediaType::Cjs, false, false, false, true, false),
+      (MediaType::TypeScript, false, false, true, false, true),
+      (MediaType::Mts, false, false, true, false, true),
+      (MediaType::Cts, false, false, true, false, true),
+      (MediaType::Dts, true, false, true, false, false),
+      (MediaType::Dmts, true, false, true, false, false),
+      (MediaType::Dcts, true, false, true, false, false),
+      (MediaType::Tsx, false, true, true, false, true),
+      (MediaType::Json, false, false, false, false, false),
+      (MediaType::Wasm, false, false, false, false, false),
+      (MediaType::TsBuildInfo, false, false, false, false, false),
+      (MediaType::SourceMap, false, false, false, false, false),
+      (MediaType::Unknown, false, false, false, false, false),
+    ];
+    // every media type is in the table
+    assert_eq!(cases.len(), MEDIA_TYPE_NAMES.len());
+    for (media_type, declaration, jsx, typed, javascript_like, emittable) in
+      cases
+    {
+      assert_eq!(
+        (
+          is_declaration_media_type(media_type),
+          is_jsx_media_type(media_type),
+          is_typed_media_type(media_type),
+          is_javascript_like_media_type(media_type),
+          is_emittable_media_type(media_type),
+        ),
+        (declaration, jsx, typed, javascript_like, emittable),
+        "{:?}",
+        media_type
+      );
+      assert_eq!(
+        is_emittable_media_type(media_type),
+        media_type_for_output(media_type) != media_type
+      );
+    }
+  }
+
+  #[test]
+  fn media_type_extensions_and_content_types() {
+    for (media_type, extension, content_type) in [
+      (MediaType::JavaScript, ".js", "text/javascript"),
+      (MediaType::Jsx, ".jsx", "text/jsx"),
+      (MediaType::Mjs, ".mjs", "text/javascript"),
+      (MediaType::Cjs, ".cjs", "text/javascript"),
+      (MediaType::TypeScript, ".ts", "application/typescript"),
+      (MediaType::Mts, ".mts", "application/typescript"),
+      (MediaType::Cts, ".cts", "application/typescript"),
+      (MediaType::Dts, ".d.ts", "application/typescript"),
+      (MediaType::Dmts, ".d.mts", "application/typescript"),
+      (MediaType::Dcts, ".d.cts", "application/typescript"),
+      (MediaType::Tsx, ".tsx", "text/tsx"),
+      (MediaType::Json, ".json", "application/json"),
+      (MediaType::Wasm, ".wasm", "application/wasm"),
+      (MediaType::TsBuildInfo, ".tsbuildinfo", "application/json"),
+      (MediaType::SourceMap, ".map", "application/json"),
+      (MediaType::Unknown, ".js", "application/octet-stream"),
+    ] {
+      assert_eq!(media_type_extension(media_type), extension);
+      assert_eq!(media_type_content_type(media_type), content_type);
+    }
+
+    // the content types map back to the media type for code
+    for (media_type, _) in MEDIA_TYPE_NAMES {
+      if matches!(
+        media_type,
+        MediaType::TsBuildInfo | MediaType::SourceMap | MediaType::Unknown
+      ) {
+        continue;
+      }
+      let specifier = format!(
+        "https://deno.land/x/mod{}",
+        media_type_extension(media_type)
+      );
+      assert_eq!(media_type_from_specifier(&specifier), media_type);
+      assert_eq!(
+        media_type_from_content_type(
+          &specifier,
+          media_type_content_type(media_type)
+        ),
+        media_type
+      );
+    }
+  }
+
+  #[test]
+  fn media_types_for_output() {
+    for (media_type, output_media_type) in [
+      (MediaType::TypeScript, MediaType::JavaScript),
+      (MediaType::Tsx, MediaType::JavaScript),
+      (MediaType::Jsx, MediaType::JavaScript),
+      (MediaType::Mts, MediaType::Mjs),
+      (MediaType::Cts, MediaType::Cjs),
+      (MediaType::Dts, MediaType::Dts),
+      (MediaType::Dmts, MediaType::Dmts),
+      (MediaType::Dcts, MediaType::Dcts),
+      (MediaType::Mjs, MediaType::Mjs),
+      (MediaType::Cjs, MediaType::Cjs),
+      (MediaType::Json, MediaType::Json),
+    ] {
+      assert_eq!(media_type_for_output(media_type), output_media_type);
+    }
+  }
+
+  #[cfg(feature = "module_specifier")]
+  #[test]
+  fn media_type_from_specifier_and_headers_precedence() {
+    use std::collections::HashMap;
+
+    use crate::ModuleSpecifier;
+
+    fn get(specifier: &str, maybe_content_type: Option<&str>) -> MediaType {
+      let specifier = ModuleSpecifier::parse(specifier).unwrap();
+      let headers = maybe_content_type.map(|content_type| {
+        HashMap::from([("Content-Type".to_string(), content_type.to_string())])
+      });
+      media_type_from_specifier_and_headers(&specifier, headers.as_ref())
+    }
+
+    // header wins
+    assert_eq!(
+      get("https://deno.land/x/mod.ts", Some("application/javascript")),
+      MediaType::JavaScript
+    );
+    assert_eq!(
+      get("https://deno.land/x/mod", Some("application/typescript")),
+      MediaType::TypeScript
+    );
+    assert_eq!(
+      get(
+        "https://deno.land/x/mod.d.ts",
+        Some("Application/TypeScript; charset=utf-8")
+      ),
+      MediaType::Dts
+    );
+    // generic content types defer to the extension
+    assert_eq!(
+      get("https://deno.land/x/mod.tsx", Some("text/plain")),
+      MediaType::Tsx
+    );
+    assert_eq!(
+      get(
+        "https://deno.land/x/mod.mjs",
+        Some("application/octet-stream")
+      ),
+      MediaType::Mjs
+    );
+    // no headers
+    assert_eq!(get("file:///mod.cts", None), MediaType::Cts);
+    assert_eq!(get("https://deno.land/x/mod", None), MediaType::Unknown);
+    // data urls
+    assert_eq!(
+      get("data:application/typescript;base64,ZXhwb3J0IHt9Ow==", None),
+      MediaType::TypeScript
+    );
+    assert_eq!(
+      get("data:text/javascript,export%20{}", Some("application/json")),
+      MediaType::JavaScript
+    );
+    assert_eq!(get("data:,export%20{}", None), MediaType::Unknown);
+
+    // header names are case-insensitive
+    let specifier = ModuleSpecifier::parse("https://deno.land/x/mod").unwrap();
+    let headers = HashMap::from([(
+      "content-type".to_string(),
+      "application/json".to_string(),
+    )]);
+    assert_eq!(
+      media_type_from_specifier_and_headers(&specifier, Some(&headers)),
+      MediaType::Json
+    );
+  }
+
+  #[test]
+  fn media_type_from_data_and_blob_urls() {
+    for (specifier, media_type) in [
+      (
+        "data:application/typescript,export{}",
+        MediaType::TypeScript,
+      ),
+      (
+        "DATA:text/javascript;charset=utf-8,export{}",
+        MediaType::JavaScript,
+      ),
+      ("data:application/json;base64,e30=", MediaType::Json),
+      ("data:text/tsx,export%2C{}", MediaType::Tsx),
+      // missing mime types are text/plain
+      ("data:;base64,ZXhwb3J0IHt9Ow==", MediaType::Unknown),
+      ("data:,export{}", MediaType::Unknown),
+      ("data:text/plain,a.ts", MediaType::Unknown),
+      // blob urls use the extension of the inner url
+      ("blob:https://example.com/uuid", MediaType::Unknown),
+      ("blob:https://example.com/mod.ts", MediaType::TypeScript),
+    ] {
+      assert_eq!(
+        media_type_from_specifier(specifier),
+        media_type,
+        "{}",
+        specifier
+      );
+    }
+  }
+
+  #[test]
+  fn maps_content_types() {
+    for (specifier, content_type, media_type) in [
+      (
+        "https://deno.land/x/mod",
+        "application/typescript",
+        MediaType::TypeScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "text/typescript",
+        MediaType::TypeScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "video/mp2t",
+        MediaType::TypeScript,
+      ),
+      (
+        "https://deno.land/x/mod.d.ts",
+        "application/typescript",
+        MediaType::Dts,
+      ),
+      (
+        "https://deno.land/x/mod.mts",
+        "application/typescript",
+        MediaType::Mts,
+      ),
+      (
+        "https://deno.land/x/mod.js",
+        "application/typescript",
+        MediaType::TypeScript,
+      ),
+      ("https://deno.land/x/mod", "text/tsx", MediaType::Tsx),
+      ("https://deno.land/x/mod", "text/jsx", MediaType::Jsx),
+      (
+        "https://deno.land/x/mod",
+        "application/javascript",
+        MediaType::JavaScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "text/javascript",
+        MediaType::JavaScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "application/node",
+        MediaType::JavaScript,
+      ),
+      (
+        "https://deno.land/x/mod.mjs",
+        "text/javascript",
+        MediaType::Mjs,
+      ),
+      (
+        "https://deno.land/x/mod.ts",
+        "text/javascript",
+        MediaType::JavaScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "application/json",
+        MediaType::Json,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "application/wasm",
+        MediaType::Wasm,
+      ),
+      (
+        "https://deno.land/x/mod.ts",
+        "text/html",
+        MediaType::Unknown,
+      ),
+      ("https://deno.land/x/mod.ts", "", MediaType::Unknown),
+    ] {
+      assert_eq!(
+        media_type_from_content_type(specifier, content_type),
+        media_type,
+        "{} {}",
+        specifier,
+        content_type,
+      );
+    }
+  }
+
+  #[test]
+  fn ignores_parameters_case_and_whitespace() {
+    for content_type in [
+      "application/typescript; charset=utf-8",
+      "  Application/TypeScript  ",
+      "APPLICATION/TYPESCRIPT;charset=UTF-8",
+    ] {
+      assert_eq!(
+        media_type_from_content_type("https://deno.land/x/mod", content_type),
+        MediaType::TypeScript,
+        "{}",
+        content_type,
+      );
+    }
+  }
+
+  #[test]
+  fn falls_back_to_specifier_for_generic_content_types() {
+    for (specifier, content_type, media_type) in [
+      (
+        "https://deno.land/x/mod.ts",
+        "text/plain",
+        MediaType::TypeScript,
+      ),
+      (
+        "https://deno.land/x/mod.tsx?v=1",
+        "text/plain; charset=utf-8",
+        MediaType::Tsx,
+      ),
+      (
+        "https://deno.land/x/mod.js",
+        "application/octet-stream",
+        MediaType::JavaScript,
+      ),
+      (
+        "https://deno.land/x/mod",
+        "application/octet-stream",
+        MediaType::Unknown,
+      ),
+    ] {
+      assert_eq!(
+        media_type_from_content_type(specifier, content_type),
+        media_type,
+        "{} {}",
+        specifier,
+        content_type,
+      );
+    }
+  }
+
+  #[test]
+  fn detects_json_content() {
+    let text = "{\n  \"name\": \"bundle\",\n  \"values\": [1, 2]\n}\n";
+    let specifier = "https://example.com/api/bundle";
+    assert_eq!(
+      detect_media_type_from_content(specifier, None, text),
+      MediaType::Json
+    );
+    assert_eq!(
+      detect_media_type_from_content(specifier, Some("text/plain"), text),
+      MediaType::Json
+    );
+    assert_eq!(
+      detect_media_type_from_content(specifier, None, "[1, 2].map(f);"),
+      MediaType::JavaScript
+    );
+    // the specifier and content type are used first
+    assert_eq!(
+      detect_media_type_from_content("https://example.com/data.js", None, text),
+      MediaType::JavaScript
+    );
+  }
+
+  #[test]
+  fn detects_typescript_content() {
+    let specifier = "https://example.com/bin/script";
+    for text in [
+      "#!/usr/bin/env -S deno run\nconst name: string = Deno.args[0];\n",
+      "/// <reference types=\"node\" />\nexport default 1;\n",
+      "export interface Options {\n  name: string;\n}\n",
+      "type Id = string | number;\n",
+      "enum Color { Red }\n",
+      "declare const VERSION;\n",
+      "function greet(name?: string) {}\n",
+      "export const f = (): void => {};\n",
+    ] {
+      assert_eq!(
+        detect_media_type_from_content(specifier, None, text),
+        MediaType::TypeScript,
+        "{}",
+        text,
+      );
+    }
+    assert_eq!(
+      detect_media_type_from_content(
+        specifier,
+        None,
+        "const a = <div>{name}</div>;\n"
+      ),
+      MediaType::Jsx
+    );
+    assert_eq!(
+      detect_media_type_from_content(
+        specifier,
+        None,
+        "const a: string = \"\";\nconst b = <div />;\n"
+      ),
+      MediaType::Tsx
+    );
+  }
+
+  #[test]
+  fn does_not_upgrade_javascript_content() {
+    let text = concat!(
+      "#!/usr/bin/env node\n",
+      "// @ts-check\n",
+      "/// <reference path=\"./globals.d.ts\" />\n",
+      "const type = { kind: \"a\", value: cond ? a : b };\n",
+      "const result = call(x, y ? z : w, { key: string });\n",
+      "switch (type.kind) {\n",
+      "  case \"a\":\n",
+      "    break;\n",
+      "}\n",
+      "outer: for (const item of items) {\n",
+      "  continue outer;\n",
+      "}\n",
+      "function f(a, b = { c: d }) {\n",
+      "  return a ? (b) : c;\n",
+      "}\n",
+      "const interfaces = [1, 2].map((n) => n < 2);\n",
+    );
+    assert_eq!(
+      detect_media_type_from_content("https://example.com/cli", None, text),
+      MediaType::JavaScript
+    );
+  }
+}
@@ -0,0 +1,259 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::mem::size_of;
+use std::mem::size_of_val;
+use std::sync::Arc;
+
+use crate::swc::ast::*;
+use crate::swc::common::comments::Comment;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::swc::visit::Visit;
+use crate::swc::visit::VisitWith;
+use crate::MultiThreadedComments;
+use crate::ParsedSource;
+
+/// Estimated memory used by a `ParsedSource` in bytes.
+///
+/// The numbers are estimates that increase with the size of the source,
+/// which is useful for evicting sources from a cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+  /// Length of the source text.
+  pub text_bytes: usize,
+  /// Estimate of the AST, which includes the nodes and their strings.
+  pub program_bytes_estimate: usize,
+  /// Captured or lexed tokens, which is zero when the source has none.
+  pub tokens_bytes: usize,
+  /// Comments and their text.
+  pub comments_bytes: usize,
+  /// Sum of the other fields.
+  pub total: usize,
+  /// Whether the program or tokens are also referenced outside of the
+  /// parsed source (ex. from `ParsedSource::program()`), so dropping the
+  /// parsed source won't free all of this memory. Shared data is still
+  /// only counted once.
+  pub has_shared_data: bool,
+}
+
+impl ParsedSource {
+  /// Estimates the memory used by this parsed source.
+  pub fn memory_usage(&self) -> MemoryUsage {
+    let program = self.program();
+    // excludes the reference above and the one held by this source
+    let mut has_shared_data = Arc::strong_count(&program) > 2;
+    let program_bytes_estimate = estimate_program_bytes(&program);
+    let tokens_bytes = match self.held_tokens() {
+      Some(tokens) => {
+        has_shared_data |= Arc::strong_count(tokens) > 1;
+        tokens_bytes(tokens)
+      }
+      None => 0,
+    };
+    let text_bytes = self.text_info().text_str().len();
+    let comments_bytes = comments_bytes(self.comments());
+    MemoryUsage {
+      text_bytes,
+      program_bytes_estimate,
+      tokens_bytes,
+      comments_bytes,
+      total: text_bytes
+        + program_bytes_estimate
+        + tokens_bytes
+        + comments_bytes,
+      has_shared_data,
+    }
+  }
+}
+
+fn estimate_program_bytes(program: &Program) -> usize {
+  let mut estimator = ProgramBytesEstimator { bytes: 0 };
+  program.visit_with(&mut estimator);
+  size_of::<Program>() + estimator.bytes
+}
+
+fn tokens_bytes(tokens: &Vec<TokenAndSpan>) -> usize {
+  size_of::<Vec<TokenAndSpan>>() + tokens.capacity() * size_of::<TokenAndSpan>()
+}
+
+fn comments_bytes(comments: &MultiThreadedComments) -> usize {
+  [comments.leading_map(), comments.trailing_map()]
+    .into_iter()
+    .flat_map(|map| map.iter())
+    .map(|(pos, comments)| {
+      size_of_val(pos)
+        + size_of::<Vec<Comment>>()
+        + comments.capacity() * size_of::<Comment>()
+        + comments
+          .iter()
+          .map(|comment| comment.text.len())
+          .sum::<usize>()
+    })
+    .sum()
+}
+
+/// Sums the sizes of the AST nodes that are stored separately from
+/// their parent (ex. boxed or in a vector) and the strings in them.
+struct ProgramBytesEstimator {
+  bytes: usize,
+}
+
+impl Visit for ProgramBytesEstimator {
+  fn visit_module_item(&mut self, n: &ModuleItem) {
+    self.bytes += size_of::<ModuleItem>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_stmt(&mut self, n: &Stmt) {
+    self.bytes += size_of::<Stmt>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_decl(&mut self, n: &Decl) {
+    self.bytes += size_of::<Decl>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_expr(&mut self, n: &Expr) {
+    self.bytes += size_of::<Expr>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_pat(&mut self, n: &Pat) {
+    self.bytes += size_of::<Pat>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_prop(&mut self, n: &Prop) {
+    self.bytes += size_of::<Prop>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_class_member(&mut self, n: &ClassMember) {
+    self.bytes += size_of::<ClassMember>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_function(&mut self, n: &Function) {
+    self.bytes += size_of::<Function>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_class(&mut self, n: &Class) {
+    self.bytes += size_of::<Class>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_ts_type(&mut self, n: &TsType) {
+    self.bytes += size_of::<TsType>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_ts_type_element(&mut self, n: &TsTypeElement) {
+    self.bytes += size_of::<TsTypeElement>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_jsx_element_child(&mut self, n: &JSXElementChild) {
+    self.bytes += size_of::<JSXElementChild>();
+    n.visit_children_with(self);
+  }
+
+  fn visit_ident(&mut self, n: &Ident) {
+    self.bytes += n.sym.len();
+  }
+
+  fn visit_str(&mut self, n: &Str) {
+    self.bytes +=
+      n.value.len() + n.raw.as_ref().map(|raw| raw.len()).unwrap_or(0);
+  }
+
+  fn visit_tpl_element(&mut self, n: &TplElement) {
+    self.bytes += size_of::<TplElement>()
+      + n.raw.len()
+      + n.cooked.as_ref().map(|cooked| cooked.len()).unwrap_or(0);
+  }
+
+  fn visit_jsx_text(&mut self, n: &JSXText) {
+    self.bytes += n.value.len() + n.raw.len();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str, capture_tokens: bool) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(capture_tokens)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  const TEXT: &str = concat!(
+    "// comment\n",
+    "export class A<T> { b: T[] = []; c(d: string) { return `${d}e`; } }\n",
+  );
+
+  #[test]
+  fn larger_sources_use_more() {
+    let small = parse(TEXT, true).memory_usage();
+    let large = parse(&TEXT.repeat(10), true).memory_usage();
+    assert!(large.text_bytes > small.text_bytes);
+    assert!(large.program_bytes_estimate > small.program_bytes_estimate);
+    assert!(large.tokens_bytes > small.tokens_bytes);
+    assert!(large.comments_bytes > small.comments_bytes);
+    assert!(large.total > small.total);
+    assert_eq!(
+      small.total,
+      small.text_bytes
+        + small.program_bytes_estimate
+        + small.tokens_bytes
+        + small.comments_bytes
+    );
+  }
+
+  #[test]
+  fn tokens() {
+    let with_tokens = parse(TEXT, true).memory_usage();
+    let parsed_source = parse(TEXT, false);
+    let without_tokens = parsed_source.memory_usage();
+    assert!(with_tokens.tokens_bytes > 0);
+    assert_eq!(without_tokens.tokens_bytes, 0);
+    assert_eq!(
+      without_tokens.program_bytes_estimate,
+      with_tokens.program_bytes_estimate
+    );
+    assert!(without_tokens.total < with_tokens.total);
+
+    // lexed tokens are held by the source
+    parsed_source.tokens_or_lex();
+    assert!(parsed_source.memory_usage().tokens_bytes > 0);
+  }
+
+  #[test]
+  fn shared_data() {
+    let parsed_source = parse(TEXT, true);
+    assert!(!parsed_source.memory_usage().has_shared_data);
+    // clones of the parsed source aren't other references
+    let clone = parsed_source.clone();
+    assert!(!parsed_source.memory_usage().has_shared_data);
+    let program = parsed_source.program();
+    assert!(parsed_source.memory_usage().has_shared_data);
+    drop(program);
+    drop(clone);
+    assert!(!parsed_source.memory_usage().has_shared_data);
+  }
+}
@@ -0,0 +1,173 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fmt;
+
+use miette::LabeledSpan;
+use miette::MietteError;
+use miette::MietteSpanContents;
+use miette::Severity;
+use miette::SourceCode;
+use miette::SourceSpan;
+use miette::SpanContents;
+
+use crate::Diagnostic;
+use crate::DiagnosticSeverity;
+
+/// Diagnostic that can be rendered by miette with a snippet of the
+/// source text it occurred in.
+///
+/// This wraps a `Diagnostic` because the `Display` implementation of a
+/// `Diagnostic` already includes a snippet, which would otherwise be
+/// rendered twice.
+#[derive(Debug, Clone)]
+pub struct MietteDiagnostic(Diagnostic);
+
+impl MietteDiagnostic {
+  /// Gets the wrapped diagnostic.
+  pub fn diagnostic(&self) -> &Diagnostic {
+    &self.0
+  }
+}
+
+impl From<Diagnostic> for MietteDiagnostic {
+  fn from(diagnostic: Diagnostic) -> Self {
+    MietteDiagnostic(diagnostic)
+  }
+}
+
+impl fmt::Display for MietteDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0.message())
+  }
+}
+
+impl std::error::Error for MietteDiagnostic {}
+
+impl miette::Diagnostic for MietteDiagnostic {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    Some(Box::new(self.0.code()))
+  }
+
+  fn severity(&self) -> Option<Severity> {
+    Some(match self.0.severity {
+      DiagnosticSeverity::Error => Severity::Error,
+      DiagnosticSeverity::Warning => Severity::Warning,
+      DiagnosticSeverity::Info => Severity::Advice,
+    })
+  }
+
+  fn source_code(&self) -> Option<&dyn SourceCode> {
+    Some(self)
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+    Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+      Some(self.0.message().into_owned()),
+      SourceSpan::from(self.0.byte_range()),
+    ))))
+  }
+}
+
+// Works the same as miette's `NamedSource`, but without copying the text.
+impl SourceCode for MietteDiagnostic {
+  fn read_span<'a>(
+    &'a self,
+    span: &SourceSpan,
+    context_lines_before: usize,
+    context_lines_after: usize,
+  ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+    let contents = self.0.text_info().text_str().read_span(
+      span,
+      context_lines_before,
+      context_lines_after,
+    )?;
+    Ok(Box::new(MietteSpanContents::new_named(
+      self.0.specifier().to_string(),
+      contents.data(),
+      *contents.span(),
+      contents.line(),
+      contents.column(),
+      contents.line_count(),
+    )))
+  }
+}
+
+/// Collection of diagnostics that miette renders as a single report.
+#[derive(Debug, Clone)]
+pub struct MietteDiagnostics(Vec<MietteDiagnostic>);
+
+impl MietteDiagnostics {
+  pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+    MietteDiagnostics(diagnostics.into_iter().map(Into::into).collect())
+  }
+
+  /// Gets the diagnostics in the report.
+  pub fn diagnostics(&self) -> &[MietteDiagnostic] {
+    &self.0
+  }
+}
+
+impl fmt::Display for MietteDiagnostics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.0.len() {
+      1 => write!(f, "Found 1 diagnostic"),
+      len => write!(f, "Found {} diagnostics", len),
+    }
+  }
+}
+
+impl std::error::Error for MietteDiagnostics {}
+
+impl miette::Diagnostic for MietteDiagnostics {
+  fn related<'a>(
+    &'a self,
+  ) -> Option<Box<dyn Iterator<Item = &'a dyn miette::Diagnostic> + 'a>> {
+    Some(Box::new(
+      self.0.iter().map(|d| d as &'a dyn miette::Diagnostic),
+    ))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use miette::NarratableReportHandler;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  #[test]
+  fn renders_report() {
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(
+          "let a = 0, let b = 1;\nlet c = 0, let d = 1;\n".to_string(),
+        ),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap();
+    let report = parsed_source.diagnostics_report();
+    let mut output = String::new();
+    // this handler doesn't output any ansi escape codes
+    NarratableReportHandler::new()
+      .render_report(&mut output, report.as_ref())
+      .unwrap();
+    let diagnostics = parsed_source.diagnostics_sorted(None);
+    assert!(diagnostics.len() >= 2);
+    assert!(
+      output.starts_with(&format!("Found {} diagnostics", diagnostics.len())),
+      "{}",
+      output
+    );
+    for diagnostic in diagnostics {
+      assert!(output.contains(diagnostic.code().as_str()), "{}", output);
+    }
+    assert!(output.contains("file:///mod.ts"), "{}", output);
+    assert!(output.contains("let a = 0, let b = 1;"), "{}", output);
+    assert!(output.contains("let c = 0, let d = 1;"), "{}", output);
+  }
+}
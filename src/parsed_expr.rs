@@ -0,0 +1,100 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::comments::MultiThreadedComments;
+use crate::swc::ast::Expr;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::Diagnostic;
+use crate::MediaType;
+use crate::SourceTextInfo;
+
+/// A parsed standalone expression containing the expression's AST,
+/// comments, and possibly tokens.
+#[derive(Clone)]
+pub struct ParsedExpr {
+  specifier: String,
+  media_type: MediaType,
+  text_info: SourceTextInfo,
+  comments: MultiThreadedComments,
+  expr: Box<Expr>,
+  tokens: Option<Arc<Vec<TokenAndSpan>>>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedExpr {
+  pub(crate) fn new(
+    specifier: String,
+    media_type: MediaType,
+    text_info: SourceTextInfo,
+    comments: MultiThreadedComments,
+    expr: Box<Expr>,
+    tokens: Option<Arc<Vec<TokenAndSpan>>>,
+    diagnostics: Vec<Diagnostic>,
+  ) -> Self {
+    ParsedExpr {
+      specifier,
+      media_type,
+      text_info,
+      comments,
+      expr,
+      tokens,
+      diagnostics,
+    }
+  }
+
+  /// Gets the specifier of the source.
+  pub fn specifier(&self) -> &str {
+    &self.specifier
+  }
+
+  /// Gets the media type of the source.
+  pub fn media_type(&self) -> MediaType {
+    self.media_type
+  }
+
+  /// Gets the text content of the source.
+  pub fn text_info(&self) -> &SourceTextInfo {
+    &self.text_info
+  }
+
+  /// Gets the parsed expression.
+  pub fn expr(&self) -> &Expr {
+    &self.expr
+  }
+
+  /// Takes the parsed expression.
+  pub fn into_expr(self) -> Box<Expr> {
+    self.expr
+  }
+
+  /// Gets the comments found in the source.
+  pub fn comments(&self) -> &MultiThreadedComments {
+    &self.comments
+  }
+
+  /// Gets the tokens found in the source.
+  ///
+  /// This will panic if tokens were not captured during parsing.
+  pub fn tokens(&self) -> &[TokenAndSpan] {
+    self
+      .tokens
+      .as_ref()
+      .expect("Tokens not found because they were not captured during parsing.")
+  }
+
+  /// Gets extra non-fatal diagnostics found while parsing.
+  pub fn diagnostics(&self) -> &Vec<Diagnostic> {
+    &self.diagnostics
+  }
+}
+
+impl fmt::Debug for ParsedExpr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("ParsedExpr")
+      .field("comments", &self.comments)
+      .field("expr", &self.expr)
+      .finish()
+  }
+}
@@ -2,19 +2,42 @@
 
 use std::fmt;
 use std::sync::Arc;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::comments::MultiThreadedComments;
+use crate::encoding::text_info_with_bom;
+use crate::parse;
+use crate::parse_json;
+use crate::parsing::capture_tokens;
 use crate::scope_analysis_transform;
+use crate::suppression::filter_diagnostics;
+use crate::swc::ast::EsVersion;
 use crate::swc::ast::Module;
 use crate::swc::ast::Program;
 use crate::swc::ast::Script;
 use crate::swc::common::comments::Comment;
 use crate::swc::common::SyntaxContext;
 use crate::swc::parser::token::TokenAndSpan;
+use crate::swc::parser::Syntax;
 use crate::Diagnostic;
+use crate::DiagnosticKind;
+use crate::DiagnosticSeverity;
+use crate::FilteredDiagnostics;
 use crate::MediaType;
+use crate::ParseMode;
+use crate::ParseParams;
+use crate::SourcePos;
+use crate::SourceRange;
 use crate::SourceRangedForSpanned;
 use crate::SourceTextInfo;
+use crate::SourceTextInfoRangeText;
+use crate::SuppressionRules;
+use crate::SyntaxOptions;
+
+const SYNTAX_CONTEXTS_NOT_FOUND_MESSAGE: &str = "Could not get syntax context because the source was not parsed with scope analysis.";
 
 #[derive(Clone)]
 pub(crate) struct SyntaxContexts {
@@ -22,6 +45,19 @@ pub(crate) struct SyntaxContexts {
   pub top_level: SyntaxContext,
 }
 
+/// Options used when parsing a source, which are stored in order
+/// to parse again in the same way.
+#[derive(Clone)]
+pub(crate) struct ParsedSourceOptions {
+  pub parse_mode: ParseMode,
+  pub syntax: Syntax,
+  pub syntax_options: Option<SyntaxOptions>,
+  pub es_version: EsVersion,
+  pub max_diagnostics: Option<usize>,
+  pub lint_deprecations: bool,
+  pub strip_bom: bool,
+}
+
 struct ParsedSourceInner {
   specifier: String,
   media_type: MediaType,
@@ -29,8 +65,78 @@ struct ParsedSourceInner {
   comments: MultiThreadedComments,
   program: Arc<Program>,
   tokens: Option<Arc<Vec<TokenAndSpan>>>,
+  lexed_tokens: OnceLock<Arc<Vec<TokenAndSpan>>>,
+  #[cfg(feature = "visit")]
+  has_top_level_await: OnceLock<bool>,
   syntax_contexts: Option<SyntaxContexts>,
   diagnostics: Vec<Diagnostic>,
+  options: ParsedSourceOptions,
+  module_detection_reason: ModuleDetectionReason,
+  fallback_diagnostics: Vec<Diagnostic>,
+  had_bom: bool,
+}
+
+/// Kind of program that was parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramKind {
+  Module,
+  Script,
+}
+
+/// Reason the source was parsed as a module or script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModuleDetectionReason {
+  /// The caller parsed with `ParseMode::Module` or `ParseMode::Script`.
+  ParseMode,
+  /// The media type is always a module (ex. `.mjs` or `.mts`).
+  MediaType,
+  /// Module syntax was found (ex. `import`, `export`, or top level `await`).
+  ModuleSyntax,
+  /// No module syntax was found, so it fell back to a script.
+  NoModuleSyntax,
+  /// Parsing as a module only failed because of errors that are
+  /// specific to modules, so it fell back to a script
+  /// (see `parse_module_or_script`).
+  ScriptFallback,
+}
+
+/// Information about a shebang (ex. `#!/usr/bin/env -S deno run`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShebangInfo {
+  /// Text of the shebang excluding the leading `#!`.
+  pub text: String,
+  /// Range of the shebang including the leading `#!`, but excluding
+  /// the line break.
+  pub range: SourceRange,
+}
+
+/// Parts of a parsed source (see `ParsedSource::into_parts`).
+pub struct ParsedSourceParts {
+  pub specifier: String,
+  pub media_type: MediaType,
+  pub text_info: SourceTextInfo,
+  pub comments: MultiThreadedComments,
+  pub program: Arc<Program>,
+  /// Tokens when they were captured during parsing.
+  pub tokens: Option<Arc<Vec<TokenAndSpan>>>,
+  /// Top level context when parsed with scope analysis.
+  pub top_level_context: Option<SyntaxContext>,
+  /// Unresolved context when parsed with scope analysis.
+  pub unresolved_context: Option<SyntaxContext>,
+  pub diagnostics: Vec<Diagnostic>,
+  options: ParsedSourceOptions,
+  module_detection_reason: ModuleDetectionReason,
+  fallback_diagnostics: Vec<Diagnostic>,
+  had_bom: bool,
+}
+
+impl ParsedSourceParts {
+  /// Gets the program for mutation, which clones it only when it's
+  /// shared (ex. by a reference from `ParsedSource::program()`).
+  pub fn program_mut(&mut self) -> &mut Program {
+    Arc::make_mut(&mut self.program)
+  }
 }
 
 /// A parsed source containing an AST, comments, and possibly tokens.
@@ -52,6 +158,9 @@ impl ParsedSource {
     tokens: Option<Arc<Vec<TokenAndSpan>>>,
     syntax_contexts: Option<SyntaxContexts>,
     diagnostics: Vec<Diagnostic>,
+    options: ParsedSourceOptions,
+    module_detection_reason: ModuleDetectionReason,
+    had_bom: bool,
   ) -> Self {
     ParsedSource {
       inner: Arc::new(ParsedSourceInner {
@@ -61,8 +170,15 @@ impl ParsedSource {
         comments,
         program,
         tokens,
+        lexed_tokens: OnceLock::new(),
+        #[cfg(feature = "visit")]
+        has_top_level_await: OnceLock::new(),
         syntax_contexts,
         diagnostics,
+        options,
+        module_detection_reason,
+        fallback_diagnostics: Vec::new(),
+        had_bom,
       }),
     }
   }
@@ -77,11 +193,59 @@ impl ParsedSource {
     self.inner.media_type
   }
 
+  /// Gets the mode the source was parsed with.
+  pub fn parse_mode(&self) -> ParseMode {
+    self.inner.options.parse_mode
+  }
+
+  /// Gets the options the source was parsed with.
+  #[cfg(feature = "cache")]
+  pub(crate) fn options(&self) -> &ParsedSourceOptions {
+    &self.inner.options
+  }
+
+  /// Gets if tokens were captured during parsing.
+  #[cfg(feature = "cache")]
+  pub(crate) fn has_captured_tokens(&self) -> bool {
+    self.inner.tokens.is_some()
+  }
+
+  /// Gets the captured tokens or the tokens that were lexed and cached
+  /// by `tokens_or_lex`.
+  #[cfg(feature = "visit")]
+  pub(crate) fn held_tokens(&self) -> Option<&Arc<Vec<TokenAndSpan>>> {
+    self
+      .inner
+      .tokens
+      .as_ref()
+      .or_else(|| self.inner.lexed_tokens.get())
+  }
+
   /// Gets the text content of the module.
   pub fn text_info(&self) -> &SourceTextInfo {
     &self.inner.text_info
   }
 
+  /// Gets if a byte order mark was stripped from the start of the text
+  /// when parsing with `ParseParamsBuilder::strip_bom` (ex. for an
+  /// emitter to add it back).
+  ///
+  /// Without that option, a byte order mark remains in the text. Note
+  /// that `SourceTextInfo::new` already strips one, in which case this
+  /// is `false`.
+  pub fn had_bom(&self) -> bool {
+    self.inner.had_bom
+  }
+
+  /// Gets the text of the provided range of the module.
+  ///
+  /// Panics when the range is outside of the text or not on character
+  /// boundaries. Use `SourceTextInfoRangeText::try_range_text` on the
+  /// text info for a checked version.
+  pub fn text_for_range(&self, range: &SourceRange) -> &str {
+    self.inner.text_info.range_text(range)
+  }
+
   /// Gets the parsed program.
   pub fn program(&self) -> Arc<Program> {
     self.inner.program.clone()
@@ -119,8 +283,72 @@ impl ParsedSource {
 
   /// Get the source's leading comments, where triple slash directives might
   /// be located.
+  ///
+  /// When the source has a shebang, these are the comments after it.
   pub fn get_leading_comments(&self) -> Option<&Vec<Comment>> {
-    self.inner.comments.get_leading(self.inner.program.start())
+    let pos = if self.has_shebang() {
+      self
+        .first_stmt_start()
+        .unwrap_or_else(|| self.inner.program.start())
+    } else {
+      self.inner.program.start()
+    };
+    self.inner.comments.get_leading(pos)
+  }
+
+  /// Gets the comments that are within a range (ex. a function's body)
+  /// sorted by position.
+  pub fn comments_in_range(&self, range: SourceRange) -> Vec<Comment> {
+    self.inner.comments.get_in_range(range)
+  }
+
+  /// Gets the comments that lead a position, which is the start of a
+  /// node or token.
+  pub fn leading_comments_at(&self, pos: SourcePos) -> Option<&Vec<Comment>> {
+    self.inner.comments.get_leading(pos)
+  }
+
+  /// Gets the comments that trail a position, which is the end of a
+  /// node or token.
+  ///
+  /// These include comments on the following lines. Use
+  /// `is_comment_on_same_line()` to only get the ones on the same line.
+  pub fn trailing_comments_at(&self, pos: SourcePos) -> Option<&Vec<Comment>> {
+    self.inner.comments.get_trailing(pos)
+  }
+
+  /// Gets the shebang at the start of the source if it exists.
+  pub fn shebang(&self) -> Option<ShebangInfo> {
+    if !self.has_shebang() {
+      return None;
+    }
+    let text_info = self.text_info();
+    let text = text_info.text_str();
+    let line_text = match text.find(|c| c == '\r' || c == '\n') {
+      Some(index) => &text[..index],
+      None => text,
+    };
+    let start = text_info.range().start.as_source_pos();
+    Some(ShebangInfo {
+      text: line_text.strip_prefix("#!")?.to_string(),
+      range: SourceRange::new(start, start + line_text.len()),
+    })
+  }
+
+  fn has_shebang(&self) -> bool {
+    match self.program_ref() {
+      Program::Module(module) => module.shebang.is_some(),
+      Program::Script(script) => script.shebang.is_some(),
+    }
+  }
+
+  /// Gets the start of the first statement, which is after the leading
+  /// comments of the file.
+  pub(crate) fn first_stmt_start(&self) -> Option<SourcePos> {
+    match self.program_ref() {
+      Program::Module(module) => module.body.first().map(|item| item.start()),
+      Program::Script(script) => script.body.first().map(|stmt| stmt.start()),
+    }
   }
 
   /// Gets the tokens found in the source file.
@@ -128,46 +356,217 @@ impl ParsedSource {
   /// This will panic if tokens were not captured during parsing.
   pub fn tokens(&self) -> &[TokenAndSpan] {
     self
-      .inner
-      .tokens
-      .as_ref()
+      .maybe_tokens()
       .expect("Tokens not found because they were not captured during parsing.")
   }
 
-  /// Adds scope analysis to the parsed source if not parsed
-  /// with scope analysis.
+  /// Gets the tokens found in the source file or `None` when tokens
+  /// were not captured during parsing.
+  pub fn maybe_tokens(&self) -> Option<&[TokenAndSpan]> {
+    self.inner.tokens.as_ref().map(|tokens| tokens.as_slice())
+  }
+
+  /// Gets the tokens that overlap a range, which includes tokens that
+  /// start before the range but end within it and tokens that start
+  /// within it but end after it. An empty range has no tokens, so use
+  /// `token_at()` to get the token at a position.
+  ///
+  /// This will panic if tokens were not captured during parsing.
+  pub fn tokens_in_range(&self, range: SourceRange) -> &[TokenAndSpan] {
+    let tokens = self.tokens();
+    if range.start >= range.end {
+      return &[];
+    }
+    let start_index =
+      tokens.partition_point(|token| token.range().end <= range.start);
+    let end_index =
+      tokens.partition_point(|token| token.range().start < range.end);
+    &tokens[start_index..std::cmp::max(start_index, end_index)]
+  }
+
+  /// Gets the token that contains a position, where a position at the
+  /// end of a token is in the following token if they are adjacent.
+  ///
+  /// This will panic if tokens were not captured during parsing.
+  pub fn token_at(&self, pos: SourcePos) -> Option<&TokenAndSpan> {
+    let tokens = self.tokens();
+    let index = tokens.partition_point(|token| token.range().end <= pos);
+    tokens.get(index).filter(|token| token.range().start <= pos)
+  }
+
+  /// Gets the tokens found in the source file or parses the source
+  /// file again to capture them when they were not captured during
+  /// parsing.
+  ///
+  /// The tokens are cached so subsequent calls are cheap.
+  pub fn tokens_or_lex(&self) -> Arc<Vec<TokenAndSpan>> {
+    if let Some(tokens) = &self.inner.tokens {
+      return tokens.clone();
+    }
+    self
+      .inner
+      .lexed_tokens
+      .get_or_init(|| {
+        let options = &self.inner.options;
+        Arc::new(capture_tokens(
+          &self.inner.text_info,
+          options.parse_mode,
+          options.syntax,
+          options.es_version,
+        ))
+      })
+      .clone()
+  }
+
+  /// Adds scope analysis to the parsed source.
+  ///
+  /// When the source already has scope analysis, the existing syntax
+  /// contexts are removed and the program is analyzed again, so calling
+  /// this multiple times gives the same identifier relationships as
+  /// calling it once.
+  ///
+  /// Note: Scope analysis requires the `transforms` feature. Without it,
+  /// the returned source will not have scope analysis.
   ///
   /// Note: This will attempt to not clone the underlying data, but
   /// will clone if multiple clones of the `ParsedSource` exist.
   pub fn into_with_scope_analysis(self) -> Self {
-    if self.has_scope_analysis() {
-      self
-    } else {
-      let mut inner = match Arc::try_unwrap(self.inner) {
-        Ok(inner) => inner,
-        Err(arc_inner) => ParsedSourceInner {
-          // all of these are/should be cheap to clone
-          specifier: arc_inner.specifier.clone(),
-          media_type: arc_inner.media_type,
-          text_info: arc_inner.text_info.clone(),
-          comments: arc_inner.comments.clone(),
-          program: arc_inner.program.clone(),
-          tokens: arc_inner.tokens.clone(),
-          syntax_contexts: arc_inner.syntax_contexts.clone(),
-          diagnostics: arc_inner.diagnostics.clone(),
-        },
-      };
-      let program = match Arc::try_unwrap(inner.program) {
-        Ok(program) => program,
-        Err(program) => (*program).clone(),
-      };
-      let (program, context) = scope_analysis_transform(program);
+    let mut inner = self.without_scope_analysis().into_inner();
+    let program = unwrap_or_clone_program(inner.program);
+    let (program, context) = scope_analysis_transform(program);
+    inner.program = Arc::new(program);
+    inner.syntax_contexts = context;
+    ParsedSource {
+      inner: Arc::new(inner),
+    }
+  }
+
+  /// Removes the scope analysis from the parsed source, which resets the
+  /// syntax contexts of the identifiers so they're equal when they have
+  /// the same name.
+  ///
+  /// This is useful for invalidating the scope analysis after mutating
+  /// the program, which can then be analyzed again with
+  /// `into_with_scope_analysis`.
+  ///
+  /// Note: This will attempt to not clone the underlying data, but
+  /// will clone if multiple clones of the `ParsedSource` exist.
+  pub fn without_scope_analysis(self) -> Self {
+    if !self.has_scope_analysis() {
+      return self;
+    }
+    let mut inner = self.into_inner();
+    #[cfg(feature = "transforms")]
+    {
+      let mut program = unwrap_or_clone_program(inner.program);
+      crate::remove_syntax_contexts(&mut program);
       inner.program = Arc::new(program);
-      inner.syntax_contexts = context;
-      ParsedSource {
-        inner: Arc::new(inner),
-      }
     }
+    inner.syntax_contexts = None;
+    ParsedSource {
+      inner: Arc::new(inner),
+    }
+  }
+
+  /// Takes the program out of the parsed source.
+  ///
+  /// This only clones the program when it's shared (ex. by another clone
+  /// of this parsed source or a reference from `program()`).
+  pub fn into_program(self) -> Program {
+    unwrap_or_clone_program(self.into_inner().program)
+  }
+
+  /// Splits the parsed source into its parts, which may be reassembled
+  /// with `ParsedSource::from_parts` (ex. after mutating the program).
+  ///
+  /// This doesn't clone the program, so use `ParsedSourceParts::program_mut`
+  /// to mutate it without cloning when it's not shared.
+  pub fn into_parts(self) -> ParsedSourceParts {
+    let inner = self.into_inner();
+    ParsedSourceParts {
+      specifier: inner.specifier,
+      media_type: inner.media_type,
+      text_info: inner.text_info,
+      comments: inner.comments,
+      program: inner.program,
+      tokens: inner.tokens,
+      top_level_context: inner.syntax_contexts.as_ref().map(|c| c.top_level),
+      unresolved_context: inner.syntax_contexts.as_ref().map(|c| c.unresolved),
+      diagnostics: inner.diagnostics,
+      options: inner.options,
+      module_detection_reason: inner.module_detection_reason,
+      fallback_diagnostics: inner.fallback_diagnostics,
+      had_bom: inner.had_bom,
+    }
+  }
+
+  /// Creates a parsed source from the parts of one
+  /// (see `ParsedSource::into_parts`).
+  ///
+  /// Note that the comments, tokens, and diagnostics are not updated, so
+  /// they may not match the program if it was mutated.
+  pub fn from_parts(parts: ParsedSourceParts) -> Self {
+    let syntax_contexts =
+      match (parts.top_level_context, parts.unresolved_context) {
+        (Some(top_level), Some(unresolved)) => Some(SyntaxContexts {
+          top_level,
+          unresolved,
+        }),
+        _ => None,
+      };
+    ParsedSource {
+      inner: Arc::new(ParsedSourceInner {
+        specifier: parts.specifier,
+        media_type: parts.media_type,
+        text_info: parts.text_info,
+        comments: parts.comments,
+        program: parts.program,
+        tokens: parts.tokens,
+        lexed_tokens: OnceLock::new(),
+        #[cfg(feature = "visit")]
+        has_top_level_await: OnceLock::new(),
+        syntax_contexts,
+        diagnostics: parts.diagnostics,
+        options: parts.options,
+        module_detection_reason: parts.module_detection_reason,
+        fallback_diagnostics: parts.fallback_diagnostics,
+        had_bom: parts.had_bom,
+      }),
+    }
+  }
+
+  fn into_inner(self) -> ParsedSourceInner {
+    match Arc::try_unwrap(self.inner) {
+      Ok(inner) => inner,
+      Err(arc_inner) => ParsedSourceInner {
+        // all of these are/should be cheap to clone
+        specifier: arc_inner.specifier.clone(),
+        media_type: arc_inner.media_type,
+        text_info: arc_inner.text_info.clone(),
+        comments: arc_inner.comments.clone(),
+        program: arc_inner.program.clone(),
+        tokens: arc_inner.tokens.clone(),
+        lexed_tokens: arc_inner.lexed_tokens.clone(),
+        #[cfg(feature = "visit")]
+        has_top_level_await: arc_inner.has_top_level_await.clone(),
+        syntax_contexts: arc_inner.syntax_contexts.clone(),
+        diagnostics: arc_inner.diagnostics.clone(),
+        options: arc_inner.options.clone(),
+        module_detection_reason: arc_inner.module_detection_reason,
+        fallback_diagnostics: arc_inner.fallback_diagnostics.clone(),
+        had_bom: arc_inner.had_bom,
+      },
+    }
+  }
+
+  /// Gets if the source has a top level await, which is computed once
+  /// (see `compute_top_level_await()`).
+  #[cfg(feature = "visit")]
+  pub fn has_top_level_await(&self) -> bool {
+    *self.inner.has_top_level_await.get_or_init(|| {
+      crate::top_level_await::find_top_level_await(&self.inner.program)
+        .is_some()
+    })
   }
 
   /// Gets if the source's program has scope information stored
@@ -180,18 +579,30 @@ impl ParsedSource {
   ///
   /// This will panic if the source was not parsed with scope analysis.
   pub fn top_level_context(&self) -> SyntaxContext {
-    self.syntax_contexts().top_level
+    self
+      .maybe_top_level_context()
+      .expect(SYNTAX_CONTEXTS_NOT_FOUND_MESSAGE)
   }
 
   /// Gets the unresolved context used when parsing with scope analysis.
   ///
   /// This will panic if the source was not parsed with scope analysis.
   pub fn unresolved_context(&self) -> SyntaxContext {
-    self.syntax_contexts().unresolved
+    self
+      .maybe_unresolved_context()
+      .expect(SYNTAX_CONTEXTS_NOT_FOUND_MESSAGE)
+  }
+
+  /// Gets the top level context used when parsing with scope analysis
+  /// or `None` when the source was not parsed with scope analysis.
+  pub fn maybe_top_level_context(&self) -> Option<SyntaxContext> {
+    self.inner.syntax_contexts.as_ref().map(|c| c.top_level)
   }
 
-  fn syntax_contexts(&self) -> &SyntaxContexts {
-    self.inner.syntax_contexts.as_ref().expect("Could not get syntax context because the source was not parsed with scope analysis.")
+  /// Gets the unresolved context used when parsing with scope analysis
+  /// or `None` when the source was not parsed with scope analysis.
+  pub fn maybe_unresolved_context(&self) -> Option<SyntaxContext> {
+    self.inner.syntax_contexts.as_ref().map(|c| c.unresolved)
   }
 
   /// Gets extra non-fatal diagnostics found while parsing.
@@ -199,6 +610,115 @@ impl ParsedSource {
     &self.inner.diagnostics
   }
 
+  /// Gets the non-fatal diagnostics sorted by position then message with
+  /// exact duplicates removed, which is useful for displaying them.
+  ///
+  /// Provide a `max_count` to only get the first diagnostics of a source
+  /// with many of them. Use `diagnostics()` to get the original list.
+  pub fn diagnostics_sorted(
+    &self,
+    max_count: Option<usize>,
+  ) -> Vec<&Diagnostic> {
+    sort_and_dedup_diagnostics(self.diagnostics().iter().collect(), max_count)
+  }
+
+  /// Gets the non-fatal diagnostics that aren't suppressed by a comment
+  /// (ex. `// @ts-ignore`) on the same line or on its own preceding line,
+  /// along with the suppression comments that were found.
+  pub fn diagnostics_filtered(
+    &self,
+    rules: &SuppressionRules,
+  ) -> FilteredDiagnostics {
+    filter_diagnostics(
+      self.diagnostics(),
+      self.comments(),
+      self.text_info(),
+      rules,
+    )
+  }
+
+  /// Gets the non-fatal diagnostics with an error severity.
+  pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+    self.diagnostics_with_severity(DiagnosticSeverity::Error)
+  }
+
+  /// Gets the non-fatal diagnostics with a warning severity.
+  pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+    self.diagnostics_with_severity(DiagnosticSeverity::Warning)
+  }
+
+  fn diagnostics_with_severity(
+    &self,
+    severity: DiagnosticSeverity,
+  ) -> impl Iterator<Item = &Diagnostic> {
+    self
+      .diagnostics()
+      .iter()
+      .filter(move |d| d.severity == severity)
+  }
+
+  /// Creates a new parsed source by replacing the text in the provided
+  /// range and parsing the new text again.
+  ///
+  /// The new source is parsed using the same options as this source (ex.
+  /// media type, syntax, and whether tokens were captured).
+  ///
+  /// Errors with a `DiagnosticKind::InvalidEditRange` diagnostic when the
+  /// range is not within the text or doesn't start and end on character
+  /// boundaries.
+  pub fn with_edit(
+    &self,
+    range: SourceRange,
+    replacement: &str,
+  ) -> Result<ParsedSource, Diagnostic> {
+    let text_info = self.text_info();
+    let text_start = text_info.range().start.as_source_pos();
+    let text_end = text_info.range().end;
+    let Some(old_text) = text_info.try_range_text(&range) else {
+      return Err(Diagnostic::new(
+        self.specifier(),
+        SourceRange::new(text_start, text_start),
+        DiagnosticKind::InvalidEditRange,
+        text_info.clone(),
+      ));
+    };
+    let text_before =
+      text_info.range_text(&SourceRange::new(text_start, range.start));
+    let text_after =
+      text_info.range_text(&SourceRange::new(range.end, text_end));
+    let mut new_text = String::with_capacity(
+      text_info.text_str().len() - old_text.len() + replacement.len(),
+    );
+    new_text.push_str(text_before);
+    new_text.push_str(replacement);
+    new_text.push_str(text_after);
+    let options = &self.inner.options;
+    let params = ParseParams {
+      specifier: self.specifier().to_string(),
+      text_info: if self.had_bom() {
+        // add it back so the new source is stripped of it the same way
+        text_info_with_bom(&new_text)
+      } else {
+        SourceTextInfo::from_string(new_text)
+      },
+      media_type: self.media_type(),
+      capture_tokens: self.inner.tokens.is_some(),
+      scope_analysis: self.has_scope_analysis(),
+      maybe_syntax: Some(options.syntax),
+      syntax_options: options.syntax_options,
+      es_version: Some(options.es_version),
+      maybe_cancellation: None,
+      max_diagnostics: options.max_diagnostics,
+      lint_deprecations: options.lint_deprecations,
+      strip_bom: options.strip_bom,
+    };
+    if self.media_type() == MediaType::Json {
+      parse_json(params)
+    } else {
+      parse(params, options.parse_mode)
+    }
+  }
+
   /// Gets if this source is a module.
   pub fn is_module(&self) -> bool {
     matches!(self.program_ref(), Program::Module(_))
@@ -208,6 +728,41 @@ impl ParsedSource {
   pub fn is_script(&self) -> bool {
     matches!(self.program_ref(), Program::Script(_))
   }
+
+  /// Gets the kind of program that was parsed.
+  pub fn program_kind(&self) -> ProgramKind {
+    match self.program_ref() {
+      Program::Module(_) => ProgramKind::Module,
+      Program::Script(_) => ProgramKind::Script,
+    }
+  }
+
+  /// Gets why the source was parsed as a module or script.
+  pub fn module_detection_reason(&self) -> ModuleDetectionReason {
+    self.inner.module_detection_reason
+  }
+
+  /// Gets the diagnostics found when parsing as a module that caused
+  /// the source to be parsed as a script instead.
+  ///
+  /// This will be empty unless the source was parsed with
+  /// `parse_module_or_script` and fell back to a script.
+  pub fn fallback_diagnostics(&self) -> &Vec<Diagnostic> {
+    &self.inner.fallback_diagnostics
+  }
+
+  /// Marks this newly parsed script as having fallen back from being
+  /// parsed as a module because of the provided diagnostics.
+  pub(crate) fn into_script_fallback(
+    mut self,
+    fallback_diagnostics: Vec<Diagnostic>,
+  ) -> Self {
+    let inner = Arc::get_mut(&mut self.inner)
+      .expect("Parsed source should not be shared when newly parsed.");
+    inner.module_detection_reason = ModuleDetectionReason::ScriptFallback;
+    inner.fallback_diagnostics = fallback_diagnostics;
+    self
+  }
 }
 
 impl fmt::Debug for ParsedSource {
@@ -219,6 +774,37 @@ impl fmt::Debug for ParsedSource {
   }
 }
 
+#[cfg(feature = "lsp")]
+impl ParsedSource {
+  /// Gets the non-fatal diagnostics converted to language server
+  /// protocol diagnostics.
+  pub fn lsp_diagnostics(&self) -> Vec<crate::lsp::Diagnostic> {
+    self.diagnostics().iter().map(|d| d.to_lsp()).collect()
+  }
+}
+
+#[cfg(feature = "miette")]
+impl ParsedSource {
+  /// Gets a miette report of the non-fatal diagnostics sorted by position.
+  pub fn diagnostics_report(&self) -> miette::Report {
+    miette::Report::new(crate::MietteDiagnostics::new(
+      self.diagnostics_sorted(None).into_iter().cloned().collect(),
+    ))
+  }
+}
+
+#[cfg(feature = "module_specifier")]
+impl ParsedSource {
+  /// Gets the specifier of the module as a `ModuleSpecifier`.
+  ///
+  /// Errors when the specifier is not a valid URL.
+  pub fn module_specifier(
+    &self,
+  ) -> Result<crate::ModuleSpecifier, url::ParseError> {
+    crate::ModuleSpecifier::parse(self.specifier())
+  }
+}
+
 #[cfg(feature = "view")]
 impl ParsedSource {
   /// Gets a dprint-swc-ext view of the module.
@@ -249,25 +835,420 @@ impl ParsedSource {
   }
 }
 
+fn unwrap_or_clone_program(program: Arc<Program>) -> Program {
+  match Arc::try_unwrap(program) {
+    Ok(program) => program,
+    Err(program) => (*program).clone(),
+  }
+}
+
+pub(crate) fn sort_and_dedup_diagnostics(
+  mut diagnostics: Vec<&Diagnostic>,
+  max_count: Option<usize>,
+) -> Vec<&Diagnostic> {
+  diagnostics.sort_by(|a, b| {
+    a.range
+      .start
+      .cmp(&b.range.start)
+      .then_with(|| a.message().cmp(&b.message()))
+      .then_with(|| a.range.end.cmp(&b.range.end))
+  });
+  diagnostics.dedup_by(|a, b| {
+    a.range == b.range && a.code() == b.code() && a.message() == b.message()
+  });
+  if let Some(max_count) = max_count {
+    diagnostics.truncate(max_count);
+  }
+  diagnostics
+}
+
 #[cfg(test)]
 mod test {
+  use crate::is_comment_on_same_line;
+  use crate::parse_module;
+  use crate::swc::ast::ModuleItem;
+  use crate::DiagnosticKind;
+  use crate::LineAndColumnDisplay;
+
+  use super::*;
+
+  fn parse_ts_module(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///my_file.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(true)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  fn range_of(parsed_source: &ParsedSource, text: &str) -> SourceRange {
+    let start = parsed_source.text_info().range().start;
+    let index = parsed_source.text_info().text_str().find(text).unwrap();
+    SourceRange::new(start + index, start + index + text.len())
+  }
+
+  #[test]
+  fn text_for_range() {
+    let parsed_source = parse_ts_module("const a = 1;\r\nconst b = 2;");
+    let body = &parsed_source.module().body;
+    assert_eq!(
+      parsed_source.text_for_range(&body[0].range()),
+      "const a = 1;"
+    );
+    assert_eq!(
+      parsed_source.text_for_range(&body[1].range()),
+      "const b = 2;"
+    );
+    assert_eq!(
+      parsed_source.text_for_range(&range_of(
+        &parsed_source,
+        parsed_source.text_info().text_str()
+      )),
+      "const a = 1;\r\nconst b = 2;"
+    );
+  }
+
+  #[test]
+  fn should_reparse_with_edit_at_start() {
+    let parsed_source = parse_ts_module("const a = 1;\nconst b = 2;");
+    let range = range_of(&parsed_source, "const a");
+    let new_source = parsed_source.with_edit(range, "let value").unwrap();
+    assert_eq!(
+      new_source.text_info().text_str(),
+      "let value = 1;\nconst b = 2;"
+    );
+    assert_eq!(new_source.specifier(), "file:///my_file.ts");
+    assert_eq!(new_source.media_type(), MediaType::TypeScript);
+    assert_eq!(new_source.tokens().len(), parsed_source.tokens().len());
+    let body = &new_source.module().body;
+    assert_eq!(body.len(), 2);
+    assert!(matches!(body[1], ModuleItem::Stmt(_)));
+    assert_eq!(
+      new_source.text_info().range_text(&body[1].range()),
+      "const b = 2;"
+    );
+    // the original is untouched
+    assert_eq!(
+      parsed_source.text_info().text_str(),
+      "const a = 1;\nconst b = 2;"
+    );
+  }
+
+  #[test]
+  fn should_reparse_with_edit_in_template_literal() {
+    let parsed_source = parse_ts_module("const t = `hello ${name} world`;\nt;");
+    let range = range_of(&parsed_source, "hello");
+    let new_source = parsed_source.with_edit(range, "goodbye").unwrap();
+    assert_eq!(
+      new_source.text_info().text_str(),
+      "const t = `goodbye ${name} world`;\nt;"
+    );
+    let body = &new_source.module().body;
+    assert_eq!(new_source.text_info().range_text(&body[1].range()), "t;");
+  }
+
+  #[test]
+  fn should_error_when_edit_introduces_syntax_error() {
+    let parsed_source = parse_ts_module("const a = 1;\nconst b = 2;");
+    let range = range_of(&parsed_source, "2;");
+    let diagnostic = parsed_source.with_edit(range, "2 +;").unwrap_err();
+    assert_eq!(diagnostic.specifier(), "file:///my_file.ts");
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 2,
+        column_number: 14,
+      }
+    );
+  }
+
+  #[test]
+  fn should_error_when_edit_range_is_invalid() {
+    let parsed_source = parse_ts_module("const a = 'é';");
+    let start = parsed_source.text_info().range().start.as_source_pos();
+    let char_index = parsed_source.text_info().text_str().find('é').unwrap();
+    let other_source = parse_ts_module("const b = 2;\nconst c = 3;");
+    let invalid_ranges = [
+      // past the end of the text
+      SourceRange::new(start + 10, start + 20),
+      // within a multi-byte character
+      SourceRange::new(start + char_index + 1, start + char_index + 2),
+      // from a different source
+      SourceRange::new(start, other_source.text_info().range().end),
+    ];
+    for range in invalid_ranges {
+      let diagnostic = parsed_source.with_edit(range, "1").unwrap_err();
+      assert_eq!(diagnostic.kind, DiagnosticKind::InvalidEditRange);
+      assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+      assert_eq!(diagnostic.specifier(), "file:///my_file.ts");
+    }
+  }
+
+  #[test]
+  fn should_lex_same_tokens_as_captured() {
+    let text = concat!(
+      "const re = /ab+c/g;\n",
+      "const t = `a ${1 + 2} b ${`nested ${re}`}`;\n",
+      "const el = <div class=\"a\">{re.test(t) ? \"yes\" : \"no\"}</div>;\n",
+      "const half = 10 / 2 / 1;\n",
+    );
+    let get_params = |capture_tokens: bool| {
+      ParseParams::builder(
+        "file:///my_file.tsx",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      .capture_tokens(capture_tokens)
+      .build()
+    };
+    let captured = parse_module(get_params(true)).unwrap();
+    let not_captured = parse_module(get_params(false)).unwrap();
+
+    let lexed_tokens = not_captured.tokens_or_lex();
+    assert_eq!(*lexed_tokens, captured.tokens().to_vec());
+    // cached
+    assert!(Arc::ptr_eq(&lexed_tokens, &not_captured.tokens_or_lex()));
+    // uses the captured tokens when available
+    assert_eq!(captured.tokens_or_lex().len(), captured.tokens().len());
+  }
+
+  #[test]
+  fn comments_in_range() {
+    let parsed_source = parse_ts_module(concat!(
+      "/* header */\n",
+      "// leading\n",
+      "const a = 1; // trailing\n",
+      "function b(/* param */) {\n",
+      "  // inner\n",
+      "  /* block */\n",
+      "}\n",
+      "// last\n",
+    ));
+    let comment_texts = |comments: &[Comment]| {
+      comments
+        .iter()
+        .map(|comment| comment.text.to_string())
+        .collect::<Vec<_>>()
+    };
+    let body = &parsed_source.module().body;
+    assert_eq!(
+      comment_texts(&parsed_source.comments_in_range(body[1].range())),
+      [" param ", " inner", " block "]
+    );
+    assert_eq!(
+      comment_texts(&parsed_source.comments_in_range(range_of(
+        &parsed_source,
+        parsed_source.text_info().text_str()
+      ))),
+      [
+        " header ",
+        " leading",
+        " trailing",
+        " param ",
+        " inner",
+        " block ",
+        " last"
+      ]
+    );
+    // partially overlapping comments are excluded
+    let range = range_of(&parsed_source, "header */\n// leading");
+    assert_eq!(
+      comment_texts(&parsed_source.comments_in_range(range)),
+      [" leading"]
+    );
+
+    assert_eq!(
+      comment_texts(
+        parsed_source.leading_comments_at(body[0].start()).unwrap()
+      ),
+      [" header ", " leading"]
+    );
+    let trailing = parsed_source.trailing_comments_at(body[0].end()).unwrap();
+    assert_eq!(comment_texts(trailing), [" trailing"]);
+    assert!(is_comment_on_same_line(
+      parsed_source.text_info(),
+      body[0].end(),
+      &trailing[0]
+    ));
+    let leading = parsed_source.leading_comments_at(body[0].start()).unwrap();
+    assert!(!is_comment_on_same_line(
+      parsed_source.text_info(),
+      body[0].start(),
+      &leading[1]
+    ));
+    assert!(parsed_source.leading_comments_at(body[1].end()).is_none());
+  }
+
+  #[test]
+  fn tokens_in_range() {
+    let parsed_source = parse_ts_module("let a = foo(b, 1);");
+    let token_texts = |tokens: &[TokenAndSpan]| {
+      tokens
+        .iter()
+        .map(|token| parsed_source.text_for_range(&token.range()))
+        .collect::<Vec<_>>()
+    };
+
+    let call_range = range_of(&parsed_source, "foo(b, 1)");
+    assert_eq!(
+      token_texts(parsed_source.tokens_in_range(call_range)),
+      ["foo", "(", "b", ",", "1", ")"]
+    );
+    // starting mid-token includes the token
+    let range = SourceRange::new(call_range.start + 1, call_range.start + 4);
+    assert_eq!(
+      token_texts(parsed_source.tokens_in_range(range)),
+      ["foo", "("]
+    );
+    // whitespace between tokens
+    let range = range_of(&parsed_source, " ");
+    assert!(parsed_source.tokens_in_range(range).is_empty());
+    // empty ranges
+    for pos in [call_range.start, call_range.start + 1] {
+      let range = SourceRange::new(pos, pos);
+      assert!(parsed_source.tokens_in_range(range).is_empty());
+    }
+    let range = range_of(&parsed_source, parsed_source.text_info().text_str());
+    assert_eq!(
+      parsed_source.tokens_in_range(range).len(),
+      parsed_source.tokens().len()
+    );
+  }
+
+  #[test]
+  fn token_at() {
+    let parsed_source = parse_ts_module("let a = foo(b);");
+    let token_text_at = |pos: SourcePos| {
+      parsed_source
+        .token_at(pos)
+        .map(|token| parsed_source.text_for_range(&token.range()))
+    };
+    let start = parsed_source.text_info().range().start.as_source_pos();
+    assert_eq!(token_text_at(start), Some("let"));
+    assert_eq!(token_text_at(start + 2), Some("let"));
+    // whitespace
+    assert_eq!(token_text_at(start + 3), None);
+    // adjacent tokens
+    let foo_range = range_of(&parsed_source, "foo");
+    assert_eq!(token_text_at(foo_range.end), Some("("));
+    assert_eq!(token_text_at(parsed_source.text_info().range().end), None);
+  }
+
+  fn parse_program_for_detection(
+    text: &str,
+    media_type: MediaType,
+  ) -> ParsedSource {
+    crate::parse_program(
+      ParseParams::builder(
+        "file:///my_file",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(media_type)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn should_report_module_detection() {
+    let parsed_source =
+      parse_program_for_detection("export {};", MediaType::JavaScript);
+    assert_eq!(parsed_source.program_kind(), ProgramKind::Module);
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::ModuleSyntax
+    );
+
+    let parsed_source = parse_program_for_detection(
+      "await Promise.resolve();",
+      MediaType::JavaScript,
+    );
+    assert_eq!(parsed_source.program_kind(), ProgramKind::Module);
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::ModuleSyntax
+    );
+
+    let parsed_source =
+      parse_program_for_detection("var a = 1;", MediaType::JavaScript);
+    assert_eq!(parsed_source.program_kind(), ProgramKind::Script);
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::NoModuleSyntax
+    );
+
+    let parsed_source =
+      parse_program_for_detection("var a = 1;", MediaType::Mts);
+    assert_eq!(parsed_source.program_kind(), ProgramKind::Module);
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::MediaType
+    );
+
+    let parsed_source = parse_ts_module("var a = 1;");
+    assert_eq!(parsed_source.program_kind(), ProgramKind::Module);
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::ParseMode
+    );
+  }
+
+  #[cfg(feature = "module_specifier")]
+  #[test]
+  fn should_round_trip_module_specifier() {
+    use crate::ModuleSpecifier;
+
+    for specifier in ["file:///dir/my_file.ts", "https://deno.land/x/mod.ts"] {
+      let specifier = ModuleSpecifier::parse(specifier).unwrap();
+      let parsed_source = parse_module(
+        ParseParams::builder(
+          specifier.clone(),
+          SourceTextInfo::from_string("export const a = 1;".to_string()),
+        )
+        .build(),
+      )
+      .unwrap();
+      assert_eq!(parsed_source.specifier(), specifier.as_str());
+      assert_eq!(parsed_source.module_specifier().unwrap(), specifier);
+
+      let diagnostic = parse_module(
+        ParseParams::builder(
+          specifier.clone(),
+          SourceTextInfo::from_string("export const = 1;".to_string()),
+        )
+        .build(),
+      )
+      .unwrap_err();
+      assert_eq!(diagnostic.module_specifier().unwrap(), specifier);
+      assert!(diagnostic.to_string().contains(&format!(
+        " at {}:1:",
+        diagnostic.display_specifier(&Default::default())
+      )));
+    }
+
+    let parsed_source = parse_ts_module("");
+    assert!(parsed_source.module_specifier().is_ok());
+  }
+
   #[cfg(feature = "view")]
   #[test]
   fn should_parse_program() {
     use crate::parse_program;
     use crate::view::NodeTrait;
-    use crate::ParseParams;
-
-    use super::*;
 
-    let program = parse_program(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let program = parse_program(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .expect("should parse");
 
     let result = program.with_view(|program| {
@@ -279,4 +1260,146 @@ mod test {
 
     assert_eq!(result, 2);
   }
+
+  #[test]
+  fn should_sort_and_dedup_diagnostics() {
+    let text_info = SourceTextInfo::from_string("a b c d".to_string());
+    let start = text_info.range().start;
+    let create = |index: usize, kind: DiagnosticKind| {
+      Diagnostic::new(
+        "file:///mod.ts",
+        SourceRange::new(start + index, start + index + 1),
+        kind,
+        text_info.clone(),
+      )
+    };
+    let diagnostics = vec![
+      create(6, DiagnosticKind::MisplacedShebang),
+      create(2, DiagnosticKind::MisplacedShebang),
+      create(0, DiagnosticKind::MisplacedShebang),
+      create(2, DiagnosticKind::Cancelled),
+      create(2, DiagnosticKind::MisplacedShebang),
+      create(6, DiagnosticKind::MisplacedShebang),
+    ];
+    let get_summary = |diagnostics: Vec<&Diagnostic>| {
+      diagnostics
+        .into_iter()
+        .map(|d| (d.byte_range().start, d.code().as_str()))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      get_summary(sort_and_dedup_diagnostics(
+        diagnostics.iter().collect(),
+        None
+      )),
+      vec![
+        (0, "misplaced-shebang"),
+        // sorted by message when at the same position
+        (2, "misplaced-shebang"),
+        (2, "cancelled"),
+        (6, "misplaced-shebang"),
+      ]
+    );
+    assert_eq!(
+      get_summary(sort_and_dedup_diagnostics(
+        diagnostics.iter().collect(),
+        Some(2)
+      )),
+      vec![(0, "misplaced-shebang"), (2, "misplaced-shebang")]
+    );
+  }
+
+  #[test]
+  fn should_get_sorted_diagnostics() {
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///my_file.ts",
+        SourceTextInfo::from_string(
+          "let a = 0, let b = 1;\nlet c = 0, let d = 1;\n".to_string(),
+        ),
+      )
+      .media_type(MediaType::TypeScript)
+      .syntax_options(SyntaxOptions {
+        no_early_errors: Some(true),
+        ..Default::default()
+      })
+      .build(),
+    )
+    .unwrap();
+    let get_summary = |diagnostics: Vec<&Diagnostic>| {
+      diagnostics
+        .into_iter()
+        .map(|d| (d.byte_range(), d.code().as_str()))
+        .collect::<Vec<_>>()
+    };
+    // swc reports these in order and without duplicates, so reverse
+    // them and add a duplicate
+    let mut parts = parsed_source.into_parts();
+    parts.diagnostics.reverse();
+    parts.diagnostics.push(parts.diagnostics[0].clone());
+    let parsed_source = ParsedSource::from_parts(parts);
+    assert_eq!(
+      get_summary(parsed_source.diagnostics().iter().collect()),
+      vec![
+        (33..36, "invalid-ident-in-strict-mode"),
+        (11..14, "invalid-ident-in-strict-mode"),
+        (33..36, "invalid-ident-in-strict-mode"),
+      ]
+    );
+    assert_eq!(
+      get_summary(parsed_source.diagnostics_sorted(None)),
+      vec![
+        (11..14, "invalid-ident-in-strict-mode"),
+        (33..36, "invalid-ident-in-strict-mode"),
+      ]
+    );
+    assert_eq!(
+      get_summary(parsed_source.diagnostics_sorted(Some(1))),
+      vec![(11..14, "invalid-ident-in-strict-mode")]
+    );
+  }
+
+  #[test]
+  fn into_and_from_parts() {
+    let text = "// a\nconst b = 1; /* c */\n";
+    let parsed_source = parse_ts_module(text);
+    let expected_program = (*parsed_source.program()).clone();
+    let expected_comments = parsed_source.comments().get_vec();
+
+    let mut parts = parsed_source.into_parts();
+    // uniquely owned, so not cloned
+    assert_eq!(Arc::strong_count(&parts.program), 1);
+    let program_ptr = Arc::as_ptr(&parts.program);
+    parts.program_mut();
+    assert_eq!(Arc::as_ptr(&parts.program), program_ptr);
+    assert_eq!(parts.specifier, "file:///my_file.ts");
+    assert_eq!(parts.media_type, MediaType::TypeScript);
+    assert!(parts.tokens.is_some());
+
+    let parsed_source = ParsedSource::from_parts(parts);
+    assert_eq!(parsed_source.program_ref(), &expected_program);
+    assert_eq!(parsed_source.comments().get_vec(), expected_comments);
+    assert_eq!(parsed_source.text_info().text_str(), text);
+    assert_eq!(parsed_source.tokens().len(), 5);
+    assert!(!parsed_source.has_scope_analysis());
+
+    // shared, so cloned
+    let program = parsed_source.program();
+    let mut parts = parsed_source.into_parts();
+    let Program::Module(module) = parts.program_mut() else {
+      unreachable!();
+    };
+    module.body.clear();
+    assert_eq!(*program, expected_program);
+    assert!(ParsedSource::from_parts(parts).module().body.is_empty());
+  }
+
+  #[test]
+  fn into_program() {
+    let parsed_source = parse_ts_module("const a = 1;");
+    let expected_program = (*parsed_source.program()).clone();
+    let clone = parsed_source.clone();
+    assert_eq!(parsed_source.into_program(), expected_program);
+    assert_eq!(clone.into_program(), expected_program);
+  }
 }
@@ -0,0 +1,75 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fmt;
+
+use crate::comments::MultiThreadedComments;
+use crate::swc::ast::TsType;
+use crate::Diagnostic;
+use crate::SourceTextInfo;
+
+/// A parsed standalone TypeScript type.
+#[derive(Clone)]
+pub struct ParsedTsType {
+  specifier: String,
+  text_info: SourceTextInfo,
+  comments: MultiThreadedComments,
+  ts_type: Box<TsType>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedTsType {
+  pub(crate) fn new(
+    specifier: String,
+    text_info: SourceTextInfo,
+    comments: MultiThreadedComments,
+    ts_type: Box<TsType>,
+    diagnostics: Vec<Diagnostic>,
+  ) -> Self {
+    ParsedTsType {
+      specifier,
+      text_info,
+      comments,
+      ts_type,
+      diagnostics,
+    }
+  }
+
+  /// Gets the specifier of the source.
+  pub fn specifier(&self) -> &str {
+    &self.specifier
+  }
+
+  /// Gets the text content of the source.
+  pub fn text_info(&self) -> &SourceTextInfo {
+    &self.text_info
+  }
+
+  /// Gets the parsed type.
+  pub fn ts_type(&self) -> &TsType {
+    &self.ts_type
+  }
+
+  /// Takes the parsed type.
+  pub fn into_ts_type(self) -> Box<TsType> {
+    self.ts_type
+  }
+
+  /// Gets the comments found in the source.
+  pub fn comments(&self) -> &MultiThreadedComments {
+    &self.comments
+  }
+
+  /// Gets extra non-fatal diagnostics found while parsing.
+  pub fn diagnostics(&self) -> &Vec<Diagnostic> {
+    &self.diagnostics
+  }
+}
+
+impl fmt::Debug for ParsedTsType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("ParsedTsType")
+      .field("comments", &self.comments)
+      .field("ts_type", &self.ts_type)
+      .finish()
+  }
+}
@@ -1,31 +1,87 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::comments::MultiThreadedComments;
+use crate::deprecations::get_arguments_callee_ranges;
+use crate::deprecations::get_html_comment_ranges;
+use crate::import_attributes::get_import_assert_ranges;
+use crate::json::get_json_diagnostics;
+use crate::lexing::lex_tokens;
+use crate::media_type::is_declaration_media_type;
+use crate::media_type::is_jsonc_specifier;
+use crate::media_type::is_jsx_media_type;
+use crate::media_type::is_typed_media_type;
+use crate::media_type::media_type_from_specifier;
+use crate::proposals::get_auto_accessor_ranges;
+use crate::proposals::get_using_decl_ranges;
 use crate::swc::ast::EsVersion;
+use crate::swc::ast::ExportDefaultExpr;
+use crate::swc::ast::Expr;
 use crate::swc::ast::Module;
+use crate::swc::ast::ModuleDecl;
+use crate::swc::ast::ModuleItem;
 use crate::swc::ast::Program;
 use crate::swc::ast::Script;
+use crate::swc::ast::TsType;
 use crate::swc::common::comments::SingleThreadedComments;
 use crate::swc::common::input::StringInput;
 use crate::swc::parser::error::Error as SwcError;
+use crate::swc::parser::error::SyntaxError;
 use crate::swc::parser::lexer::Lexer;
 use crate::swc::parser::token::TokenAndSpan;
 use crate::swc::parser::EsConfig;
+use crate::swc::parser::Parser;
 use crate::swc::parser::Syntax;
+use crate::swc::parser::Tokens;
 use crate::swc::parser::TsConfig;
+use crate::DeprecatedSyntax;
 use crate::Diagnostic;
+use crate::DiagnosticKind;
+use crate::DiagnosticSeverity;
+use crate::ImportAttributesMode;
+use crate::JsonDiagnosticKind;
 use crate::MediaType;
+use crate::ModuleDetectionReason;
+use crate::ParsedExpr;
 use crate::ParsedSource;
+use crate::ParsedSourceOptions;
+use crate::ParsedTsType;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
 use crate::SourceTextInfo;
+use crate::StartSourcePos;
+use crate::SyntaxProposal;
+
+/// Maximum supported length in bytes of source text.
+///
+/// swc stores positions as a `u32` and reserves some values at the upper
+/// end, so larger sources cannot be parsed.
+pub const MAX_SOURCE_TEXT_LEN: usize = u32::MAX as usize - (1 << 16) - 1;
 
 /// Ecmascript version used for lexing and parsing.
 pub const ES_VERSION: EsVersion = EsVersion::Es2021;
 
 /// Parameters for parsing.
+///
+/// Use `ParseParams::builder()` to create these with defaults for the
+/// optional fields.
+#[derive(Clone)]
 pub struct ParseParams {
   /// Specifier of the source text.
+  ///
+  /// This is usually a URL. With the `module_specifier` feature, use
+  /// `ParsedSource::module_specifier()` to get it as a `ModuleSpecifier`.
   pub specifier: String,
   /// Source text stored in a `SourceTextInfo`.
   pub text_info: SourceTextInfo,
@@ -34,18 +90,268 @@ pub struct ParseParams {
   /// Whether to capture tokens or not.
   pub capture_tokens: bool,
   /// Whether to apply swc's scope analysis.
+  ///
+  /// This requires the `transforms` feature. When the feature is not
+  /// enabled, the source will be parsed without scope analysis,
+  /// `ParsedSource::has_scope_analysis()` will return `false`, and a
+  /// warning diagnostic with a `DiagnosticKind::ScopeAnalysisUnavailable`
+  /// kind will be added so that this isn't silently ignored.
   pub scope_analysis: bool,
   /// Syntax to use when parsing.
   ///
   /// `deno_ast` will get a default `Syntax` to use based on the
   /// media type, but you may use this to provide a custom `Syntax`.
   pub maybe_syntax: Option<Syntax>,
+  /// Overrides to apply on top of the default `Syntax` for the media type.
+  ///
+  /// This is mutually exclusive with `maybe_syntax` and is ignored when
+  /// a custom `Syntax` is provided.
+  pub syntax_options: Option<SyntaxOptions>,
+  /// Ecmascript version to use when lexing and parsing.
+  ///
+  /// Defaults to `ES_VERSION` when not provided.
+  pub es_version: Option<EsVersion>,
+  /// Flag to check in order to stop parsing early (see
+  /// `ParseParamsBuilder::cancellation`).
+  pub maybe_cancellation: Option<Arc<dyn CancellationFlag>>,
+  /// Maximum number of recoverable swc errors to convert to diagnostics
+  /// (see `ParseParamsBuilder::max_diagnostics`).
+  pub max_diagnostics: Option<usize>,
+  /// Surface warnings for deprecated syntax that still parses (see
+  /// `ParseParamsBuilder::lint_deprecations`).
+  pub lint_deprecations: bool,
+  /// Strip a leading byte order mark from the text before parsing (see
+  /// `ParseParamsBuilder::strip_bom`).
+  pub strip_bom: bool,
+}
+
+/// A flag that is checked to tell if parsing should be cancelled.
+pub trait CancellationFlag: Send + Sync {
+  /// Gets if parsing should be cancelled.
+  fn is_cancelled(&self) -> bool;
+}
+
+impl CancellationFlag for AtomicBool {
+  fn is_cancelled(&self) -> bool {
+    self.load(Ordering::Relaxed)
+  }
+}
+
+impl ParseParams {
+  /// Creates a builder for `ParseParams` with sensible defaults.
+  ///
+  /// When not provided, the media type will be inferred from the
+  /// extension of the specifier's path, ignoring any query string or
+  /// fragment.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let params = deno_ast::ParseParams::builder(
+  ///   "file:///my_file.ts",
+  ///   deno_ast::SourceTextInfo::from_string("".to_string()),
+  /// )
+  /// .capture_tokens(true)
+  /// .build();
+  /// assert_eq!(params.media_type, deno_ast::MediaType::TypeScript);
+  /// ```
+  pub fn builder(
+    specifier: impl Into<String>,
+    text_info: SourceTextInfo,
+  ) -> ParseParamsBuilder {
+    ParseParamsBuilder::new(specifier, text_info)
+  }
+
+  /// Creates a builder for `ParseParams` from text that may be shared
+  /// with other code.
+  ///
+  /// The text is not copied, so this is useful for avoiding a copy of
+  /// large sources that are already stored in an `Arc<str>`. Text that
+  /// starts with a byte order mark is still copied in order to strip it.
+  pub fn builder_from_arc_str(
+    specifier: impl Into<String>,
+    text: Arc<str>,
+  ) -> ParseParamsBuilder {
+    ParseParamsBuilder::new(specifier, SourceTextInfo::new(text))
+  }
+
+  fn syntax(&self) -> Syntax {
+    self.maybe_syntax.unwrap_or_else(|| {
+      get_syntax_with_options(
+        self.media_type,
+        self.syntax_options.unwrap_or_default(),
+      )
+    })
+  }
+}
+
+/// Builder for `ParseParams`.
+pub struct ParseParamsBuilder {
+  specifier: String,
+  text_info: SourceTextInfo,
+  media_type: Option<MediaType>,
+  capture_tokens: bool,
+  scope_analysis: bool,
+  maybe_syntax: Option<Syntax>,
+  syntax_options: Option<SyntaxOptions>,
+  es_version: Option<EsVersion>,
+  maybe_cancellation: Option<Arc<dyn CancellationFlag>>,
+  max_diagnostics: Option<usize>,
+  lint_deprecations: bool,
+  strip_bom: bool,
+}
+
+impl ParseParamsBuilder {
+  pub fn new(specifier: impl Into<String>, text_info: SourceTextInfo) -> Self {
+    Self {
+      specifier: specifier.into(),
+      text_info,
+      media_type: None,
+      capture_tokens: false,
+      scope_analysis: false,
+      maybe_syntax: None,
+      syntax_options: None,
+      es_version: None,
+      maybe_cancellation: None,
+      max_diagnostics: None,
+      lint_deprecations: false,
+      strip_bom: false,
+    }
+  }
+
+  /// Sets the media type of the source text. Otherwise it will be
+  /// inferred from the specifier.
+  pub fn media_type(mut self, media_type: MediaType) -> Self {
+    self.media_type = Some(media_type);
+    self
+  }
+
+  /// Sets whether to capture tokens. Defaults to `false`.
+  pub fn capture_tokens(mut self, capture_tokens: bool) -> Self {
+    self.capture_tokens = capture_tokens;
+    self
+  }
+
+  /// Sets whether to apply swc's scope analysis. Defaults to `false`.
+  pub fn scope_analysis(mut self, scope_analysis: bool) -> Self {
+    self.scope_analysis = scope_analysis;
+    self
+  }
+
+  /// Sets a custom `Syntax` to use instead of the default one
+  /// for the media type.
+  ///
+  /// This will clear any previously provided syntax options.
+  pub fn syntax(mut self, syntax: Syntax) -> Self {
+    self.maybe_syntax = Some(syntax);
+    self.syntax_options = None;
+    self
+  }
+
+  /// Sets overrides to apply on top of the default `Syntax` for
+  /// the media type.
+  ///
+  /// This will clear any previously provided custom `Syntax`.
+  pub fn syntax_options(mut self, syntax_options: SyntaxOptions) -> Self {
+    self.syntax_options = Some(syntax_options);
+    self.maybe_syntax = None;
+    self
+  }
+
+  /// Sets the Ecmascript version to use when parsing. Defaults
+  /// to `ES_VERSION`.
+  pub fn es_version(mut self, es_version: EsVersion) -> Self {
+    self.es_version = Some(es_version);
+    self
+  }
+
+  /// Sets a flag to check in order to stop parsing early (ex. when the
+  /// source is no longer needed).
+  ///
+  /// This is checked before and after parsing, and before doing scope
+  /// analysis. A cancelled parse will return a diagnostic with a
+  /// `DiagnosticKind::Cancelled` kind.
+  pub fn cancellation(mut self, flag: Arc<dyn CancellationFlag>) -> Self {
+    self.maybe_cancellation = Some(flag);
+    self
+  }
+
+  /// Sets the maximum number of recoverable swc errors to convert
+  /// to diagnostics.
+  ///
+  /// When there are more, a single `DiagnosticKind::TooManyDiagnostics`
+  /// diagnostic with the number of remaining errors is added instead.
+  /// This does not affect fatal errors.
+  pub fn max_diagnostics(mut self, max_diagnostics: usize) -> Self {
+    self.max_diagnostics = Some(max_diagnostics);
+    self
+  }
+
+  /// Sets whether to surface warnings for deprecated syntax that still
+  /// parses. Defaults to `false`.
+  ///
+  /// When enabled, a successful parse gets a warning diagnostic for each:
+  ///
+  /// - `assert` keyword used for import attributes
+  ///   (`DiagnosticKind::ImportAssertKeyword`). This is skipped when
+  ///   `SyntaxOptions::import_attributes_mode` already reports it.
+  /// - HTML-like comment (`<!--` and `-->`) in a script.
+  /// - Use of `arguments.callee`.
+  pub fn lint_deprecations(mut self, lint_deprecations: bool) -> Self {
+    self.lint_deprecations = lint_deprecations;
+    self
+  }
+
+  /// Sets whether to strip a leading byte order mark from the text
+  /// before parsing so that positions are relative to the text without
+  /// it. Defaults to `false`.
+  ///
+  /// Whether one was stripped is provided by `ParsedSource::had_bom()`.
+  /// This copies the text when it has a byte order mark.
+  ///
+  /// Note that `SourceTextInfo::new` and `SourceTextInfo::from_string`
+  /// already strip a leading byte order mark, so this only applies to
+  /// text created in other ways (ex. `SourceTextInfo::new_with_indent_width`).
+  /// Use `SourceTextInfo::from_string_handling_bom` or `decode_text` to
+  /// find out whether text had one before creating the `SourceTextInfo`.
+  pub fn strip_bom(mut self, strip_bom: bool) -> Self {
+    self.strip_bom = strip_bom;
+    self
+  }
+
+  pub fn build(self) -> ParseParams {
+    let media_type = self
+      .media_type
+      .unwrap_or_else(|| media_type_from_specifier(&self.specifier));
+    ParseParams {
+      specifier: self.specifier,
+      text_info: self.text_info,
+      media_type,
+      capture_tokens: self.capture_tokens,
+      scope_analysis: self.scope_analysis,
+      maybe_syntax: self.maybe_syntax,
+      syntax_options: self.syntax_options,
+      es_version: self.es_version,
+      maybe_cancellation: self.maybe_cancellation,
+      max_diagnostics: self.max_diagnostics,
+      lint_deprecations: self.lint_deprecations,
+      strip_bom: self.strip_bom,
+    }
+  }
+}
+
+/// Parses the provided information using the provided mode.
+pub fn parse(
+  params: ParseParams,
+  mode: ParseMode,
+) -> Result<ParsedSource, Diagnostic> {
+  ParseSession::new().parse(params, mode)
 }
 
 /// Parses the provided information attempting to figure out if the provided
 /// text is for a script or a module.
 pub fn parse_program(params: ParseParams) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Program, |p| p)
+  parse(params, ParseMode::Program)
 }
 
 /// Parses the provided information as a program with the option of providing some
@@ -55,14 +361,13 @@ pub fn parse_program(params: ParseParams) -> Result<ParsedSource, Diagnostic> {
 ///
 /// ```
 /// deno_ast::parse_program_with_post_process(
-///  deno_ast::ParseParams {
-///    specifier: "file:///my_file.ts".to_string(),
-///    media_type: deno_ast::MediaType::TypeScript,
-///    text_info: deno_ast::SourceTextInfo::from_string("".to_string()),
-///    capture_tokens: true,
-///    maybe_syntax: None,
-///    scope_analysis: false,
-///  },
+///  deno_ast::ParseParams::builder(
+///    "file:///my_file.ts",
+///    deno_ast::SourceTextInfo::from_string("".to_string()),
+///  )
+///  .media_type(deno_ast::MediaType::TypeScript)
+///  .capture_tokens(true)
+///  .build(),
 ///  |program| {
 ///    // do something with the program here before it gets stored
 ///    program
@@ -73,12 +378,61 @@ pub fn parse_program_with_post_process(
   params: ParseParams,
   post_process: impl FnOnce(Program) -> Program,
 ) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Program, post_process)
+  parse_inner(
+    &ParseSession::new(),
+    params,
+    ParseMode::Program,
+    |program, _| Ok(post_process(program)),
+  )
+}
+
+/// Parses the provided information as a program with the option of providing
+/// some fallible post-processing to the result.
+///
+/// The error type must be convertable from a `Diagnostic` so that parse
+/// errors may be surfaced along with any errors from the post-processing.
+pub fn try_parse_program_with_post_process<E: From<Diagnostic>>(
+  params: ParseParams,
+  post_process: impl FnOnce(Program) -> Result<Program, E>,
+) -> Result<ParsedSource, E> {
+  parse_inner(
+    &ParseSession::new(),
+    params,
+    ParseMode::Program,
+    |program, _| post_process(program),
+  )
+}
+
+/// Information available to post-processing that occurs before the
+/// `ParsedSource` is created.
+pub struct PostProcessContext<'a> {
+  /// Specifier of the source text.
+  pub specifier: &'a str,
+  /// Media type of the source text.
+  pub media_type: MediaType,
+  /// Source text that was parsed.
+  pub text_info: &'a SourceTextInfo,
+  /// Comments found when parsing.
+  pub comments: &'a MultiThreadedComments,
+}
+
+/// Parses the provided information using the provided mode with the
+/// option of providing some fallible post-processing to the result.
+///
+/// Unlike the other post-processing functions, the post-processing is
+/// provided the comments and text of the source (ex. for finding
+/// statements with a certain leading comment).
+pub fn try_parse_with_post_process<E: From<Diagnostic>>(
+  params: ParseParams,
+  mode: ParseMode,
+  post_process: impl FnOnce(Program, PostProcessContext) -> Result<Program, E>,
+) -> Result<ParsedSource, E> {
+  parse_inner(&ParseSession::new(), params, mode, post_process)
 }
 
 /// Parses the provided information to a module.
 pub fn parse_module(params: ParseParams) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Module, |p| p)
+  parse(params, ParseMode::Module)
 }
 
 /// Parses a module with post processing (see docs on `parse_program_with_post_process`).
@@ -86,15 +440,29 @@ pub fn parse_module_with_post_process(
   params: ParseParams,
   post_process: impl FnOnce(Module) -> Module,
 ) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Module, |program| match program {
-    Program::Module(module) => Program::Module(post_process(module)),
-    Program::Script(_) => unreachable!(),
-  })
+  try_parse_module_with_post_process(params, |module| Ok(post_process(module)))
+}
+
+/// Parses a module with fallible post processing (see docs on
+/// `try_parse_program_with_post_process`).
+pub fn try_parse_module_with_post_process<E: From<Diagnostic>>(
+  params: ParseParams,
+  post_process: impl FnOnce(Module) -> Result<Module, E>,
+) -> Result<ParsedSource, E> {
+  parse_inner(
+    &ParseSession::new(),
+    params,
+    ParseMode::Module,
+    |program, _| match program {
+      Program::Module(module) => Ok(Program::Module(post_process(module)?)),
+      Program::Script(_) => unreachable!(),
+    },
+  )
 }
 
 /// Parses the provided information to a script.
 pub fn parse_script(params: ParseParams) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Script, |p| p)
+  parse(params, ParseMode::Script)
 }
 
 /// Parses a script with post processing (see docs on `parse_program_with_post_process`).
@@ -102,43 +470,523 @@ pub fn parse_script_with_post_process(
   params: ParseParams,
   post_process: impl FnOnce(Script) -> Script,
 ) -> Result<ParsedSource, Diagnostic> {
-  parse(params, ParseMode::Script, |program| match program {
-    Program::Module(_) => unreachable!(),
-    Program::Script(script) => Program::Script(post_process(script)),
-  })
+  try_parse_script_with_post_process(params, |script| Ok(post_process(script)))
+}
+
+/// Parses a script with fallible post processing (see docs on
+/// `try_parse_program_with_post_process`).
+pub fn try_parse_script_with_post_process<E: From<Diagnostic>>(
+  params: ParseParams,
+  post_process: impl FnOnce(Script) -> Result<Script, E>,
+) -> Result<ParsedSource, E> {
+  parse_inner(
+    &ParseSession::new(),
+    params,
+    ParseMode::Script,
+    |program, _| match program {
+      Program::Module(_) => unreachable!(),
+      Program::Script(script) => Ok(Program::Script(post_process(script)?)),
+    },
+  )
+}
+
+/// Parses the provided information to a module, falling back to
+/// parsing a script when the only problems found are ones specific to
+/// modules (ex. `with` statements, HTML-like comments, or legacy
+/// octal literals).
+///
+/// Use `ParsedSource::parse_mode()` to tell which mode succeeded. When
+/// falling back to a script, the diagnostics found when parsing as a
+/// module are available via `ParsedSource::fallback_diagnostics()`.
+pub fn parse_module_or_script(
+  params: ParseParams,
+) -> Result<ParsedSource, Diagnostic> {
+  let script_params = params.clone();
+  let session = ParseSession::new();
+  let (module_result, module_diagnostics) = match session.parse_module(params) {
+    Ok(parsed_source) => {
+      let module_diagnostics = parsed_source
+        .diagnostics()
+        .iter()
+        .filter(|d| is_module_only_diagnostic(d))
+        .cloned()
+        .collect::<Vec<_>>();
+      (Ok(parsed_source), module_diagnostics)
+    }
+    Err(diagnostic) if is_module_only_diagnostic(&diagnostic) => {
+      (Err(diagnostic.clone()), vec![diagnostic])
+    }
+    Err(diagnostic) => return Err(diagnostic),
+  };
+  if module_diagnostics.is_empty() {
+    return module_result;
+  }
+  match session.parse_script(script_params) {
+    Ok(parsed_source) => {
+      Ok(parsed_source.into_script_fallback(module_diagnostics))
+    }
+    // the source must use module syntax, so surface the module result
+    Err(_) => module_result,
+  }
+}
+
+/// Gets if the diagnostic would not occur when parsing as a script.
+fn is_module_only_diagnostic(diagnostic: &Diagnostic) -> bool {
+  matches!(
+    diagnostic.kind,
+    DiagnosticKind::Syntax(
+      SyntaxError::WithInStrict
+        | SyntaxError::LegacyCommentInModule
+        | SyntaxError::LegacyOctal
+        | SyntaxError::LegacyDecimal
+        | SyntaxError::EvalAndArgumentsInStrict
+        | SyntaxError::InvalidIdentInStrict(_)
+    )
+  )
+}
+
+/// Parses the provided information to a standalone expression.
+///
+/// Any tokens found after the expression will cause an error to be
+/// returned. Note that scope analysis is not supported when parsing an
+/// expression, so `scope_analysis` is ignored.
+///
+/// # Example
+///
+/// ```
+/// let parsed_expr = deno_ast::parse_expr(
+///   deno_ast::ParseParams::builder(
+///     "file:///my_file.ts",
+///     deno_ast::SourceTextInfo::from_string("foo.bar(1) + 2".to_string()),
+///   )
+///   .build(),
+/// )
+/// .unwrap();
+/// assert!(matches!(
+///   parsed_expr.expr(),
+///   deno_ast::swc::ast::Expr::Bin(_)
+/// ));
+/// ```
+pub fn parse_expr(params: ParseParams) -> Result<ParsedExpr, Diagnostic> {
+  let syntax = params.syntax();
+  let source = params.text_info;
+  let specifier = params.specifier;
+  ensure_source_text_len_supported(&specifier, &source)?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  let media_type = params.media_type;
+  let es_version = params.es_version.unwrap_or(ES_VERSION);
+  let (comments, expr, tokens, errors) = parse_string_input(
+    source.as_string_input(),
+    syntax,
+    es_version,
+    params.capture_tokens,
+    ExprParseTarget,
+  )
+  .map_err(|(err, other_errors)| {
+    fatal_error_to_diagnostic(err, other_errors, &specifier, &source)
+  })?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  if let Some(diagnostic) = get_trailing_token_diagnostic(
+    &specifier,
+    &source,
+    expr.end(),
+    syntax,
+    es_version,
+  ) {
+    return Err(diagnostic);
+  }
+  let diagnostics = swc_errors_to_diagnostics(
+    errors,
+    &specifier,
+    &source,
+    params.max_diagnostics,
+  );
+
+  Ok(ParsedExpr::new(
+    specifier,
+    media_type,
+    source,
+    MultiThreadedComments::from_single_threaded(comments),
+    expr,
+    tokens.map(Arc::new),
+    diagnostics,
+  ))
+}
+
+/// Parses the provided information as a JSON module.
+///
+/// The JSON value is provided as the default export of the returned
+/// module (ex. `export default { "a": 1 };`) while keeping the ranges
+/// of the original text. Anything not allowed in JSON (ex. comments or
+/// trailing commas) will cause an error to be returned, except for
+/// duplicate keys which are surfaced as non-fatal diagnostics.
+///
+/// Note that scope analysis is not supported when parsing JSON, so
+/// `scope_analysis` is ignored.
+pub fn parse_json(params: ParseParams) -> Result<ParsedSource, Diagnostic> {
+  let syntax = params.syntax();
+  let (source, had_bom) = get_text_info(params.text_info, params.strip_bom);
+  let specifier = params.specifier;
+  ensure_source_text_len_supported(&specifier, &source)?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  let es_version = params.es_version.unwrap_or(ES_VERSION);
+  // tokens are always captured in order to find trailing commas
+  let (comments, expr, tokens, errors) = parse_string_input(
+    source.as_string_input(),
+    syntax,
+    es_version,
+    true,
+    ExprParseTarget,
+  )
+  .map_err(|(err, other_errors)| {
+    fatal_error_to_diagnostic(err, other_errors, &specifier, &source)
+  })?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  if let Some(diagnostic) = get_trailing_token_diagnostic(
+    &specifier,
+    &source,
+    expr.end(),
+    syntax,
+    es_version,
+  ) {
+    return Err(diagnostic);
+  }
+  let comments = MultiThreadedComments::from_single_threaded(comments);
+  let tokens = tokens.unwrap_or_default();
+  let mut diagnostics = swc_errors_to_diagnostics(
+    errors,
+    &specifier,
+    &source,
+    params.max_diagnostics,
+  );
+  let is_jsonc = params
+    .syntax_options
+    .and_then(|options| options.jsonc)
+    .unwrap_or_else(|| is_jsonc_specifier(&specifier));
+  for diagnostic in
+    get_json_diagnostics(&specifier, &source, &expr, &comments, &tokens)
+  {
+    match &diagnostic.kind {
+      DiagnosticKind::Json(
+        JsonDiagnosticKind::Comment | JsonDiagnosticKind::TrailingComma,
+      ) if is_jsonc => {}
+      DiagnosticKind::Json(kind) if kind.is_fatal() => return Err(diagnostic),
+      _ => diagnostics.push(diagnostic),
+    }
+  }
+
+  let range = source.range();
+  let program = Program::Module(Module {
+    span: SourceRange::new(range.start.as_source_pos(), range.end).into(),
+    body: vec![ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+      ExportDefaultExpr {
+        span: expr.range().into(),
+        expr,
+      },
+    ))],
+    shebang: None,
+  });
+
+  Ok(ParsedSource::new(
+    specifier,
+    params.media_type,
+    source,
+    comments,
+    Arc::new(program),
+    if params.capture_tokens {
+      Some(Arc::new(tokens))
+    } else {
+      None
+    },
+    None,
+    diagnostics,
+    ParsedSourceOptions {
+      parse_mode: ParseMode::Module,
+      syntax,
+      syntax_options: params.syntax_options,
+      es_version,
+      max_diagnostics: params.max_diagnostics,
+      lint_deprecations: params.lint_deprecations,
+      strip_bom: params.strip_bom,
+    },
+    ModuleDetectionReason::MediaType,
+    had_bom,
+  ))
+}
+
+/// Parses the provided text to a standalone TypeScript type
+/// (ex. `Map<string, Array<Foo>>`).
+///
+/// Any tokens found after the type will cause an error to be returned.
+pub fn parse_ts_type(
+  text: &str,
+  specifier: &str,
+) -> Result<ParsedTsType, Diagnostic> {
+  let source = SourceTextInfo::from_string(text.to_string());
+  let syntax = get_syntax(MediaType::TypeScript);
+  ensure_source_text_len_supported(specifier, &source)?;
+  let (comments, ts_type, _, errors) = parse_string_input(
+    source.as_string_input(),
+    syntax,
+    ES_VERSION,
+    false,
+    TsTypeParseTarget,
+  )
+  .map_err(|(err, other_errors)| {
+    fatal_error_to_diagnostic(err, other_errors, specifier, &source)
+  })?;
+  if let Some(diagnostic) = get_trailing_token_diagnostic(
+    specifier,
+    &source,
+    ts_type.end(),
+    syntax,
+    ES_VERSION,
+  ) {
+    return Err(diagnostic);
+  }
+  let diagnostics = swc_errors_to_diagnostics(errors, specifier, &source, None);
+
+  Ok(ParsedTsType::new(
+    specifier.to_string(),
+    source,
+    MultiThreadedComments::from_single_threaded(comments),
+    ts_type,
+    diagnostics,
+  ))
+}
+
+/// Gets a diagnostic for the first token found after the provided position.
+fn get_trailing_token_diagnostic(
+  specifier: &str,
+  source: &SourceTextInfo,
+  pos: SourcePos,
+  syntax: Syntax,
+  es_version: EsVersion,
+) -> Option<Diagnostic> {
+  let end = source.range().end;
+  let text = source.range_text(&SourceRange::new(pos, end));
+  let mut lexer = Lexer::new(
+    syntax,
+    es_version,
+    StringInput::new(text, pos.as_byte_pos(), end.as_byte_pos()),
+    None,
+  );
+  let token = lexer.next()?;
+  let range = token.range();
+  Some(Diagnostic::new(
+    specifier,
+    range,
+    DiagnosticKind::UnexpectedTrailingToken {
+      got: source.range_text(&range).to_string(),
+    },
+    source.clone(),
+  ))
+}
+
+/// Parses the provided sources in parallel on up to `thread_count`
+/// threads, returning the results in the same order as the input.
+///
+/// When doing scope analysis, each source is analyzed with its own swc
+/// `Globals`, so the syntax contexts of one returned source should not
+/// be compared with the syntax contexts of another.
+pub fn parse_many(
+  params: Vec<ParseParams>,
+  mode: ParseMode,
+  thread_count: NonZeroUsize,
+) -> Vec<Result<ParsedSource, Diagnostic>> {
+  let len = params.len();
+  let thread_count = std::cmp::min(thread_count.get(), len);
+  if thread_count <= 1 {
+    return params
+      .into_iter()
+      .map(|params| parse(params, mode))
+      .collect();
+  }
+
+  let pending = Mutex::new(params.into_iter().enumerate());
+  let results = Mutex::new((0..len).map(|_| None).collect::<Vec<_>>());
+  std::thread::scope(|scope| {
+    for _ in 0..thread_count {
+      scope.spawn(|| loop {
+        let next = pending.lock().unwrap().next();
+        let Some((index, params)) = next else {
+          break;
+        };
+        let result = parse(params, mode);
+        results.lock().unwrap()[index] = Some(result);
+      });
+    }
+  });
+  results
+    .into_inner()
+    .unwrap()
+    .into_iter()
+    .map(|result| result.unwrap())
+    .collect()
 }
 
-enum ParseMode {
+/// How to parse the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParseMode {
+  /// Figure out if the source is a script or module.
   Program,
   Module,
   Script,
 }
 
-fn parse(
+/// What to parse the input as.
+trait ParseTarget {
+  type Output;
+
+  fn parse<I: Tokens>(
+    self,
+    parser: &mut Parser<I>,
+  ) -> Result<Self::Output, SwcError>;
+}
+
+impl ParseTarget for ParseMode {
+  type Output = Program;
+
+  fn parse<I: Tokens>(
+    self,
+    parser: &mut Parser<I>,
+  ) -> Result<Program, SwcError> {
+    Ok(match self {
+      ParseMode::Program => parser.parse_program()?,
+      ParseMode::Module => Program::Module(parser.parse_module()?),
+      ParseMode::Script => Program::Script(parser.parse_script()?),
+    })
+  }
+}
+
+struct ExprParseTarget;
+
+impl ParseTarget for ExprParseTarget {
+  type Output = Box<Expr>;
+
+  fn parse<I: Tokens>(
+    self,
+    parser: &mut Parser<I>,
+  ) -> Result<Box<Expr>, SwcError> {
+    parser.parse_expr()
+  }
+}
+
+struct TsTypeParseTarget;
+
+impl ParseTarget for TsTypeParseTarget {
+  type Output = Box<TsType>;
+
+  fn parse<I: Tokens>(
+    self,
+    parser: &mut Parser<I>,
+  ) -> Result<Box<TsType>, SwcError> {
+    parser.parse_type()
+  }
+}
+
+fn parse_inner<E: From<Diagnostic>>(
+  session: &ParseSession,
   params: ParseParams,
   parse_mode: ParseMode,
-  post_process: impl FnOnce(Program) -> Program,
-) -> Result<ParsedSource, Diagnostic> {
-  let source = params.text_info;
+  post_process: impl FnOnce(Program, PostProcessContext) -> Result<Program, E>,
+) -> Result<ParsedSource, E> {
+  let syntax = params.syntax();
+  let (source, had_bom) = get_text_info(params.text_info, params.strip_bom);
   let specifier = params.specifier;
+  ensure_source_text_len_supported(&specifier, &source)?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  if params.maybe_syntax.is_none() {
+    ensure_media_type_supported(&specifier, params.media_type, &source)?;
+  }
   let input = source.as_string_input();
-  let media_type = params.media_type;
-  let syntax = params
-    .maybe_syntax
-    .unwrap_or_else(|| get_syntax(media_type));
-  let (comments, program, tokens, errors) =
-    parse_string_input(input, syntax, params.capture_tokens, parse_mode)
-      .map_err(|err| {
-        Diagnostic::from_swc_error(err, &specifier, source.clone())
-      })?;
-  let diagnostics = errors
-    .into_iter()
-    .map(|err| Diagnostic::from_swc_error(err, &specifier, source.clone()))
-    .collect();
-  let program = post_process(program);
+  let es_version = params.es_version.unwrap_or(ES_VERSION);
+  let target_mode = match parse_mode {
+    ParseMode::Program if is_module_media_type(params.media_type) => {
+      ParseMode::Module
+    }
+    mode => mode,
+  };
+  let (comments, program, tokens, errors) = parse_string_input(
+    input,
+    syntax,
+    es_version,
+    params.capture_tokens,
+    target_mode,
+  )
+  .map_err(|(err, other_errors)| {
+    fatal_error_to_diagnostic(err, other_errors, &specifier, &source)
+  })?;
+  ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+  let module_detection_reason = match (parse_mode, target_mode, &program) {
+    (ParseMode::Module | ParseMode::Script, _, _) => {
+      ModuleDetectionReason::ParseMode
+    }
+    (ParseMode::Program, ParseMode::Module, _) => {
+      ModuleDetectionReason::MediaType
+    }
+    (ParseMode::Program, _, Program::Module(_)) => {
+      ModuleDetectionReason::ModuleSyntax
+    }
+    (ParseMode::Program, _, Program::Script(_)) => {
+      ModuleDetectionReason::NoModuleSyntax
+    }
+  };
+  let mut diagnostics = swc_errors_to_diagnostics(
+    errors,
+    &specifier,
+    &source,
+    params.max_diagnostics,
+  );
+  diagnostics.extend(get_syntax_options_diagnostics(
+    params.syntax_options.unwrap_or_default(),
+    &program,
+    tokens.as_deref(),
+    &specifier,
+    &source,
+    syntax,
+    es_version,
+  )?);
+  let comments = MultiThreadedComments::from_single_threaded(comments);
+  if params.lint_deprecations {
+    diagnostics.extend(get_deprecation_diagnostics(
+      params.syntax_options.unwrap_or_default(),
+      &program,
+      tokens.as_deref(),
+      &comments,
+      &specifier,
+      &source,
+      syntax,
+      es_version,
+    ));
+  }
+  let program = post_process(
+    program,
+    PostProcessContext {
+      specifier: &specifier,
+      media_type: params.media_type,
+      text_info: &source,
+      comments: &comments,
+    },
+  )?;
 
   let (program, syntax_contexts) = if params.scope_analysis {
-    scope_analysis_transform(program)
+    ensure_not_cancelled(&params.maybe_cancellation, &specifier, &source)?;
+    let (program, syntax_contexts) = session.scope_analysis_transform(program);
+    if syntax_contexts.is_none() {
+      let start = source.range().start.as_source_pos();
+      diagnostics.push(
+        Diagnostic::new(
+          &specifier,
+          SourceRange::new(start, start),
+          DiagnosticKind::ScopeAnalysisUnavailable,
+          source.clone(),
+        )
+        .with_severity(DiagnosticSeverity::Warning),
+      );
+    }
+    (program, syntax_contexts)
   } else {
     (program, None)
   };
@@ -147,186 +995,2343 @@ fn parse(
     specifier,
     params.media_type.to_owned(),
     source,
-    MultiThreadedComments::from_single_threaded(comments),
+    comments,
     Arc::new(program),
     tokens.map(Arc::new),
     syntax_contexts,
     diagnostics,
+    ParsedSourceOptions {
+      parse_mode,
+      syntax,
+      syntax_options: params.syntax_options,
+      es_version,
+      max_diagnostics: params.max_diagnostics,
+      lint_deprecations: params.lint_deprecations,
+      strip_bom: params.strip_bom,
+    },
+    module_detection_reason,
+    had_bom,
   ))
 }
 
-pub(crate) fn scope_analysis_transform(
-  _program: Program,
-) -> (Program, Option<crate::SyntaxContexts>) {
-  #[cfg(feature = "transforms")]
-  {
-    scope_analysis_transform_inner(_program)
+/// Gets the text info to parse, stripping any byte order mark when
+/// requested.
+fn get_text_info(
+  text_info: SourceTextInfo,
+  strip_bom: bool,
+) -> (SourceTextInfo, bool) {
+  if strip_bom {
+    crate::encoding::strip_bom(text_info)
+  } else {
+    (text_info, false)
   }
-  #[cfg(not(feature = "transforms"))]
-  panic!(
-    "Cannot parse with scope analysis. Please enable the 'transforms' feature."
-  )
 }
 
-#[cfg(feature = "transforms")]
-fn scope_analysis_transform_inner(
-  program: Program,
-) -> (Program, Option<crate::SyntaxContexts>) {
-  use crate::swc::common::Globals;
-  use crate::swc::common::Mark;
-  use crate::swc::common::SyntaxContext;
-  use crate::swc::transforms::resolver;
-  use crate::swc::visit::FoldWith;
+/// Gets the diagnostics for syntax that was disabled via the syntax
+/// options, but which swc still parsed.
+///
+/// Errors when the legacy `assert` keyword for import attributes is
+/// used and not allowed.
+fn get_syntax_options_diagnostics(
+  options: SyntaxOptions,
+  program: &Program,
+  maybe_tokens: Option<&[TokenAndSpan]>,
+  specifier: &str,
+  source: &SourceTextInfo,
+  syntax: Syntax,
+  es_version: EsVersion,
+) -> Result<Vec<Diagnostic>, Diagnostic> {
+  let import_attributes_mode =
+    options.import_attributes_mode.unwrap_or_default();
+  // quick checks to avoid lexing when unnecessary
+  let text = source.text_str();
+  let check_import_assert = import_attributes_mode
+    != ImportAttributesMode::AllowBoth
+    && text.contains("assert");
+  let check_auto_accessors =
+    options.auto_accessors == Some(false) && text.contains("accessor");
+  let check_using = options.explicit_resource_management == Some(false)
+    && text.contains("using");
+  if !check_import_assert && !check_auto_accessors && !check_using {
+    return Ok(Vec::new());
+  }
+  let lexed_tokens;
+  let tokens = match maybe_tokens {
+    Some(tokens) => tokens,
+    None => {
+      lexed_tokens = lex_tokens(source, syntax, es_version);
+      &lexed_tokens
+    }
+  };
+  let create_diagnostic = |range: SourceRange, kind: DiagnosticKind| {
+    Diagnostic::new(specifier, range, kind, source.clone())
+  };
 
-  let globals = Globals::new();
-  crate::swc::common::GLOBALS.set(&globals, || {
-    let unresolved_mark = Mark::new();
-    let top_level_mark = Mark::new();
-    let program =
-      program.fold_with(&mut resolver(unresolved_mark, top_level_mark, true));
+  let mut diagnostics = Vec::new();
+  if check_import_assert {
+    let mut ranges =
+      get_import_assert_ranges(program, tokens, source).into_iter();
+    match import_attributes_mode {
+      ImportAttributesMode::WithOnly => {
+        if let Some(range) = ranges.next() {
+          return Err(create_diagnostic(
+            range,
+            DiagnosticKind::ImportAssertKeyword,
+          ));
+        }
+      }
+      ImportAttributesMode::AllowBoth
+      | ImportAttributesMode::AssertDeprecatedWarning => {
+        diagnostics.extend(ranges.map(|range| {
+          create_diagnostic(range, DiagnosticKind::ImportAssertKeyword)
+            .with_severity(DiagnosticSeverity::Warning)
+        }));
+      }
+    }
+  }
+  if check_auto_accessors {
+    diagnostics.extend(
+      get_auto_accessor_ranges(tokens, source)
+        .into_iter()
+        .map(|range| {
+          create_diagnostic(
+            range,
+            DiagnosticKind::DisabledProposal(SyntaxProposal::AutoAccessors),
+          )
+        }),
+    );
+  }
+  if check_using {
+    diagnostics.extend(get_using_decl_ranges(tokens, source).into_iter().map(
+      |range| {
+        create_diagnostic(
+          range,
+          DiagnosticKind::DisabledProposal(
+            SyntaxProposal::ExplicitResourceManagement,
+          ),
+        )
+      },
+    ));
+  }
+  Ok(diagnostics)
+}
 
-    (
-      program,
-      Some(crate::SyntaxContexts {
-        unresolved: SyntaxContext::empty().apply_mark(unresolved_mark),
-        top_level: SyntaxContext::empty().apply_mark(top_level_mark),
-      }),
-    )
+/// Gets warning diagnostics for the deprecated syntax found in
+/// successfully parsed source text.
+#[allow(clippy::too_many_arguments)]
+fn get_deprecation_diagnostics(
+  options: SyntaxOptions,
+  program: &Program,
+  maybe_tokens: Option<&[TokenAndSpan]>,
+  comments: &MultiThreadedComments,
+  specifier: &str,
+  source: &SourceTextInfo,
+  syntax: Syntax,
+  es_version: EsVersion,
+) -> Vec<Diagnostic> {
+  // `get_syntax_options_diagnostics` already reports the `assert`
+  // keyword when it's not allowed
+  let text = source.text_str();
+  let check_import_assert = options.import_attributes_mode.unwrap_or_default()
+    == ImportAttributesMode::AllowBoth
+    && text.contains("assert");
+  let check_arguments_callee = text.contains("callee");
+  let create_diagnostic = |range: SourceRange, kind: DiagnosticKind| {
+    Diagnostic::new(specifier, range, kind, source.clone())
+      .with_severity(DiagnosticSeverity::Warning)
+  };
+
+  let mut diagnostics = get_html_comment_ranges(comments, source)
+    .into_iter()
+    .map(|range| {
+      create_diagnostic(
+        range,
+        DiagnosticKind::DeprecatedSyntax(DeprecatedSyntax::HtmlComment),
+      )
+    })
+    .collect::<Vec<_>>();
+  if !check_import_assert && !check_arguments_callee {
+    return diagnostics;
+  }
+  let lexed_tokens;
+  let tokens = match maybe_tokens {
+    Some(tokens) => tokens,
+    None => {
+      lexed_tokens = lex_tokens(source, syntax, es_version);
+      &lexed_tokens
+    }
+  };
+  if check_import_assert {
+    diagnostics.extend(
+      get_import_assert_ranges(program, tokens, source)
+        .into_iter()
+        .map(|range| {
+          create_diagnostic(range, DiagnosticKind::ImportAssertKeyword)
+        }),
+    );
+  }
+  if check_arguments_callee {
+    diagnostics.extend(
+      get_arguments_callee_ranges(tokens, source)
+        .into_iter()
+        .map(|range| {
+          create_diagnostic(
+            range,
+            DiagnosticKind::DeprecatedSyntax(DeprecatedSyntax::ArgumentsCallee),
+          )
+        }),
+    );
+  }
+  diagnostics
+}
+
+/// Gets if the media type is always a module.
+fn is_module_media_type(media_type: MediaType) -> bool {
+  matches!(
+    media_type,
+    MediaType::Mjs | MediaType::Mts | MediaType::Dmts
+  )
+}
+
+fn is_source_text_len_supported(len: usize) -> bool {
+  len <= MAX_SOURCE_TEXT_LEN
+}
+
+/// Ensures the source text is not too large to be parsed.
+fn ensure_source_text_len_supported(
+  specifier: &str,
+  source: &SourceTextInfo,
+) -> Result<(), Diagnostic> {
+  let len = source.text_str().len();
+  if is_source_text_len_supported(len) {
+    Ok(())
+  } else {
+    // don't use `source.range()` because the end position would overflow
+    let start = SourcePos::unsafely_from_byte_pos(
+      StartSourcePos::START_SOURCE_POS.as_byte_pos(),
+    );
+    Err(Diagnostic::new(
+      specifier,
+      SourceRange::new(start, start),
+      DiagnosticKind::SourceTooLarge { len },
+      source.clone(),
+    ))
+  }
+}
+
+/// Ensures parsing has not been cancelled.
+fn ensure_not_cancelled(
+  maybe_cancellation: &Option<Arc<dyn CancellationFlag>>,
+  specifier: &str,
+  source: &SourceTextInfo,
+) -> Result<(), Diagnostic> {
+  match maybe_cancellation {
+    Some(flag) if flag.is_cancelled() => {
+      let start = source.range().start.as_source_pos();
+      Err(Diagnostic::new(
+        specifier,
+        SourceRange::new(start, start),
+        DiagnosticKind::Cancelled,
+        source.clone(),
+      ))
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Ensures the media type is for source text that can be parsed.
+fn ensure_media_type_supported(
+  specifier: &str,
+  media_type: MediaType,
+  source: &SourceTextInfo,
+) -> Result<(), Diagnostic> {
+  match media_type {
+    MediaType::Wasm | MediaType::SourceMap | MediaType::TsBuildInfo => {
+      let start = source.range().start.as_source_pos();
+      Err(Diagnostic::new(
+        specifier,
+        SourceRange::new(start, start),
+        DiagnosticKind::UnsupportedMediaType { media_type },
+        source.clone(),
+      ))
+    }
+    MediaType::JavaScript
+    | MediaType::Jsx
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Tsx
+    | MediaType::Json
+    | MediaType::Unknown => Ok(()),
+  }
+}
+
+fn fatal_error_to_diagnostic(
+  err: SwcError,
+  other_errors: Vec<SwcError>,
+  specifier: &str,
+  source: &SourceTextInfo,
+) -> Diagnostic {
+  let diagnostic = Diagnostic::from_swc_error(err, specifier, source.clone());
+  let diagnostic =
+    match get_misplaced_shebang_range(source, diagnostic.range.start) {
+      Some(range) => Diagnostic::new(
+        specifier,
+        range,
+        DiagnosticKind::MisplacedShebang,
+        source.clone(),
+      ),
+      None => diagnostic,
+    };
+  diagnostic.with_other_errors(swc_errors_to_diagnostics(
+    other_errors,
+    specifier,
+    source,
+    None,
+  ))
+}
+
+fn swc_errors_to_diagnostics(
+  mut errors: Vec<SwcError>,
+  specifier: &str,
+  source: &SourceTextInfo,
+  max_diagnostics: Option<usize>,
+) -> Vec<Diagnostic> {
+  let remaining_errors = match max_diagnostics {
+    Some(max) if errors.len() > max => errors.split_off(max),
+    _ => Vec::new(),
+  };
+  // share the specifier between all the diagnostics
+  let specifier: Arc<str> = specifier.into();
+  let mut diagnostics = errors
+    .into_iter()
+    .map(|err| {
+      Diagnostic::from_swc_error_with_shared_specifier(
+        err,
+        specifier.clone(),
+        source.clone(),
+      )
+    })
+    .collect::<Vec<_>>();
+  if let Some(first_remaining) = remaining_errors.first() {
+    diagnostics.push(
+      Diagnostic::new(
+        &specifier,
+        first_remaining.range(),
+        DiagnosticKind::TooManyDiagnostics {
+          additional_count: remaining_errors.len(),
+        },
+        source.clone(),
+      )
+      .with_severity(DiagnosticSeverity::Info),
+    );
+  }
+  diagnostics
+}
+
+/// Gets the range of a shebang on the line of the provided position
+/// when the shebang is not at the start of the file.
+fn get_misplaced_shebang_range(
+  source: &SourceTextInfo,
+  pos: SourcePos,
+) -> Option<SourceRange> {
+  let line_index = source.line_index(pos);
+  let line_start = source.line_start(line_index);
+  let line_text = source.line_text(line_index);
+  let trimmed_text = line_text.trim_start();
+  let leading_whitespace_len = line_text.len() - trimmed_text.len();
+  // a shebang at the start of the file (possibly after a BOM) is valid
+  if !trimmed_text.starts_with("#!")
+    || (line_index == 0 && leading_whitespace_len == 0)
+  {
+    return None;
+  }
+  Some(SourceRange::new(
+    line_start + leading_whitespace_len,
+    line_start + line_text.len(),
+  ))
+}
+
+/// A session for parsing many sources that shares swc `Globals` between
+/// them.
+///
+/// When doing scope analysis, the sources parsed with a session share
+/// the same swc `Globals` instead of creating new `Globals` per source,
+/// which means the syntax contexts of the sources may be compared.
+///
+/// This is the only state shared between parses. The comments and
+/// tokens found when parsing are moved into each `ParsedSource`, so
+/// there are no buffers left over to reuse for the next parse.
+///
+/// A session is not `Sync`, so create one per thread when parsing
+/// in parallel.
+pub struct ParseSession {
+  #[cfg(feature = "transforms")]
+  globals: std::cell::OnceCell<crate::swc::common::Globals>,
+  _not_sync: PhantomData<Cell<()>>,
+}
+
+impl Default for ParseSession {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ParseSession {
+  pub fn new() -> Self {
+    Self {
+      #[cfg(feature = "transforms")]
+      globals: Default::default(),
+      _not_sync: PhantomData,
+    }
+  }
+
+  /// Parses the provided information using the provided mode.
+  pub fn parse(
+    &self,
+    params: ParseParams,
+    mode: ParseMode,
+  ) -> Result<ParsedSource, Diagnostic> {
+    parse_inner(self, params, mode, |program, _| Ok(program))
+  }
+
+  /// Parses the provided information attempting to figure out if the
+  /// provided text is for a script or a module.
+  pub fn parse_program(
+    &self,
+    params: ParseParams,
+  ) -> Result<ParsedSource, Diagnostic> {
+    self.parse(params, ParseMode::Program)
+  }
+
+  /// Parses the provided information to a module.
+  pub fn parse_module(
+    &self,
+    params: ParseParams,
+  ) -> Result<ParsedSource, Diagnostic> {
+    self.parse(params, ParseMode::Module)
+  }
+
+  /// Parses the provided information to a script.
+  pub fn parse_script(
+    &self,
+    params: ParseParams,
+  ) -> Result<ParsedSource, Diagnostic> {
+    self.parse(params, ParseMode::Script)
+  }
+
+  /// Applies scope analysis to the program.
+  ///
+  /// When the `transforms` feature is not enabled, this will return the
+  /// program untouched and no syntax contexts.
+  fn scope_analysis_transform(
+    &self,
+    program: Program,
+  ) -> (Program, Option<crate::SyntaxContexts>) {
+    #[cfg(feature = "transforms")]
+    {
+      scope_analysis_transform_inner(
+        program,
+        self.globals.get_or_init(crate::swc::common::Globals::new),
+      )
+    }
+    #[cfg(not(feature = "transforms"))]
+    {
+      (program, None)
+    }
+  }
+}
+
+/// Applies scope analysis to the program with new swc `Globals`.
+pub(crate) fn scope_analysis_transform(
+  program: Program,
+) -> (Program, Option<crate::SyntaxContexts>) {
+  ParseSession::new().scope_analysis_transform(program)
+}
+
+#[cfg(feature = "transforms")]
+fn scope_analysis_transform_inner(
+  program: Program,
+  globals: &crate::swc::common::Globals,
+) -> (Program, Option<crate::SyntaxContexts>) {
+  use crate::swc::common::Mark;
+  use crate::swc::common::SyntaxContext;
+  use crate::swc::transforms::resolver;
+  use crate::swc::visit::FoldWith;
+
+  crate::swc::common::GLOBALS.set(globals, || {
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    let program =
+      program.fold_with(&mut resolver(unresolved_mark, top_level_mark, true));
+
+    (
+      program,
+      Some(crate::SyntaxContexts {
+        unresolved: SyntaxContext::empty().apply_mark(unresolved_mark),
+        top_level: SyntaxContext::empty().apply_mark(top_level_mark),
+      }),
+    )
   })
 }
 
+/// Removes the syntax contexts from scope analysis, which resets the
+/// program to how it was before `scope_analysis_transform`.
+#[cfg(feature = "visit")]
+pub(crate) fn remove_syntax_contexts(program: &mut Program) {
+  use crate::swc::visit::VisitMutWith;
+
+  program.visit_mut_with(&mut SyntaxContextRemover);
+}
+
+#[cfg(feature = "visit")]
+struct SyntaxContextRemover;
+
+#[cfg(feature = "visit")]
+impl crate::swc::visit::VisitMut for SyntaxContextRemover {
+  #[allow(clippy::disallowed_types)]
+  fn visit_mut_span(&mut self, span: &mut crate::swc::common::Span) {
+    span.ctxt = crate::swc::common::SyntaxContext::empty();
+  }
+}
+
+/// Parses the source text again in order to get the tokens that are
+/// captured when parsing it, falling back to lexing the text when it
+/// has a fatal syntax error.
+pub(crate) fn capture_tokens(
+  source: &SourceTextInfo,
+  parse_mode: ParseMode,
+  syntax: Syntax,
+  es_version: EsVersion,
+) -> Vec<TokenAndSpan> {
+  match parse_string_input(
+    source.as_string_input(),
+    syntax,
+    es_version,
+    true,
+    parse_mode,
+  ) {
+    Ok((_, _, tokens, _)) => tokens.unwrap_or_default(),
+    Err(_) => lex_tokens(source, syntax, es_version),
+  }
+}
+
 #[allow(clippy::type_complexity)]
-fn parse_string_input(
+fn parse_string_input<T: ParseTarget>(
   input: StringInput,
   syntax: Syntax,
+  es_version: EsVersion,
   capture_tokens: bool,
-  parse_mode: ParseMode,
+  target: T,
 ) -> Result<
   (
     SingleThreadedComments,
-    Program,
+    T::Output,
     Option<Vec<TokenAndSpan>>,
     Vec<SwcError>,
   ),
-  SwcError,
+  // the fatal error along with any errors found before it
+  (SwcError, Vec<SwcError>),
 > {
   let comments = SingleThreadedComments::default();
-  let lexer = Lexer::new(syntax, ES_VERSION, input, Some(&comments));
+  let lexer = Lexer::new(syntax, es_version, input, Some(&comments));
 
   if capture_tokens {
     let lexer = crate::swc::parser::Capturing::new(lexer);
-    let mut parser = crate::swc::parser::Parser::new_from(lexer);
-    let program = match parse_mode {
-      ParseMode::Program => parser.parse_program()?,
-      ParseMode::Module => Program::Module(parser.parse_module()?),
-      ParseMode::Script => Program::Script(parser.parse_script()?),
-    };
+    let mut parser = Parser::new_from(lexer);
+    let output = target
+      .parse(&mut parser)
+      .map_err(|err| (err, parser.take_errors()))?;
     let tokens = parser.input().take();
     let errors = parser.take_errors();
 
-    Ok((comments, program, Some(tokens), errors))
-  } else {
-    let mut parser = crate::swc::parser::Parser::new_from(lexer);
-    let program = match parse_mode {
-      ParseMode::Program => parser.parse_program()?,
-      ParseMode::Module => Program::Module(parser.parse_module()?),
-      ParseMode::Script => Program::Script(parser.parse_script()?),
-    };
-    let errors = parser.take_errors();
+    Ok((comments, output, Some(tokens), errors))
+  } else {
+    let mut parser = Parser::new_from(lexer);
+    let output = target
+      .parse(&mut parser)
+      .map_err(|err| (err, parser.take_errors()))?;
+    let errors = parser.take_errors();
+
+    Ok((comments, output, None, errors))
+  }
+}
+
+/// Gets the default `Syntax` used by `deno_ast` for the provided media type.
+pub fn get_syntax(media_type: MediaType) -> Syntax {
+  if is_typed_media_type(media_type) {
+    Syntax::Typescript(TsConfig {
+      decorators: true,
+      // should be true for mts and cts:
+      // https://babeljs.io/docs/babel-preset-typescript#disallowambiguousjsxlike
+      disallow_ambiguous_jsx_like: matches!(
+        media_type,
+        MediaType::Mts | MediaType::Cts
+      ),
+      dts: is_declaration_media_type(media_type),
+      tsx: is_jsx_media_type(media_type),
+      no_early_errors: false,
+    })
+  } else {
+    Syntax::Es(EsConfig {
+      allow_return_outside_function: true,
+      allow_super_outside_method: true,
+      auto_accessors: true,
+      decorators: false,
+      decorators_before_export: false,
+      export_default_from: true,
+      fn_bind: false,
+      import_attributes: true,
+      jsx: is_jsx_media_type(media_type),
+      explicit_resource_management: true,
+    })
+  }
+}
+
+/// Overrides to apply on top of the default `Syntax` that `deno_ast`
+/// uses for a media type.
+///
+/// Options that are `None` will use the default for the media type.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxOptions {
+  /// Whether to parse decorators.
+  ///
+  /// Decorators are parsed by default for TypeScript media types, but not
+  /// for JavaScript media types. When enabled for JavaScript, decorators
+  /// may appear before or after the `export` keyword like in TypeScript.
+  pub decorators: Option<bool>,
+  /// Whether to parse JSX. For TypeScript media types, this
+  /// controls whether the source is parsed as TSX.
+  pub jsx: Option<bool>,
+  /// Whether to parse import attributes.
+  ///
+  /// Only applies to JavaScript media types because swc always
+  /// parses import attributes in TypeScript.
+  pub import_attributes: Option<bool>,
+  /// Whether to allow auto-accessors (`accessor` class fields).
+  ///
+  /// When disabled, swc still parses auto-accessors so that a
+  /// `DiagnosticKind::DisabledProposal` diagnostic can be surfaced
+  /// for each one.
+  pub auto_accessors: Option<bool>,
+  /// Whether to allow explicit resource management (`using` declarations).
+  ///
+  /// When disabled, swc still parses `using` declarations so that a
+  /// `DiagnosticKind::DisabledProposal` diagnostic can be surfaced
+  /// for each one.
+  pub explicit_resource_management: Option<bool>,
+  /// Whether to skip reporting early errors (ex. duplicate parameter names).
+  ///
+  /// Only applies to TypeScript media types because swc does not support
+  /// this for JavaScript. Note that errors only applicable to strict mode
+  /// code will still be reported as non-fatal diagnostics when parsing a
+  /// module. Use `ParseMode::Script` to parse the source as non-strict code.
+  pub no_early_errors: Option<bool>,
+  /// How to handle the legacy `assert` keyword for import attributes.
+  ///
+  /// Defaults to `ImportAttributesMode::AllowBoth`. Unlike the other
+  /// options, this also applies when a custom `Syntax` is provided.
+  pub import_attributes_mode: Option<ImportAttributesMode>,
+  /// Whether to allow comments and trailing commas when parsing JSON
+  /// (JSONC).
+  ///
+  /// Defaults to whether the specifier has a `.jsonc` or `.json5`
+  /// extension. JSON5 is only supported to the extent of JSONC, so its
+  /// other syntax (ex. unquoted keys) is still an error.
+  pub jsonc: Option<bool>,
+}
+
+/// Gets the default `Syntax` used by `deno_ast` for the provided media
+/// type with the provided overrides applied.
+pub fn get_syntax_with_options(
+  media_type: MediaType,
+  options: SyntaxOptions,
+) -> Syntax {
+  let mut syntax = get_syntax(media_type);
+  match &mut syntax {
+    Syntax::Typescript(config) => {
+      if let Some(decorators) = options.decorators {
+        config.decorators = decorators;
+      }
+      if let Some(jsx) = options.jsx {
+        config.tsx = jsx;
+      }
+      if let Some(no_early_errors) = options.no_early_errors {
+        config.no_early_errors = no_early_errors;
+      }
+    }
+    Syntax::Es(config) => {
+      if let Some(decorators) = options.decorators {
+        config.decorators = decorators;
+        config.decorators_before_export = decorators;
+      }
+      if let Some(jsx) = options.jsx {
+        config.jsx = jsx;
+      }
+      if let Some(import_attributes) = options.import_attributes {
+        config.import_attributes = import_attributes;
+      }
+    }
+  }
+  syntax
+}
+
+#[cfg(test)]
+mod test {
+  use crate::encoding::text_info_with_bom;
+  use crate::DiagnosticDisplayRange;
+  use crate::JsonDiagnosticKind;
+  use crate::LineAndColumnDisplay;
+
+  use super::*;
+
+  const BOM_CHAR: char = '\u{FEFF}';
+
+  #[test]
+  fn should_parse_program() {
+    let program = parse_program(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(program.specifier(), "my_file.js");
+    assert_eq!(program.text_info().text_str(), "// 1\n1 + 1\n// 2");
+    assert_eq!(program.media_type(), MediaType::JavaScript);
+    assert!(matches!(
+      program.script().body[0],
+      crate::swc::ast::Stmt::Expr(..)
+    ));
+    assert_eq!(program.get_leading_comments().unwrap().len(), 1);
+    assert_eq!(program.get_leading_comments().unwrap()[0].text, " 1");
+    assert_eq!(program.tokens().len(), 3);
+    assert_eq!(program.comments().get_vec().len(), 2);
+  }
+
+  #[test]
+  fn should_build_params_inferring_media_type() {
+    let params = ParseParams::builder(
+      "file:///my_file.tsx",
+      SourceTextInfo::from_string("const a = <div />;".to_string()),
+    )
+    .build();
+    assert_eq!(params.specifier, "file:///my_file.tsx");
+    assert_eq!(params.media_type, MediaType::Tsx);
+    assert!(!params.capture_tokens);
+    assert!(!params.scope_analysis);
+    assert!(params.maybe_syntax.is_none());
+
+    let program = parse_module(params).unwrap();
+    assert_eq!(program.media_type(), MediaType::Tsx);
+  }
+
+  #[test]
+  fn should_infer_media_type_ignoring_query_and_fragment() {
+    for (specifier, media_type) in [
+      ("file:///mod.ts?v=1", MediaType::TypeScript),
+      (
+        "https://esm.sh/react@18.2.0/index.js?dev",
+        MediaType::JavaScript,
+      ),
+      ("file:///types.d.ts#fragment", MediaType::Dts),
+      ("https://example.com/mod?file=a.js", MediaType::Unknown),
+      ("https://example.com/mod#a.js", MediaType::Unknown),
+      ("file:///mod%2Ets", MediaType::TypeScript),
+      ("file:///dir%20name/mod.tsx?a#b", MediaType::Tsx),
+      ("/dir/mod#1.ts", MediaType::TypeScript),
+      ("C:\\dir\\mod.js", MediaType::JavaScript),
+    ] {
+      let params = ParseParams::builder(
+        specifier,
+        SourceTextInfo::from_string(String::new()),
+      )
+      .build();
+      assert_eq!(params.media_type, media_type, "{}", specifier);
+    }
+  }
+
+  #[test]
+  fn should_build_params_with_overrides() {
+    let params = ParseParams::builder(
+      "file:///my_file.tsx",
+      SourceTextInfo::from_string("1 + 1".to_string()),
+    )
+    .media_type(MediaType::JavaScript)
+    .capture_tokens(true)
+    .scope_analysis(true)
+    .syntax(get_syntax(MediaType::Jsx))
+    .build();
+    assert_eq!(params.media_type, MediaType::JavaScript);
+    assert!(params.capture_tokens);
+    assert!(params.scope_analysis);
+    assert!(matches!(params.maybe_syntax, Some(Syntax::Es(_))));
+    assert!(params.es_version.is_none());
+  }
+
+  #[test]
+  fn should_parse_with_provided_es_version() {
+    // swc doesn't restrict syntax like class static blocks or top level
+    // await based on the version, but it does report legacy octal
+    // literals in TypeScript only when targeting ES5 or later
+    let get_diagnostic_kinds = |es_version: EsVersion| {
+      let params = ParseParams::builder(
+        "file:///my_file.ts",
+        SourceTextInfo::from_string("const a = 01;".to_string()),
+      )
+      .es_version(es_version)
+      .build();
+      assert_eq!(params.es_version, Some(es_version));
+      parse_script(params)
+        .unwrap()
+        .diagnostics()
+        .iter()
+        .map(|d| d.kind.clone())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(get_diagnostic_kinds(EsVersion::Es3), Vec::new());
+    assert_eq!(
+      get_diagnostic_kinds(EsVersion::EsNext),
+      vec![DiagnosticKind::Syntax(SyntaxError::TS1085)]
+    );
+  }
+
+  #[derive(Debug)]
+  enum EvalCheckError {
+    Parse(Diagnostic),
+    ContainsEval(crate::SourceRange),
+  }
+
+  impl From<Diagnostic> for EvalCheckError {
+    fn from(diagnostic: Diagnostic) -> Self {
+      EvalCheckError::Parse(diagnostic)
+    }
+  }
+
+  fn parse_module_rejecting_eval(
+    text_info: SourceTextInfo,
+  ) -> Result<ParsedSource, EvalCheckError> {
+    use crate::swc::ast::Callee;
+    use crate::swc::ast::Stmt;
+
+    try_parse_module_with_post_process(
+      ParseParams::builder("my_file.js", text_info)
+        .media_type(MediaType::JavaScript)
+        .build(),
+      |module| {
+        for item in &module.body {
+          if let ModuleItem::Stmt(Stmt::Expr(expr_stmt)) = item {
+            if let Expr::Call(call_expr) = &*expr_stmt.expr {
+              if let Callee::Expr(callee) = &call_expr.callee {
+                if let Expr::Ident(ident) = &**callee {
+                  if &*ident.sym == "eval" {
+                    return Err(EvalCheckError::ContainsEval(
+                      call_expr.range(),
+                    ));
+                  }
+                }
+              }
+            }
+          }
+        }
+        Ok(module)
+      },
+    )
+  }
+
+  #[test]
+  fn should_surface_error_from_fallible_post_process() {
+    let text_info =
+      SourceTextInfo::from_string("const a = 1;\neval('a');".to_string());
+    let err = parse_module_rejecting_eval(text_info.clone()).unwrap_err();
+    match err {
+      EvalCheckError::ContainsEval(range) => {
+        assert_eq!(range.as_byte_range(text_info.range().start), 13..22);
+        assert_eq!(text_info.range_text(&range), "eval('a')");
+      }
+      EvalCheckError::Parse(_) => unreachable!(),
+    }
+
+    let parsed_source = parse_module_rejecting_eval(
+      SourceTextInfo::from_string("const a = 1;".to_string()),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.module().body.len(), 1);
+
+    let err = parse_module_rejecting_eval(SourceTextInfo::from_string(
+      "t u".to_string(),
+    ))
+    .unwrap_err();
+    match err {
+      EvalCheckError::Parse(diagnostic) => {
+        assert_eq!(diagnostic.message(), "Expected ';', '}' or <eof>");
+      }
+      EvalCheckError::ContainsEval(_) => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn should_parse_arrow_fn_expr() {
+    let parsed_expr =
+      parse_expr_for_test("(a, b) => a + b", MediaType::JavaScript).unwrap();
+    assert!(matches!(parsed_expr.expr(), Expr::Arrow(_)));
+    assert_eq!(parsed_expr.specifier(), "my_file.js");
+    assert_eq!(parsed_expr.text_info().text_str(), "(a, b) => a + b");
+  }
+
+  #[test]
+  fn should_parse_object_lit_expr() {
+    let parsed_expr = parse_expr_for_test(
+      "// leading\n{ a: 1, b: [2] } /* trailing */",
+      MediaType::JavaScript,
+    )
+    .unwrap();
+    match parsed_expr.expr() {
+      Expr::Object(obj) => assert_eq!(obj.props.len(), 2),
+      _ => unreachable!(),
+    }
+    assert_eq!(parsed_expr.comments().get_vec().len(), 2);
+  }
+
+  #[test]
+  fn should_parse_tsx_expr() {
+    let parsed_expr =
+      parse_expr_for_test("<div>{value as string}</div>", MediaType::Tsx)
+        .unwrap();
+    assert!(matches!(parsed_expr.expr(), Expr::JSXElement(_)));
+  }
+
+  #[test]
+  fn should_error_on_trailing_tokens_after_expr() {
+    let diagnostic =
+      parse_expr_for_test("a + b c", MediaType::JavaScript).unwrap_err();
+    assert_eq!(
+      diagnostic.kind,
+      DiagnosticKind::UnexpectedTrailingToken {
+        got: "c".to_string(),
+      }
+    );
+    assert_eq!(
+      diagnostic.message(),
+      "Unexpected token `c`. Expected the end of the input"
+    );
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 7,
+      }
+    );
+
+    let diagnostic =
+      parse_expr_for_test("a + b;", MediaType::JavaScript).unwrap_err();
+    assert_eq!(diagnostic.range.start, diagnostic.range.end - 1);
+  }
+
+  #[test]
+  fn should_have_diagnostic_display_range() {
+    let text = "const a = 'ü\nconst b = 2;";
+    // an unterminated string literal is recovered from
+    let parsed_source = parse_script(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap();
+    let diagnostic = &parsed_source.diagnostics()[0];
+    assert_eq!(
+      diagnostic.kind,
+      DiagnosticKind::Syntax(SyntaxError::UnterminatedStrLit)
+    );
+    let text_info = SourceTextInfo::from_string(text.to_string());
+    assert_eq!(text_info.range_text(&diagnostic.range), "'ü");
+    assert_eq!(
+      diagnostic.display_range(),
+      DiagnosticDisplayRange {
+        start: LineAndColumnDisplay {
+          line_number: 1,
+          column_number: 11,
+        },
+        end: LineAndColumnDisplay {
+          line_number: 1,
+          column_number: 13,
+        },
+      }
+    );
+
+    // multi-line range with a multi-byte character on the last line
+    let text_info = SourceTextInfo::from_string("a\n≥≥ b".to_string());
+    let start = text_info.range().start.as_source_pos();
+    let diagnostic = Diagnostic::new(
+      "file:///mod.js",
+      SourceRange::new(start, start + "a\n≥≥".len()),
+      DiagnosticKind::MisplacedShebang,
+      text_info,
+    );
+    assert_eq!(
+      diagnostic.display_range(),
+      DiagnosticDisplayRange {
+        start: LineAndColumnDisplay {
+          line_number: 1,
+          column_number: 1,
+        },
+        end: LineAndColumnDisplay {
+          line_number: 2,
+          column_number: 3,
+        },
+      }
+    );
+  }
+
+  #[test]
+  fn should_parse_conditional_ts_type() {
+    let parsed_type = parse_ts_type(
+      "T extends Map<string, Array<Foo>> ? \"a\" : \"b\"",
+      "my_file.ts",
+    )
+    .unwrap();
+    assert!(matches!(
+      parsed_type.ts_type(),
+      TsType::TsConditionalType(_)
+    ));
+    assert_eq!(parsed_type.specifier(), "my_file.ts");
+  }
+
+  #[test]
+  fn should_parse_tpl_lit_ts_type() {
+    let parsed_type =
+      parse_ts_type("`prefix-${string}`", "my_file.ts").unwrap();
+    assert!(matches!(parsed_type.ts_type(), TsType::TsLitType(_)));
+  }
+
+  #[test]
+  fn should_error_for_invalid_ts_type() {
+    let diagnostic = parse_ts_type("Map<", "my_file.ts").unwrap_err();
+    assert_eq!(diagnostic.specifier(), "my_file.ts");
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 5,
+      }
+    );
+
+    let diagnostic = parse_ts_type("string number", "my_file.ts").unwrap_err();
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 8,
+      }
+    );
+  }
+
+  fn parse_expr_for_test(
+    text: &str,
+    media_type: MediaType,
+  ) -> Result<ParsedExpr, Diagnostic> {
+    parse_expr(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(media_type)
+      .capture_tokens(true)
+      .build(),
+    )
+  }
+
+  fn parse_json_for_test(text: &str) -> Result<ParsedSource, Diagnostic> {
+    parse_json(
+      ParseParams::builder(
+        "file:///data.json",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::Json)
+      .build(),
+    )
+  }
+
+  #[test]
+  fn should_parse_json() {
+    let parsed_source = parse_json_for_test(
+      "{\n  \"a\": [1, -2.5e3, true, null],\n  \"b\": {}\n}",
+    )
+    .unwrap();
+    assert_eq!(parsed_source.media_type(), MediaType::Json);
+    assert!(parsed_source.diagnostics().is_empty());
+    let module = parsed_source.module();
+    assert_eq!(module.body.len(), 1);
+    match &module.body[0] {
+      ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => {
+        assert!(matches!(*export.expr, Expr::Object(_)));
+        assert_eq!(
+          parsed_source.text_info().range_text(&export.expr.range()),
+          parsed_source.text_info().text_str(),
+        );
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn should_error_for_json_with_comments() {
+    let diagnostic =
+      parse_json_for_test("{\n  // comment\n  \"a\": 1\n}").unwrap_err();
+    assert_eq!(
+      diagnostic.kind,
+      DiagnosticKind::Json(JsonDiagnosticKind::Comment)
+    );
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 2,
+        column_number: 3,
+      }
+    );
+  }
+
+  #[test]
+  fn should_parse_jsonc() {
+    let text = concat!(
+      "// configuration\n",
+      "{\n",
+      "  /* compiler */\n",
+      "  \"compilerOptions\": {\n",
+      "    \"strict\": true, // recommended\n",
+      "  },\n",
+      "  \"exclude\": [\"dist\",],\n",
+      "}\n",
+    );
+    for specifier in ["file:///deno.jsonc", "file:///config.json5?v=1"] {
+      let params = ParseParams::builder(
+        specifier,
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .build();
+      assert_eq!(params.media_type, MediaType::Json);
+      let parsed_source = parse_json(params).unwrap();
+      assert!(parsed_source.diagnostics().is_empty());
+      assert_eq!(parsed_source.comments().get_vec().len(), 3);
+      let ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) =
+        &parsed_source.module().body[0]
+      else {
+        unreachable!();
+      };
+      let Expr::Object(object) = &*export.expr else {
+        unreachable!();
+      };
+      assert_eq!(object.props.len(), 2);
+      // positions are of the original text
+      assert_eq!(
+        parsed_source
+          .text_info()
+          .range_text(&object.props[1].range()),
+        "\"exclude\": [\"dist\",]"
+      );
+    }
+
+    // the extension can be overridden
+    let get_params = |specifier: &str, jsonc: bool| {
+      ParseParams::builder(
+        specifier,
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .syntax_options(SyntaxOptions {
+        jsonc: Some(jsonc),
+        ..Default::default()
+      })
+      .build()
+    };
+    assert!(parse_json(get_params("file:///config.json", true)).is_ok());
+    assert_eq!(
+      parse_json(get_params("file:///deno.jsonc", false))
+        .unwrap_err()
+        .kind,
+      DiagnosticKind::Json(JsonDiagnosticKind::Comment)
+    );
+  }
+
+  #[test]
+  fn should_error_for_json_only_syntax() {
+    let diagnostic = parse_json_for_test("[1, 2,]").unwrap_err();
+    assert_eq!(
+      diagnostic.kind,
+      DiagnosticKind::Json(JsonDiagnosticKind::TrailingComma)
+    );
+    assert_eq!(diagnostic.display_position().column_number, 6);
+
+    for text in ["{ a: 1 }", "['a']", "[0x10]", "[undefined]", "[1,,2]"] {
+      assert_eq!(
+        parse_json_for_test(text).unwrap_err().kind,
+        DiagnosticKind::Json(JsonDiagnosticKind::UnsupportedSyntax),
+        "{}",
+        text,
+      );
+    }
+  }
+
+  #[test]
+  fn should_report_duplicate_json_keys() {
+    let parsed_source =
+      parse_json_for_test(r#"{ "a": 1, "b": { "a": 2 }, "a": 3 }"#).unwrap();
+    let diagnostics = parsed_source.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+      diagnostics[0].kind,
+      DiagnosticKind::Json(JsonDiagnosticKind::DuplicateKey {
+        key: "a".to_string(),
+      })
+    );
+    assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(diagnostics[0].display_position().column_number, 28);
+    assert_eq!(diagnostics[0].message(), "Duplicate key 'a' in JSON object");
+    assert_eq!(diagnostics[0].related.len(), 1);
+    assert_eq!(
+      diagnostics[0]
+        .text_info()
+        .line_and_column_display(diagnostics[0].related[0].range.start)
+        .column_number,
+      3
+    );
+  }
+
+  #[test]
+  fn should_parse_many_in_order() {
+    let params = (0..50)
+      .map(|i| {
+        ParseParams::builder(
+          format!("file:///mod_{}.ts", i),
+          SourceTextInfo::from_string(if i % 10 == 3 {
+            "t u".to_string()
+          } else {
+            format!("export const value{}: number = {};", i, i)
+          }),
+        )
+        .media_type(MediaType::TypeScript)
+        .scope_analysis(i % 2 == 0)
+        .build()
+      })
+      .collect::<Vec<_>>();
+    let results =
+      parse_many(params, ParseMode::Module, NonZeroUsize::new(4).unwrap());
+    assert_eq!(results.len(), 50);
+    for (i, result) in results.into_iter().enumerate() {
+      let specifier = format!("file:///mod_{}.ts", i);
+      if i % 10 == 3 {
+        let diagnostic = result.unwrap_err();
+        assert_eq!(diagnostic.specifier(), specifier);
+        assert_eq!(diagnostic.message(), "Expected ';', '}' or <eof>");
+      } else {
+        let parsed_source = result.unwrap();
+        assert_eq!(parsed_source.specifier(), specifier);
+        assert!(parsed_source
+          .text_info()
+          .text_str()
+          .starts_with(&format!("export const value{}:", i)));
+        #[cfg(feature = "transforms")]
+        assert_eq!(parsed_source.has_scope_analysis(), i % 2 == 0);
+      }
+    }
+  }
+
+  #[test]
+  fn should_parse_with_mode() {
+    let get_params = |text: &str| {
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build()
+    };
+    for mode in [ParseMode::Program, ParseMode::Module, ParseMode::Script] {
+      let parsed_source = parse(get_params("1 + 1;"), mode).unwrap();
+      assert_eq!(parsed_source.parse_mode(), mode);
+    }
+    assert!(parse(get_params("1 + 1;"), ParseMode::Module)
+      .unwrap()
+      .is_module());
+    assert!(parse(get_params("1 + 1;"), ParseMode::Script)
+      .unwrap()
+      .is_script());
+
+    let message =
+      match parse(get_params("import a from './a.js';"), ParseMode::Script) {
+        Ok(parsed_source) => {
+          parsed_source.diagnostics()[0].message().to_string()
+        }
+        Err(diagnostic) => diagnostic.message().to_string(),
+      };
+    assert_eq!(
+      message,
+      "'import', and 'export' cannot be used outside of module code"
+    );
+  }
+
+  #[test]
+  fn should_get_syntax_with_options() {
+    assert_eq!(
+      format!(
+        "{:?}",
+        get_syntax_with_options(MediaType::Tsx, SyntaxOptions::default())
+      ),
+      format!("{:?}", get_syntax(MediaType::Tsx))
+    );
+    match get_syntax_with_options(
+      MediaType::TypeScript,
+      SyntaxOptions {
+        decorators: Some(false),
+        jsx: Some(true),
+        ..Default::default()
+      },
+    ) {
+      Syntax::Typescript(config) => {
+        assert!(!config.decorators);
+        assert!(config.tsx);
+      }
+      Syntax::Es(_) => unreachable!(),
+    }
+    match get_syntax_with_options(
+      MediaType::JavaScript,
+      SyntaxOptions {
+        import_attributes: Some(false),
+        explicit_resource_management: Some(false),
+        ..Default::default()
+      },
+    ) {
+      Syntax::Es(config) => {
+        assert!(!config.import_attributes);
+        // still parsed in order to surface a diagnostic
+        assert!(config.explicit_resource_management);
+        assert!(!config.jsx);
+      }
+      Syntax::Typescript(_) => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn should_parse_with_syntax_options() {
+    let get_params = |syntax_options: SyntaxOptions| {
+      ParseParams::builder(
+        "my_file.ts",
+        SourceTextInfo::from_string("@dec\nclass A {}".to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .syntax_options(syntax_options)
+      .build()
+    };
+    let parsed_source =
+      parse_module(get_params(SyntaxOptions::default())).unwrap();
+    assert!(parsed_source.diagnostics().is_empty());
+
+    let result = parse_module(get_params(SyntaxOptions {
+      decorators: Some(false),
+      ..Default::default()
+    }));
+    let has_diagnostic = match result {
+      Ok(parsed_source) => !parsed_source.diagnostics().is_empty(),
+      Err(_) => true,
+    };
+    assert!(has_diagnostic);
+  }
+
+  #[test]
+  fn should_parse_decorators_in_js_when_opted_in() {
+    fn parse_with_decorators(
+      text: &str,
+      media_type: MediaType,
+      decorators: Option<bool>,
+    ) -> Result<ParsedSource, Diagnostic> {
+      parse_module(
+        ParseParams::builder(
+          "my_file",
+          SourceTextInfo::from_string(text.to_string()),
+        )
+        .media_type(media_type)
+        .syntax_options(SyntaxOptions {
+          decorators,
+          ..Default::default()
+        })
+        .build(),
+      )
+    }
+
+    let text = "@dec\nclass A {}\n@dec export class B {}";
+
+    // off by default for javascript
+    let has_diagnostic =
+      match parse_with_decorators(text, MediaType::JavaScript, None) {
+        Ok(parsed_source) => !parsed_source.diagnostics().is_empty(),
+        Err(_) => true,
+      };
+    assert!(has_diagnostic);
+
+    // opted in
+    for media_type in [MediaType::JavaScript, MediaType::Mjs, MediaType::Jsx] {
+      let parsed_source =
+        parse_with_decorators(text, media_type, Some(true)).unwrap();
+      assert!(parsed_source.diagnostics().is_empty());
+      assert_eq!(parsed_source.module().body.len(), 2);
+    }
+
+    // typescript is unaffected
+    for media_type in [MediaType::TypeScript, MediaType::Tsx] {
+      for decorators in [None, Some(true)] {
+        let parsed_source =
+          parse_with_decorators(text, media_type, decorators).unwrap();
+        assert!(parsed_source.diagnostics().is_empty());
+      }
+    }
+  }
+
+  #[test]
+  fn should_parse_without_early_errors() {
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "my_file.ts",
+        SourceTextInfo::from_string("let a = 0, let b = 1;".to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .syntax_options(SyntaxOptions {
+        no_early_errors: Some(true),
+        ..Default::default()
+      })
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.diagnostics().len(), 1);
+    assert_eq!(
+      parsed_source.diagnostics()[0].message(),
+      "`let` cannot be used as an identifier in strict mode"
+    );
+
+    match get_syntax_with_options(
+      MediaType::Mts,
+      SyntaxOptions {
+        no_early_errors: Some(true),
+        ..Default::default()
+      },
+    ) {
+      Syntax::Typescript(config) => assert!(config.no_early_errors),
+      Syntax::Es(_) => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn should_get_shebang_followed_by_block_comment() {
+    let parsed_source = parse_shebang_module(
+      "#!/usr/bin/env -S deno run\n/* block */\nconsole.log(1);",
+    )
+    .unwrap();
+    let shebang = parsed_source.shebang().unwrap();
+    assert_eq!(shebang.text, "/usr/bin/env -S deno run");
+    assert_eq!(
+      parsed_source.text_info().range_text(&shebang.range),
+      "#!/usr/bin/env -S deno run"
+    );
+    let leading_comments = parsed_source.get_leading_comments().unwrap();
+    assert_eq!(leading_comments.len(), 1);
+    assert_eq!(leading_comments[0].text, " block ");
+  }
+
+  #[test]
+  fn should_get_shebang_with_crlf() {
+    let parsed_source =
+      parse_shebang_module("#!/usr/bin/env deno\r\nconsole.log(1);").unwrap();
+    let shebang = parsed_source.shebang().unwrap();
+    assert_eq!(shebang.text, "/usr/bin/env deno");
+    assert_eq!(
+      parsed_source.text_info().range_text(&shebang.range),
+      "#!/usr/bin/env deno"
+    );
+    assert!(parsed_source.get_leading_comments().is_none());
+  }
+
+  #[test]
+  fn should_strip_bom() {
+    let parse = |text_info: SourceTextInfo| {
+      parse_module(
+        ParseParams::builder("file:///mod.ts", text_info)
+          .capture_tokens(true)
+          .strip_bom(true)
+          .build(),
+      )
+    };
+    let text = "export const a = 1;\nconst b = 'é';";
+    let with_bom = parse(text_info_with_bom(text)).unwrap();
+    let without_bom =
+      parse(SourceTextInfo::from_string(text.to_string())).unwrap();
+    assert!(with_bom.had_bom());
+    assert!(!without_bom.had_bom());
+    assert_eq!(with_bom.text_info().text_str(), text);
+    assert_eq!(with_bom.module(), without_bom.module());
+    assert_eq!(with_bom.tokens(), without_bom.tokens());
+    // reparsing keeps track of the byte order mark
+    let start = with_bom.text_info().range().start.as_source_pos();
+    let edited = with_bom
+      .with_edit(SourceRange::new(start, start + 6), "")
+      .unwrap();
+    assert!(edited.had_bom());
+    assert_eq!(edited.text_info().text_str(), &text[6..]);
+
+    // diagnostics at the start of the text are at the same position
+    let first_diagnostic =
+      |result: Result<ParsedSource, Diagnostic>| match result {
+        Ok(parsed_source) => parsed_source.diagnostics()[0].clone(),
+        Err(diagnostic) => diagnostic,
+      };
+    let with_bom = first_diagnostic(parse(text_info_with_bom("}")));
+    let without_bom =
+      first_diagnostic(parse(SourceTextInfo::from_string("}".to_string())));
+    assert_eq!(
+      with_bom.display_position(),
+      LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 1,
+      }
+    );
+    assert_eq!(with_bom.range, without_bom.range);
+    assert_eq!(with_bom.to_string(), without_bom.to_string());
+
+    // the byte order mark is kept by default
+    let parsed_source = parse_module(
+      ParseParams::builder("file:///mod.ts", text_info_with_bom(text)).build(),
+    )
+    .unwrap();
+    assert!(!parsed_source.had_bom());
+    assert!(parsed_source.text_info().text_str().starts_with(BOM_CHAR));
+  }
+
+  #[test]
+  fn should_get_shebang_after_bom() {
+    let parsed_source =
+      parse_shebang_module("\u{FEFF}#!/usr/bin/env deno\nconsole.log(1);")
+        .unwrap();
+    let shebang = parsed_source.shebang().unwrap();
+    assert_eq!(shebang.text, "/usr/bin/env deno");
+    // the byte order mark is stripped when creating the text
+    assert!(!parsed_source.text_info().text_str().starts_with(BOM_CHAR));
+    let start = parsed_source.text_info().range().start;
+    assert_eq!(shebang.range.start, start.as_source_pos());
+    assert_eq!(
+      parsed_source.text_info().range_text(&shebang.range),
+      "#!/usr/bin/env deno"
+    );
+  }
+
+  #[test]
+  fn should_not_get_shebang_when_none() {
+    let parsed_source = parse_shebang_module("// 1\nconsole.log(1);").unwrap();
+    assert!(parsed_source.shebang().is_none());
+    assert_eq!(parsed_source.get_leading_comments().unwrap()[0].text, " 1");
+  }
+
+  #[test]
+  fn should_diagnostic_for_misplaced_shebang() {
+    let diagnostic =
+      parse_shebang_module("console.log(1);\n  #!/usr/bin/env deno\n")
+        .unwrap_err();
+    assert_eq!(diagnostic.kind, DiagnosticKind::MisplacedShebang);
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 2,
+        column_number: 3,
+      }
+    );
+    assert_eq!(
+      diagnostic.message(),
+      "A shebang (#!) is only allowed at the start of a file"
+    );
+  }
+
+  fn parse_shebang_module(text: &str) -> Result<ParsedSource, Diagnostic> {
+    parse_module(
+      ParseParams::builder(
+        "file:///main.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+  }
+
+  #[test]
+  fn should_check_source_text_len() {
+    assert!(is_source_text_len_supported(0));
+    assert!(is_source_text_len_supported(MAX_SOURCE_TEXT_LEN));
+    assert!(!is_source_text_len_supported(MAX_SOURCE_TEXT_LEN + 1));
+    assert!(!is_source_text_len_supported(usize::MAX));
+    assert_eq!(
+      DiagnosticKind::SourceTooLarge { len: 5_000_000_000 }.msg(),
+      "Source text of 5000000000 bytes exceeds the maximum supported size (4 GiB)"
+    );
+  }
+
+  #[test]
+  #[ignore = "allocates over 4 GiB"]
+  fn should_diagnostic_for_oversized_source() {
+    let text = " ".repeat(MAX_SOURCE_TEXT_LEN + 1);
+    let diagnostic = parse_module(
+      ParseParams::builder(
+        "file:///large.js",
+        SourceTextInfo::from_string(text),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap_err();
+    assert_eq!(diagnostic.specifier(), "file:///large.js");
+    assert_eq!(
+      diagnostic.kind,
+      DiagnosticKind::SourceTooLarge {
+        len: MAX_SOURCE_TEXT_LEN + 1
+      }
+    );
+  }
+
+  #[test]
+  fn should_parse_with_session_same_as_standalone() {
+    let get_params = |text: &str| {
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(true)
+      .build()
+    };
+    let texts = [
+      "// 1\nimport { a } from './a.ts';\nexport const b: number = a;",
+      "/* 2 */ let a = 0, let b = 1;",
+    ];
+    let session = ParseSession::new();
+    for text in texts {
+      let from_session = session.parse_module(get_params(text)).unwrap();
+      let standalone = parse_module(get_params(text)).unwrap();
+      assert_eq!(from_session.program_ref(), standalone.program_ref());
+      assert_eq!(from_session.tokens(), standalone.tokens());
+      assert_eq!(
+        from_session.comments().get_vec(),
+        standalone.comments().get_vec()
+      );
+      assert_eq!(from_session.diagnostics(), standalone.diagnostics());
+      assert_eq!(from_session.parse_mode(), standalone.parse_mode());
+    }
+  }
+
+  #[cfg(feature = "transforms")]
+  #[test]
+  fn should_share_globals_in_session() {
+    let get_params = || {
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string("const a = 1; a;".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .scope_analysis(true)
+      .build()
+    };
+    let session = ParseSession::new();
+    let first = session.parse_module(get_params()).unwrap();
+    let second = session.parse_module(get_params()).unwrap();
+    assert!(first.has_scope_analysis());
+    assert!(second.has_scope_analysis());
+    // new marks are created for each source in the same globals
+    assert_ne!(first.top_level_context(), second.top_level_context());
+  }
+
+  #[test]
+  fn should_error_for_unsupported_media_types() {
+    for media_type in [
+      MediaType::Wasm,
+      MediaType::SourceMap,
+      MediaType::TsBuildInfo,
+    ] {
+      let diagnostic = parse_module(
+        ParseParams::builder(
+          "file:///mod.bin",
+          SourceTextInfo::from_string("\0asm".to_string()),
+        )
+        .media_type(media_type)
+        .build(),
+      )
+      .unwrap_err();
+      assert_eq!(
+        diagnostic.kind,
+        DiagnosticKind::UnsupportedMediaType { media_type }
+      );
+      assert_eq!(diagnostic.specifier(), "file:///mod.bin");
+      assert_eq!(
+        diagnostic.message(),
+        format!("Media type {:?} cannot be parsed as ECMAScript", media_type)
+      );
+    }
+
+    // providing a syntax overrides the check
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.map",
+        SourceTextInfo::from_string("export {};".to_string()),
+      )
+      .media_type(MediaType::SourceMap)
+      .syntax(get_syntax(MediaType::JavaScript))
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.media_type(), MediaType::SourceMap);
+  }
+
+  fn parse_module_or_script_for_test(text: &str) -> ParsedSource {
+    parse_module_or_script(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn should_parse_shared_text_without_copying() {
+    let text: Arc<str> = "export const a = `test`;".into();
+    let parsed_source = parse_module(
+      ParseParams::builder_from_arc_str("file:///mod.ts", text.clone())
+        .capture_tokens(true)
+        .build(),
+    )
+    .unwrap();
+    assert_eq!(Arc::strong_count(&text), 2);
+    let text_info = parsed_source.text_info();
+    assert_eq!(text_info.text_str().as_ptr(), text.as_ptr());
+    assert_eq!(text_info.text_str(), &*text);
+    let decl = parsed_source.module().body[0].range();
+    assert_eq!(text_info.range_text(&decl), "export const a = `test`;");
+    assert!(!parsed_source.tokens().is_empty());
+
+    drop(parsed_source);
+    assert_eq!(Arc::strong_count(&text), 1);
+  }
+
+  #[test]
+  fn should_parse_shared_text_same_as_owned_text() {
+    let text = "const a: number = 1;\nlet b = 'c\nconst d = 'é';";
+    let shared_text: Arc<str> = text.into();
+    let shared = parse_module(
+      ParseParams::builder_from_arc_str("file:///mod.ts", shared_text.clone())
+        .capture_tokens(true)
+        .build(),
+    )
+    .unwrap();
+    let owned = parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .capture_tokens(true)
+      .build(),
+    )
+    .unwrap();
+    assert!(Arc::ptr_eq(&shared.text_info().text(), &shared_text));
+    assert_eq!(shared.module(), owned.module());
+    assert_eq!(shared.tokens(), owned.tokens());
+    assert!(!shared.diagnostics().is_empty());
+    assert_eq!(
+      shared
+        .diagnostics()
+        .iter()
+        .map(|d| (d.range, d.to_string()))
+        .collect::<Vec<_>>(),
+      owned
+        .diagnostics()
+        .iter()
+        .map(|d| (d.range, d.to_string()))
+        .collect::<Vec<_>>(),
+    );
+  }
+
+  #[test]
+  fn should_share_text_info_allocation() {
+    let text: Arc<str> = "const a = 1;\r\nconst b = 'é';".into();
+    let text_info = SourceTextInfo::new(text.clone());
+    // cloning is cheap and doesn't copy the text
+    let cloned_text_info = text_info.clone();
+    assert_eq!(cloned_text_info.text_str().as_ptr(), text.as_ptr());
+    let parsed_source = parse_module(
+      ParseParams::builder("file:///mod.ts", text_info)
+        .media_type(MediaType::TypeScript)
+        .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.text_info().text_str().as_ptr(), text.as_ptr());
+    assert!(Arc::ptr_eq(&parsed_source.text_info().text(), &text));
+  }
+
+  #[test]
+  fn should_handle_import_attributes_mode() {
+    fn parse_with_mode(
+      text: &str,
+      mode: Option<ImportAttributesMode>,
+    ) -> Result<ParsedSource, Diagnostic> {
+      parse_module(
+        ParseParams::builder(
+          "file:///mod.js",
+          SourceTextInfo::from_string(text.to_string()),
+        )
+        .syntax_options(SyntaxOptions {
+          import_attributes_mode: mode,
+          ..Default::default()
+        })
+        .build(),
+      )
+    }
+
+    let assert_texts = [
+      (
+        "import data from './a.json' assert { type: 'json' };",
+        "assert",
+      ),
+      (
+        "export * from './a.json' /* c */ assert { type: 'json' };",
+        "assert",
+      ),
+      (
+        "const data = await import('./a.json', { assert: { type: 'json' } });",
+        "assert",
+      ),
+      (
+        "await import(getUrl('a', 'b'), {\n  'assert': { type: 'json' },\n});",
+        "'assert'",
+      ),
+    ];
+    let with_texts = [
+      "import data from './a.json' with { type: 'json' };",
+      "export { a } from './a.json' with { type: 'json' };",
+      "const data = await import('./a.json', { with: { type: 'json' } });",
+      // not import attributes
+      "const assert = 1; import('./a.js', assert);",
+    ];
+
+    for mode in [None, Some(ImportAttributesMode::AllowBoth)] {
+      for (text, _) in assert_texts {
+        let parsed_source = parse_with_mode(text, mode).unwrap();
+        assert!(parsed_source.diagnostics().is_empty(), "{}", text);
+      }
+      for text in with_texts {
+        let parsed_source = parse_with_mode(text, mode).unwrap();
+        assert!(parsed_source.diagnostics().is_empty(), "{}", text);
+      }
+    }
+
+    let get_range_text = |text: &str, range: SourceRange| {
+      SourceTextInfo::from_string(text.to_string())
+        .range_text(&range)
+        .to_string()
+    };
+    for (text, diagnostic_text) in assert_texts {
+      let diagnostic =
+        parse_with_mode(text, Some(ImportAttributesMode::WithOnly))
+          .unwrap_err();
+      assert_eq!(diagnostic.kind, DiagnosticKind::ImportAssertKeyword);
+      assert_eq!(get_range_text(text, diagnostic.range), diagnostic_text);
+
+      let parsed_source = parse_with_mode(
+        text,
+        Some(ImportAttributesMode::AssertDeprecatedWarning),
+      )
+      .unwrap();
+      let diagnostics = parsed_source.diagnostics();
+      assert_eq!(diagnostics.len(), 1, "{}", text);
+      assert_eq!(diagnostics[0].kind, DiagnosticKind::ImportAssertKeyword);
+      assert_eq!(diagnostics[0].range, diagnostic.range);
+    }
+    for mode in [
+      ImportAttributesMode::WithOnly,
+      ImportAttributesMode::AssertDeprecatedWarning,
+    ] {
+      for text in with_texts {
+        let parsed_source = parse_with_mode(text, Some(mode)).unwrap();
+        assert!(parsed_source.diagnostics().is_empty(), "{}", text);
+      }
+    }
+  }
+
+  #[test]
+  fn should_surface_warnings_for_deprecated_syntax() {
+    fn parse_with_lint(
+      text: &str,
+      lint_deprecations: bool,
+      parse_mode: ParseMode,
+    ) -> ParsedSource {
+      parse(
+        ParseParams::builder(
+          "file:///mod.js",
+          SourceTextInfo::from_string(text.to_string()),
+        )
+        .lint_deprecations(lint_deprecations)
+        .build(),
+        parse_mode,
+      )
+      .unwrap()
+    }
+    fn get_warnings(
+      parsed_source: &ParsedSource,
+    ) -> Vec<(DiagnosticKind, String, String)> {
+      parsed_source
+        .diagnostics()
+        .iter()
+        .map(|d| {
+          assert_eq!(d.severity, DiagnosticSeverity::Warning);
+          (
+            d.kind.clone(),
+            d.code().to_string(),
+            parsed_source.text_info().range_text(&d.range).to_string(),
+          )
+        })
+        .collect()
+    }
+
+    let module_text =
+      "import x from \"./a.json\" assert { type: \"json\" };\nconsole.log(x);";
+    let script_text = concat!(
+      "<!-- html comment\n",
+      "function f() {\n",
+      "  return arguments.callee;\n",
+      "}\n",
+      "--> also a comment\n",
+      "f.arguments.callee;\n",
+    );
+    assert_eq!(
+      get_warnings(&parse_with_lint(module_text, true, ParseMode::Module)),
+      vec![(
+        DiagnosticKind::ImportAssertKeyword,
+        "import-assert-keyword".to_string(),
+        "assert".to_string(),
+      )],
+    );
+    assert_eq!(
+      get_warnings(&parse_with_lint(script_text, true, ParseMode::Script)),
+      vec![
+        (
+          DiagnosticKind::DeprecatedSyntax(DeprecatedSyntax::HtmlComment),
+          "deprecated-html-comment".to_string(),
+          "<!-- html comment".to_string(),
+        ),
+        (
+          DiagnosticKind::DeprecatedSyntax(DeprecatedSyntax::HtmlComment),
+          "deprecated-html-comment".to_string(),
+          "--> also a comment".to_string(),
+        ),
+        (
+          DiagnosticKind::DeprecatedSyntax(DeprecatedSyntax::ArgumentsCallee),
+          "deprecated-arguments-callee".to_string(),
+          "arguments.callee".to_string(),
+        ),
+      ],
+    );
+
+    // no warnings when the flag is off
+    assert!(parse_with_lint(module_text, false, ParseMode::Module)
+      .diagnostics()
+      .is_empty());
+    assert!(parse_with_lint(script_text, false, ParseMode::Script)
+      .diagnostics()
+      .is_empty());
+
+    // not reported twice when the import attributes mode reports it
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string(module_text.to_string()),
+      )
+      .syntax_options(SyntaxOptions {
+        import_attributes_mode: Some(
+          ImportAttributesMode::AssertDeprecatedWarning,
+        ),
+        ..Default::default()
+      })
+      .lint_deprecations(true)
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.diagnostics().len(), 1);
+  }
+
+  #[test]
+  fn should_surface_diagnostics_for_disabled_proposals() {
+    fn parse_with_options(
+      text: &str,
+      media_type: MediaType,
+      syntax_options: SyntaxOptions,
+    ) -> ParsedSource {
+      parse_module(
+        ParseParams::builder(
+          "file:///mod",
+          SourceTextInfo::from_string(text.to_string()),
+        )
+        .media_type(media_type)
+        .syntax_options(syntax_options)
+        .build(),
+      )
+      .unwrap()
+    }
+
+    fn get_diagnostic_texts(parsed_source: &ParsedSource) -> Vec<String> {
+      parsed_source
+        .diagnostics()
+        .iter()
+        .map(|d| {
+          format!(
+            "{}: {}",
+            parsed_source.text_info().range_text(&d.range),
+            d.message()
+          )
+        })
+        .collect()
+    }
+
+    let using_text = concat!(
+      "using x = f();\n",
+      "await using y = g();\n",
+      "for (using z of items) {}\n",
+      "using[0];\n",
+      "const using = 1;\n",
+      "console.log(using in obj, using);\n",
+    );
+    let accessor_text = concat!(
+      "class A {\n",
+      "  accessor a = 1;\n",
+      "  static accessor #b;\n",
+      "  accessor() {}\n",
+      "  accessor;\n",
+      "  method() { const accessor = { class: 1 }; return accessor; }\n",
+      "}\n",
+      "const B = class extends A { accessor ['c'] = 2; };\n",
+    );
+    for media_type in [MediaType::TypeScript, MediaType::JavaScript] {
+      // allowed by default
+      for text in [using_text, accessor_text] {
+        let parsed_source =
+          parse_with_options(text, media_type, Default::default());
+        assert!(parsed_source.diagnostics().is_empty());
+      }
 
-    Ok((comments, program, None, errors))
+      let options = SyntaxOptions {
+        explicit_resource_management: Some(false),
+        auto_accessors: Some(false),
+        ..Default::default()
+      };
+      let using_message =
+        "Explicit resource management (`using` declarations) is not enabled";
+      assert_eq!(
+        get_diagnostic_texts(&parse_with_options(
+          using_text, media_type, options
+        )),
+        vec![
+          format!("using: {}", using_message),
+          format!("using: {}", using_message),
+          format!("using: {}", using_message),
+        ]
+      );
+      let accessor_message =
+        "Auto-accessors (`accessor` class fields) are not enabled";
+      let parsed_source =
+        parse_with_options(accessor_text, media_type, options);
+      assert_eq!(
+        get_diagnostic_texts(&parsed_source),
+        vec![
+          format!("accessor: {}", accessor_message),
+          format!("accessor: {}", accessor_message),
+          format!("accessor: {}", accessor_message),
+        ]
+      );
+      assert_eq!(
+        parsed_source
+          .diagnostics()
+          .iter()
+          .map(|d| d.display_position().line_number)
+          .collect::<Vec<_>>(),
+        vec![2, 3, 8],
+      );
+      assert_eq!(
+        parsed_source.diagnostics()[0].kind,
+        DiagnosticKind::DisabledProposal(SyntaxProposal::AutoAccessors)
+      );
+    }
   }
-}
 
-/// Gets the default `Syntax` used by `deno_ast` for the provided media type.
-pub fn get_syntax(media_type: MediaType) -> Syntax {
-  match media_type {
-    MediaType::TypeScript
-    | MediaType::Mts
-    | MediaType::Cts
-    | MediaType::Dts
-    | MediaType::Dmts
-    | MediaType::Dcts
-    | MediaType::Tsx => {
-      Syntax::Typescript(TsConfig {
-        decorators: true,
-        // should be true for mts and cts:
-        // https://babeljs.io/docs/babel-preset-typescript#disallowambiguousjsxlike
-        disallow_ambiguous_jsx_like: matches!(
-          media_type,
-          MediaType::Mts | MediaType::Cts
+  #[test]
+  fn should_have_diagnostic_severities() {
+    // recoverable errors are still errors
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string("with (obj) {}".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap();
+    assert!(!parsed_source.diagnostics().is_empty());
+    assert!(parsed_source
+      .diagnostics()
+      .iter()
+      .all(|d| d.severity == DiagnosticSeverity::Error));
+    assert_eq!(
+      parsed_source.errors().count(),
+      parsed_source.diagnostics().len()
+    );
+    assert_eq!(parsed_source.warnings().count(), 0);
+
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "file:///mod.js",
+        SourceTextInfo::from_string(
+          "import data from './a.json' assert { type: 'json' };".to_string(),
         ),
-        dts: matches!(
-          media_type,
-          MediaType::Dts | MediaType::Dmts | MediaType::Dcts
+      )
+      .syntax_options(SyntaxOptions {
+        import_attributes_mode: Some(
+          ImportAttributesMode::AssertDeprecatedWarning,
         ),
-        tsx: media_type == MediaType::Tsx,
-        no_early_errors: false,
+        ..Default::default()
       })
+      .build(),
+    )
+    .unwrap();
+    assert_eq!(parsed_source.errors().count(), 0);
+    let warnings = parsed_source.warnings().collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, DiagnosticSeverity::Warning);
+    assert!(warnings[0]
+      .to_string()
+      .starts_with("Warning: The 'assert' keyword for import attributes"));
+
+    let range = parsed_source.text_info().range();
+    let diagnostic = Diagnostic::new(
+      "file:///mod.js",
+      SourceRange::new(range.start.as_source_pos(), range.end),
+      DiagnosticKind::MisplacedShebang,
+      parsed_source.text_info().clone(),
+    );
+    assert!(diagnostic.to_string().starts_with("A shebang"));
+    let diagnostic = diagnostic.with_severity(DiagnosticSeverity::Info);
+    assert!(diagnostic.to_string().starts_with("Info: A shebang"));
+  }
+
+  #[test]
+  fn should_cancel_parsing() {
+    use std::sync::atomic::AtomicUsize;
+
+    /// Becomes cancelled after being checked a certain number of times.
+    struct CancelAfterChecks(AtomicUsize);
+
+    impl CancellationFlag for CancelAfterChecks {
+      fn is_cancelled(&self) -> bool {
+        self.0.fetch_sub(1, Ordering::SeqCst) == 0
+      }
     }
-    MediaType::JavaScript
-    | MediaType::Mjs
-    | MediaType::Cjs
-    | MediaType::Jsx
-    | MediaType::Json
-    | MediaType::Wasm
-    | MediaType::TsBuildInfo
-    | MediaType::SourceMap
-    | MediaType::Unknown => Syntax::Es(EsConfig {
-      allow_return_outside_function: true,
-      allow_super_outside_method: true,
-      auto_accessors: true,
-      decorators: false,
-      decorators_before_export: false,
-      export_default_from: true,
-      fn_bind: false,
-      import_attributes: true,
-      jsx: media_type == MediaType::Jsx,
-      explicit_resource_management: true,
-    }),
+
+    for (flag, scope_analysis) in [
+      // cancelled before parsing
+      (Arc::new(CancelAfterChecks(AtomicUsize::new(0))), false),
+      // cancelled after parsing
+      (Arc::new(CancelAfterChecks(AtomicUsize::new(1))), false),
+      // cancelled before scope analysis
+      (Arc::new(CancelAfterChecks(AtomicUsize::new(2))), true),
+    ] {
+      let diagnostic = parse_module(
+        ParseParams::builder(
+          "file:///mod.ts",
+          SourceTextInfo::from_string("export const a = 1;".to_string()),
+        )
+        .scope_analysis(scope_analysis)
+        .cancellation(flag)
+        .build(),
+      )
+      .unwrap_err();
+      assert_eq!(diagnostic.kind, DiagnosticKind::Cancelled);
+      assert_eq!(diagnostic.message(), "Parsing was cancelled");
+    }
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let params = || {
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string("1 + 1".to_string()),
+      )
+      .cancellation(flag.clone())
+      .build()
+    };
+    assert!(parse_expr(params()).is_ok());
+    flag.store(true, Ordering::Relaxed);
+    assert_eq!(
+      parse_expr(params()).unwrap_err().kind,
+      DiagnosticKind::Cancelled
+    );
   }
-}
 
-#[cfg(test)]
-mod test {
-  use crate::LineAndColumnDisplay;
+  #[test]
+  fn should_provide_context_to_post_process() {
+    let parsed_source = try_parse_with_post_process(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(
+          concat!(
+            "const a = 1;\n",
+            "// @remove\n",
+            "console.log(a);\n",
+            "/* other */\n",
+            "export { a };\n",
+          )
+          .to_string(),
+        ),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+      ParseMode::Module,
+      |program, context| -> Result<Program, Diagnostic> {
+        assert_eq!(context.specifier, "file:///mod.ts");
+        assert_eq!(context.media_type, MediaType::TypeScript);
+        let Program::Module(mut module) = program else {
+          unreachable!();
+        };
+        module.body.retain(|item| {
+          !context
+            .comments
+            .get_leading(item.start())
+            .map(|comments| comments.iter().any(|c| c.text.trim() == "@remove"))
+            .unwrap_or(false)
+        });
+        assert_eq!(
+          context.text_info.range_text(&module.body[1].range()),
+          "export { a };"
+        );
+        Ok(Program::Module(module))
+      },
+    )
+    .unwrap();
+    assert_eq!(parsed_source.module().body.len(), 2);
+    assert_eq!(parsed_source.comments().get_vec().len(), 2);
+  }
 
-  use super::*;
+  #[test]
+  fn should_fall_back_to_script() {
+    let parsed_source = parse_module_or_script_for_test(
+      "with (obj) {\n  console.log(a);\n}\nmodule.exports = 010;\n",
+    );
+    assert_eq!(parsed_source.parse_mode(), ParseMode::Script);
+    assert!(parsed_source.is_script());
+    assert_eq!(
+      parsed_source.module_detection_reason(),
+      ModuleDetectionReason::ScriptFallback
+    );
+    assert!(parsed_source.diagnostics().is_empty());
+    let fallback_diagnostics = parsed_source.fallback_diagnostics();
+    assert!(!fallback_diagnostics.is_empty());
+    assert!(fallback_diagnostics.iter().all(is_module_only_diagnostic));
+    assert_eq!(fallback_diagnostics[0].display_position().line_number, 1);
+  }
 
   #[test]
-  fn should_parse_program() {
-    let program = parse_program(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+  fn should_fall_back_to_script_with_same_params() {
+    let parsed_source = parse_module_or_script(
+      ParseParams::builder(
+        "file:///mod.js",
+        text_info_with_bom("with (obj) {}\n"),
+      )
+      .capture_tokens(true)
+      .strip_bom(true)
+      .build(),
+    )
     .unwrap();
-    assert_eq!(program.specifier(), "my_file.js");
-    assert_eq!(program.text_info().text_str(), "// 1\n1 + 1\n// 2");
-    assert_eq!(program.media_type(), MediaType::JavaScript);
-    assert!(matches!(
-      program.script().body[0],
-      crate::swc::ast::Stmt::Expr(..)
-    ));
-    assert_eq!(program.get_leading_comments().unwrap().len(), 1);
-    assert_eq!(program.get_leading_comments().unwrap()[0].text, " 1");
-    assert_eq!(program.tokens().len(), 3);
-    assert_eq!(program.comments().get_vec().len(), 2);
+    assert_eq!(parsed_source.parse_mode(), ParseMode::Script);
+    assert!(parsed_source.had_bom());
+    assert_eq!(parsed_source.text_info().text_str(), "with (obj) {}\n");
+    assert!(parsed_source.maybe_tokens().is_some());
+  }
+
+  #[test]
+  fn should_not_fall_back_to_script_for_esm() {
+    for text in [
+      "export const a = 1;",
+      "import a from './a.js';\nwith (a) {}\n",
+    ] {
+      let parsed_source = parse_module_or_script_for_test(text);
+      assert_eq!(parsed_source.parse_mode(), ParseMode::Module, "{}", text);
+      assert!(parsed_source.is_module());
+      assert_eq!(
+        parsed_source.module_detection_reason(),
+        ModuleDetectionReason::ParseMode
+      );
+      assert!(parsed_source.fallback_diagnostics().is_empty());
+    }
+  }
+
+  #[test]
+  fn should_classify_unexpected_eof() {
+    fn parse_script_for_diagnostic(text: &str) -> Diagnostic {
+      let result = parse_script(
+        ParseParams::builder(
+          "file:///repl.ts",
+          SourceTextInfo::from_string(text.to_string()),
+        )
+        .media_type(MediaType::TypeScript)
+        .build(),
+      );
+      // some incomplete input is only a non-fatal diagnostic
+      match result {
+        Ok(parsed_source) => parsed_source.diagnostics()[0].clone(),
+        Err(diagnostic) => diagnostic,
+      }
+    }
+
+    for text in [
+      "const x = {",
+      "function f() {",
+      "`unterminated",
+      "'unterminated",
+      "console.log(1, 2",
+      "/* comment",
+    ] {
+      assert!(
+        parse_script_for_diagnostic(text).is_unexpected_eof(),
+        "{}",
+        text
+      );
+    }
+    assert!(!parse_script_for_diagnostic("1 +* 2").is_unexpected_eof());
+    assert!(!parse_script_for_diagnostic("t u").is_unexpected_eof());
   }
 
   #[test]
   fn should_parse_module() {
-    let program = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let program = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .unwrap();
     assert!(matches!(
       program.module().body[0],
@@ -341,16 +3346,17 @@ mod test {
     use crate::view::ClassMethod;
     use crate::view::NodeTrait;
 
-    let program = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string(
-        "class T { method() { #test in this; } }".to_string(),
-      ),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let program = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(
+          "class T { method() { #test in this; } }".to_string(),
+        ),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .unwrap();
 
     program.with_view(|program| {
@@ -366,31 +3372,54 @@ mod test {
     expected = "Tokens not found because they were not captured during parsing."
   )]
   fn should_panic_when_getting_tokens_and_tokens_not_captured() {
-    let program = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let program = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
     .unwrap();
+    assert!(program.maybe_tokens().is_none());
     program.tokens();
   }
 
+  #[test]
+  fn should_get_maybe_tokens() {
+    let parse = |capture_tokens: bool| {
+      parse_module(
+        ParseParams::builder(
+          "my_file.js",
+          SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+        )
+        .media_type(MediaType::JavaScript)
+        .capture_tokens(capture_tokens)
+        .build(),
+      )
+      .unwrap()
+    };
+    let parsed_source = parse(true);
+    let tokens = parsed_source.maybe_tokens().unwrap();
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens.len(), parsed_source.tokens().len());
+    assert!(parse(false).maybe_tokens().is_none());
+  }
+
   #[test]
   fn should_handle_parse_error() {
-    let diagnostic = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("t u".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let diagnostic = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("t u".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .err()
     .unwrap();
-    assert_eq!(diagnostic.specifier, "my_file.js".to_string());
+    assert_eq!(diagnostic.specifier(), "my_file.js");
     assert_eq!(
       diagnostic.display_position(),
       LineAndColumnDisplay {
@@ -406,7 +3435,9 @@ mod test {
     expected = "Could not get syntax context because the source was not parsed with scope analysis."
   )]
   fn should_panic_when_getting_top_level_context_and_scope_analysis_false() {
-    get_scope_analysis_false_parsed_source().top_level_context();
+    let parsed_source = get_scope_analysis_false_parsed_source();
+    assert!(parsed_source.maybe_top_level_context().is_none());
+    parsed_source.top_level_context();
   }
 
   #[test]
@@ -414,34 +3445,87 @@ mod test {
     expected = "Could not get syntax context because the source was not parsed with scope analysis."
   )]
   fn should_panic_when_getting_unresolved_context_and_scope_analysis_false() {
-    get_scope_analysis_false_parsed_source().unresolved_context();
+    let parsed_source = get_scope_analysis_false_parsed_source();
+    assert!(parsed_source.maybe_unresolved_context().is_none());
+    parsed_source.unresolved_context();
   }
 
   fn get_scope_analysis_false_parsed_source() -> ParsedSource {
-    parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
-      media_type: MediaType::JavaScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("// 1\n1 + 1\n// 2".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[cfg(feature = "transforms")]
+  #[test]
+  fn should_have_syntax_contexts_when_scope_analysis() {
+    let parsed_source = get_scope_analysis_true_parsed_source();
+    assert!(parsed_source.has_scope_analysis());
+    assert!(parsed_source.diagnostics().is_empty());
+    assert_eq!(
+      parsed_source.maybe_top_level_context(),
+      Some(parsed_source.top_level_context())
+    );
+    assert_eq!(
+      parsed_source.maybe_unresolved_context(),
+      Some(parsed_source.unresolved_context())
+    );
+  }
+
+  #[cfg(not(feature = "transforms"))]
+  #[test]
+  fn should_fallback_without_scope_analysis_when_transforms_disabled() {
+    let parsed_source = get_scope_analysis_true_parsed_source();
+    assert!(!parsed_source.has_scope_analysis());
+    let diagnostics = parsed_source.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+      diagnostics[0].kind,
+      DiagnosticKind::ScopeAnalysisUnavailable
+    );
+    assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(diagnostics[0].code().as_str(), "scope-analysis-unavailable");
+    assert!(parsed_source.maybe_top_level_context().is_none());
+    assert!(parsed_source.maybe_unresolved_context().is_none());
+    assert!(!parsed_source
+      .into_with_scope_analysis()
+      .has_scope_analysis());
+  }
+
+  fn get_scope_analysis_true_parsed_source() -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string("const a = 1; a;".to_string()),
+      )
+      .media_type(MediaType::JavaScript)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap()
   }
 
   #[cfg(all(feature = "view", feature = "transforms"))]
   #[test]
   fn should_do_scope_analysis() {
-    let parsed_source = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string(
-        "export function test() { const test = 2; test; } test()".to_string(),
-      ),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(
+          "export function test() { const test = 2; test; } test()".to_string(),
+        ),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
 
     parsed_source.with_view(|view| {
@@ -471,16 +3555,17 @@ mod test {
   #[cfg(all(feature = "view", feature = "transforms"))]
   #[test]
   fn should_allow_scope_analysis_after_the_fact() {
-    let parsed_source = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string(
-        "export function test() { const test = 2; test; } test()".to_string(),
-      ),
-      media_type: MediaType::JavaScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(
+          "export function test() { const test = 2; test; } test()".to_string(),
+        ),
+      )
+      .media_type(MediaType::JavaScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .unwrap();
 
     parsed_source.with_view(|view| {
@@ -515,23 +3600,94 @@ mod test {
     });
   }
 
+  #[cfg(all(feature = "visit", feature = "transforms"))]
+  #[test]
+  fn should_remove_and_redo_scope_analysis() {
+    use crate::swc::ast::Id;
+    use crate::swc::ast::Ident;
+    use crate::swc::visit::Visit;
+    use crate::swc::visit::VisitWith;
+
+    struct IdCollector(Vec<Id>);
+
+    impl Visit for IdCollector {
+      fn visit_ident(&mut self, ident: &Ident) {
+        self.0.push(ident.to_id());
+      }
+    }
+
+    // the syntax contexts differ between analyses, so compare which
+    // identifiers are the same
+    fn id_relationships(parsed_source: &ParsedSource) -> Vec<Vec<bool>> {
+      let mut collector = IdCollector(Vec::new());
+      parsed_source.program_ref().visit_with(&mut collector);
+      let ids = collector.0;
+      ids
+        .iter()
+        .map(|a| ids.iter().map(|b| a == b).collect())
+        .collect()
+    }
+
+    fn parse(scope_analysis: bool) -> ParsedSource {
+      parse_module(
+        ParseParams::builder(
+          "file:///my_file.ts",
+          SourceTextInfo::from_string(
+            "let a = 1; function b(a: number) { { const a = 2; a; } a; } a; b;"
+              .to_string(),
+          ),
+        )
+        .media_type(MediaType::TypeScript)
+        .scope_analysis(scope_analysis)
+        .build(),
+      )
+      .unwrap()
+    }
+
+    let expected = id_relationships(&parse(true));
+    let unanalyzed = id_relationships(&parse(false));
+    assert_ne!(expected, unanalyzed);
+
+    let parsed_source = parse(true).without_scope_analysis();
+    assert!(!parsed_source.has_scope_analysis());
+    assert!(parsed_source.maybe_top_level_context().is_none());
+    assert_eq!(id_relationships(&parsed_source), unanalyzed);
+
+    let parsed_source = parsed_source.into_with_scope_analysis();
+    assert!(parsed_source.has_scope_analysis());
+    assert_eq!(id_relationships(&parsed_source), expected);
+
+    // analyzing again is the same as analyzing once
+    let parsed_source = parsed_source.into_with_scope_analysis();
+    assert_eq!(id_relationships(&parsed_source), expected);
+
+    // shared sources are cloned
+    let clone = parsed_source.clone();
+    let parsed_source = parsed_source.without_scope_analysis();
+    assert!(clone.has_scope_analysis());
+    assert_eq!(id_relationships(&clone), expected);
+    assert_eq!(id_relationships(&parsed_source), unanalyzed);
+  }
+
   #[cfg(all(feature = "view", feature = "transforms"))]
   #[test]
   fn should_scope_analyze_typescript() {
-    let parsed_source = parse_module(ParseParams {
-      specifier: "my_file.ts".to_string(),
-      text_info: SourceTextInfo::from_string(
-        r#"import type { Foo } from "./foo.ts";
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "my_file.ts",
+        SourceTextInfo::from_string(
+          r#"import type { Foo } from "./foo.ts";
 function _bar(...Foo: Foo) {
   console.log(Foo);
 }"#
-          .to_string(),
-      ),
-      media_type: MediaType::TypeScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+            .to_string(),
+        ),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(true)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
 
     parsed_source.with_view(|view| {
@@ -595,6 +3751,87 @@ function _bar(...Foo: Foo) {
     );
   }
 
+  #[test]
+  fn should_hint_for_no_equals_sign_in_var_decl() {
+    let diagnostic =
+      parse_for_diagnostic("const Methods {\nf: (x, y) => x + y,\n};");
+    assert_eq!(
+      diagnostic.hint.as_deref(),
+      Some("Add an initializer or declare it with `let`")
+    );
+    assert_eq!(diagnostic.related.len(), 1);
+    assert_eq!(
+      diagnostic
+        .text_info()
+        .range_text(&diagnostic.related[0].range),
+      "const"
+    );
+    let text = diagnostic.display_with_snippet(Default::default());
+    assert!(
+      text.ends_with(concat!(
+        "\n  = note: 'const' declared here at my_file.ts:1:1",
+        "\n  = hint: Add an initializer or declare it with `let`",
+      )),
+      "{}",
+      text
+    );
+  }
+
+  #[test]
+  fn should_suggest_fixes() {
+    fn get_first_diagnostic(text: &str) -> Diagnostic {
+      match parse_ts_module(text) {
+        Ok(parsed_source) => parsed_source.diagnostics()[0].clone(),
+        Err(diagnostic) => diagnostic,
+      }
+    }
+    fn apply_suggestion(text: &str) -> (String, String) {
+      let diagnostic = get_first_diagnostic(text);
+      assert_eq!(diagnostic.suggestions.len(), 1, "{}", text);
+      let suggestion = &diagnostic.suggestions[0];
+      (
+        suggestion.description.clone(),
+        crate::apply_text_changes(text, suggestion.edits.clone()),
+      )
+    }
+
+    for (text, expected_description, expected_text) in [
+      (
+        "console.log(1) console.log(2);",
+        "Insert a semicolon",
+        "console.log(1); console.log(2);",
+      ),
+      (
+        "const a = \"hello;\nconsole.log(a);",
+        "Terminate the string",
+        "const a = \"hello;\"\nconsole.log(a);",
+      ),
+      (
+        "const a = 'test",
+        "Terminate the string",
+        "const a = 'test'",
+      ),
+    ] {
+      let (description, fixed_text) = apply_suggestion(text);
+      assert_eq!(description, expected_description);
+      assert_eq!(fixed_text, expected_text);
+      let parsed_source = parse_ts_module(&fixed_text).unwrap();
+      assert!(parsed_source.diagnostics().is_empty(), "{}", fixed_text);
+    }
+
+    let (description, fixed_text) = apply_suggestion("const a;");
+    assert_eq!(description, "Declare it with `let`");
+    assert_eq!(fixed_text, "let a;");
+    assert!(parse_ts_module(&fixed_text)
+      .unwrap()
+      .diagnostics()
+      .is_empty());
+
+    // no suggestion when the fix isn't mechanical
+    let diagnostic = get_first_diagnostic("let a = 0, let b = 1;");
+    assert!(diagnostic.suggestions.is_empty());
+  }
+
   #[test]
   fn should_diganotic_when_var_stmts_sep_by_comma() {
     let diagnostic = parse_for_diagnostic("let a = 0, let b = 1;");
@@ -620,19 +3857,157 @@ function _bar(...Foo: Foo) {
     );
   }
 
+  #[test]
+  fn should_get_stable_diagnostic_codes() {
+    let get_fatal_code =
+      |text: &str| parse_ts_module(text).err().unwrap().code().as_str();
+    assert_eq!(get_fatal_code("test;\nas#;"), "expected-semi");
+    assert_eq!(get_fatal_code("test;\n`abc"), "unexpected-eof");
+    assert_eq!(get_fatal_code("/* abc"), "unterminated-block-comment");
+    assert_eq!(
+      parse_for_diagnostic("type T =\n  | unknown\n  { } & unknown;")
+        .code()
+        .as_str(),
+      "expression-expected"
+    );
+    assert_eq!(
+      parse_for_diagnostic("using test").code().as_str(),
+      "using-needs-init"
+    );
+  }
+
+  #[test]
+  fn should_include_other_errors_in_fatal_diagnostic() {
+    let diagnostic = parse_ts_module(concat!(
+      "function a() {\n",
+      "  let b = 0, let c = 1;\n",
+      "}\n",
+      "function d() {\n",
+      "  return 1 +;\n",
+      "}\n",
+    ))
+    .err()
+    .unwrap();
+    assert_eq!(diagnostic.message(), "Expression expected");
+    assert_eq!(diagnostic.other_errors().len(), 2);
+    let errors = diagnostic.into_all_errors();
+    let errors = errors
+      .iter()
+      .map(|d| {
+        let position = d.display_position();
+        (
+          d.message().to_string(),
+          position.line_number,
+          position.column_number,
+        )
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      errors,
+      vec![
+        (
+          "`let` cannot be used as an identifier in strict mode".to_string(),
+          2,
+          14
+        ),
+        ("Expected a semicolon".to_string(), 2, 18),
+        ("Expression expected".to_string(), 5, 13),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_share_source_between_diagnostics() {
+    let text = "let a = 0, let b = 1;\n".repeat(100);
+    let parsed_source = parse_ts_module(&text).unwrap();
+    let diagnostics = parsed_source.diagnostics();
+    assert!(diagnostics.len() >= 100);
+    let text_ptr = parsed_source.text_info().text_str().as_ptr();
+    for diagnostic in diagnostics {
+      assert_eq!(diagnostic.text_info().text_str().as_ptr(), text_ptr);
+      assert!(Arc::ptr_eq(
+        &diagnostic.specifier,
+        &diagnostics[0].specifier
+      ));
+    }
+  }
+
+  #[test]
+  fn should_provide_swc_error_as_error_source() {
+    use std::error::Error;
+
+    fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+    assert_send_sync_static::<Diagnostic>();
+
+    fn parse_with_anyhow(text: &str) -> anyhow::Result<ParsedSource> {
+      Ok(parse_ts_module(text)?)
+    }
+
+    let err = parse_with_anyhow("test;\nas#;").err().unwrap();
+    let diagnostic = err.downcast_ref::<Diagnostic>().unwrap();
+    let source = diagnostic.source().unwrap();
+    assert_eq!(source.to_string(), diagnostic.message());
+    assert_eq!(
+      &DiagnosticKind::Syntax(diagnostic.swc_error().unwrap().kind().clone()),
+      &diagnostic.kind
+    );
+  }
+
+  #[test]
+  fn should_cap_diagnostics() {
+    let text = "let a = 0, let b = 1;\n".repeat(50);
+    let parse_with_max = |max_diagnostics: Option<usize>| {
+      let mut params = ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.clone()),
+      );
+      if let Some(max_diagnostics) = max_diagnostics {
+        params = params.max_diagnostics(max_diagnostics);
+      }
+      parse_module(params.build()).unwrap()
+    };
+    let total_count = parse_with_max(None).diagnostics().len();
+    assert!(total_count > 10);
+    let parsed_source = parse_with_max(Some(10));
+    let diagnostics = parsed_source.diagnostics();
+    assert_eq!(diagnostics.len(), 11);
+    assert_eq!(
+      diagnostics[10].kind,
+      DiagnosticKind::TooManyDiagnostics {
+        additional_count: total_count - 10,
+      }
+    );
+    assert_eq!(diagnostics[10].severity, DiagnosticSeverity::Info);
+    assert_eq!(
+      diagnostics[10].message(),
+      format!("Additional {} errors not shown", total_count - 10)
+    );
+    // the fatal error path is unaffected
+    let diagnostic = parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string("t u".to_string()),
+      )
+      .max_diagnostics(0)
+      .build(),
+    )
+    .unwrap_err();
+    assert_eq!(diagnostic.message(), "Expected ';', '}' or <eof>");
+  }
+
   fn parse_for_diagnostic(text: &str) -> Diagnostic {
     let result = parse_ts_module(text).unwrap();
     result.diagnostics().first().unwrap().to_owned()
   }
 
   fn parse_ts_module(text: &str) -> Result<ParsedSource, Diagnostic> {
-    parse_module(ParseParams {
-      specifier: "my_file.ts".to_string(),
-      text_info: SourceTextInfo::from_string(text.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    parse_module(
+      ParseParams::builder(
+        "my_file.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
   }
 }
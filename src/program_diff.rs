@@ -0,0 +1,369 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::mem::discriminant;
+
+use crate::swc::ast::*;
+use crate::swc::common::EqIgnoreSpan;
+use crate::swc::visit::Visit;
+use crate::swc::visit::VisitWith;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+
+/// Structural difference between the programs of two parsed sources
+/// (see `diff_programs`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramDiff {
+  /// Whether the programs are equal when ignoring positions, so changes
+  /// to whitespace, comments, or other formatting are equal.
+  pub is_equal: bool,
+  /// Whether the text of the comments changed.
+  pub comments_changed: bool,
+  /// Ranges in the new source of the top level items that are not in
+  /// the old source or that moved.
+  pub changed_ranges: Vec<SourceRange>,
+  /// Ranges in the old source of the top level items that are not in
+  /// the new source or that moved.
+  pub removed_ranges: Vec<SourceRange>,
+}
+
+/// Compares the programs of two parsed sources ignoring positions.
+///
+/// Top level items are matched between the sources by a hash of their
+/// structure, so this is linear in the size of the programs unless many
+/// items have the same hash.
+pub fn diff_programs(old: &ParsedSource, new: &ParsedSource) -> ProgramDiff {
+  let old_items = top_level_items(old.program_ref());
+  let new_items = top_level_items(new.program_ref());
+  let mut old_indexes_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+  for (index, item) in old_items.iter().enumerate() {
+    old_indexes_by_hash
+      .entry(item.hash)
+      .or_default()
+      .push(index);
+  }
+
+  let mut old_matched = vec![false; old_items.len()];
+  let mut changed_ranges = Vec::new();
+  // items are only matched in order so that moved items are changes
+  let mut next_old_index = 0;
+  for new_item in &new_items {
+    let maybe_old_index =
+      old_indexes_by_hash.get(&new_item.hash).and_then(|indexes| {
+        indexes.iter().copied().find(|index| {
+          *index >= next_old_index
+            && !old_matched[*index]
+            && old_items[*index].node.eq_ignore_span(&new_item.node)
+        })
+      });
+    match maybe_old_index {
+      Some(old_index) => {
+        old_matched[old_index] = true;
+        next_old_index = old_index + 1;
+      }
+      None => changed_ranges.push(new_item.range),
+    }
+  }
+  let removed_ranges = old_items
+    .iter()
+    .zip(old_matched)
+    .filter(|(_, matched)| !matched)
+    .map(|(item, _)| item.range)
+    .collect::<Vec<_>>();
+
+  ProgramDiff {
+    is_equal: changed_ranges.is_empty()
+      && removed_ranges.is_empty()
+      && old.program_ref().eq_ignore_span(new.program_ref()),
+    comments_changed: !comments_equal(old, new),
+    changed_ranges,
+    removed_ranges,
+  }
+}
+
+fn comments_equal(old: &ParsedSource, new: &ParsedSource) -> bool {
+  let old_comments = old.comments().get_vec();
+  let new_comments = new.comments().get_vec();
+  old_comments.len() == new_comments.len()
+    && old_comments
+      .iter()
+      .zip(new_comments.iter())
+      .all(|(old, new)| old.kind == new.kind && old.text == new.text)
+}
+
+#[derive(Clone, Copy)]
+enum TopLevelNode<'a> {
+  ModuleItem(&'a ModuleItem),
+  Stmt(&'a Stmt),
+}
+
+impl TopLevelNode<'_> {
+  fn eq_ignore_span(&self, other: &Self) -> bool {
+    match (self, other) {
+      (TopLevelNode::ModuleItem(a), TopLevelNode::ModuleItem(b)) => {
+        a.eq_ignore_span(b)
+      }
+      (TopLevelNode::Stmt(a), TopLevelNode::Stmt(b)) => a.eq_ignore_span(b),
+      _ => false,
+    }
+  }
+}
+
+struct TopLevelItem<'a> {
+  node: TopLevelNode<'a>,
+  range: SourceRange,
+  hash: u64,
+}
+
+fn top_level_items(program: &Program) -> Vec<TopLevelItem> {
+  match program {
+    Program::Module(module) => module
+      .body
+      .iter()
+      .map(|item| TopLevelItem {
+        node: TopLevelNode::ModuleItem(item),
+        range: item.range(),
+        hash: structure_hash(item),
+      })
+      .collect(),
+    Program::Script(script) => script
+      .body
+      .iter()
+      .map(|stmt| TopLevelItem {
+        node: TopLevelNode::Stmt(stmt),
+        range: stmt.range(),
+        hash: structure_hash(stmt),
+      })
+      .collect(),
+  }
+}
+
+/// Hashes the kinds of nodes, names, and literal values, which are equal
+/// for nodes that are equal ignoring spans.
+fn structure_hash<T: VisitWith<StructureHasher>>(node: &T) -> u64 {
+  let mut hasher = StructureHasher(DefaultHasher::new());
+  node.visit_with(&mut hasher);
+  hasher.0.finish()
+}
+
+struct StructureHasher(DefaultHasher);
+
+impl Visit for StructureHasher {
+  fn visit_module_decl(&mut self, n: &ModuleDecl) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_stmt(&mut self, n: &Stmt) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_decl(&mut self, n: &Decl) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_expr(&mut self, n: &Expr) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_pat(&mut self, n: &Pat) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_prop(&mut self, n: &Prop) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_class_member(&mut self, n: &ClassMember) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_ts_type(&mut self, n: &TsType) {
+    discriminant(n).hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_bin_expr(&mut self, n: &BinExpr) {
+    n.op.hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_unary_expr(&mut self, n: &UnaryExpr) {
+    n.op.hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_assign_expr(&mut self, n: &AssignExpr) {
+    n.op.hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_var_decl(&mut self, n: &VarDecl) {
+    n.kind.hash(&mut self.0);
+    n.visit_children_with(self);
+  }
+
+  fn visit_ident(&mut self, n: &Ident) {
+    n.sym.hash(&mut self.0);
+  }
+
+  fn visit_str(&mut self, n: &Str) {
+    n.value.hash(&mut self.0);
+  }
+
+  fn visit_number(&mut self, n: &Number) {
+    n.value.to_bits().hash(&mut self.0);
+  }
+
+  fn visit_bool(&mut self, n: &Bool) {
+    n.value.hash(&mut self.0);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  fn range_text(parsed_source: &ParsedSource, range: SourceRange) -> &str {
+    parsed_source.text_for_range(&range)
+  }
+
+  const TEXT: &str = concat!(
+    "import { a } from './a.ts';\n",
+    "function b(c: number) {\n",
+    "  return a(c) + 1;\n",
+    "}\n",
+    "function d() {\n",
+    "  return [1, 2];\n",
+    "}\n",
+  );
+
+  #[test]
+  fn reformatted() {
+    let old = parse(TEXT);
+    let new = parse(concat!(
+      "import {a} from './a.ts'\n\n",
+      "function b(c: number) { return a(c) + 1 }\n",
+      "// comment\n",
+      "function d() {\n",
+      "    return [\n      1,\n      2,\n    ]\n",
+      "}",
+    ));
+    assert_eq!(
+      diff_programs(&old, &new),
+      ProgramDiff {
+        is_equal: true,
+        comments_changed: true,
+        changed_ranges: Vec::new(),
+        removed_ranges: Vec::new(),
+      }
+    );
+
+    let new = parse(&TEXT.replace("  return", "\treturn"));
+    assert_eq!(
+      diff_programs(&old, &new),
+      ProgramDiff {
+        is_equal: true,
+        comments_changed: false,
+        changed_ranges: Vec::new(),
+        removed_ranges: Vec::new(),
+      }
+    );
+  }
+
+  #[test]
+  fn added_statement() {
+    let old = parse(TEXT);
+    let new = parse(&TEXT.replace("function d", "console.log(b);\nfunction d"));
+    let diff = diff_programs(&old, &new);
+    assert!(!diff.is_equal);
+    assert!(!diff.comments_changed);
+    assert_eq!(
+      diff
+        .changed_ranges
+        .iter()
+        .map(|range| range_text(&new, *range))
+        .collect::<Vec<_>>(),
+      vec!["console.log(b);"]
+    );
+    assert!(diff.removed_ranges.is_empty());
+  }
+
+  #[test]
+  fn edited_function() {
+    let old = parse(TEXT);
+    let new = parse(&TEXT.replace("a(c) + 1", "a(c) + 2"));
+    let diff = diff_programs(&old, &new);
+    assert!(!diff.is_equal);
+    assert_eq!(
+      diff
+        .changed_ranges
+        .iter()
+        .map(|range| range_text(&new, *range))
+        .collect::<Vec<_>>(),
+      vec!["function b(c: number) {\n  return a(c) + 2;\n}"]
+    );
+    assert_eq!(
+      diff
+        .removed_ranges
+        .iter()
+        .map(|range| range_text(&old, *range))
+        .collect::<Vec<_>>(),
+      vec!["function b(c: number) {\n  return a(c) + 1;\n}"]
+    );
+  }
+
+  #[test]
+  fn moved_and_removed() {
+    let old = parse("a();\nb();\nc();\n");
+    let new = parse("c();\na();\n");
+    let diff = diff_programs(&old, &new);
+    assert!(!diff.is_equal);
+    // `c();` is matched first, so `a();` moved
+    assert_eq!(
+      diff
+        .changed_ranges
+        .iter()
+        .map(|range| range_text(&new, *range))
+        .collect::<Vec<_>>(),
+      vec!["a();"]
+    );
+    assert_eq!(
+      diff
+        .removed_ranges
+        .iter()
+        .map(|range| range_text(&old, *range))
+        .collect::<Vec<_>>(),
+      vec!["a();", "b();"]
+    );
+  }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+
+use crate::swc::parser::token::Token;
+use crate::swc::parser::token::TokenAndSpan;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+
+/// A syntax proposal that may be disabled via `SyntaxOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxProposal {
+  /// Auto-accessors (ex. `accessor value = 1;` in a class body).
+  AutoAccessors,
+  /// Explicit resource management (ex. `using file = open();`).
+  ExplicitResourceManagement,
+}
+
+impl SyntaxProposal {
+  /// Message text for when the proposal is used while disabled.
+  pub fn disabled_msg(&self) -> Cow<str> {
+    match self {
+      SyntaxProposal::AutoAccessors => Cow::Borrowed(
+        "Auto-accessors (`accessor` class fields) are not enabled",
+      ),
+      SyntaxProposal::ExplicitResourceManagement => Cow::Borrowed(
+        "Explicit resource management (`using` declarations) is not enabled",
+      ),
+    }
+  }
+}
+
+/// Gets the ranges of the `accessor` keyword of any auto-accessors
+/// found in the tokens of successfully parsed source text.
+pub(crate) fn get_auto_accessor_ranges(
+  tokens: &[TokenAndSpan],
+  source: &SourceTextInfo,
+) -> Vec<SourceRange> {
+  // stack of open braces where `true` means the brace is a class body
+  let mut braces: Vec<bool> = Vec::new();
+  let mut paren_depth: usize = 0;
+  // paren depth and brace count of a class whose body has not started
+  let mut pending_class: Option<(usize, usize)> = None;
+  let mut ranges = Vec::new();
+  for (i, token) in tokens.iter().enumerate() {
+    match &token.token {
+      Token::LParen | Token::LBracket => paren_depth += 1,
+      Token::RParen | Token::RBracket => {
+        paren_depth = paren_depth.saturating_sub(1)
+      }
+      Token::LBrace | Token::DollarLBrace => {
+        let is_class_body = pending_class == Some((paren_depth, braces.len()))
+          && token.token == Token::LBrace;
+        if is_class_body {
+          pending_class = None;
+        }
+        braces.push(is_class_body);
+      }
+      Token::RBrace => {
+        braces.pop();
+      }
+      Token::Word(_) => {
+        let text = source.range_text(&token.range());
+        let prev = i.checked_sub(1).and_then(|i| tokens.get(i));
+        let is_member_name = prev.is_some_and(|prev| prev.token == Token::Dot);
+        let is_key = tokens
+          .get(i + 1)
+          .is_some_and(|next| next.token == Token::Colon);
+        if text == "class" && !is_member_name && !is_key {
+          pending_class = Some((paren_depth, braces.len()));
+        } else if text == "accessor"
+          && braces.last() == Some(&true)
+          && is_auto_accessor_name_start(tokens.get(i + 1), source)
+        {
+          ranges.push(token.range());
+        }
+      }
+      _ => {}
+    }
+  }
+  ranges
+}
+
+fn is_auto_accessor_name_start(
+  next: Option<&TokenAndSpan>,
+  source: &SourceTextInfo,
+) -> bool {
+  let Some(next) = next else {
+    return false;
+  };
+  if next.had_line_break {
+    return false;
+  }
+  match &next.token {
+    Token::Word(_) => !matches!(
+      source.range_text(&next.range()),
+      "in" | "instanceof" | "as" | "satisfies"
+    ),
+    Token::Str { .. }
+    | Token::Num { .. }
+    | Token::BigInt { .. }
+    | Token::LBracket
+    | Token::Hash => true,
+    _ => false,
+  }
+}
+
+/// Gets the ranges of the `using` keyword of any `using` declarations
+/// found in the tokens of successfully parsed source text.
+pub(crate) fn get_using_decl_ranges(
+  tokens: &[TokenAndSpan],
+  source: &SourceTextInfo,
+) -> Vec<SourceRange> {
+  tokens
+    .windows(2)
+    .filter(|window| {
+      let (token, next) = (&window[0], &window[1]);
+      matches!(token.token, Token::Word(_))
+        && matches!(next.token, Token::Word(_))
+        && !next.had_line_break
+        && source.range_text(&token.range()) == "using"
+        && !matches!(
+          source.range_text(&next.range()),
+          "in" | "instanceof" | "of" | "as" | "satisfies"
+        )
+    })
+    .map(|window| window[0].range())
+    .collect()
+}
@@ -0,0 +1,74 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::SourceRange;
+use crate::SourceTextInfo;
+
+/// Checked slicing of the source text.
+pub trait SourceTextInfoRangeText {
+  /// Gets the text of the provided range or `None` when the range is
+  /// outside of the text (ex. it's from a different file) or doesn't
+  /// start and end on character boundaries.
+  ///
+  /// Use `SourceTextInfo::range_text` when the range is known to be
+  /// valid, which panics instead.
+  fn try_range_text(&self, range: &SourceRange) -> Option<&str>;
+}
+
+impl SourceTextInfoRangeText for SourceTextInfo {
+  fn try_range_text(&self, range: &SourceRange) -> Option<&str> {
+    let text_range = self.range();
+    if range.start < text_range.start
+      || range.end > text_range.end
+      || range.start > range.end
+    {
+      return None;
+    }
+    self
+      .text_str()
+      .get(range.start - text_range.start..range.end - text_range.start)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn try_range_text() {
+    let text = "let a = 'é';\r\nlet b;";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    let range_of = |start_index: usize, end_index: usize| {
+      SourceRange::new(start + start_index, start + end_index)
+    };
+
+    // spanning a CRLF newline
+    let crlf_index = text.find('\r').unwrap();
+    let range = range_of(crlf_index - 2, crlf_index + 5);
+    assert_eq!(source.try_range_text(&range), Some("';\r\nlet"));
+    assert_eq!(
+      source.try_range_text(&range),
+      Some(source.range_text(&range))
+    );
+    assert_eq!(source.try_range_text(&range_of(0, text.len())), Some(text));
+    assert_eq!(source.try_range_text(&range_of(3, 3)), Some(""));
+
+    // within a multi-byte character
+    let char_index = text.find('é').unwrap();
+    assert_eq!(source.try_range_text(&range_of(char_index + 1, 12)), None);
+    assert_eq!(source.try_range_text(&range_of(0, char_index + 1)), None);
+    assert_eq!(
+      source.try_range_text(&range_of(char_index, char_index + 2)),
+      Some("é")
+    );
+
+    // outside of the text
+    assert_eq!(source.try_range_text(&range_of(0, text.len() + 1)), None);
+    assert_eq!(
+      source.try_range_text(&range_of(text.len() + 5, text.len() + 10)),
+      None
+    );
+  }
+}
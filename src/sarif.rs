@@ -0,0 +1,229 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversion of diagnostics to the Static Analysis Results Interchange
+//! Format (SARIF) 2.1.0.
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Diagnostic;
+use crate::DiagnosticSeverity;
+
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLog {
+  #[serde(rename = "$schema")]
+  pub schema: String,
+  pub version: String,
+  pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Run {
+  pub tool: Tool,
+  pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+  pub driver: ToolComponent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolComponent {
+  pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+  pub rule_id: String,
+  pub level: Level,
+  pub message: Message,
+  pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Level {
+  Error,
+  Warning,
+  Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+  pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+  pub physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+  pub artifact_location: ArtifactLocation,
+  pub region: Region,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactLocation {
+  pub uri: String,
+}
+
+/// 1-indexed region where the columns are in UTF-16 code units, which is
+/// SARIF's default column kind. The end column is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+  pub start_line: usize,
+  pub start_column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+}
+
+/// Converts the diagnostics to a SARIF log with a single run of the
+/// provided tool, with one result per diagnostic.
+///
+/// Specifiers are used as the artifact location URIs as-is.
+pub fn diagnostics_to_sarif(
+  diagnostics: &[Diagnostic],
+  tool_name: &str,
+) -> SarifLog {
+  SarifLog {
+    schema: SARIF_SCHEMA.to_string(),
+    version: SARIF_VERSION.to_string(),
+    runs: vec![Run {
+      tool: Tool {
+        driver: ToolComponent {
+          name: tool_name.to_string(),
+        },
+      },
+      results: diagnostics.iter().map(diagnostic_to_result).collect(),
+    }],
+  }
+}
+
+fn diagnostic_to_result(diagnostic: &Diagnostic) -> SarifResult {
+  let range = diagnostic.display_range_utf16();
+  SarifResult {
+    rule_id: diagnostic.code().as_str().to_string(),
+    level: match diagnostic.severity {
+      DiagnosticSeverity::Error => Level::Error,
+      DiagnosticSeverity::Warning => Level::Warning,
+      DiagnosticSeverity::Info => Level::Note,
+    },
+    message: Message {
+      text: diagnostic.message().into_owned(),
+    },
+    locations: vec![Location {
+      physical_location: PhysicalLocation {
+        artifact_location: ArtifactLocation {
+          uri: diagnostic.specifier().to_string(),
+        },
+        region: Region {
+          start_line: range.start.line_number,
+          start_column: range.start.column_number,
+          end_line: range.end.line_number,
+          end_column: range.end.column_number,
+        },
+      },
+    }],
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::DiagnosticKind;
+  use crate::JsonDiagnosticKind;
+  use crate::SourceRange;
+  use crate::SourceTextInfo;
+
+  #[test]
+  fn converts_diagnostics() {
+    let text = "{\n  \"a\": 1,\n  \"a\": 2, // 𝒳\n}";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let create = |specifier: &str, find_text: &str, kind: DiagnosticKind| {
+      let start = source.range().start + text.find(find_text).unwrap();
+      Diagnostic::new(
+        specifier,
+        SourceRange::new(start, start + find_text.len()),
+        kind,
+        source.clone(),
+      )
+    };
+    let diagnostics = vec![
+      create(
+        "file:///data.json",
+        "\"a\": 2",
+        DiagnosticKind::Json(JsonDiagnosticKind::DuplicateKey {
+          key: "a".to_string(),
+        }),
+      )
+      .with_severity(DiagnosticSeverity::Warning),
+      create(
+        "data.json",
+        "// 𝒳",
+        DiagnosticKind::Json(JsonDiagnosticKind::Comment),
+      ),
+    ];
+    let log = diagnostics_to_sarif(&diagnostics, "my-tool");
+    assert_eq!(
+      serde_json::to_value(&log).unwrap(),
+      serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+          "tool": { "driver": { "name": "my-tool" } },
+          "results": [{
+            "ruleId": "json-duplicate-key",
+            "level": "warning",
+            "message": { "text": "Duplicate key 'a' in JSON object" },
+            "locations": [{
+              "physicalLocation": {
+                "artifactLocation": { "uri": "file:///data.json" },
+                "region": {
+                  "startLine": 3,
+                  "startColumn": 3,
+                  "endLine": 3,
+                  "endColumn": 9,
+                },
+              },
+            }],
+          }, {
+            "ruleId": "json-comment",
+            "level": "error",
+            "message": { "text": "Comments are not allowed in JSON" },
+            "locations": [{
+              "physicalLocation": {
+                "artifactLocation": { "uri": "data.json" },
+                "region": {
+                  "startLine": 3,
+                  "startColumn": 11,
+                  "endLine": 3,
+                  "endColumn": 16,
+                },
+              },
+            }],
+          }],
+        }],
+      })
+    );
+    let json = serde_json::to_string(&log).unwrap();
+    assert_eq!(serde_json::from_str::<SarifLog>(&json).unwrap(), log);
+  }
+}
@@ -361,14 +361,16 @@ mod tests {
   use crate::SourceTextInfo;
 
   fn test_scope(source_code: &str, test: impl Fn(Scope)) {
-    let parsed_source = parse_module(ParseParams {
-      specifier: "my_file.js".to_string(),
-      text_info: SourceTextInfo::from_string(source_code.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: true,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        "my_file.js",
+        SourceTextInfo::from_string(source_code.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(true)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
 
     parsed_source.with_view(|view| {
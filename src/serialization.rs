@@ -0,0 +1,336 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Serialization of the position types, which are defined outside this
+//! crate, for use with `#[serde(with = "...")]`:
+//!
+//! ```
+//! # use deno_ast::SourceRange;
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Item {
+//!   #[serde(with = "deno_ast::serialization::source_range")]
+//!   range: SourceRange,
+//! }
+//! ```
+//!
+//! Positions serialize as byte indexes in the text, which assumes the
+//! text starts at `StartSourcePos::START_SOURCE_POS` as it does for text
+//! created with `SourceTextInfo::new`. The serialized shapes are:
+//!
+//! - `SourcePos`: `10`
+//! - `SourceRange`: `{ "start": 10, "end": 15 }`
+//! - `LineAndColumnIndex`: `{ "lineIndex": 0, "columnIndex": 4 }`
+//! - `LineAndColumnDisplay`: `{ "lineNumber": 1, "columnNumber": 5 }`
+//! - `MediaType`: `"typescript"` (see `media_type_name()`)
+
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::SourcePos;
+use crate::StartSourcePos;
+use crate::MAX_SOURCE_TEXT_LEN;
+
+fn pos_to_byte_index(pos: SourcePos) -> usize {
+  pos.as_byte_index(StartSourcePos::START_SOURCE_POS)
+}
+
+fn byte_index_to_pos<E: Error>(byte_index: usize) -> Result<SourcePos, E> {
+  if byte_index > MAX_SOURCE_TEXT_LEN {
+    Err(E::custom(format!(
+      "Byte index {} was greater than the maximum supported index {}.",
+      byte_index, MAX_SOURCE_TEXT_LEN
+    )))
+  } else {
+    Ok(StartSourcePos::START_SOURCE_POS + byte_index)
+  }
+}
+
+/// Serializes a `SourcePos` as its byte index.
+pub mod source_pos {
+  use super::*;
+
+  pub fn serialize<S: Serializer>(
+    pos: &SourcePos,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    pos_to_byte_index(*pos).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<SourcePos, D::Error> {
+    byte_index_to_pos(usize::deserialize(deserializer)?)
+  }
+}
+
+/// Serializes a `SourceRange` as its start and end byte indexes.
+pub mod source_range {
+  use super::*;
+  use crate::SourceRange;
+
+  #[derive(Serialize, Deserialize)]
+  struct SerializedRange {
+    start: usize,
+    end: usize,
+  }
+
+  pub fn serialize<S: Serializer>(
+    range: &SourceRange,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    SerializedRange {
+      start: pos_to_byte_index(range.start),
+      end: pos_to_byte_index(range.end),
+    }
+    .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<SourceRange, D::Error> {
+    let range = SerializedRange::deserialize(deserializer)?;
+    if range.start > range.end {
+      return Err(D::Error::custom(format!(
+        "Range start {} was greater than end {}.",
+        range.start, range.end
+      )));
+    }
+    Ok(SourceRange::new(
+      byte_index_to_pos(range.start)?,
+      byte_index_to_pos(range.end)?,
+    ))
+  }
+}
+
+/// Serializes a `LineAndColumnIndex` as its 0-indexed line and column.
+pub mod line_and_column_index {
+  use super::*;
+  use crate::LineAndColumnIndex;
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct SerializedLineAndColumnIndex {
+    line_index: usize,
+    column_index: usize,
+  }
+
+  pub fn serialize<S: Serializer>(
+    value: &LineAndColumnIndex,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    SerializedLineAndColumnIndex {
+      line_index: value.line_index,
+      column_index: value.column_index,
+    }
+    .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<LineAndColumnIndex, D::Error> {
+    let value = SerializedLineAndColumnIndex::deserialize(deserializer)?;
+    Ok(LineAndColumnIndex {
+      line_index: value.line_index,
+      column_index: value.column_index,
+    })
+  }
+}
+
+/// Serializes a `LineAndColumnDisplay` as its 1-indexed line and column
+/// numbers.
+pub mod line_and_column_display {
+  use super::*;
+  use crate::LineAndColumnDisplay;
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct SerializedLineAndColumnDisplay {
+    line_number: usize,
+    column_number: usize,
+  }
+
+  pub fn serialize<S: Serializer>(
+    value: &LineAndColumnDisplay,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    SerializedLineAndColumnDisplay {
+      line_number: value.line_number,
+      column_number: value.column_number,
+    }
+    .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<LineAndColumnDisplay, D::Error> {
+    let value = SerializedLineAndColumnDisplay::deserialize(deserializer)?;
+    Ok(LineAndColumnDisplay {
+      line_number: value.line_number,
+      column_number: value.column_number,
+    })
+  }
+}
+
+/// Serializes a `MediaType` as its stable name from
+/// `media_type_name()`.
+///
+/// Unrecognized names deserialize as `MediaType::Unknown` so that data
+/// written by newer versions can still be read.
+pub mod media_type {
+  use super::*;
+  use crate::media_type_from_name;
+  use crate::media_type_name;
+  use crate::MediaType;
+
+  pub fn serialize<S: Serializer>(
+    media_type: &MediaType,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    media_type_name(*media_type).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<MediaType, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Ok(media_type_from_name(&name))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+  use crate::DiagnosticDisplayRange;
+  use crate::LineAndColumnDisplay;
+  use crate::LineAndColumnIndex;
+  use crate::MediaType;
+  use crate::SourceRange;
+  use crate::SourceTextInfo;
+  use crate::TextEdit;
+
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct Positions {
+    #[serde(with = "source_pos")]
+    pos: SourcePos,
+    #[serde(with = "source_range")]
+    range: SourceRange,
+    #[serde(with = "line_and_column_index")]
+    index: LineAndColumnIndex,
+    #[serde(with = "line_and_column_display")]
+    display: LineAndColumnDisplay,
+  }
+
+  #[test]
+  fn serializes_positions() {
+    let text_info = SourceTextInfo::from_string("let a;\nlet b;".to_string());
+    let start = text_info.range().start;
+    let positions = Positions {
+      pos: start + 11,
+      range: SourceRange::new(start + 7, start + 13),
+      index: text_info.line_and_column_index(start + 11),
+      display: text_info.line_and_column_display(start + 11),
+    };
+    let value = serde_json::to_value(&positions).unwrap();
+    // the shape is depended on, so don't change it
+    assert_eq!(
+      value,
+      json!({
+        "pos": 11,
+        "range": { "start": 7, "end": 13 },
+        "index": { "lineIndex": 1, "columnIndex": 4 },
+        "display": { "lineNumber": 2, "columnNumber": 5 },
+      })
+    );
+    assert_eq!(
+      serde_json::from_value::<Positions>(value).unwrap(),
+      positions
+    );
+    assert_eq!(text_info.range_text(&positions.range), "let b;");
+  }
+
+  #[test]
+  fn serializes_positional_structs() {
+    let start = SourceTextInfo::from_string("ab".to_string()).range().start;
+    let edit = TextEdit::new(SourceRange::new(start + 1, start + 2), "b");
+    let value = serde_json::to_value(&edit).unwrap();
+    assert_eq!(
+      value,
+      json!({ "range": { "start": 1, "end": 2 }, "newText": "b" })
+    );
+    assert_eq!(serde_json::from_value::<TextEdit>(value).unwrap(), edit);
+
+    let display_range = DiagnosticDisplayRange {
+      start: LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 2,
+      },
+      end: LineAndColumnDisplay {
+        line_number: 3,
+        column_number: 4,
+      },
+    };
+    let value = serde_json::to_value(&display_range).unwrap();
+    assert_eq!(
+      value,
+      json!({
+        "start": { "lineNumber": 1, "columnNumber": 2 },
+        "end": { "lineNumber": 3, "columnNumber": 4 },
+      })
+    );
+    assert_eq!(
+      serde_json::from_value::<DiagnosticDisplayRange>(value).unwrap(),
+      display_range
+    );
+  }
+
+  #[test]
+  fn serializes_media_types() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct CacheEntry {
+      #[serde(with = "media_type")]
+      media_type: MediaType,
+    }
+
+    let entry = CacheEntry {
+      media_type: MediaType::Dmts,
+    };
+    let value = serde_json::to_value(&entry).unwrap();
+    assert_eq!(value, json!({ "mediaType": "dmts" }));
+    assert_eq!(serde_json::from_value::<CacheEntry>(value).unwrap(), entry);
+    assert_eq!(
+      serde_json::from_value::<CacheEntry>(json!({ "mediaType": "wat" }))
+        .unwrap(),
+      CacheEntry {
+        media_type: MediaType::Unknown,
+      }
+    );
+  }
+
+  #[test]
+  fn rejects_invalid_ranges() {
+    let err = serde_json::from_value::<TextEdit>(
+      json!({ "range": { "start": 2, "end": 1 }, "newText": "" }),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Range start 2 was greater than end 1.");
+    let err = serde_json::from_value::<TextEdit>(json!({
+      "range": { "start": 0, "end": MAX_SOURCE_TEXT_LEN + 1 },
+      "newText": "",
+    }))
+    .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Byte index {} was greater than the maximum supported index {}.",
+        MAX_SOURCE_TEXT_LEN + 1,
+        MAX_SOURCE_TEXT_LEN
+      )
+    );
+  }
+}
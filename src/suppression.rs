@@ -0,0 +1,296 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::comments::MultiThreadedComments;
+use crate::Diagnostic;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SourceTextInfo;
+
+/// Kind of comment directive that suppresses diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionKind {
+  /// `// @ts-ignore`
+  TsIgnore,
+  /// `// @ts-expect-error`, which is expected to suppress a diagnostic.
+  TsExpectError,
+  /// `// deno-lint-ignore`, optionally followed by the codes of the
+  /// diagnostics to suppress (ex. `// deno-lint-ignore expected-semi`).
+  DenoLintIgnore,
+}
+
+impl SuppressionKind {
+  fn directive(&self) -> &'static str {
+    match self {
+      SuppressionKind::TsIgnore => "@ts-ignore",
+      SuppressionKind::TsExpectError => "@ts-expect-error",
+      SuppressionKind::DenoLintIgnore => "deno-lint-ignore",
+    }
+  }
+}
+
+/// Which suppression comments to honor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuppressionRules {
+  pub kinds: Vec<SuppressionKind>,
+}
+
+impl Default for SuppressionRules {
+  fn default() -> Self {
+    Self {
+      kinds: vec![
+        SuppressionKind::TsIgnore,
+        SuppressionKind::TsExpectError,
+        SuppressionKind::DenoLintIgnore,
+      ],
+    }
+  }
+}
+
+/// A suppression comment found in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+  pub kind: SuppressionKind,
+  /// Range of the comment.
+  pub range: SourceRange,
+  /// Number of diagnostics the comment suppressed.
+  pub suppressed_count: usize,
+}
+
+impl Suppression {
+  /// Gets if this is a `@ts-expect-error` that did not suppress anything.
+  pub fn is_unused_expect_error(&self) -> bool {
+    self.kind == SuppressionKind::TsExpectError && self.suppressed_count == 0
+  }
+}
+
+/// Diagnostics that were not suppressed along with the suppression
+/// comments found in the source.
+#[derive(Debug, Clone)]
+pub struct FilteredDiagnostics<'a> {
+  pub kept: Vec<&'a Diagnostic>,
+  /// Suppression comments sorted by position.
+  pub suppressions: Vec<Suppression>,
+}
+
+struct SuppressionComment {
+  suppression: Suppression,
+  line_index: usize,
+  /// Whether there is only whitespace before the comment on its line,
+  /// in which case it also applies to the next line.
+  is_own_line: bool,
+  /// Codes of the diagnostics to suppress or empty for all of them.
+  codes: Vec<String>,
+}
+
+pub(crate) fn filter_diagnostics<'a>(
+  diagnostics: &'a [Diagnostic],
+  comments: &MultiThreadedComments,
+  source: &SourceTextInfo,
+  rules: &SuppressionRules,
+) -> FilteredDiagnostics<'a> {
+  let mut suppression_comments = comments
+    .iter_unstable()
+    .filter_map(|comment| {
+      let (kind, codes) = parse_suppression_comment(&comment.text, rules)?;
+      let range = comment.range();
+      let line_start = source.line_start(source.line_index(range.start));
+      let is_own_line = source
+        .range_text(&SourceRange::new(line_start, range.start))
+        .trim()
+        .is_empty();
+      Some(SuppressionComment {
+        suppression: Suppression {
+          kind,
+          range,
+          suppressed_count: 0,
+        },
+        // comments that span multiple lines apply from their last line
+        line_index: source.line_index(range.end),
+        is_own_line,
+        codes,
+      })
+    })
+    .collect::<Vec<_>>();
+  suppression_comments.sort_by_key(|c| c.suppression.range.start);
+
+  let kept = diagnostics
+    .iter()
+    .filter(|diagnostic| {
+      let line_index = source.line_index(diagnostic.range.start);
+      let code = diagnostic.code();
+      let mut is_suppressed = false;
+      for comment in suppression_comments.iter_mut() {
+        // applies to the same line or, when not after code, the line after
+        let applies = (comment.line_index == line_index
+          || (comment.is_own_line && comment.line_index + 1 == line_index))
+          && (comment.codes.is_empty()
+            || comment.codes.iter().any(|c| c == code.as_str()));
+        if applies {
+          comment.suppression.suppressed_count += 1;
+          is_suppressed = true;
+        }
+      }
+      !is_suppressed
+    })
+    .collect();
+
+  FilteredDiagnostics {
+    kept,
+    suppressions: suppression_comments
+      .into_iter()
+      .map(|c| c.suppression)
+      .collect(),
+  }
+}
+
+/// Parses the text of a comment (without the `//` or `/* */`) as a
+/// suppression directive, ignoring any explanation after it.
+pub(crate) fn parse_suppression_comment(
+  text: &str,
+  rules: &SuppressionRules,
+) -> Option<(SuppressionKind, Vec<String>)> {
+  let text = text.trim_start().trim_start_matches('*').trim_start();
+  rules.kinds.iter().find_map(|kind| {
+    let rest = text.strip_prefix(kind.directive())?;
+    // ensure this isn't a different directive (ex. `@ts-ignore-foo`)
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '-') {
+      return None;
+    }
+    let codes = match kind {
+      SuppressionKind::DenoLintIgnore => rest
+        .split_whitespace()
+        // explanations come after the codes (ex. `-- reason`)
+        .take_while(|word| {
+          word.starts_with(|c: char| c.is_ascii_alphabetic())
+            && word
+              .chars()
+              .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(|word| word.to_string())
+        .collect(),
+      SuppressionKind::TsIgnore | SuppressionKind::TsExpectError => Vec::new(),
+    };
+    Some((*kind, codes))
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::ParsedSource;
+
+  fn parse(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  fn get_kept_lines(parsed_source: &ParsedSource) -> Vec<usize> {
+    let mut lines = parsed_source
+      .diagnostics_filtered(&SuppressionRules::default())
+      .kept
+      .into_iter()
+      .map(|d| d.display_position().line_number)
+      .collect::<Vec<_>>();
+    lines.dedup();
+    lines
+  }
+
+  #[test]
+  fn same_line_suppression() {
+    let parsed_source = parse(concat!(
+      "let a = 0, let b = 1; // deno-lint-ignore -- explanation\n",
+      "let c = 0, let d = 1; /* @ts-ignore: explanation */\n",
+      "let e = 0, let f = 1;\n",
+      "  /* @ts-ignore */\n",
+      "let g = 0, let h = 1; // @ts-ignore\n",
+    ));
+    // a comment after code only applies to its own line, while one
+    // without code before it also applies to the next line
+    assert_eq!(get_kept_lines(&parsed_source), vec![3]);
+    let filtered =
+      parsed_source.diagnostics_filtered(&SuppressionRules::default());
+    let line_2_count = parsed_source
+      .diagnostics()
+      .iter()
+      .filter(|d| d.display_position().line_number == 2)
+      .count();
+    assert!(line_2_count > 0);
+    assert_eq!(filtered.suppressions[1].suppressed_count, line_2_count);
+  }
+
+  #[test]
+  fn preceding_line_suppression() {
+    let parsed_source = parse(concat!(
+      "// @ts-ignore because of reasons\n",
+      "let a = 0, let b = 1;\n",
+      "// @ts-ignore\n",
+      "\n",
+      "let c = 0, let d = 1;\n",
+      "// deno-lint-ignore some-other-code\n",
+      "let e = 0, let f = 1;\n",
+    ));
+    // only applies to the next line and to the matching codes
+    assert_eq!(get_kept_lines(&parsed_source), vec![5, 7]);
+  }
+
+  #[test]
+  fn unused_expect_error() {
+    let parsed_source = parse(concat!(
+      "// @ts-expect-error\n",
+      "let a = 0, let b = 1;\n",
+      "// @ts-expect-error - nothing to suppress\n",
+      "const c = 1;\n",
+    ));
+    let filtered =
+      parsed_source.diagnostics_filtered(&SuppressionRules::default());
+    assert!(filtered.kept.is_empty());
+    assert_eq!(filtered.suppressions.len(), 2);
+    assert!(!filtered.suppressions[0].is_unused_expect_error());
+    assert!(filtered.suppressions[1].is_unused_expect_error());
+    assert_eq!(
+      parsed_source
+        .text_info()
+        .range_text(&filtered.suppressions[1].range),
+      "// @ts-expect-error - nothing to suppress"
+    );
+  }
+
+  #[test]
+  fn parses_suppression_comments() {
+    let rules = SuppressionRules::default();
+    assert_eq!(
+      parse_suppression_comment(" @ts-ignore", &rules),
+      Some((SuppressionKind::TsIgnore, Vec::new()))
+    );
+    assert_eq!(
+      parse_suppression_comment(" deno-lint-ignore a-b c -- reason", &rules),
+      Some((
+        SuppressionKind::DenoLintIgnore,
+        vec!["a-b".to_string(), "c".to_string()]
+      ))
+    );
+    assert_eq!(parse_suppression_comment(" @ts-ignored", &rules), None);
+    assert_eq!(parse_suppression_comment(" some text", &rules), None);
+    assert_eq!(
+      parse_suppression_comment(
+        " @ts-ignore",
+        &SuppressionRules {
+          kinds: vec![SuppressionKind::TsExpectError],
+        }
+      ),
+      None
+    );
+  }
+}
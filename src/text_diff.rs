@@ -0,0 +1,260 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::ops::Range;
+
+use crate::SourceRange;
+use crate::SourceTextInfo;
+use crate::TextEdit;
+
+/// Maximum number of line insertions and deletions searched for before
+/// falling back to replacing the entire changed text, which keeps the
+/// memory used by the line diff bounded.
+const MAX_LINE_EDIT_DISTANCE: usize = 2_000;
+
+/// Gets the edits in the old text's coordinates that will produce the
+/// new text when applied with `apply_text_edits`.
+///
+/// The common prefix and suffix of the texts are trimmed, then the
+/// remaining lines are compared with a Myers diff.
+pub fn diff_texts(old: &SourceTextInfo, new: &str) -> Vec<TextEdit> {
+  let old_text = old.text_str();
+  let prefix_len = old_text
+    .chars()
+    .zip(new.chars())
+    .take_while(|(a, b)| a == b)
+    .map(|(c, _)| c.len_utf8())
+    .sum::<usize>();
+  let suffix_len = old_text[prefix_len..]
+    .chars()
+    .rev()
+    .zip(new[prefix_len..].chars().rev())
+    .take_while(|(a, b)| a == b)
+    .map(|(c, _)| c.len_utf8())
+    .sum::<usize>();
+  let old_middle = &old_text[prefix_len..old_text.len() - suffix_len];
+  let new_middle = &new[prefix_len..new.len() - suffix_len];
+  if old_middle.is_empty() && new_middle.is_empty() {
+    return Vec::new();
+  }
+
+  let start_pos = old.range().start + prefix_len;
+  let old_lines = old_middle.split_inclusive('\n').collect::<Vec<_>>();
+  let new_lines = new_middle.split_inclusive('\n').collect::<Vec<_>>();
+  let Some(hunks) = diff_lines(&old_lines, &new_lines) else {
+    return vec![TextEdit::new(
+      SourceRange::new(start_pos, start_pos + old_middle.len()),
+      new_middle,
+    )];
+  };
+  let old_offsets = get_line_offsets(&old_lines);
+  let new_offsets = get_line_offsets(&new_lines);
+  hunks
+    .into_iter()
+    .map(|hunk| {
+      TextEdit::new(
+        SourceRange::new(
+          start_pos + old_offsets[hunk.old.start],
+          start_pos + old_offsets[hunk.old.end],
+        ),
+        &new_middle[new_offsets[hunk.new.start]..new_offsets[hunk.new.end]],
+      )
+    })
+    .collect()
+}
+
+/// Range of old lines replaced by a range of new lines.
+#[derive(Debug, PartialEq, Eq)]
+struct Hunk {
+  old: Range<usize>,
+  new: Range<usize>,
+}
+
+/// Gets the byte offset of the start of each line along with the end
+/// of the last line.
+fn get_line_offsets(lines: &[&str]) -> Vec<usize> {
+  let mut offsets = Vec::with_capacity(lines.len() + 1);
+  let mut offset = 0;
+  offsets.push(offset);
+  for line in lines {
+    offset += line.len();
+    offsets.push(offset);
+  }
+  offsets
+}
+
+/// Compares the lines using the Myers diff algorithm, returning `None`
+/// when they differ by more than `MAX_LINE_EDIT_DISTANCE` lines.
+fn diff_lines(old: &[&str], new: &[&str]) -> Option<Vec<Hunk>> {
+  let n = old.len() as isize;
+  let m = new.len() as isize;
+  let max_d = std::cmp::min(old.len() + new.len(), MAX_LINE_EDIT_DISTANCE);
+  let offset = max_d as isize + 1;
+  let mut v = vec![0isize; 2 * max_d + 3];
+  // the furthest reaching x for each diagonal k in -d..=d before step d
+  let mut trace: Vec<Vec<isize>> = Vec::new();
+  let mut found = false;
+  'outer: for d in 0..=max_d as isize {
+    trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    for k in (-d..=d).step_by(2) {
+      let i = (k + offset) as usize;
+      let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+        v[i + 1]
+      } else {
+        v[i - 1] + 1
+      };
+      let mut y = x - k;
+      while x < n && y < m && old[x as usize] == new[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[i] = x;
+      if x >= n && y >= m {
+        found = true;
+        break 'outer;
+      }
+    }
+  }
+  if !found {
+    return None;
+  }
+
+  // walk back through the trace to find the changed lines
+  let mut changes = Vec::new();
+  let (mut x, mut y) = (n, m);
+  for (d, v) in trace.iter().enumerate().skip(1).rev() {
+    let d = d as isize;
+    let get_x = |k: isize| v[(k + d) as usize];
+    let k = x - y;
+    let is_insert = k == -d || (k != d && get_x(k - 1) < get_x(k + 1));
+    let prev_k = if is_insert { k + 1 } else { k - 1 };
+    let prev_x = get_x(prev_k);
+    let prev_y = prev_x - prev_k;
+    changes.push((prev_x as usize, prev_y as usize, !is_insert));
+    x = prev_x;
+    y = prev_y;
+  }
+  changes.reverse();
+
+  // merge adjacent line insertions and deletions into hunks
+  let mut hunks: Vec<Hunk> = Vec::new();
+  for (x, y, is_delete) in changes {
+    let (old_end, new_end) = if is_delete { (x + 1, y) } else { (x, y + 1) };
+    match hunks.last_mut() {
+      Some(hunk) if hunk.old.end == x && hunk.new.end == y => {
+        hunk.old.end = old_end;
+        hunk.new.end = new_end;
+      }
+      _ => hunks.push(Hunk {
+        old: x..old_end,
+        new: y..new_end,
+      }),
+    }
+  }
+  Some(hunks)
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::apply_text_edits;
+
+  /// Diffs the texts and asserts that applying the edits to the old text
+  /// produces the new text.
+  fn diff_and_apply(old: &str, new: &str) -> Vec<TextEdit> {
+    let info = SourceTextInfo::from_string(old.to_string());
+    let edits = diff_texts(&info, new);
+    let (new_info, _) = apply_text_edits(&info, &edits).unwrap();
+    assert_eq!(new_info.text_str(), new);
+    edits
+  }
+
+  fn edit(old: &str, range: Range<usize>, new_text: &str) -> TextEdit {
+    let start = SourceTextInfo::from_string(old.to_string()).range().start;
+    TextEdit::new(
+      SourceRange::new(start + range.start, start + range.end),
+      new_text,
+    )
+  }
+
+  #[test]
+  fn no_change() {
+    assert_eq!(diff_and_apply("", ""), vec![]);
+    assert_eq!(
+      diff_and_apply("let a;\nlet b;\n", "let a;\nlet b;\n"),
+      vec![]
+    );
+  }
+
+  #[test]
+  fn single_char_insert() {
+    let old = "const a = 1;\nconst b = 2;\n";
+    assert_eq!(
+      diff_and_apply(old, "const a = 12;\nconst b = 2;\n"),
+      vec![edit(old, 11..11, "2")],
+    );
+    assert_eq!(
+      diff_and_apply(old, &format!("{}x", old)),
+      vec![edit(old, old.len()..old.len(), "x")]
+    );
+    assert_eq!(
+      diff_and_apply(old, &format!("x{}", old)),
+      vec![edit(old, 0..0, "x")]
+    );
+    // multi-byte characters
+    diff_and_apply("'é';\n'😀';\n", "'é😀';\n'😀';\n");
+    diff_and_apply("'😀';", "'😁';");
+  }
+
+  #[test]
+  fn line_reorder() {
+    let old = "import a;\nimport b;\nimport c;\nimport d;\n";
+    let new = "import b;\nimport a;\nimport c;\nimport d;\n";
+    assert_eq!(
+      diff_and_apply(old, new),
+      vec![edit(old, 7..18, "b;\nimport a")]
+    );
+
+    let old = "a\nb\nc\nd\ne\nf\ng\n";
+    let new = "a\nf\nc\nd\ne\nb\ng\n";
+    let edits = diff_and_apply(old, new);
+    assert_eq!(edits, vec![edit(old, 2..4, "f\n"), edit(old, 10..11, "b")]);
+    diff_and_apply(old, "g\nf\ne\nd\nc\nb\na\n");
+  }
+
+  #[test]
+  fn complete_replacement() {
+    let old = "const a = 1;\nconst b = 2;\n";
+    assert_eq!(
+      diff_and_apply(old, "let x;\nlet y;"),
+      vec![edit(old, 0..old.len(), "let x;\nlet y;")]
+    );
+    assert_eq!(diff_and_apply(old, ""), vec![edit(old, 0..old.len(), "")]);
+    assert_eq!(
+      diff_and_apply("", "let x;\n"),
+      vec![edit("", 0..0, "let x;\n")]
+    );
+  }
+
+  #[test]
+  fn mixed_changes() {
+    diff_and_apply("a\r\nb\r\nc\r\nd\r\n", "a\r\nc\r\nx\r\nd\r\ne\r\n");
+    diff_and_apply("a\nb\nc\nd\ne", "z\nb\nc\ny\nd\ne\nf");
+    diff_and_apply("a\nb\na\nb\na\n", "b\na\nb\na\nb\n");
+  }
+
+  #[test]
+  fn falls_back_for_large_changes() {
+    let old = (0..MAX_LINE_EDIT_DISTANCE)
+      .map(|i| format!("a{}\n", i))
+      .collect::<Vec<_>>()
+      .concat();
+    let new = (0..MAX_LINE_EDIT_DISTANCE)
+      .map(|i| format!("b{}\n", i))
+      .collect::<Vec<_>>()
+      .concat();
+    let edits = diff_and_apply(&old, &new);
+    assert_eq!(edits.len(), 1);
+  }
+}
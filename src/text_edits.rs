@@ -0,0 +1,295 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::apply_text_changes;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+use crate::SourceTextInfoRangeText;
+use crate::TextChange;
+
+/// Edit of a range of source text.
+///
+/// This serializes as `{ "range": { "start": 1, "end": 2 }, "newText": "" }`
+/// with the range in byte indexes (see `crate::serialization`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+  /// Range of the text to replace.
+  #[serde(with = "crate::serialization::source_range")]
+  pub range: SourceRange,
+  /// New text to insert or replace the range with.
+  pub new_text: String,
+}
+
+impl TextEdit {
+  pub fn new(range: SourceRange, new_text: impl Into<String>) -> Self {
+    Self {
+      range,
+      new_text: new_text.into(),
+    }
+  }
+}
+
+/// Error applying text edits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEditError {
+  /// The range of an edit was outside the text or did not start and
+  /// end on character boundaries.
+  InvalidRange(SourceRange),
+  /// The ranges of two edits overlapped.
+  Overlapping(SourceRange, SourceRange),
+}
+
+impl std::error::Error for TextEditError {}
+
+impl fmt::Display for TextEditError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TextEditError::InvalidRange(range) => write!(
+        f,
+        "Text edit range {:?} was outside the text or not on character boundaries.",
+        range
+      ),
+      TextEditError::Overlapping(first, second) => write!(
+        f,
+        "Text edit ranges {:?} and {:?} were overlapping.",
+        first, second
+      ),
+    }
+  }
+}
+
+/// Maps positions in the text before applying text edits to positions
+/// in the text after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEditPositionMap {
+  old_start_pos: SourcePos,
+  new_start_pos: SourcePos,
+  /// Sorted old byte ranges of the edits along with the length of
+  /// their new text.
+  edits: Vec<(std::ops::Range<usize>, usize)>,
+}
+
+impl TextEditPositionMap {
+  /// Gets the position in the new text of a position in the old text.
+  ///
+  /// A position at the start of an edit stays before its new text, a
+  /// position at the end of an edit moves after its new text, and a
+  /// position within a replaced range moves to the end of its new text.
+  pub fn map_pos(&self, pos: SourcePos) -> SourcePos {
+    let index = pos - self.old_start_pos;
+    let mut new_index = index;
+    for (range, new_len) in &self.edits {
+      if index <= range.start {
+        break;
+      }
+      new_index = if index < range.end {
+        new_index + range.end - index - (range.end - range.start) + new_len
+      } else {
+        new_index - (range.end - range.start) + new_len
+      };
+    }
+    self.new_start_pos + new_index
+  }
+
+  /// Gets the range in the new text of a range in the old text.
+  pub fn map_range(&self, range: SourceRange) -> SourceRange {
+    SourceRange::new(self.map_pos(range.start), self.map_pos(range.end))
+  }
+}
+
+/// Applies the edits to the text in a single pass, returning the new
+/// text along with a map for converting positions in the old text to
+/// positions in the new text.
+///
+/// Edits may be provided in any order. Edits at the same position are
+/// applied in the order provided. Adjacent edits are allowed, but an
+/// error is returned for overlapping edits.
+pub fn apply_text_edits(
+  info: &SourceTextInfo,
+  edits: &[TextEdit],
+) -> Result<(SourceTextInfo, TextEditPositionMap), TextEditError> {
+  let mut edits = edits.iter().collect::<Vec<_>>();
+  // stable sort to keep the order of edits at the same position
+  edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+  for (i, edit) in edits.iter().enumerate() {
+    if info.try_range_text(&edit.range).is_none() {
+      return Err(TextEditError::InvalidRange(edit.range));
+    }
+    if let Some(previous) = i.checked_sub(1).map(|i| edits[i]) {
+      if edit.range.start < previous.range.end {
+        return Err(TextEditError::Overlapping(previous.range, edit.range));
+      }
+    }
+  }
+
+  let old_start_pos = info.range().start.as_source_pos();
+  let changes = edits
+    .iter()
+    .map(|edit| {
+      TextChange::new(
+        edit.range.start - old_start_pos,
+        edit.range.end - old_start_pos,
+        edit.new_text.clone(),
+      )
+    })
+    .collect::<Vec<_>>();
+  let position_edits = changes
+    .iter()
+    .map(|change| (change.range.clone(), change.new_text.len()))
+    .collect();
+  let new_info =
+    SourceTextInfo::from_string(apply_text_changes(info.text_str(), changes));
+  let position_map = TextEditPositionMap {
+    old_start_pos,
+    new_start_pos: new_info.range().start.as_source_pos(),
+    edits: position_edits,
+  };
+  Ok((new_info, position_map))
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn range_of(info: &SourceTextInfo, text: &str) -> SourceRange {
+    let start = info.range().start;
+    let index = info.text_str().find(text).unwrap();
+    SourceRange::new(start + index, start + index + text.len())
+  }
+
+  #[test]
+  fn rejects_overlapping_edits() {
+    let info = SourceTextInfo::from_string("let value = 1;".to_string());
+    let value_range = range_of(&info, "value");
+    let alue_range = range_of(&info, "alue = 1");
+    assert_eq!(
+      apply_text_edits(
+        &info,
+        &[
+          TextEdit::new(alue_range, "x"),
+          TextEdit::new(value_range, "y"),
+        ]
+      )
+      .unwrap_err(),
+      TextEditError::Overlapping(value_range, alue_range),
+    );
+    // an insertion within a replaced range
+    let insert_range =
+      SourceRange::new(value_range.start + 1, value_range.start + 1);
+    assert_eq!(
+      apply_text_edits(
+        &info,
+        &[
+          TextEdit::new(value_range, "y"),
+          TextEdit::new(insert_range, "z"),
+        ]
+      )
+      .unwrap_err(),
+      TextEditError::Overlapping(value_range, insert_range),
+    );
+    // adjacent edits are allowed
+    let (new_info, _) = apply_text_edits(
+      &info,
+      &[
+        TextEdit::new(range_of(&info, "let"), "const"),
+        TextEdit::new(range_of(&info, " "), ""),
+        TextEdit::new(range_of(&info, "value"), " other"),
+      ],
+    )
+    .unwrap();
+    assert_eq!(new_info.text_str(), "const other = 1;");
+  }
+
+  #[test]
+  fn rejects_invalid_ranges() {
+    let info = SourceTextInfo::from_string("'é';".to_string());
+    let start = info.range().start.as_source_pos();
+    for range in [
+      SourceRange::new(start + 2, start + 3),
+      SourceRange::new(start, info.range().end + 1),
+    ] {
+      assert_eq!(
+        apply_text_edits(&info, &[TextEdit::new(range, "")]).unwrap_err(),
+        TextEditError::InvalidRange(range),
+      );
+    }
+  }
+
+  #[test]
+  fn orders_insertions_at_same_position_stably() {
+    let info = SourceTextInfo::from_string("a;".to_string());
+    let pos = info.range().start + 1;
+    let (new_info, map) = apply_text_edits(
+      &info,
+      &[
+        TextEdit::new(SourceRange::new(pos, pos), "1"),
+        TextEdit::new(SourceRange::new(pos, pos + 1), ""),
+        TextEdit::new(SourceRange::new(pos, pos), "2"),
+        TextEdit::new(SourceRange::new(pos, pos), "3"),
+      ],
+    )
+    .unwrap();
+    assert_eq!(new_info.text_str(), "a123");
+    assert_eq!(map.map_pos(pos), new_info.range().start + 1);
+    assert_eq!(map.map_pos(pos + 1), new_info.range().start + 4);
+  }
+
+  #[test]
+  fn maps_positions_for_mixed_edits() {
+    let text = "const a = 1;\nconst b = 2;\nconst c = a + b;\n";
+    let info = SourceTextInfo::from_string(text.to_string());
+    let a_decl = range_of(&info, "const a = 1;\n");
+    let b_name = range_of(&info, "b =");
+    let c_decl = range_of(&info, "const c");
+    let (new_info, map) = apply_text_edits(
+      &info,
+      &[
+        // delete
+        TextEdit::new(a_decl, ""),
+        // replace
+        TextEdit::new(
+          SourceRange::new(b_name.start, b_name.start + 1),
+          "value",
+        ),
+        // insert
+        TextEdit::new(SourceRange::new(c_decl.start, c_decl.start), "// c\n"),
+      ],
+    )
+    .unwrap();
+    assert_eq!(
+      new_info.text_str(),
+      "const value = 2;\n// c\nconst c = a + b;\n"
+    );
+
+    // old positions are translated into the new text
+    let old_sum = range_of(&info, "a + b");
+    let new_sum = map.map_range(old_sum);
+    assert_eq!(new_info.range_text(&new_sum), "a + b");
+    assert_eq!(
+      new_info.range_text(&map.map_range(range_of(&info, "const b"))),
+      "const value"
+    );
+    assert_eq!(
+      new_info.range_text(&map.map_range(range_of(&info, "= 2;"))),
+      "= 2;"
+    );
+    // within a deleted range moves to the end of the new text
+    assert_eq!(map.map_pos(a_decl.start + 3), new_info.range().start);
+
+    // a second round of edits using the mapped positions
+    let (new_info, _) =
+      apply_text_edits(&new_info, &[TextEdit::new(new_sum, "a * b")]).unwrap();
+    assert_eq!(
+      new_info.text_str(),
+      "const value = 2;\n// c\nconst c = a * b;\n"
+    );
+  }
+}
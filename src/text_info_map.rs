@@ -0,0 +1,297 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use crate::lines::clamp_pos;
+use crate::lines::clamp_range;
+use crate::text_provider::SpecifiedTextInfo;
+use crate::types::display_styled_in_source;
+use crate::DiagnosticSeverity;
+use crate::DisplayStyle;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+
+/// Identifier of a specifier registered in a `SourceTextInfoMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpecifierId(u32);
+
+impl SpecifierId {
+  /// Gets the index of the specifier in the order it was registered.
+  pub fn as_u32(&self) -> u32 {
+    self.0
+  }
+}
+
+/// Range in the source text of a specifier registered in a
+/// `SourceTextInfoMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalRange {
+  pub specifier_id: SpecifierId,
+  pub range: SourceRange,
+}
+
+/// Source text of many specifiers, for identifying ranges across files
+/// (ex. for project-wide analysis).
+///
+/// This can be shared across threads. The text is not copied when
+/// registered because `SourceTextInfo` shares it.
+#[derive(Debug, Default)]
+pub struct SourceTextInfoMap {
+  inner: RwLock<SourceTextInfoMapInner>,
+}
+
+#[derive(Debug, Default)]
+struct SourceTextInfoMapInner {
+  ids: HashMap<Arc<str>, SpecifierId>,
+  entries: Vec<(Arc<str>, SourceTextInfo)>,
+}
+
+impl SourceTextInfoMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers the text of a specifier, returning its identifier.
+  ///
+  /// Registering a specifier that was already registered replaces its
+  /// text and returns the same identifier.
+  pub fn register(
+    &self,
+    specifier: &str,
+    text_info: SourceTextInfo,
+  ) -> SpecifierId {
+    let mut inner = self.inner.write().unwrap();
+    if let Some(id) = inner.ids.get(specifier).copied() {
+      inner.entries[id.0 as usize].1 = text_info;
+      return id;
+    }
+    let id = SpecifierId(
+      u32::try_from(inner.entries.len())
+        .expect("Exceeded the maximum number of specifiers."),
+    );
+    let specifier: Arc<str> = specifier.into();
+    inner.ids.insert(specifier.clone(), id);
+    inner.entries.push((specifier, text_info));
+    id
+  }
+
+  /// Gets the identifier of a registered specifier.
+  pub fn specifier_id(&self, specifier: &str) -> Option<SpecifierId> {
+    self.inner.read().unwrap().ids.get(specifier).copied()
+  }
+
+  /// Gets the specifier of an identifier.
+  pub fn specifier(&self, specifier_id: SpecifierId) -> Option<Arc<str>> {
+    self.get(specifier_id).map(|(specifier, _)| specifier)
+  }
+
+  /// Gets the text of a specifier.
+  pub fn text_info(&self, specifier_id: SpecifierId) -> Option<SourceTextInfo> {
+    self.get(specifier_id).map(|(_, text_info)| text_info)
+  }
+
+  /// Number of registered specifiers.
+  pub fn len(&self) -> usize {
+    self.inner.read().unwrap().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Gets a range in the text of a registered specifier or `None` when
+  /// the specifier isn't registered.
+  pub fn global_range(
+    &self,
+    specifier: &str,
+    range: SourceRange,
+  ) -> Option<GlobalRange> {
+    self
+      .specifier_id(specifier)
+      .map(|specifier_id| GlobalRange {
+        specifier_id,
+        range,
+      })
+  }
+
+  /// Gets the specifier, text, and range of a global range or `None`
+  /// when its specifier isn't registered.
+  pub fn resolve(
+    &self,
+    range: &GlobalRange,
+  ) -> Option<(Arc<str>, SourceTextInfo, SourceRange)> {
+    self
+      .get(range.specifier_id)
+      .map(|(specifier, text_info)| (specifier, text_info, range.range))
+  }
+
+  /// Formats the start of a global range as `specifier:line:column`
+  /// using 1-indexed line and column numbers.
+  pub fn display_position(&self, range: &GlobalRange) -> Option<String> {
+    let (specifier, text_info, range) = self.resolve(range)?;
+    let position =
+      text_info.line_and_column_display(clamp_pos(&text_info, range.start));
+    Some(format!(
+      "{}:{}:{}",
+      specifier, position.line_number, position.column_number
+    ))
+  }
+
+  /// Renders a message for a global range along with the source text
+  /// of its specifier, the same as `Diagnostic::display_styled()`.
+  pub fn display_styled(
+    &self,
+    range: &GlobalRange,
+    severity: DiagnosticSeverity,
+    message: &str,
+    style: DisplayStyle,
+  ) -> Option<String> {
+    let (specifier, text_info, range) = self.resolve(range)?;
+    let range = clamp_range(&text_info, range);
+    Some(display_styled_in_source(
+      &SpecifiedTextInfo {
+        specifier: &specifier,
+        text_info: &text_info,
+      },
+      range.as_byte_range(text_info.range().start),
+      severity,
+      message,
+      &[],
+      None,
+      style,
+    ))
+  }
+
+  fn get(
+    &self,
+    specifier_id: SpecifierId,
+  ) -> Option<(Arc<str>, SourceTextInfo)> {
+    self
+      .inner
+      .read()
+      .unwrap()
+      .entries
+      .get(specifier_id.0 as usize)
+      .cloned()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn range_of(text_info: &SourceTextInfo, text: &str) -> SourceRange {
+    let start =
+      text_info.range().start + text_info.text_str().find(text).unwrap();
+    SourceRange::new(start, start + text.len())
+  }
+
+  #[test]
+  fn registers_and_resolves_ranges() {
+    let map = SourceTextInfoMap::new();
+    let files = [
+      (
+        "https://deno.land/a.ts",
+        "import { b } from './b.ts';\nb();\n",
+        "b()",
+      ),
+      ("file:///b.ts", "export function b() {\n  c();\n}\n", "c()"),
+      ("file:///c.ts", "export function c() {}\n", "c()"),
+    ];
+    let ranges = files
+      .iter()
+      .map(|(specifier, text, search_text)| {
+        let text_info = SourceTextInfo::from_string(text.to_string());
+        map.register(specifier, text_info.clone());
+        map
+          .global_range(specifier, range_of(&text_info, search_text))
+          .unwrap()
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(map.len(), 3);
+    for (range, (specifier, text, _)) in ranges.iter().zip(files) {
+      let (resolved_specifier, text_info, resolved_range) =
+        map.resolve(range).unwrap();
+      assert_eq!(&*resolved_specifier, specifier);
+      assert_eq!(text_info.text_str(), text);
+      assert_eq!(resolved_range, range.range);
+    }
+    let text_info = map.text_info(ranges[1].specifier_id).unwrap();
+    assert_eq!(text_info.range_text(&ranges[1].range), "c()");
+    assert_eq!(
+      map.display_position(&ranges[1]).unwrap(),
+      "file:///b.ts:2:3"
+    );
+    assert_eq!(
+      map
+        .display_styled(
+          &ranges[0],
+          DiagnosticSeverity::Warning,
+          "Call",
+          Default::default(),
+        )
+        .unwrap(),
+      concat!(
+        "https://deno.land/a.ts:2:1: Warning: Call\n",
+        "  b();\n",
+        "  ^^^"
+      ),
+    );
+
+    // unregistered specifiers
+    assert_eq!(map.global_range("file:///d.ts", ranges[0].range), None);
+    let unknown = GlobalRange {
+      specifier_id: SpecifierId(3),
+      range: ranges[0].range,
+    };
+    assert!(map.resolve(&unknown).is_none());
+    assert_eq!(map.display_position(&unknown), None);
+  }
+
+  #[test]
+  fn deduplicates_specifiers() {
+    let map = SourceTextInfoMap::new();
+    let a = map
+      .register("file:///a.ts", SourceTextInfo::from_string("1".to_string()));
+    let b = map
+      .register("file:///b.ts", SourceTextInfo::from_string("2".to_string()));
+    let a_again = map
+      .register("file:///a.ts", SourceTextInfo::from_string("3".to_string()));
+    assert_eq!(a, a_again);
+    assert_ne!(a, b);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.specifier_id("file:///a.ts"), Some(a));
+    assert_eq!(map.specifier(b).as_deref(), Some("file:///b.ts"));
+    assert_eq!(map.text_info(a).unwrap().text_str(), "3");
+  }
+
+  #[test]
+  fn shares_across_threads() {
+    let map = Arc::new(SourceTextInfoMap::new());
+    let handles = (0..4)
+      .map(|i| {
+        let map = map.clone();
+        std::thread::spawn(move || {
+          (0..250)
+            .map(|j| {
+              map.register(
+                &format!("file:///{}.ts", j),
+                SourceTextInfo::from_string(format!("{}", i)),
+              )
+            })
+            .collect::<Vec<_>>()
+        })
+      })
+      .collect::<Vec<_>>();
+    let ids = handles
+      .into_iter()
+      .map(|handle| handle.join().unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(map.len(), 250);
+    assert!(ids.iter().all(|thread_ids| thread_ids == &ids[0]));
+  }
+}
@@ -0,0 +1,356 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::types::display_styled_in_source;
+use crate::DiagnosticSeverity;
+use crate::DisplayStyle;
+use crate::LineAndColumnDisplay;
+use crate::LineAndColumnIndex;
+use crate::ParsedSource;
+use crate::SourceTextInfo;
+
+/// Source text that diagnostics can be rendered for without storing it
+/// in a `SourceTextInfo` (ex. text held in a rope by an editor).
+///
+/// Positions are byte indexes in the text. Lines are separated by `\n`,
+/// which may be preceded by `\r`.
+pub trait SourceTextProvider {
+  /// Specifier of the source.
+  fn specifier(&self) -> &str;
+
+  /// Length of the text in bytes.
+  fn text_len(&self) -> usize;
+
+  /// Number of lines in the text.
+  fn lines_count(&self) -> usize;
+
+  /// Gets the byte index of the start of a 0-indexed line.
+  fn line_start(&self, line_index: usize) -> usize;
+
+  /// Gets the text of a byte range, which starts and ends on character
+  /// boundaries.
+  fn range_text(&self, range: Range<usize>) -> Cow<str>;
+
+  /// Gets the 0-indexed line a byte index is on.
+  fn line_index(&self, byte_index: usize) -> usize {
+    // the first line always starts at 0
+    let mut low = 1;
+    let mut high = self.lines_count();
+    while low < high {
+      let mid = low + (high - low) / 2;
+      if self.line_start(mid) <= byte_index {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+    low - 1
+  }
+
+  /// Gets the byte index of the end of a 0-indexed line, excluding its
+  /// line break.
+  fn line_end(&self, line_index: usize) -> usize {
+    if line_index + 1 >= self.lines_count() {
+      return self.text_len();
+    }
+    let line_start = self.line_start(line_index);
+    let next_line_start = self.line_start(line_index + 1);
+    let text = self.range_text(line_start..next_line_start);
+    let line_len = text.trim_end_matches('\n').trim_end_matches('\r').len();
+    line_start + line_len
+  }
+
+  /// Gets the text of a 0-indexed line, excluding its line break.
+  fn line_text(&self, line_index: usize) -> Cow<str> {
+    self.range_text(self.line_start(line_index)..self.line_end(line_index))
+  }
+
+  /// Gets the 0-indexed line and column of a byte index, where the
+  /// column is in characters.
+  fn line_and_column_index(&self, byte_index: usize) -> LineAndColumnIndex {
+    let line_index = self.line_index(byte_index);
+    let line_start = self.line_start(line_index);
+    LineAndColumnIndex {
+      line_index,
+      column_index: self.range_text(line_start..byte_index).chars().count(),
+    }
+  }
+
+  /// Gets the 1-indexed line and column of a byte index.
+  fn line_and_column_display(&self, byte_index: usize) -> LineAndColumnDisplay {
+    let index = self.line_and_column_index(byte_index);
+    LineAndColumnDisplay {
+      line_number: index.line_index + 1,
+      column_number: index.column_index + 1,
+    }
+  }
+}
+
+impl SourceTextProvider for ParsedSource {
+  fn specifier(&self) -> &str {
+    ParsedSource::specifier(self)
+  }
+
+  fn text_len(&self) -> usize {
+    self.text_info().text_str().len()
+  }
+
+  fn lines_count(&self) -> usize {
+    self.text_info().lines_count()
+  }
+
+  fn line_start(&self, line_index: usize) -> usize {
+    let text_info = self.text_info();
+    text_info.line_start(line_index) - text_info.range().start
+  }
+
+  fn range_text(&self, range: Range<usize>) -> Cow<str> {
+    Cow::Borrowed(&self.text_info().text_str()[range])
+  }
+}
+
+/// `SourceTextInfo` along with the specifier of the source.
+pub(crate) struct SpecifiedTextInfo<'a> {
+  pub specifier: &'a str,
+  pub text_info: &'a SourceTextInfo,
+}
+
+impl<'a> SourceTextProvider for SpecifiedTextInfo<'a> {
+  fn specifier(&self) -> &str {
+    self.specifier
+  }
+
+  fn text_len(&self) -> usize {
+    self.text_info.text_str().len()
+  }
+
+  fn lines_count(&self) -> usize {
+    self.text_info.lines_count()
+  }
+
+  fn line_start(&self, line_index: usize) -> usize {
+    self.text_info.line_start(line_index) - self.text_info.range().start
+  }
+
+  fn range_text(&self, range: Range<usize>) -> Cow<str> {
+    Cow::Borrowed(&self.text_info.text_str()[range])
+  }
+}
+
+/// Borrowed text along with a precomputed index of its line starts.
+#[derive(Debug, Clone)]
+pub struct StrSourceText<'a> {
+  specifier: &'a str,
+  text: &'a str,
+  line_starts: Vec<usize>,
+}
+
+impl<'a> StrSourceText<'a> {
+  pub fn new(specifier: &'a str, text: &'a str) -> Self {
+    let line_starts = std::iter::once(0)
+      .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+      .collect();
+    Self {
+      specifier,
+      text,
+      line_starts,
+    }
+  }
+
+  /// Gets the text.
+  pub fn text(&self) -> &'a str {
+    self.text
+  }
+}
+
+impl<'a> SourceTextProvider for StrSourceText<'a> {
+  fn specifier(&self) -> &str {
+    self.specifier
+  }
+
+  fn text_len(&self) -> usize {
+    self.text.len()
+  }
+
+  fn lines_count(&self) -> usize {
+    self.line_starts.len()
+  }
+
+  fn line_start(&self, line_index: usize) -> usize {
+    self.line_starts[line_index]
+  }
+
+  fn range_text(&self, range: Range<usize>) -> Cow<str> {
+    Cow::Borrowed(&self.text[range])
+  }
+
+  fn line_index(&self, byte_index: usize) -> usize {
+    self
+      .line_starts
+      .partition_point(|line_start| *line_start <= byte_index)
+      - 1
+  }
+}
+
+/// Renders a diagnostic message for a byte range of the source text the
+/// same as `Diagnostic::display_styled()`.
+///
+/// The range is clamped to the text and must otherwise start and end on
+/// character boundaries.
+pub fn display_source_diagnostic(
+  source: &impl SourceTextProvider,
+  range: Range<usize>,
+  severity: DiagnosticSeverity,
+  message: &str,
+  style: DisplayStyle,
+) -> String {
+  let text_len = source.text_len();
+  let start = std::cmp::min(range.start, text_len);
+  let end = std::cmp::max(start, std::cmp::min(range.end, text_len));
+  display_styled_in_source(
+    source,
+    start..end,
+    severity,
+    message,
+    &[],
+    None,
+    style,
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Diagnostic;
+  use crate::DiagnosticKind;
+  use crate::SnippetOptions;
+  use crate::SourceRange;
+
+  /// Text stored in two chunks to test rendering without a contiguous
+  /// string.
+  struct TwoChunkRope {
+    chunks: [String; 2],
+    line_starts: Vec<usize>,
+  }
+
+  impl TwoChunkRope {
+    fn new(text: &str, split_index: usize) -> Self {
+      let (first, second) = text.split_at(split_index);
+      Self {
+        chunks: [first.to_string(), second.to_string()],
+        line_starts: StrSourceText::new("", text).line_starts,
+      }
+    }
+  }
+
+  impl SourceTextProvider for TwoChunkRope {
+    fn specifier(&self) -> &str {
+      "mod.ts"
+    }
+
+    fn text_len(&self) -> usize {
+      self.chunks[0].len() + self.chunks[1].len()
+    }
+
+    fn lines_count(&self) -> usize {
+      self.line_starts.len()
+    }
+
+    fn line_start(&self, line_index: usize) -> usize {
+      self.line_starts[line_index]
+    }
+
+    fn range_text(&self, range: Range<usize>) -> Cow<str> {
+      let split_index = self.chunks[0].len();
+      if range.end <= split_index {
+        Cow::Borrowed(&self.chunks[0][range])
+      } else if range.start >= split_index {
+        Cow::Borrowed(
+          &self.chunks[1][range.start - split_index..range.end - split_index],
+        )
+      } else {
+        Cow::Owned(format!(
+          "{}{}",
+          &self.chunks[0][range.start..],
+          &self.chunks[1][..range.end - split_index]
+        ))
+      }
+    }
+  }
+
+  #[test]
+  fn renders_diagnostic_from_rope() {
+    let text = "let a;\r\nconst b = 'é' #!c;\nlet d;\n";
+    let split_index = text.find("#!").unwrap() + 1;
+    let rope = TwoChunkRope::new(text, split_index);
+    let range = text.find("#!").unwrap()..text.find("c;").unwrap();
+    let style = DisplayStyle {
+      snippet: SnippetOptions {
+        context_lines: 1,
+        show_line_numbers: true,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let rendered = display_source_diagnostic(
+      &rope,
+      range.clone(),
+      DiagnosticSeverity::Error,
+      &DiagnosticKind::MisplacedShebang.msg(),
+      style,
+    );
+    assert_eq!(
+      rendered,
+      concat!(
+        "mod.ts:2:15: A shebang (#!) is only allowed at the start of a file\n",
+        "  1 | let a;\n",
+        "  2 | const b = 'é' #!c;\n",
+        "    |               ^^\n",
+        "  3 | let d;",
+      ),
+    );
+
+    // same as rendering from a source text info
+    let text_info = SourceTextInfo::new(text.into());
+    let start = text_info.range().start;
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(start + range.start, start + range.end),
+      DiagnosticKind::MisplacedShebang,
+      text_info,
+    );
+    assert_eq!(diagnostic.display_styled(style), rendered);
+  }
+
+  #[test]
+  fn line_positions() {
+    let text = "a\r\nbé\n\nc";
+    let rope = TwoChunkRope::new(text, 4);
+    let source = StrSourceText::new("file:///mod.ts", text);
+    let text_info = SourceTextInfo::from_string(text.to_string());
+    let start = text_info.range().start;
+    for provider in [&rope as &dyn SourceTextProvider, &source] {
+      assert_eq!(provider.lines_count(), 4);
+      for line_index in 0..4 {
+        assert_eq!(
+          provider.line_text(line_index),
+          text_info.line_text(line_index)
+        );
+        assert_eq!(
+          provider.line_end(line_index),
+          text_info.line_end(line_index) - start
+        );
+      }
+      for (byte_index, _) in text.char_indices().chain([(text.len(), ' ')]) {
+        assert_eq!(
+          provider.line_and_column_index(byte_index),
+          text_info.line_and_column_index(start + byte_index)
+        );
+      }
+    }
+  }
+}
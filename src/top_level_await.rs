@@ -0,0 +1,184 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::swc::ast::*;
+use crate::swc::visit::noop_visit_type;
+use crate::swc::visit::Visit;
+use crate::swc::visit::VisitWith;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+
+impl ParsedSource {
+  /// Gets the range of the first top level await in the source, which
+  /// includes `for await` loops and `await using` declarations that are
+  /// not within a function.
+  ///
+  /// Use `has_top_level_await()` to only check if the source has one,
+  /// which is cached.
+  pub fn compute_top_level_await(&self) -> Option<SourceRange> {
+    find_top_level_await(self.program_ref())
+  }
+}
+
+pub(crate) fn find_top_level_await(program: &Program) -> Option<SourceRange> {
+  let mut finder = TopLevelAwaitFinder { found: None };
+  program.visit_with(&mut finder);
+  finder.found
+}
+
+struct TopLevelAwaitFinder {
+  found: Option<SourceRange>,
+}
+
+impl Visit for TopLevelAwaitFinder {
+  noop_visit_type!();
+
+  fn visit_await_expr(&mut self, n: &AwaitExpr) {
+    if self.found.is_none() {
+      self.found = Some(n.range());
+    }
+  }
+
+  fn visit_for_of_stmt(&mut self, n: &ForOfStmt) {
+    if self.found.is_some() {
+      return;
+    }
+    if n.is_await {
+      self.found = Some(n.range());
+    } else {
+      n.visit_children_with(self);
+    }
+  }
+
+  fn visit_using_decl(&mut self, n: &UsingDecl) {
+    if self.found.is_some() {
+      return;
+    }
+    if n.is_await {
+      self.found = Some(n.range());
+    } else {
+      n.visit_children_with(self);
+    }
+  }
+
+  fn visit_stmt(&mut self, n: &Stmt) {
+    if self.found.is_none() {
+      n.visit_children_with(self);
+    }
+  }
+
+  fn visit_expr(&mut self, n: &Expr) {
+    if self.found.is_none() {
+      n.visit_children_with(self);
+    }
+  }
+
+  // awaits within these are not at the top level
+
+  fn visit_function(&mut self, _n: &Function) {}
+
+  fn visit_arrow_expr(&mut self, _n: &ArrowExpr) {}
+
+  fn visit_constructor(&mut self, _n: &Constructor) {}
+
+  fn visit_getter_prop(&mut self, n: &GetterProp) {
+    n.key.visit_with(self);
+  }
+
+  fn visit_setter_prop(&mut self, n: &SetterProp) {
+    n.key.visit_with(self);
+  }
+
+  fn visit_class_prop(&mut self, n: &ClassProp) {
+    n.decorators.visit_with(self);
+    n.key.visit_with(self);
+  }
+
+  fn visit_private_prop(&mut self, n: &PrivateProp) {
+    n.decorators.visit_with(self);
+  }
+
+  fn visit_static_block(&mut self, _n: &StaticBlock) {}
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  fn top_level_await_text(text: &str) -> Option<String> {
+    let parsed_source = parse(text);
+    let range = parsed_source.compute_top_level_await();
+    assert_eq!(parsed_source.has_top_level_await(), range.is_some());
+    range.map(|range| parsed_source.text_for_range(&range).to_string())
+  }
+
+  #[test]
+  fn await_expr() {
+    assert_eq!(
+      top_level_await_text(
+        "const res = await fetch('https://deno.land');\nawait res.text();"
+      ),
+      Some("await fetch('https://deno.land')".to_string())
+    );
+    // within a top level block
+    assert_eq!(
+      top_level_await_text("if (a) { try { await b(); } catch {} }"),
+      Some("await b()".to_string())
+    );
+  }
+
+  #[test]
+  fn await_in_functions() {
+    assert_eq!(
+      top_level_await_text(concat!(
+        "(async () => { await fetch('a'); })();\n",
+        "(async function() { await fetch('b'); })();\n",
+        "async function c() { await d; }\n",
+        "class E { async f() { await g; } }\n",
+        "const h = { async i() { await j; } };\n",
+      )),
+      None
+    );
+    assert_eq!(
+      top_level_await_text("const a = { [await b]: async () => await c };"),
+      Some("await b".to_string())
+    );
+  }
+
+  #[test]
+  fn for_await() {
+    assert_eq!(
+      top_level_await_text("for await (const x of y) {}"),
+      Some("for await (const x of y) {}".to_string())
+    );
+    assert_eq!(top_level_await_text("for (const x of y) {}"), None);
+  }
+
+  #[test]
+  fn await_using() {
+    assert_eq!(
+      top_level_await_text("await using res = open()\n"),
+      Some("await using res = open()".to_string())
+    );
+    assert_eq!(top_level_await_text("using res = open();"), None);
+  }
+}
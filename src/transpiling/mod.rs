@@ -28,8 +28,8 @@ use crate::swc::transforms::resolver;
 use crate::swc::transforms::typescript;
 use crate::swc::visit::FoldWith;
 use crate::Diagnostic;
+use crate::DiagnosticKind;
 use crate::DiagnosticsError;
-use crate::ModuleSpecifier;
 use crate::ParsedSource;
 
 use std::cell::RefCell;
@@ -186,7 +186,7 @@ impl ParsedSource {
     let source_map_config = SourceMapConfig {
       inline_sources: options.inline_sources,
     };
-    let file_name = match ModuleSpecifier::parse(self.specifier()) {
+    let file_name = match self.module_specifier() {
       Ok(specifier) => FileName::Url(specifier),
       Err(_) => FileName::Custom(self.specifier().to_string()),
     };
@@ -437,7 +437,23 @@ fn ensure_no_fatal_diagnostics(
 ) -> Result<(), DiagnosticsError> {
   let fatal_diagnostics = diagnostics
     .iter()
-    .filter(|d| is_fatal_syntax_error(&d.kind))
+    .filter(|d| match &d.kind {
+      DiagnosticKind::Syntax(kind) => is_fatal_syntax_error(kind),
+      DiagnosticKind::MisplacedShebang
+      | DiagnosticKind::SourceTooLarge { .. }
+      | DiagnosticKind::UnsupportedMediaType { .. }
+      | DiagnosticKind::DisabledProposal(_)
+      | DiagnosticKind::Cancelled
+      | DiagnosticKind::InvalidEditRange
+      | DiagnosticKind::UnexpectedTrailingToken { .. } => true,
+      DiagnosticKind::Json(kind) => kind.is_fatal(),
+      // the errors that weren't shown may have been fatal
+      DiagnosticKind::TooManyDiagnostics { .. } => true,
+      // only surfaced as a diagnostic when it's a warning
+      DiagnosticKind::ImportAssertKeyword
+      | DiagnosticKind::DeprecatedSyntax(_)
+      | DiagnosticKind::ScopeAnalysisUnavailable => false,
+    })
     .map(ToOwned::to_owned)
     .collect::<Vec<_>>();
   if !fatal_diagnostics.is_empty() {
@@ -491,6 +507,7 @@ mod tests {
 
   use crate::parse_module;
   use crate::MediaType;
+  use crate::ModuleSpecifier;
   use crate::ParseParams;
   use crate::SourceTextInfo;
 
@@ -531,14 +548,14 @@ export class A {
   }
 }
     "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     let transpiled_source = module.transpile(&EmitOptions::default()).unwrap();
     let expected_text = r#"var D;
@@ -585,14 +602,14 @@ export class A {
     let specifier =
       ModuleSpecifier::parse("https://deno.land/x/mod.ts").unwrap();
     let source = "using data = create();\nconsole.log(data);";
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     let transpiled_source = module.transpile(&EmitOptions::default()).unwrap();
     let expected_text = r#"function dispose_SuppressedError(suppressed, error) {
@@ -682,14 +699,16 @@ try {
       }
     }
     "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Tsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true, // ensure scope analysis doesn't conflict with a second resolver pass
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      // ensure scope analysis doesn't conflict with a second resolver pass
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let transpiled_source = module.transpile(&EmitOptions::default()).unwrap();
     assert!(transpiled_source
@@ -708,14 +727,16 @@ try {
       }
     }
     "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Tsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true, // ensure scope analysis doesn't conflict with a second resolver pass
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      // ensure scope analysis doesn't conflict with a second resolver pass
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let transpiled_source = module.transpile(&EmitOptions::default()).unwrap();
     assert!(transpiled_source
@@ -738,14 +759,15 @@ function App() {
     <div><></></div>
   );
 }"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Jsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Jsx)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let code = module.transpile(&EmitOptions::default()).unwrap().text;
     let expected = r#"/** @jsx h */ /** @jsxFrag Fragment */ import { h, Fragment } from "https://deno.land/x/mod.ts";
@@ -767,14 +789,15 @@ function App() {
     <div><></></div>
   );
 }"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Jsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Jsx)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let code = module.transpile(&EmitOptions::default()).unwrap().text;
     let expected = r#"/** @jsxImportSource jsx_lib */ import { jsx as _jsx, Fragment as _Fragment } from "jsx_lib/jsx-runtime";
@@ -796,14 +819,15 @@ function App() {
     <div><></></div>
   );
 }"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Jsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Jsx)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let emit_options = EmitOptions {
       jsx_automatic: true,
@@ -830,14 +854,15 @@ function App() {
     <div><></></div>
   );
 }"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Jsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Jsx)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let emit_options = EmitOptions {
       jsx_automatic: true,
@@ -873,14 +898,15 @@ function App() {
     <div><></></div>
   );
 }"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Jsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: true,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Jsx)
+      .scope_analysis(true)
+      .build(),
+    )
     .unwrap();
     let emit_options = EmitOptions {
       var_decl_imports: true,
@@ -919,14 +945,14 @@ function App() {
       }
     }
     "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     let code = module.transpile(&EmitOptions::default()).unwrap().text;
     let expected = r#"function _ts_decorate(decorators, target, key, desc) {
@@ -966,14 +992,14 @@ export function g() {
   )
 }
   "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     let emit_options = EmitOptions {
       transform_jsx: true,
@@ -996,14 +1022,14 @@ export function g() {
     let source = r#"
 for (let i = 0; i < testVariable >> 1; i++) callCount++;
   "#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     let code = module.transpile(&Default::default()).unwrap().text;
     let expected = r#"for(let i = 0; i < testVariable >> 1; i++)callCount++;"#;
@@ -1017,14 +1043,14 @@ for (let i = 0; i < testVariable >> 1; i++) callCount++;
     let source = r#"const A = () => {
   return <div>{...[]}</div>;
 };"#;
-    let parsed_source = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Tsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      .build(),
+    )
     .unwrap();
     assert!(parsed_source.transpile(&Default::default()).is_ok());
   }
@@ -1091,14 +1117,14 @@ for (let i = 0; i < testVariable >> 1; i++) callCount++;
   fn get_diagnostic(source: &str) -> String {
     let specifier =
       ModuleSpecifier::parse("https://deno.land/x/mod.ts").unwrap();
-    let parsed_source = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::TypeScript,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let parsed_source = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .build(),
+    )
     .unwrap();
     parsed_source
       .transpile(&Default::default())
@@ -1112,19 +1138,20 @@ for (let i = 0; i < testVariable >> 1; i++) callCount++;
     // Ref https://github.com/denoland/deno/issues/10936
     // Ref https://github.com/swc-project/swc/issues/3288#issuecomment-1117252904
 
-    let p = parse_module(ParseParams {
-      specifier: "file:///Users/ib/dev/deno/foo.ts".to_string(),
-      text_info: SourceTextInfo::from_string(
-        r#"export default function () {
+    let p = parse_module(
+      ParseParams::builder(
+        "file:///Users/ib/dev/deno/foo.ts",
+        SourceTextInfo::from_string(
+          r#"export default function () {
     return "📣❓";
 }"#
-          .to_string(),
-      ),
-      media_type: MediaType::TypeScript,
-      capture_tokens: true,
-      scope_analysis: false,
-      maybe_syntax: None,
-    })
+            .to_string(),
+        ),
+      )
+      .media_type(MediaType::TypeScript)
+      .capture_tokens(true)
+      .build(),
+    )
     .unwrap();
 
     let transpiled = p.transpile(&Default::default()).unwrap();
@@ -1141,14 +1168,14 @@ for (let i = 0; i < testVariable >> 1; i++) callCount++;
       ModuleSpecifier::parse("https://deno.land/x/mod.tsx").unwrap();
     let source =
       r#"const a = <Foo><span>hello</span>foo<Bar><p>asdf</p></Bar></Foo>;"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Tsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      .build(),
+    )
     .unwrap();
     let options = EmitOptions {
       transform_jsx: false,
@@ -1179,14 +1206,14 @@ const a = _jsx(Foo, {
     let specifier =
       ModuleSpecifier::parse("https://deno.land/x/mod.tsx").unwrap();
     let source = r#"{ const foo = "bar"; };"#;
-    let module = parse_module(ParseParams {
-      specifier: specifier.as_str().to_string(),
-      text_info: SourceTextInfo::from_string(source.to_string()),
-      media_type: MediaType::Tsx,
-      capture_tokens: false,
-      maybe_syntax: None,
-      scope_analysis: false,
-    })
+    let module = parse_module(
+      ParseParams::builder(
+        specifier.as_str().to_string(),
+        SourceTextInfo::from_string(source.to_string()),
+      )
+      .media_type(MediaType::Tsx)
+      .build(),
+    )
     .unwrap();
     let options = EmitOptions {
       inline_source_map: true,
@@ -0,0 +1,314 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::swc::common::comments::CommentKind;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+
+/// Kind of a triple-slash reference directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TripleSlashReferenceKind {
+  /// `/// <reference path="..." />`
+  Path,
+  /// `/// <reference types="..." />`
+  Types,
+  /// `/// <reference lib="..." />`
+  Lib,
+  /// `/// <reference no-default-lib="true" />`
+  NoDefaultLib,
+}
+
+impl TripleSlashReferenceKind {
+  fn from_attribute_name(name: &str) -> Option<Self> {
+    match name {
+      "path" => Some(Self::Path),
+      "types" => Some(Self::Types),
+      "lib" => Some(Self::Lib),
+      "no-default-lib" => Some(Self::NoDefaultLib),
+      _ => None,
+    }
+  }
+}
+
+/// A triple-slash reference directive (ex. `/// <reference path="a.ts" />`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripleSlashReference {
+  pub kind: TripleSlashReferenceKind,
+  pub value: String,
+  /// Range of the value without the quotes.
+  pub value_range: SourceRange,
+  /// Range of the comment.
+  pub range: SourceRange,
+}
+
+/// A triple-slash reference directive that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripleSlashReferenceError {
+  pub message: String,
+  /// Range of the comment.
+  pub range: SourceRange,
+}
+
+/// Triple-slash reference directives found by
+/// `ParsedSource::triple_slash_references()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TripleSlashReferences {
+  /// References in source order.
+  pub references: Vec<TripleSlashReference>,
+  /// Directives that could not be parsed in source order.
+  pub errors: Vec<TripleSlashReferenceError>,
+}
+
+impl ParsedSource {
+  /// Gets the triple-slash reference directives in the leading comments
+  /// of the file.
+  ///
+  /// Like TypeScript, directives after the first statement are ignored.
+  pub fn triple_slash_references(&self) -> TripleSlashReferences {
+    let maybe_first_stmt_start = self.first_stmt_start();
+    let mut result = TripleSlashReferences::default();
+    for comment in self.comments().get_vec() {
+      let range = comment.range();
+      if maybe_first_stmt_start.is_some_and(|start| range.start >= start) {
+        break;
+      }
+      if comment.kind != CommentKind::Line {
+        continue;
+      }
+      // the comment text doesn't include the first two slashes
+      let Some(text) = comment.text.strip_prefix('/') else {
+        continue;
+      };
+      let trimmed_text = text.trim_start();
+      if !trimmed_text.starts_with("<reference") {
+        continue;
+      }
+      let text_offset = 3 + text.len() - trimmed_text.len();
+      match parse_reference(trimmed_text) {
+        Ok((kind, value, value_offset)) => {
+          let value_start = range.start + text_offset + value_offset;
+          result.references.push(TripleSlashReference {
+            kind,
+            value_range: SourceRange::new(
+              value_start,
+              value_start + value.len(),
+            ),
+            value: value.to_string(),
+            range,
+          });
+        }
+        Err(message) => {
+          result
+            .errors
+            .push(TripleSlashReferenceError { message, range });
+        }
+      }
+    }
+    result
+  }
+}
+
+/// Parses the text of a reference directive starting at `<reference`
+/// into its kind, value, and the offset of the value in the text.
+fn parse_reference(
+  text: &str,
+) -> Result<(TripleSlashReferenceKind, &str, usize), String> {
+  let mut offset = "<reference".len();
+  let mut maybe_reference = None;
+  loop {
+    let rest = &text[offset..];
+    let trimmed = rest.trim_start();
+    offset += rest.len() - trimmed.len();
+    if trimmed.starts_with("/>") {
+      break;
+    }
+    if offset == "<reference".len() && !trimmed.is_empty() {
+      // ex. `<referencex`
+      return Err("Expected whitespace after <reference.".to_string());
+    }
+    let name_len = trimmed
+      .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+      .unwrap_or(trimmed.len());
+    if name_len == 0 {
+      return Err("Expected an attribute or />.".to_string());
+    }
+    let name = &trimmed[..name_len];
+    offset += name_len;
+
+    let rest = &text[offset..];
+    let Some(rest) = rest.trim_start().strip_prefix('=') else {
+      return Err(format!("Expected = after attribute {}.", name));
+    };
+    let trimmed = rest.trim_start();
+    offset = text.len() - trimmed.len();
+    let Some(quote) =
+      trimmed.chars().next().filter(|c| *c == '"' || *c == '\'')
+    else {
+      return Err(format!("Expected a quoted value for attribute {}.", name));
+    };
+    let value_offset = offset + 1;
+    let Some(value_len) = text[value_offset..].find(quote) else {
+      return Err(format!("Unterminated value for attribute {}.", name));
+    };
+    let value = &text[value_offset..value_offset + value_len];
+    offset = value_offset + value_len + 1;
+
+    if maybe_reference.is_none() {
+      if let Some(kind) = TripleSlashReferenceKind::from_attribute_name(name) {
+        maybe_reference = Some((kind, value, value_offset));
+      }
+    }
+  }
+  maybe_reference.ok_or_else(|| {
+    "Expected a path, types, lib, or no-default-lib attribute.".to_string()
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.d.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::Dts)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn references() {
+    let parsed_source = parse(concat!(
+      "/// <reference path=\"./a.d.ts\" />\n",
+      "///<reference   types='node'/>\n",
+      "// regular comment\n",
+      "/** block */\n",
+      "/// <reference lib=\"deno.ns\" />\n",
+      "/// some documentation\n",
+      "/// <reference no-default-lib=\"true\"/>\n",
+      "/// <reference types=\"npm:@types/a\" resolution-mode=\"import\" />\n",
+      "/// <reference path=\"./b.d.ts\"\n",
+      "/// <reference foo=\"bar\" />\n",
+      "declare const a: string;\n",
+      "/// <reference path=\"./c.d.ts\" />\n",
+    ));
+    let result = parsed_source.triple_slash_references();
+    assert_eq!(
+      result
+        .references
+        .iter()
+        .map(|reference| {
+          assert_eq!(
+            parsed_source.text_for_range(&reference.value_range),
+            reference.value
+          );
+          (
+            reference.kind,
+            reference.value.as_str(),
+            parsed_source.text_for_range(&reference.range),
+          )
+        })
+        .collect::<Vec<_>>(),
+      vec![
+        (
+          TripleSlashReferenceKind::Path,
+          "./a.d.ts",
+          "/// <reference path=\"./a.d.ts\" />"
+        ),
+        (
+          TripleSlashReferenceKind::Types,
+          "node",
+          "///<reference   types='node'/>"
+        ),
+        (
+          TripleSlashReferenceKind::Lib,
+          "deno.ns",
+          "/// <reference lib=\"deno.ns\" />"
+        ),
+        (
+          TripleSlashReferenceKind::NoDefaultLib,
+          "true",
+          "/// <reference no-default-lib=\"true\"/>"
+        ),
+        (
+          TripleSlashReferenceKind::Types,
+          "npm:@types/a",
+          "/// <reference types=\"npm:@types/a\" resolution-mode=\"import\" />"
+        ),
+      ]
+    );
+    assert_eq!(
+      result
+        .errors
+        .iter()
+        .map(|error| (
+          error.message.as_str(),
+          parsed_source.text_for_range(&error.range)
+        ))
+        .collect::<Vec<_>>(),
+      vec![
+        (
+          "Expected an attribute or />.",
+          "/// <reference path=\"./b.d.ts\""
+        ),
+        (
+          "Expected a path, types, lib, or no-default-lib attribute.",
+          "/// <reference foo=\"bar\" />"
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn no_statements() {
+    let parsed_source = parse("/// <reference types=\"node\" />\n");
+    assert_eq!(
+      parsed_source
+        .triple_slash_references()
+        .references
+        .iter()
+        .map(|reference| reference.value.as_str())
+        .collect::<Vec<_>>(),
+      vec!["node"]
+    );
+  }
+
+  #[test]
+  fn malformed() {
+    let get_error = |text: &str| {
+      parse(text)
+        .triple_slash_references()
+        .errors
+        .into_iter()
+        .map(|error| error.message)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      get_error("/// <reference path=./a.d.ts />"),
+      vec!["Expected a quoted value for attribute path."]
+    );
+    assert_eq!(
+      get_error("/// <reference path=\"./a.d.ts />"),
+      vec!["Unterminated value for attribute path."]
+    );
+    assert_eq!(
+      get_error("/// <reference path />"),
+      vec!["Expected = after attribute path."]
+    );
+    assert_eq!(
+      get_error("/// <referenced />"),
+      vec!["Expected whitespace after <reference."]
+    );
+  }
+}
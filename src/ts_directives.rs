@@ -0,0 +1,226 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::suppression::parse_suppression_comment;
+use crate::swc::common::comments::CommentKind;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+use crate::SuppressionKind;
+use crate::SuppressionRules;
+
+/// A `// @ts-check` or `// @ts-nocheck` comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsCheckDirective {
+  /// Range of the comment.
+  pub range: SourceRange,
+  /// Whether the comment is before the first statement, which is the
+  /// only position where TypeScript honors it.
+  pub is_valid_position: bool,
+}
+
+/// A `@ts-expect-error` or `@ts-ignore` comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsSuppressionDirective {
+  /// `SuppressionKind::TsExpectError` or `SuppressionKind::TsIgnore`.
+  pub kind: SuppressionKind,
+  /// Range of the comment.
+  pub range: SourceRange,
+  /// 0-indexed line that the comment applies to, which is the next line
+  /// that isn't blank or a line comment. This is `None` when there is
+  /// no such line.
+  pub line_index: Option<usize>,
+}
+
+/// TypeScript comment directives found by `ParsedSource::ts_directives()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TsDirectives {
+  /// `// @ts-check` comments in source order.
+  pub check: Vec<TsCheckDirective>,
+  /// `// @ts-nocheck` comments in source order.
+  pub no_check: Vec<TsCheckDirective>,
+  /// `@ts-expect-error` and `@ts-ignore` comments in source order.
+  pub suppressions: Vec<TsSuppressionDirective>,
+}
+
+impl TsDirectives {
+  /// Gets if the file opts in to type checking with `// @ts-check`.
+  pub fn has_check(&self) -> bool {
+    self.check.iter().any(|d| d.is_valid_position)
+  }
+
+  /// Gets if the file opts out of type checking with `// @ts-nocheck`.
+  pub fn has_no_check(&self) -> bool {
+    self.no_check.iter().any(|d| d.is_valid_position)
+  }
+}
+
+impl ParsedSource {
+  /// Gets the `@ts-check`, `@ts-nocheck`, `@ts-expect-error`, and
+  /// `@ts-ignore` comments in the source.
+  pub fn ts_directives(&self) -> TsDirectives {
+    let maybe_first_stmt_start = self.first_stmt_start();
+    let text_info = self.text_info();
+    let suppression_rules = SuppressionRules {
+      kinds: vec![SuppressionKind::TsExpectError, SuppressionKind::TsIgnore],
+    };
+    let mut directives = TsDirectives::default();
+    for comment in self.comments().get_vec() {
+      let range = comment.range();
+      if comment.kind == CommentKind::Line {
+        let check_directives = if is_directive(&comment.text, "@ts-check") {
+          Some(&mut directives.check)
+        } else if is_directive(&comment.text, "@ts-nocheck") {
+          Some(&mut directives.no_check)
+        } else {
+          None
+        };
+        if let Some(check_directives) = check_directives {
+          check_directives.push(TsCheckDirective {
+            range,
+            is_valid_position: maybe_first_stmt_start
+              .map(|start| range.end <= start)
+              .unwrap_or(true),
+          });
+          continue;
+        }
+      }
+      if let Some((kind, _)) =
+        parse_suppression_comment(&comment.text, &suppression_rules)
+      {
+        let next_line_index = text_info.line_index(range.end) + 1;
+        let line_index = (next_line_index..text_info.lines_count()).find(|i| {
+          let line_text = text_info.line_text(*i).trim();
+          !line_text.is_empty() && !line_text.starts_with("//")
+        });
+        directives.suppressions.push(TsSuppressionDirective {
+          kind,
+          range,
+          line_index,
+        });
+      }
+    }
+    directives
+  }
+}
+
+/// Gets if the text of a line comment (without the `//`) starts with the
+/// directive, which may be followed by an explanation.
+fn is_directive(text: &str, directive: &str) -> bool {
+  // allow `/// @ts-nocheck`
+  let text = text.strip_prefix('/').unwrap_or(text).trim_start();
+  match text.strip_prefix(directive) {
+    Some(rest) => !rest.starts_with(|c: char| c.is_alphanumeric() || c == '-'),
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str, media_type: MediaType) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(media_type)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn no_check_first_line() {
+    let parsed_source = parse(
+      "// @ts-nocheck -- generated\nimport { a } from './a.ts';\na;",
+      MediaType::TypeScript,
+    );
+    let directives = parsed_source.ts_directives();
+    assert!(directives.has_no_check());
+    assert!(!directives.has_check());
+    assert_eq!(directives.no_check.len(), 1);
+    assert_eq!(
+      parsed_source.text_for_range(&directives.no_check[0].range),
+      "// @ts-nocheck -- generated"
+    );
+  }
+
+  #[test]
+  fn no_check_after_import() {
+    let parsed_source = parse(
+      "import { a } from './a.ts';\n// @ts-nocheck\na;",
+      MediaType::TypeScript,
+    );
+    let directives = parsed_source.ts_directives();
+    assert!(!directives.has_no_check());
+    assert_eq!(directives.no_check.len(), 1);
+    assert!(!directives.no_check[0].is_valid_position);
+
+    // after code on the first line
+    let directives =
+      parse("a; // @ts-nocheck", MediaType::TypeScript).ts_directives();
+    assert!(!directives.has_no_check());
+    assert_eq!(directives.no_check.len(), 1);
+  }
+
+  #[test]
+  fn check_in_js() {
+    let directives = parse(
+      "/** @file */\n// @ts-check\nconst a = '// @ts-nocheck';",
+      MediaType::JavaScript,
+    )
+    .ts_directives();
+    assert!(directives.has_check());
+    assert!(directives.no_check.is_empty());
+    // not a different directive
+    let directives =
+      parse("// @ts-checked\n", MediaType::JavaScript).ts_directives();
+    assert!(directives.check.is_empty());
+  }
+
+  #[test]
+  fn suppressions() {
+    let parsed_source = parse(
+      concat!(
+        "// @ts-expect-error\n",
+        "\n",
+        "const a: number = 'a';\n",
+        "/* @ts-ignore */\n",
+        "// other comment\n",
+        "const b: number = 'b';\n",
+        "const c = `// @ts-ignore`;\n",
+        "// @ts-expect-error: trailing\n",
+      ),
+      MediaType::TypeScript,
+    );
+    let directives = parsed_source.ts_directives();
+    assert_eq!(
+      directives
+        .suppressions
+        .iter()
+        .map(|d| (d.kind, parsed_source.text_for_range(&d.range), d.line_index))
+        .collect::<Vec<_>>(),
+      vec![
+        (
+          SuppressionKind::TsExpectError,
+          "// @ts-expect-error",
+          Some(2)
+        ),
+        (SuppressionKind::TsIgnore, "/* @ts-ignore */", Some(5)),
+        (
+          SuppressionKind::TsExpectError,
+          "// @ts-expect-error: trailing",
+          None
+        ),
+      ]
+    );
+  }
+}
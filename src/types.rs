@@ -2,23 +2,380 @@
 
 use std::borrow::Cow;
 use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::lines::clamp_pos;
+use crate::lines::clamp_range;
+use crate::lines::get_char_width;
 use crate::swc::parser::error::SyntaxError;
+use crate::swc::parser::token::Token;
+use crate::text_provider::SpecifiedTextInfo;
+use crate::DeprecatedSyntax;
+use crate::JsonDiagnosticKind;
 use crate::LineAndColumnDisplay;
+use crate::MediaType;
+use crate::SourcePos;
 use crate::SourceRange;
 use crate::SourceRangedForSpanned;
 use crate::SourceTextInfo;
+use crate::SourceTextInfoLines;
+use crate::SourceTextInfoUtf16;
+use crate::SourceTextProvider;
+use crate::SyntaxProposal;
+use crate::TextChange;
+
+/// Kind of parsing diagnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+  /// Swc syntax error.
+  Syntax(SyntaxError),
+  /// A shebang (`#!`) found somewhere other than the start of the file.
+  MisplacedShebang,
+  /// The source text exceeds `MAX_SOURCE_TEXT_LEN`.
+  SourceTooLarge {
+    /// Length of the source text in bytes.
+    len: usize,
+  },
+  /// Something not allowed in JSON found when parsing a JSON source.
+  Json(JsonDiagnosticKind),
+  /// The media type is not for source text that can be parsed (ex. Wasm).
+  UnsupportedMediaType {
+    /// Media type of the source.
+    media_type: MediaType,
+  },
+  /// The legacy `assert` keyword was used for import attributes
+  /// (see `ImportAttributesMode`).
+  ImportAssertKeyword,
+  /// Syntax of a proposal that was disabled via `SyntaxOptions`.
+  DisabledProposal(SyntaxProposal),
+  /// Parsing was stopped early because the cancellation flag
+  /// provided in the parse params was set.
+  Cancelled,
+  /// More recoverable errors were found than the `max_diagnostics`
+  /// provided in the parse params.
+  TooManyDiagnostics {
+    /// Number of errors that were not converted to diagnostics.
+    additional_count: usize,
+  },
+  /// Deprecated syntax was used. Only surfaced when
+  /// `ParseParamsBuilder::lint_deprecations` is enabled.
+  DeprecatedSyntax(DeprecatedSyntax),
+  /// Scope analysis was requested, but could not be done because the
+  /// `transforms` feature is not enabled.
+  ScopeAnalysisUnavailable,
+  /// The range provided to `ParsedSource::with_edit` is not within the
+  /// text or doesn't start and end on character boundaries.
+  InvalidEditRange,
+  /// A token was found after the end of a standalone expression, JSON
+  /// value, or TypeScript type.
+  UnexpectedTrailingToken {
+    /// Text of the unexpected token.
+    got: String,
+  },
+}
+
+impl DiagnosticKind {
+  /// Message text of the diagnostic kind.
+  pub fn msg(&self) -> Cow<str> {
+    match self {
+      DiagnosticKind::Syntax(kind) => kind.msg(),
+      DiagnosticKind::MisplacedShebang => {
+        Cow::Borrowed("A shebang (#!) is only allowed at the start of a file")
+      }
+      DiagnosticKind::SourceTooLarge { len } => Cow::Owned(format!(
+        "Source text of {} bytes exceeds the maximum supported size (4 GiB)",
+        len
+      )),
+      DiagnosticKind::Json(kind) => kind.msg(),
+      DiagnosticKind::UnsupportedMediaType { media_type } => Cow::Owned(
+        format!("Media type {:?} cannot be parsed as ECMAScript", media_type),
+      ),
+      DiagnosticKind::ImportAssertKeyword => Cow::Borrowed(
+        "The 'assert' keyword for import attributes is deprecated. Use the 'with' keyword instead.",
+      ),
+      DiagnosticKind::DisabledProposal(proposal) => proposal.disabled_msg(),
+      DiagnosticKind::Cancelled => Cow::Borrowed("Parsing was cancelled"),
+      DiagnosticKind::TooManyDiagnostics { additional_count } => Cow::Owned(
+        format!("Additional {} errors not shown", additional_count),
+      ),
+      DiagnosticKind::DeprecatedSyntax(syntax) => syntax.msg(),
+      DiagnosticKind::ScopeAnalysisUnavailable => Cow::Borrowed(
+        "Scope analysis was skipped because the transforms feature is not enabled",
+      ),
+      DiagnosticKind::InvalidEditRange => Cow::Borrowed(
+        "Edit range is not within the text or is not on character boundaries",
+      ),
+      DiagnosticKind::UnexpectedTrailingToken { got } => Cow::Owned(format!(
+        "Unexpected token `{}`. Expected the end of the input",
+        got
+      )),
+    }
+  }
+
+  /// Stable machine-readable code of the diagnostic kind.
+  ///
+  /// Unlike the message, this does not change across swc releases.
+  pub fn code(&self) -> DiagnosticCode {
+    DiagnosticCode(match self {
+      DiagnosticKind::Syntax(kind) => get_syntax_error_code(kind),
+      DiagnosticKind::MisplacedShebang => "misplaced-shebang",
+      DiagnosticKind::SourceTooLarge { .. } => "source-too-large",
+      DiagnosticKind::Json(kind) => match kind {
+        JsonDiagnosticKind::Comment => "json-comment",
+        JsonDiagnosticKind::TrailingComma => "json-trailing-comma",
+        JsonDiagnosticKind::DuplicateKey { .. } => "json-duplicate-key",
+        JsonDiagnosticKind::UnsupportedSyntax => "json-unsupported-syntax",
+      },
+      DiagnosticKind::UnsupportedMediaType { .. } => "unsupported-media-type",
+      DiagnosticKind::ImportAssertKeyword => "import-assert-keyword",
+      DiagnosticKind::DisabledProposal(proposal) => match proposal {
+        SyntaxProposal::AutoAccessors => "disabled-auto-accessors",
+        SyntaxProposal::ExplicitResourceManagement => {
+          "disabled-explicit-resource-management"
+        }
+      },
+      DiagnosticKind::Cancelled => "cancelled",
+      DiagnosticKind::TooManyDiagnostics { .. } => "too-many-diagnostics",
+      DiagnosticKind::DeprecatedSyntax(syntax) => match syntax {
+        DeprecatedSyntax::HtmlComment => "deprecated-html-comment",
+        DeprecatedSyntax::ArgumentsCallee => "deprecated-arguments-callee",
+      },
+      DiagnosticKind::ScopeAnalysisUnavailable => "scope-analysis-unavailable",
+      DiagnosticKind::InvalidEditRange => "invalid-edit-range",
+      DiagnosticKind::UnexpectedTrailingToken { .. } => {
+        "unexpected-trailing-token"
+      }
+    })
+  }
+}
+
+fn get_syntax_error_code(kind: &SyntaxError) -> &'static str {
+  match kind {
+    SyntaxError::Eof => "unexpected-eof",
+    SyntaxError::TS1003 => "identifier-expected",
+    SyntaxError::TS1005 | SyntaxError::ExpectedSemiForExprStmt { .. } => {
+      "expected-semi"
+    }
+    SyntaxError::TS1085 | SyntaxError::LegacyOctal => "legacy-octal",
+    SyntaxError::LegacyDecimal => "legacy-decimal",
+    SyntaxError::TS1109 => "expression-expected",
+    SyntaxError::UnterminatedStrLit => "unterminated-string",
+    SyntaxError::UnterminatedTpl => "unterminated-template",
+    SyntaxError::UnterminatedBlockComment => "unterminated-block-comment",
+    SyntaxError::UnterminatedRegExp => "unterminated-regexp",
+    SyntaxError::UnterminatedJSXContents => "unterminated-jsx-contents",
+    SyntaxError::NullishCoalescingWithLogicalOp => {
+      "nullish-coalescing-with-logical-op"
+    }
+    SyntaxError::InitRequiredForUsingDecl => "using-needs-init",
+    SyntaxError::Expected(_, _) => "expected-token",
+    SyntaxError::Unexpected { .. } => "unexpected-token",
+    SyntaxError::WithInStrict => "with-in-strict-mode",
+    SyntaxError::LegacyCommentInModule => "legacy-comment-in-module",
+    SyntaxError::EvalAndArgumentsInStrict => "eval-or-arguments-in-strict-mode",
+    SyntaxError::InvalidIdentInStrict(_) => "invalid-ident-in-strict-mode",
+    // not yet mapped to a more specific code
+    _ => "syntax-error",
+  }
+}
+
+/// Stable machine-readable code of a diagnostic (ex. `"expected-semi"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DiagnosticCode(&'static str);
+
+impl DiagnosticCode {
+  /// Text of the code.
+  pub fn as_str(&self) -> &'static str {
+    self.0
+  }
+}
+
+impl fmt::Display for DiagnosticCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.0)
+  }
+}
+
+impl From<SyntaxError> for DiagnosticKind {
+  fn from(kind: SyntaxError) -> Self {
+    DiagnosticKind::Syntax(kind)
+  }
+}
+
+/// Severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticSeverity {
+  Error,
+  Warning,
+  Info,
+}
+
+/// 1-indexed display range of a diagnostic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticDisplayRange {
+  /// Start position of the range.
+  #[serde(with = "crate::serialization::line_and_column_display")]
+  pub start: LineAndColumnDisplay,
+  /// Exclusive end position of the range.
+  #[serde(with = "crate::serialization::line_and_column_display")]
+  pub end: LineAndColumnDisplay,
+}
+
+/// Diagnostic that is detached from the source text it occurred in.
+///
+/// This is the serialized form of a `Diagnostic`:
+///
+/// ```json
+/// {
+///   "specifier": "file:///mod.ts",
+///   "message": "Expression expected",
+///   "code": "expression-expected",
+///   "severity": "error",
+///   "range": { "start": 10, "end": 11 },
+///   "lineNumber": 2,
+///   "columnNumber": 5
+/// }
+/// ```
+///
+/// The range is of the byte indexes in the source text and the line and
+/// column numbers are the 1-indexed display position of the start.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetachedDiagnostic {
+  pub specifier: String,
+  pub message: String,
+  pub code: String,
+  pub severity: DiagnosticSeverity,
+  pub range: Range<usize>,
+  pub line_number: usize,
+  pub column_number: usize,
+}
+
+/// Location related to a diagnostic (ex. where something was first
+/// declared).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedInformation {
+  /// Message describing how the location is related.
+  pub message: String,
+  /// Range of the location in the same source as the diagnostic.
+  pub range: SourceRange,
+}
+
+/// A machine-applicable fix for a diagnostic.
+#[derive(Debug, Clone)]
+pub struct FixSuggestion {
+  /// Description of the fix (ex. "Insert a semicolon").
+  pub description: String,
+  /// Edits to apply to the source text in order to fix the diagnostic.
+  ///
+  /// These can be applied using `apply_text_changes`.
+  pub edits: Vec<TextChange>,
+}
+
+/// Options for rendering the source text of a diagnostic with
+/// `Diagnostic::display_with_snippet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetOptions {
+  /// Number of lines of source text to show before and after the
+  /// lines of the range.
+  pub context_lines: usize,
+  /// Maximum number of characters of a line to show. Longer lines are
+  /// truncated around the range and marked with `...`.
+  ///
+  /// Defaults to 80.
+  pub max_line_width: Option<usize>,
+  /// Show line numbers in a gutter to the left of the source text.
+  pub show_line_numbers: bool,
+  /// Expand tabs in the source text to spaces, advancing to the next
+  /// multiple of this width, and position the underline accordingly.
+  ///
+  /// By default, tabs are kept in the source text and underline so
+  /// that the underline lines up regardless of the terminal's tab width.
+  pub tab_width: Option<usize>,
+}
+
+impl Default for SnippetOptions {
+  fn default() -> Self {
+    Self {
+      context_lines: 0,
+      max_line_width: Some(80),
+      show_line_numbers: false,
+      tab_width: None,
+    }
+  }
+}
+
+/// Style used when rendering a diagnostic with
+/// `Diagnostic::display_styled`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayStyle {
+  /// Color the output using ANSI escape codes.
+  pub color: bool,
+  /// Underline the range using unicode characters instead of `^`.
+  pub unicode: bool,
+  /// Options for rendering the source text.
+  pub snippet: SnippetOptions,
+}
+
+/// Options for `Diagnostic::display_specifier`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplaySpecifierOptions {
+  /// Directory that paths within it are displayed relative to.
+  ///
+  /// Paths outside of this directory are displayed as absolute paths.
+  pub base_dir: Option<PathBuf>,
+  /// Display `file:` URLs as Windows paths (ex. `C:\dir\mod.ts`).
+  ///
+  /// Defaults to whether the current platform is Windows.
+  pub windows_paths: bool,
+}
+
+// not derivable because the default differs on Windows
+#[allow(clippy::derivable_impls)]
+impl Default for DisplaySpecifierOptions {
+  fn default() -> Self {
+    Self {
+      base_dir: None,
+      windows_paths: cfg!(windows),
+    }
+  }
+}
+
+const ANSI_BOLD: &str = "1";
+const ANSI_DIM: &str = "2";
+const ANSI_RED: &str = "1;31";
+const ANSI_YELLOW: &str = "1;33";
+const ANSI_CYAN: &str = "1;36";
 
 /// Parsing diagnostic.
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
   /// Specifier of the source the diagnostic occurred in.
-  pub specifier: String,
+  ///
+  /// This is shared between the diagnostics of a source.
+  pub specifier: Arc<str>,
   /// Range of the diagnostic.
   pub range: SourceRange,
-  /// Swc syntax error
-  pub kind: SyntaxError,
+  /// Kind of diagnostic.
+  pub kind: DiagnosticKind,
+  /// Severity of the diagnostic.
+  pub severity: DiagnosticSeverity,
+  /// Suggestion on how to fix the diagnostic.
+  pub hint: Option<String>,
+  /// Other locations that are related to the diagnostic.
+  pub related: Vec<RelatedInformation>,
+  /// Fixes that can be applied without any further analysis.
+  pub suggestions: Vec<FixSuggestion>,
+  // cheap to clone because the text is shared
   source: SourceTextInfo,
+  other_errors: Vec<Diagnostic>,
+  swc_error: Option<SwcSyntaxError>,
 }
 
 impl PartialEq for Diagnostic {
@@ -27,63 +384,543 @@ impl PartialEq for Diagnostic {
     self.specifier == other.specifier
       && self.range == other.range
       && self.kind == other.kind
+      && self.severity == other.severity
   }
 }
 
 impl Diagnostic {
+  /// Specifier of the source the diagnostic occurred in.
+  pub fn specifier(&self) -> &str {
+    &self.specifier
+  }
+
+  /// Specifier of the source formatted for display to a user.
+  ///
+  /// `file:` URLs are converted to local paths, optionally relative to
+  /// `options.base_dir`. Other specifiers (ex. `https:` or `npm:`) and
+  /// `file:` URLs that can't be represented as a path are returned
+  /// unchanged.
+  pub fn display_specifier(
+    &self,
+    options: &DisplaySpecifierOptions,
+  ) -> Cow<str> {
+    get_display_specifier(&self.specifier, options)
+  }
+
+  /// Text information of the source the diagnostic occurred in.
+  pub fn text_info(&self) -> &SourceTextInfo {
+    &self.source
+  }
+
   /// Message text of the diagnostic.
   pub fn message(&self) -> Cow<str> {
     self.kind.msg()
   }
 
+  /// Stable machine-readable code of the diagnostic.
+  pub fn code(&self) -> DiagnosticCode {
+    self.kind.code()
+  }
+
+  /// Gets the diagnostic with the provided hint.
+  pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+    self.hint = Some(hint.into());
+    self
+  }
+
+  /// Gets the diagnostic with the provided related information added.
+  pub fn with_related(mut self, related: RelatedInformation) -> Self {
+    self.related.push(related);
+    self
+  }
+
+  /// Gets the diagnostic with the provided severity.
+  pub fn with_severity(mut self, severity: DiagnosticSeverity) -> Self {
+    self.severity = severity;
+    self
+  }
+
   /// 1-indexed display position the diagnostic occurred at.
   pub fn display_position(&self) -> LineAndColumnDisplay {
-    self.source.line_and_column_display(self.range.start)
+    self
+      .source
+      .line_and_column_display(clamp_pos(&self.source, self.range.start))
+  }
+
+  /// 1-indexed display position the diagnostic occurred at where the
+  /// column accounts for tabs advancing to the next multiple of the tab
+  /// width, as they do in terminals and editors.
+  pub fn display_position_with_tab_width(
+    &self,
+    tab_width: usize,
+  ) -> LineAndColumnDisplay {
+    LineAndColumnDisplay {
+      line_number: self.display_position().line_number,
+      column_number: self.source.display_column(self.range.start, tab_width),
+    }
+  }
+
+  /// 1-indexed display range of the diagnostic.
+  pub fn display_range(&self) -> DiagnosticDisplayRange {
+    DiagnosticDisplayRange {
+      start: self.display_position(),
+      end: self
+        .source
+        .line_and_column_display(clamp_pos(&self.source, self.range.end)),
+    }
+  }
+
+  /// Renders the diagnostic as a `specifier:line:column` header followed
+  /// by the source lines of the range with each line underlined, then
+  /// any related information and hint.
+  ///
+  /// The specifier is formatted with `display_specifier()` using the
+  /// default options, the same as the `Display` implementation.
+  ///
+  /// When a range spans more than five lines, the lines in the middle
+  /// are elided and marked with `...`.
+  pub fn display_with_snippet(&self, options: SnippetOptions) -> String {
+    self.display_styled(DisplayStyle {
+      snippet: options,
+      ..Default::default()
+    })
+  }
+
+  /// Renders the diagnostic the same as `display_with_snippet()`, but
+  /// using the provided style.
+  ///
+  /// The output is identical to `display_with_snippet()` when not using
+  /// color or unicode.
+  pub fn display_styled(&self, style: DisplayStyle) -> String {
+    let text_start = self.source.range().start;
+    let related = self
+      .related
+      .iter()
+      .map(|related| {
+        (
+          related.message.as_str(),
+          clamp_pos(&self.source, related.range.start) - text_start,
+        )
+      })
+      .collect::<Vec<_>>();
+    display_styled_in_source(
+      &SpecifiedTextInfo {
+        specifier: &self.specifier,
+        text_info: &self.source,
+      },
+      clamp_range(&self.source, self.range).as_byte_range(text_start),
+      self.severity,
+      &self.message(),
+      &related,
+      self.hint.as_deref(),
+      style,
+    )
+  }
+
+  /// Gets the diagnostic without its source text.
+  pub fn to_detached(&self) -> DetachedDiagnostic {
+    let display_position = self.display_position();
+    DetachedDiagnostic {
+      specifier: self.specifier.to_string(),
+      message: self.message().into_owned(),
+      code: self.code().as_str().to_string(),
+      severity: self.severity,
+      range: self.byte_range(),
+      line_number: display_position.line_number,
+      column_number: display_position.column_number,
+    }
+  }
+
+  /// Other errors found while parsing before the parser bailed on this
+  /// fatal diagnostic, sorted by position.
+  ///
+  /// This will be empty for non-fatal diagnostics.
+  pub fn other_errors(&self) -> &[Diagnostic] {
+    &self.other_errors
+  }
+
+  /// Gets this diagnostic along with the other errors found while
+  /// parsing, sorted by position.
+  pub fn into_all_errors(mut self) -> Vec<Diagnostic> {
+    let mut errors = std::mem::take(&mut self.other_errors);
+    errors.push(self);
+    errors.sort_by_key(|d| d.range.start);
+    errors
+  }
+
+  /// 1-indexed display position the diagnostic occurred at where the
+  /// column is in UTF-16 code units (ex. for editors and V8 stack traces).
+  pub fn display_position_utf16(&self) -> LineAndColumnDisplay {
+    get_line_and_column_display_utf16(&self.source, self.range.start)
+  }
+
+  /// 1-indexed display range of the diagnostic where the columns are in
+  /// UTF-16 code units.
+  pub fn display_range_utf16(&self) -> DiagnosticDisplayRange {
+    DiagnosticDisplayRange {
+      start: self.display_position_utf16(),
+      end: get_line_and_column_display_utf16(&self.source, self.range.end),
+    }
+  }
+
+  /// Range of the diagnostic as byte indexes in the source text.
+  pub fn byte_range(&self) -> Range<usize> {
+    self.range.as_byte_range(self.source.range().start)
+  }
+
+  /// The swc syntax error the diagnostic was created from, which is
+  /// also provided as the source of the error.
+  pub fn swc_error(&self) -> Option<&SwcSyntaxError> {
+    self.swc_error.as_ref()
+  }
+
+  /// Gets if the diagnostic was caused by the input ending unexpectedly
+  /// (ex. an unclosed block, string, template literal, or parenthesis).
+  ///
+  /// This is useful for knowing whether to prompt for more input in a REPL.
+  pub fn is_unexpected_eof(&self) -> bool {
+    match &self.kind {
+      DiagnosticKind::Syntax(kind) => {
+        matches!(
+          kind,
+          SyntaxError::Eof
+            | SyntaxError::UnterminatedBlockComment
+            | SyntaxError::UnterminatedStrLit
+            | SyntaxError::UnterminatedTpl
+            | SyntaxError::UnterminatedRegExp
+            | SyntaxError::UnterminatedJSXContents
+        ) || matches!(kind, SyntaxError::Expected(_, got) if got == "<eof>")
+          || self.range.start == self.source.range().end
+      }
+      DiagnosticKind::MisplacedShebang
+      | DiagnosticKind::SourceTooLarge { .. }
+      | DiagnosticKind::Json(_)
+      | DiagnosticKind::UnsupportedMediaType { .. }
+      | DiagnosticKind::ImportAssertKeyword
+      | DiagnosticKind::DisabledProposal(_)
+      | DiagnosticKind::Cancelled
+      | DiagnosticKind::TooManyDiagnostics { .. }
+      | DiagnosticKind::DeprecatedSyntax(_)
+      | DiagnosticKind::ScopeAnalysisUnavailable
+      | DiagnosticKind::InvalidEditRange
+      | DiagnosticKind::UnexpectedTrailingToken { .. } => false,
+    }
   }
 }
 
 impl Diagnostic {
+  pub(crate) fn new(
+    specifier: &str,
+    range: SourceRange,
+    kind: DiagnosticKind,
+    source: SourceTextInfo,
+  ) -> Diagnostic {
+    Diagnostic {
+      range,
+      specifier: specifier.into(),
+      kind,
+      severity: DiagnosticSeverity::Error,
+      hint: None,
+      related: Vec::new(),
+      suggestions: Vec::new(),
+      source,
+      other_errors: Vec::new(),
+      swc_error: None,
+    }
+  }
+
+  pub(crate) fn with_other_errors(
+    mut self,
+    mut other_errors: Vec<Diagnostic>,
+  ) -> Diagnostic {
+    other_errors.sort_by_key(|d| d.range.start);
+    self.other_errors = other_errors;
+    self
+  }
+
   pub fn from_swc_error(
     err: crate::swc::parser::error::Error,
     specifier: &str,
     source: SourceTextInfo,
   ) -> Diagnostic {
+    Diagnostic::from_swc_error_with_shared_specifier(
+      err,
+      specifier.into(),
+      source,
+    )
+  }
+
+  pub(crate) fn from_swc_error_with_shared_specifier(
+    err: crate::swc::parser::error::Error,
+    specifier: Arc<str>,
+    source: SourceTextInfo,
+  ) -> Diagnostic {
+    // clamp in case swc provides a range outside the text
+    let range = widen_empty_range(clamp_range(&source, err.range()), &source);
+    let kind = err.into_kind();
+    let (hint, related) =
+      get_syntax_error_hint_and_related(&kind, range, &source);
+    let suggestions = get_syntax_error_suggestions(&kind, range, &source);
     Diagnostic {
-      range: err.range(),
-      specifier: specifier.to_string(),
-      kind: err.into_kind(),
+      range,
+      specifier,
+      kind: DiagnosticKind::Syntax(kind.clone()),
+      severity: DiagnosticSeverity::Error,
+      hint,
+      related: related.into_iter().collect(),
+      suggestions: suggestions.into_iter().collect(),
       source,
+      other_errors: Vec::new(),
+      swc_error: Some(SwcSyntaxError(kind)),
+    }
+  }
+}
+
+#[cfg(feature = "module_specifier")]
+impl Diagnostic {
+  /// Gets the specifier of the source as a `ModuleSpecifier`.
+  ///
+  /// Errors when the specifier is not a valid URL.
+  pub fn module_specifier(
+    &self,
+  ) -> Result<crate::ModuleSpecifier, url::ParseError> {
+    crate::ModuleSpecifier::parse(&self.specifier)
+  }
+}
+
+#[cfg(feature = "lsp")]
+impl Diagnostic {
+  /// Converts the diagnostic to a language server protocol diagnostic
+  /// with UTF-16 positions.
+  pub fn to_lsp(&self) -> crate::lsp::Diagnostic {
+    crate::lsp::to_lsp_diagnostic(self, &self.source)
+  }
+}
+
+fn get_line_and_column_display_utf16(
+  source: &SourceTextInfo,
+  pos: SourcePos,
+) -> LineAndColumnDisplay {
+  let position = source.line_and_column_utf16(pos);
+  LineAndColumnDisplay {
+    line_number: position.line_index + 1,
+    column_number: position.column_index + 1,
+  }
+}
+
+fn get_syntax_error_hint_and_related(
+  kind: &SyntaxError,
+  range: SourceRange,
+  source: &SourceTextInfo,
+) -> (Option<String>, Option<RelatedInformation>) {
+  match kind {
+    SyntaxError::InitRequiredForUsingDecl => (
+      Some("Add an initializer (ex. `using res = getResource();`)".to_string()),
+      None,
+    ),
+    SyntaxError::NullishCoalescingWithLogicalOp => (
+      Some("Wrap either side of the `??` in parentheses".to_string()),
+      None,
+    ),
+    SyntaxError::TS1085 | SyntaxError::LegacyOctal => (
+      Some("Use the `0o` prefix for octal literals (ex. `0o777`)".to_string()),
+      None,
+    ),
+    // swc has no dedicated error kind for this one
+    kind if kind.msg() == "'const' declarations must be initialized" => (
+      Some("Add an initializer or declare it with `let`".to_string()),
+      find_preceding_keyword(source, range.start, "const").map(|range| {
+        RelatedInformation {
+          message: "'const' declared here".to_string(),
+          range,
+        }
+      }),
+    ),
+    _ => (None, None),
+  }
+}
+
+fn get_syntax_error_suggestions(
+  kind: &SyntaxError,
+  range: SourceRange,
+  source: &SourceTextInfo,
+) -> Option<FixSuggestion> {
+  let start_pos = source.range().start;
+  let insert = |description: &str, pos: SourcePos, text: &str| FixSuggestion {
+    description: description.to_string(),
+    edits: vec![TextChange::new(
+      pos - start_pos,
+      pos - start_pos,
+      text.to_string(),
+    )],
+  };
+  match kind {
+    SyntaxError::TS1005 | SyntaxError::ExpectedSemiForExprStmt { .. } => {
+      Some(insert(
+        "Insert a semicolon",
+        get_previous_non_whitespace_end(source, range.start),
+        ";",
+      ))
+    }
+    SyntaxError::Expected(Token::RParen, _) => Some(insert(
+      "Insert a closing parenthesis",
+      get_previous_non_whitespace_end(source, range.start),
+      ")",
+    )),
+    SyntaxError::UnterminatedStrLit => {
+      let text =
+        source.range_text(&SourceRange::new(range.start, source.range().end));
+      let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+      let line_len = text.find(['\r', '\n']).unwrap_or(text.len());
+      Some(insert(
+        "Terminate the string",
+        range.start + line_len,
+        &quote.to_string(),
+      ))
     }
+    kind if kind.msg() == "'const' declarations must be initialized" => {
+      let keyword_range = find_preceding_keyword(source, range.start, "const")?;
+      Some(FixSuggestion {
+        description: "Declare it with `let`".to_string(),
+        edits: vec![TextChange::new(
+          keyword_range.start - start_pos,
+          keyword_range.end - start_pos,
+          "let".to_string(),
+        )],
+      })
+    }
+    _ => None,
+  }
+}
+
+/// Gets the end of the text before the provided position, excluding
+/// any whitespace (ex. to insert a semicolon after the previous token).
+fn get_previous_non_whitespace_end(
+  source: &SourceTextInfo,
+  pos: SourcePos,
+) -> SourcePos {
+  let start = source.range().start.as_source_pos();
+  let text = source.range_text(&SourceRange::new(start, pos));
+  start + text.trim_end().len()
+}
+
+/// Finds the closest keyword before the provided position on its line.
+fn find_preceding_keyword(
+  source: &SourceTextInfo,
+  pos: SourcePos,
+  keyword: &str,
+) -> Option<SourceRange> {
+  let line_start = source.line_start(source.line_index(pos));
+  let text = source.range_text(&SourceRange::new(line_start, pos));
+  let is_word_boundary = |c: Option<char>| {
+    !c.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+  };
+  let index =
+    text
+      .rmatch_indices(keyword)
+      .map(|(index, _)| index)
+      .find(|index| {
+        is_word_boundary(text[..*index].chars().next_back())
+          && is_word_boundary(text[index + keyword.len()..].chars().next())
+      })?;
+  let start = line_start + index;
+  Some(SourceRange::new(start, start + keyword.len()))
+}
+
+/// Widens an empty range to include the character at its position
+/// so that there is something to display.
+fn widen_empty_range(
+  range: SourceRange,
+  source: &SourceTextInfo,
+) -> SourceRange {
+  if range.start != range.end {
+    return range;
+  }
+  let source_range = source.range();
+  if range.start < source_range.start || range.start >= source_range.end {
+    return range;
+  }
+  let byte_index = range.start - source_range.start;
+  match source.text_str()[byte_index..].chars().next() {
+    // don't widen onto the next line
+    Some('\n' | '\r') | None => range,
+    Some(c) => SourceRange::new(range.start, range.start + c.len_utf8()),
+  }
+}
+
+impl Serialize for Diagnostic {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    // excludes the source
+    self.to_detached().serialize(serializer)
+  }
+}
+
+impl std::error::Error for Diagnostic {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    self
+      .swc_error
+      .as_ref()
+      .map(|err| err as &(dyn std::error::Error + 'static))
   }
 }
 
-impl std::error::Error for Diagnostic {}
+/// Syntax error from swc that a `Diagnostic` was created from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwcSyntaxError(SyntaxError);
+
+impl SwcSyntaxError {
+  /// Kind of swc syntax error.
+  pub fn kind(&self) -> &SyntaxError {
+    &self.0
+  }
+}
+
+impl fmt::Display for SwcSyntaxError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0.msg())
+  }
+}
+
+impl std::error::Error for SwcSyntaxError {}
 
 impl fmt::Display for Diagnostic {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     let display_position = self.display_position();
+    // errors are not prefixed in order to keep their output the same
+    // as before severities existed
+    match self.severity {
+      DiagnosticSeverity::Error => {}
+      DiagnosticSeverity::Warning => write!(f, "Warning: ")?,
+      DiagnosticSeverity::Info => write!(f, "Info: ")?,
+    }
     write!(
       f,
       "{} at {}:{}:{}\n\n{}",
       self.message(),
-      self.specifier,
+      self.display_specifier(&Default::default()),
       display_position.line_number,
       display_position.column_number,
       // todo(dsherret): remove this catch unwind once we've
       // tested this out a lot
       std::panic::catch_unwind(|| {
-        get_range_text_highlight(&self.source, self.range)
-          .lines()
-          // indent two spaces
-          .map(|l| {
-            if l.trim().is_empty() {
-              String::new()
-            } else {
-              format!("  {}", l)
-            }
-          })
-          .collect::<Vec<_>>()
-          .join("\n")
+        get_range_text_highlight(
+          &self.source,
+          clamp_range(&self.source, self.range),
+        )
+        .lines()
+        // indent two spaces
+        .map(|l| {
+          if l.trim().is_empty() {
+            String::new()
+          } else {
+            format!("  {}", l)
+          }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
       })
       .unwrap_or_else(|err| {
         format!("Bug. Please report this issue: {:?}", err)
@@ -92,6 +929,109 @@ impl fmt::Display for Diagnostic {
   }
 }
 
+/// Formats a specifier for display (see `Diagnostic::display_specifier`).
+fn get_display_specifier<'a>(
+  specifier: &'a str,
+  options: &DisplaySpecifierOptions,
+) -> Cow<'a, str> {
+  match file_url_to_display_path(specifier, options) {
+    Some(path) => Cow::Owned(path),
+    None => Cow::Borrowed(specifier),
+  }
+}
+
+fn file_url_to_display_path(
+  specifier: &str,
+  options: &DisplaySpecifierOptions,
+) -> Option<String> {
+  let scheme = specifier.get(.."file://".len())?;
+  if !scheme.eq_ignore_ascii_case("file://") {
+    return None;
+  }
+  let rest = &specifier[scheme.len()..];
+  let rest = &rest[..rest.find(['?', '#']).unwrap_or(rest.len())];
+  let path_start = rest.find('/').unwrap_or(rest.len());
+  let host = &rest[..path_start];
+  let host = if host.eq_ignore_ascii_case("localhost") {
+    ""
+  } else {
+    host
+  };
+  let path = percent_decode(&rest[path_start..])?;
+  let path = if options.windows_paths {
+    let path = path.replace('/', "\\");
+    if !host.is_empty() {
+      // UNC path (ex. file://server/share/mod.ts)
+      format!("\\\\{}{}", host, path)
+    } else {
+      // drive letter path (ex. file:///C:/dir/mod.ts)
+      let mut chars = path.chars();
+      match (chars.next(), chars.next(), chars.next()) {
+        (Some('\\'), Some(letter), Some(':' | '|'))
+          if letter.is_ascii_alphabetic() =>
+        {
+          format!("{}:{}", letter, &path[3..])
+        }
+        _ => return None,
+      }
+    }
+  } else if host.is_empty() && !path.is_empty() {
+    path
+  } else {
+    return None;
+  };
+
+  if let Some(base_dir) = &options.base_dir {
+    let base_dir = base_dir.to_string_lossy();
+    let (base_dir, separator) = if options.windows_paths {
+      (Cow::Owned(base_dir.replace('/', "\\")), '\\')
+    } else {
+      (base_dir, '/')
+    };
+    let base_dir = base_dir.trim_end_matches(separator);
+    let is_match = path.len() > base_dir.len()
+      && path.is_char_boundary(base_dir.len())
+      && path[base_dir.len()..].starts_with(separator)
+      && if options.windows_paths {
+        path[..base_dir.len()].eq_ignore_ascii_case(base_dir)
+      } else {
+        path[..base_dir.len()] == *base_dir
+      };
+    if is_match {
+      return Some(path[base_dir.len() + 1..].to_string());
+    }
+  }
+
+  Some(path)
+}
+
+/// Decodes the percent-encoded bytes in a URL path, returning `None`
+/// if the result isn't valid UTF-8.
+pub(crate) fn percent_decode(text: &str) -> Option<String> {
+  fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+  }
+
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let byte = bytes[i];
+    if byte == b'%' && i + 2 < bytes.len() {
+      if let (Some(high), Some(low)) =
+        (hex_value(bytes[i + 1]), hex_value(bytes[i + 2]))
+      {
+        decoded.push(high * 16 + low);
+        i += 3;
+        continue;
+      }
+    }
+    decoded.push(byte);
+    i += 1;
+  }
+  String::from_utf8(decoded).ok()
+}
+
 #[derive(Debug)]
 pub struct DiagnosticsError(pub Vec<Diagnostic>);
 
@@ -111,6 +1051,277 @@ impl fmt::Display for DiagnosticsError {
   }
 }
 
+/// Renders a diagnostic for a byte range of the source text, which has
+/// been clamped to the text.
+///
+/// Related information is provided as the message and byte index of
+/// each location.
+pub(crate) fn display_styled_in_source(
+  source: &(impl SourceTextProvider + ?Sized),
+  range: Range<usize>,
+  severity: DiagnosticSeverity,
+  message: &str,
+  related: &[(&str, usize)],
+  hint: Option<&str>,
+  style: DisplayStyle,
+) -> String {
+  // ranges spanning more lines than this have their middle lines elided
+  const MAX_RANGE_LINES: usize = 5;
+
+  let options = style.snippet;
+  let paint = |text: &str, code: &str| {
+    if style.color {
+      format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+      text.to_string()
+    }
+  };
+  let get_char_count =
+    |start: usize, end: usize| source.range_text(start..end).chars().count();
+  // formatted the same as the `Display` implementation of `Diagnostic`
+  let specifier =
+    get_display_specifier(source.specifier(), &Default::default());
+  let display_position = source.line_and_column_display(range.start);
+  let start_line_index = source.line_index(range.start);
+  let end_line_index = {
+    let line_index = source.line_index(range.end);
+    // don't include the next line when the range ends with a newline
+    if line_index > start_line_index
+      && source.line_start(line_index) == range.end
+    {
+      line_index - 1
+    } else {
+      line_index
+    }
+  };
+  let first_line_index = start_line_index.saturating_sub(options.context_lines);
+  let last_line_index = std::cmp::min(
+    end_line_index + options.context_lines,
+    source.lines_count() - 1,
+  );
+  let gutter_width = (last_line_index + 1).to_string().len();
+  let format_line = |line_index: Option<usize>, text: &str| {
+    if !options.show_line_numbers {
+      format!("\n  {}", text)
+    } else if let Some(line_index) = line_index {
+      let line_number = paint(
+        &format!("{:>width$} |", line_index + 1, width = gutter_width),
+        ANSI_DIM,
+      );
+      format!("\n  {} {}", line_number, text)
+    } else {
+      let gutter =
+        paint(&format!("{:width$} |", "", width = gutter_width), ANSI_DIM);
+      format!("\n  {} {}", gutter, text)
+    }
+  };
+  let (prefix, severity_color) = match severity {
+    DiagnosticSeverity::Error => ("", ANSI_RED),
+    DiagnosticSeverity::Warning => ("Warning: ", ANSI_YELLOW),
+    DiagnosticSeverity::Info => ("Info: ", ANSI_CYAN),
+  };
+
+  let mut text = format!(
+    "{} {}",
+    paint(
+      &format!(
+        "{}:{}:{}:",
+        specifier, display_position.line_number, display_position.column_number,
+      ),
+      ANSI_DIM,
+    ),
+    paint(&format!("{}{}", prefix, message), severity_color),
+  );
+  let range_line_count = end_line_index - start_line_index + 1;
+  for line_index in first_line_index..=last_line_index {
+    let line_text = source.line_text(line_index);
+    if line_index < start_line_index || line_index > end_line_index {
+      text.push_str(&format_line(
+        Some(line_index),
+        &expand_tabs(
+          &truncate_line(&line_text, options.max_line_width),
+          options.tab_width,
+        ),
+      ));
+      continue;
+    }
+    if range_line_count > MAX_RANGE_LINES {
+      let elided_start = start_line_index + 2;
+      let elided_end = end_line_index - 2;
+      if line_index == elided_start {
+        text.push_str("\n  ...");
+      }
+      if line_index >= elided_start && line_index <= elided_end {
+        continue;
+      }
+    }
+
+    let line_start = source.line_start(line_index);
+    let line_end = source.line_end(line_index);
+    let start_char_index = if line_index == start_line_index {
+      get_char_count(line_start, range.start)
+    } else {
+      // don't underline the indentation
+      line_text.chars().take_while(|c| c.is_whitespace()).count()
+    };
+    let end_char_index = if line_index == end_line_index {
+      let range_end =
+        std::cmp::max(range.start, std::cmp::min(range.end, line_end));
+      std::cmp::max(start_char_index, get_char_count(line_start, range_end))
+    } else {
+      line_text.chars().count()
+    };
+    if line_index != start_line_index && start_char_index == end_char_index {
+      // nothing to underline on this line
+      text.push_str(&format_line(
+        Some(line_index),
+        &expand_tabs(
+          &truncate_line(&line_text, options.max_line_width),
+          options.tab_width,
+        ),
+      ));
+      continue;
+    }
+    let (line_text, underline) = get_line_snippet(
+      &line_text,
+      start_char_index,
+      end_char_index,
+      if style.unicode { '━' } else { '^' },
+      options.max_line_width,
+      options.tab_width,
+    );
+    let padding_len = underline
+      .find(|c: char| !c.is_whitespace())
+      .unwrap_or(underline.len());
+    text.push_str(&format_line(Some(line_index), &line_text));
+    text.push_str(&format_line(
+      None,
+      &format!(
+        "{}{}",
+        &underline[..padding_len],
+        paint(&underline[padding_len..], severity_color),
+      ),
+    ));
+  }
+  for (related_message, related_start) in related {
+    let position = source.line_and_column_display(*related_start);
+    text.push_str(&format!(
+      "\n  {} {} at {}:{}:{}",
+      paint("= note:", ANSI_BOLD),
+      related_message,
+      specifier,
+      position.line_number,
+      position.column_number,
+    ));
+  }
+  if let Some(hint) = hint {
+    text.push_str(&format!("\n  {} {}", paint("= hint:", ANSI_BOLD), hint));
+  }
+  text
+}
+
+/// Gets the text of the line to display along with a caret underline
+/// of the provided char range. Long lines are truncated around the range
+/// and tabs are expanded to spaces when there is a tab width.
+fn get_line_snippet(
+  line: &str,
+  start_char_index: usize,
+  end_char_index: usize,
+  underline_char: char,
+  max_line_width: Option<usize>,
+  tab_width: Option<usize>,
+) -> (String, String) {
+  let chars = line.chars().collect::<Vec<_>>();
+  let (display_start, display_end) = match max_line_width {
+    Some(max_chars) if chars.len() > max_chars => {
+      let leading_chars = std::cmp::min(20, max_chars / 4);
+      let display_start = std::cmp::min(
+        start_char_index.saturating_sub(leading_chars),
+        chars.len() - max_chars,
+      );
+      (display_start, display_start + max_chars)
+    }
+    _ => (0, chars.len()),
+  };
+  let mut line_text = String::new();
+  let mut underline = String::new();
+  if display_start > 0 {
+    line_text.push_str("...");
+    underline.push_str("   ");
+  }
+  let mut has_caret = false;
+  let mut column = 0;
+  for (i, c) in chars.iter().enumerate().take(display_end) {
+    let char_width = match tab_width {
+      Some(tab_width) => get_char_width(*c, column, tab_width),
+      None => 1,
+    };
+    column += char_width;
+    if i < display_start {
+      continue;
+    }
+    if *c == '\t' && tab_width.is_some() {
+      line_text.push_str(&" ".repeat(char_width));
+    } else {
+      line_text.push(*c);
+    }
+    if i < start_char_index {
+      if *c == '\t' && tab_width.is_none() {
+        // keep tabs so the caret lines up regardless of the tab width
+        underline.push('\t');
+      } else {
+        underline.push_str(&" ".repeat(char_width));
+      }
+    } else if i < end_char_index {
+      for _ in 0..char_width {
+        underline.push(underline_char);
+      }
+      has_caret = true;
+    }
+  }
+  if display_end < chars.len() {
+    line_text.push_str("...");
+  }
+  if !has_caret {
+    // the range is empty or at the end of the line
+    underline.push(underline_char);
+  }
+  (line_text, underline)
+}
+
+/// Expands the tabs in a line to spaces when there is a tab width.
+fn expand_tabs(line: &str, tab_width: Option<usize>) -> Cow<str> {
+  match tab_width {
+    Some(tab_width) if line.contains('\t') => {
+      let mut text = String::with_capacity(line.len());
+      let mut column = 0;
+      for c in line.chars() {
+        let char_width = get_char_width(c, column, tab_width);
+        if c == '\t' {
+          text.push_str(&" ".repeat(char_width));
+        } else {
+          text.push(c);
+        }
+        column += char_width;
+      }
+      Cow::Owned(text)
+    }
+    _ => Cow::Borrowed(line),
+  }
+}
+
+/// Truncates a line that's wider than the maximum width, marking
+/// it with `...`.
+fn truncate_line(line: &str, max_line_width: Option<usize>) -> Cow<str> {
+  match max_line_width {
+    Some(max_chars) if line.chars().count() > max_chars => Cow::Owned(format!(
+      "{}...",
+      line.chars().take(max_chars).collect::<String>()
+    )),
+    _ => Cow::Borrowed(line),
+  }
+}
+
 /// Code in this function was adapted from:
 /// https://github.com/dprint/dprint/blob/a026a1350d27a61ea18207cb31897b18eaab51a1/crates/core/src/formatting/utils/string_utils.rs#L62
 fn get_range_text_highlight(
@@ -230,7 +1441,22 @@ mod test {
   use dprint_swc_ext::common::SourceTextInfo;
   use pretty_assertions::assert_eq;
 
+  use super::find_preceding_keyword;
+  use super::get_line_snippet;
   use super::get_range_text_highlight;
+  use super::widen_empty_range;
+  use super::DetachedDiagnostic;
+  use super::Diagnostic;
+  use super::DiagnosticDisplayRange;
+  use super::DiagnosticKind;
+  use super::DiagnosticSeverity;
+  use super::DisplaySpecifierOptions;
+  use super::DisplayStyle;
+  use super::RelatedInformation;
+  use super::SnippetOptions;
+  use crate::swc::parser::error::SyntaxError;
+  use crate::JsonDiagnosticKind;
+  use crate::LineAndColumnDisplay;
 
   #[test]
   fn range_highlight_all_text() {
@@ -349,4 +1575,697 @@ mod test {
       ),
     );
   }
+
+  #[test]
+  fn widen_empty_ranges() {
+    let text = SourceTextInfo::from_string("a≥\n".to_string());
+    let range =
+      SourceRange::new(text.range().start.as_source_pos(), text.range().end);
+    let empty = SourceRange::new(range.start + 1, range.start + 1);
+    assert_eq!(
+      widen_empty_range(empty, &text),
+      SourceRange::new(range.start + 1, range.end - 1)
+    );
+    // doesn't widen onto the next line
+    let empty = SourceRange::new(range.end - 1, range.end - 1);
+    assert_eq!(widen_empty_range(empty, &text), empty);
+    // nothing to widen to at the end of the text
+    let empty = SourceRange::new(range.end, range.end);
+    assert_eq!(widen_empty_range(empty, &text), empty);
+    // non-empty ranges are unchanged
+    assert_eq!(widen_empty_range(range, &text), range);
+  }
+
+  #[test]
+  fn display_specifier() {
+    fn display(specifier: &str, options: &DisplaySpecifierOptions) -> String {
+      let source = SourceTextInfo::from_string("".to_string());
+      let start = source.range().start.as_source_pos();
+      Diagnostic::new(
+        specifier,
+        SourceRange::new(start, start),
+        DiagnosticKind::MisplacedShebang,
+        source,
+      )
+      .display_specifier(options)
+      .to_string()
+    }
+
+    let unix = DisplaySpecifierOptions {
+      base_dir: None,
+      windows_paths: false,
+    };
+    let windows = DisplaySpecifierOptions {
+      base_dir: None,
+      windows_paths: true,
+    };
+    assert_eq!(
+      display("file:///home/me/my%20project/mod.ts", &unix),
+      "/home/me/my project/mod.ts"
+    );
+    assert_eq!(
+      display("file:///C%3A/Users/me/my%20project/mod.ts", &windows),
+      "C:\\Users\\me\\my project\\mod.ts"
+    );
+    assert_eq!(
+      display("file:///c:/dir/mod.ts", &windows),
+      "c:\\dir\\mod.ts"
+    );
+    assert_eq!(
+      display("file://server/share/mod.ts", &windows),
+      "\\\\server\\share\\mod.ts"
+    );
+    // can't be represented as a path
+    assert_eq!(
+      display("file://server/share/mod.ts", &unix),
+      "file://server/share/mod.ts"
+    );
+    assert_eq!(
+      display("file:///dir/mod.ts", &windows),
+      "file:///dir/mod.ts"
+    );
+    assert_eq!(display("file:///%FF.ts", &unix), "file:///%FF.ts");
+    // remote specifiers pass through unchanged
+    for specifier in [
+      "https://deno.land/x/my%20mod/mod.ts",
+      "npm:chalk@5",
+      "my_file.ts",
+    ] {
+      assert_eq!(display(specifier, &unix), specifier);
+      assert_eq!(display(specifier, &windows), specifier);
+    }
+
+    // relative to a base directory
+    let unix = DisplaySpecifierOptions {
+      base_dir: Some("/home/me/project/".into()),
+      ..unix
+    };
+    assert_eq!(
+      display("file:///home/me/project/src/mod.ts", &unix),
+      "src/mod.ts"
+    );
+    assert_eq!(
+      display("file:///home/me/project2/mod.ts", &unix),
+      "/home/me/project2/mod.ts"
+    );
+    let windows = DisplaySpecifierOptions {
+      base_dir: Some("C:/Users/me/project".into()),
+      ..windows
+    };
+    assert_eq!(
+      display("file:///c%3A/users/me/project/src/mod.ts", &windows),
+      "src\\mod.ts"
+    );
+    assert_eq!(
+      display("https://deno.land/x/mod.ts", &windows),
+      "https://deno.land/x/mod.ts"
+    );
+  }
+
+  #[test]
+  fn display_uses_display_specifier() {
+    let source = SourceTextInfo::from_string("a".to_string());
+    let range = SourceRange::new(
+      source.range().start.as_source_pos(),
+      source.range().end,
+    );
+    let diagnostic = Diagnostic::new(
+      "file:///dir/my%20mod.ts",
+      range,
+      DiagnosticKind::MisplacedShebang,
+      source.clone(),
+    )
+    .with_related(RelatedInformation {
+      message: "Related".to_string(),
+      range,
+    });
+    let expected_specifier = diagnostic
+      .display_specifier(&DisplaySpecifierOptions::default())
+      .to_string();
+    if !cfg!(windows) {
+      assert_eq!(expected_specifier, "/dir/my mod.ts");
+    }
+    assert!(diagnostic.to_string().starts_with(&format!(
+      "A shebang (#!) is only allowed at the start of a file at {}:1:1",
+      expected_specifier
+    )));
+    // the snippet uses the same specifier in the header and notes
+    let snippet = diagnostic.display_with_snippet(Default::default());
+    assert!(
+      snippet.starts_with(&format!("{}:1:1: ", expected_specifier)),
+      "{}",
+      snippet
+    );
+    assert!(
+      snippet
+        .ends_with(&format!("= note: Related at {}:1:1", expected_specifier)),
+      "{}",
+      snippet
+    );
+    assert!(diagnostic
+      .display_styled(DisplayStyle {
+        unicode: true,
+        ..Default::default()
+      })
+      .starts_with(&format!("{}:1:1: ", expected_specifier)));
+  }
+
+  #[test]
+  fn display_out_of_range_positions() {
+    let text = "let a = 'é';";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    let end = source.range().end;
+    let char_index = text.find('é').unwrap();
+    // one past the end of the file
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(end + 1, end + 1),
+      DiagnosticKind::MisplacedShebang,
+      source.clone(),
+    );
+    assert_eq!(
+      diagnostic.display_position(),
+      LineAndColumnDisplay {
+        line_number: 1,
+        column_number: 13,
+      }
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.ts:1:13: A shebang (#!) is only allowed at the start of a file\n",
+        "  let a = 'é';\n",
+        "              ^",
+      ),
+    );
+    assert!(diagnostic.to_string().contains("at mod.ts:1:13"));
+    // in the middle of a multi-byte character
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(start + char_index + 1, end + 5),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    assert_eq!(
+      diagnostic.display_range(),
+      DiagnosticDisplayRange {
+        start: LineAndColumnDisplay {
+          line_number: 1,
+          column_number: 10,
+        },
+        end: LineAndColumnDisplay {
+          line_number: 1,
+          column_number: 13,
+        },
+      }
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.ts:1:10: A shebang (#!) is only allowed at the start of a file\n",
+        "  let a = 'é';\n",
+        "           ^^^",
+      ),
+    );
+  }
+
+  #[test]
+  fn display_with_snippet_multi_byte_chars() {
+    let text = "let a = 1;\nconst b = a ≥ 2 ≥ 3;\n";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start + text.rfind('≥').unwrap();
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(start, start + '≥'.len_utf8()),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.ts:2:17: A shebang (#!) is only allowed at the start of a file\n",
+        "  const b = a ≥ 2 ≥ 3;\n",
+        "                  ^",
+      ),
+    );
+  }
+
+  #[test]
+  fn display_with_snippet_crlf() {
+    let text = "let a;\r\nlet b = #!c;\r\nlet d;\r\n";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start + text.find("#!").unwrap();
+    let diagnostic = Diagnostic::new(
+      "mod.js",
+      SourceRange::new(start, start + 2),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.js:2:9: A shebang (#!) is only allowed at the start of a file\n",
+        "  let b = #!c;\n",
+        "          ^^",
+      ),
+    );
+  }
+
+  #[test]
+  fn line_snippet_tabs() {
+    assert_eq!(
+      get_line_snippet("\tif (a) {\t}", 10, 11, '^', Some(80), None),
+      ("\tif (a) {\t}".to_string(), "\t        \t^".to_string()),
+    );
+  }
+
+  #[test]
+  fn line_snippet_expands_tabs() {
+    assert_eq!(
+      get_line_snippet("\tif (a) {\t}", 10, 11, '^', Some(80), Some(4)),
+      (
+        "    if (a) {    }".to_string(),
+        format!("{}^", " ".repeat(16))
+      ),
+    );
+    // a tab in the range is underlined for its full width
+    assert_eq!(
+      get_line_snippet("a\tb", 1, 2, '^', Some(80), Some(4)),
+      ("a   b".to_string(), " ^^^".to_string()),
+    );
+  }
+
+  #[test]
+  fn display_with_snippet_tab_width() {
+    let text = "function f() {\n\tconst a = 1;\n\t  b();\n\tif (a) {\t}\n}";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let create_diagnostic = |search_text: &str| {
+      let start = source.range().start + text.find(search_text).unwrap();
+      Diagnostic::new(
+        "mod.ts",
+        SourceRange::new(start, start + search_text.len()),
+        DiagnosticKind::MisplacedShebang,
+        source.clone(),
+      )
+    };
+    let options = SnippetOptions {
+      context_lines: 1,
+      tab_width: Some(4),
+      ..Default::default()
+    };
+    // indented by a tab
+    let diagnostic = create_diagnostic("a = 1");
+    assert_eq!(
+      diagnostic.display_with_snippet(options),
+      concat!(
+        "mod.ts:2:8: A shebang (#!) is only allowed at the start of a file\n",
+        "  function f() {\n",
+        "      const a = 1;\n",
+        "            ^^^^^\n",
+        "        b();",
+      ),
+    );
+    assert_eq!(
+      diagnostic.display_position_with_tab_width(4).column_number,
+      11
+    );
+    // tab then spaces
+    let diagnostic = create_diagnostic("b()");
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        context_lines: 0,
+        ..options
+      }),
+      concat!(
+        "mod.ts:3:4: A shebang (#!) is only allowed at the start of a file\n",
+        "        b();\n",
+        "        ^^^",
+      ),
+    );
+    assert_eq!(
+      diagnostic.display_position_with_tab_width(4).column_number,
+      7
+    );
+    assert_eq!(
+      diagnostic.display_position_with_tab_width(8).column_number,
+      11
+    );
+    // tab mid-line after text
+    let diagnostic = create_diagnostic("}\n");
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        context_lines: 0,
+        ..options
+      }),
+      concat!(
+        "mod.ts:4:11: A shebang (#!) is only allowed at the start of a file\n",
+        "      if (a) {    }\n",
+        "                  ^",
+      ),
+    );
+    // without a tab width, each tab is the indent width of the text
+    assert_eq!(diagnostic.display_position().column_number, 13);
+    assert_eq!(
+      diagnostic.display_position_with_tab_width(4).column_number,
+      17
+    );
+    // tabs are kept by default
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.ts:4:11: A shebang (#!) is only allowed at the start of a file\n",
+        "  \tif (a) {\t}\n",
+        "  \t        \t^",
+      ),
+    );
+  }
+
+  #[test]
+  fn line_snippet_empty_range_end_of_line() {
+    assert_eq!(
+      get_line_snippet("let a", 5, 5, '^', Some(80), None),
+      ("let a".to_string(), "     ^".to_string()),
+    );
+  }
+
+  #[test]
+  fn line_snippet_truncates_long_lines() {
+    let line = format!("{}target{}", "a".repeat(100), "b".repeat(100));
+    let (line_text, underline) =
+      get_line_snippet(&line, 100, 106, '^', Some(80), None);
+    assert_eq!(
+      line_text,
+      format!("...{}target{}...", "a".repeat(20), "b".repeat(54)),
+    );
+    assert_eq!(underline, format!("{}^^^^^^", " ".repeat(23)));
+  }
+
+  #[test]
+  fn diagnostic_codes() {
+    let codes = [
+      DiagnosticKind::Syntax(SyntaxError::Eof),
+      DiagnosticKind::Syntax(SyntaxError::TS1003),
+      DiagnosticKind::Syntax(SyntaxError::TS1109),
+      DiagnosticKind::Syntax(SyntaxError::UnterminatedStrLit),
+      DiagnosticKind::Syntax(SyntaxError::LegacyOctal),
+      DiagnosticKind::MisplacedShebang,
+      DiagnosticKind::Json(JsonDiagnosticKind::TrailingComma),
+    ]
+    .iter()
+    .map(|kind| kind.code().as_str())
+    .collect::<Vec<_>>();
+    assert_eq!(
+      codes,
+      vec![
+        "unexpected-eof",
+        "identifier-expected",
+        "expression-expected",
+        "unterminated-string",
+        "legacy-octal",
+        "misplaced-shebang",
+        "json-trailing-comma",
+      ]
+    );
+  }
+
+  #[test]
+  fn diagnostic_codes_stable_across_messages() {
+    let kind_a = DiagnosticKind::Syntax(SyntaxError::Unexpected {
+      got: "a".to_string(),
+      expected: "<eof>",
+    });
+    let kind_b = DiagnosticKind::Syntax(SyntaxError::Unexpected {
+      got: "b".to_string(),
+      expected: "';'",
+    });
+    assert_ne!(kind_a.msg(), kind_b.msg());
+    assert_eq!(kind_a.code(), kind_b.code());
+    assert_eq!(kind_a.code().to_string(), "unexpected-token");
+    // unmapped swc errors get a generic code
+    assert_eq!(
+      DiagnosticKind::Syntax(SyntaxError::LineBreakInThrow)
+        .code()
+        .as_str(),
+      "syntax-error"
+    );
+  }
+
+  #[test]
+  fn serialize_diagnostic() {
+    let text = "let a;\nlet b = #!c;";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start + text.find("#!").unwrap();
+    let diagnostic = Diagnostic::new(
+      "file:///mod.js",
+      SourceRange::new(start, start + 2),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    )
+    .with_severity(DiagnosticSeverity::Warning);
+    let json = serde_json::to_string_pretty(&diagnostic).unwrap();
+    assert_eq!(
+      json,
+      r#"{
+  "specifier": "file:///mod.js",
+  "message": "A shebang (#!) is only allowed at the start of a file",
+  "code": "misplaced-shebang",
+  "severity": "warning",
+  "range": {
+    "start": 15,
+    "end": 17
+  },
+  "lineNumber": 2,
+  "columnNumber": 9
+}"#
+    );
+    let detached = serde_json::from_str::<DetachedDiagnostic>(&json).unwrap();
+    assert_eq!(detached, diagnostic.to_detached());
+  }
+
+  #[test]
+  fn diagnostic_coordinates() {
+    // 𝒳 is 4 bytes, 1 char, and 2 UTF-16 code units
+    let text = "a;\nlet x = \"𝒳\"; #!b";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start + text.find("#!").unwrap();
+    let diagnostic = Diagnostic::new(
+      "file:///mod.js",
+      SourceRange::new(start, start + 2),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    assert_eq!(diagnostic.byte_range(), 19..21);
+    let range = diagnostic.display_range();
+    assert_eq!(
+      (range.start.line_number, range.start.column_number),
+      (2, 14)
+    );
+    assert_eq!((range.end.line_number, range.end.column_number), (2, 16));
+    let range = diagnostic.display_range_utf16();
+    assert_eq!(
+      (range.start.line_number, range.start.column_number),
+      (2, 15)
+    );
+    assert_eq!((range.end.line_number, range.end.column_number), (2, 17));
+    assert_eq!(diagnostic.display_position_utf16(), range.start);
+  }
+
+  #[test]
+  fn finds_preceding_keyword() {
+    let text = "a;\nconst constant = 1, const_b";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    assert_eq!(
+      find_preceding_keyword(&source, start + text.len(), "const"),
+      Some(SourceRange::new(start + 3, start + 8))
+    );
+    assert_eq!(find_preceding_keyword(&source, start + 2, "const"), None);
+  }
+
+  #[test]
+  fn display_with_snippet_context_lines() {
+    let text = (1..=10)
+      .map(|i| format!("const line{0} = {0};\n", i))
+      .collect::<Vec<_>>()
+      .concat();
+    let source = SourceTextInfo::from_string(text.clone());
+    let create_diagnostic = |line_number: usize| {
+      let start = source.range().start
+        + text.find(&format!("line{} ", line_number)).unwrap();
+      Diagnostic::new(
+        "mod.ts",
+        SourceRange::new(start, start + 5),
+        DiagnosticKind::MisplacedShebang,
+        source.clone(),
+      )
+    };
+    let diagnostic = create_diagnostic(5);
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        context_lines: 0,
+        ..Default::default()
+      }),
+      concat!(
+        "mod.ts:5:7: A shebang (#!) is only allowed at the start of a file\n",
+        "  const line5 = 5;\n",
+        "        ^^^^^",
+      ),
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        context_lines: 1,
+        max_line_width: None,
+        show_line_numbers: true,
+        tab_width: None,
+      }),
+      concat!(
+        "mod.ts:5:7: A shebang (#!) is only allowed at the start of a file\n",
+        "  4 | const line4 = 4;\n",
+        "  5 | const line5 = 5;\n",
+        "    |       ^^^^^\n",
+        "  6 | const line6 = 6;",
+      ),
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        context_lines: 2,
+        max_line_width: None,
+        show_line_numbers: true,
+        tab_width: None,
+      }),
+      concat!(
+        "mod.ts:5:7: A shebang (#!) is only allowed at the start of a file\n",
+        "  3 | const line3 = 3;\n",
+        "  4 | const line4 = 4;\n",
+        "  5 | const line5 = 5;\n",
+        "    |       ^^^^^\n",
+        "  6 | const line6 = 6;\n",
+        "  7 | const line7 = 7;",
+      ),
+    );
+    // line numbers are aligned
+    assert_eq!(
+      create_diagnostic(9).display_with_snippet(SnippetOptions {
+        context_lines: 1,
+        max_line_width: None,
+        show_line_numbers: true,
+        tab_width: None,
+      }),
+      concat!(
+        "mod.ts:9:7: A shebang (#!) is only allowed at the start of a file\n",
+        "   8 | const line8 = 8;\n",
+        "   9 | const line9 = 9;\n",
+        "     |       ^^^^^\n",
+        "  10 | const line10 = 10;",
+      ),
+    );
+
+    // middle lines of a long multi-line range are elided
+    let start = source.range().start + text.find("const line2").unwrap();
+    let end = source.range().start + text.find("\nconst line10").unwrap();
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(start, end),
+      DiagnosticKind::MisplacedShebang,
+      source.clone(),
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(Default::default()),
+      concat!(
+        "mod.ts:2:1: A shebang (#!) is only allowed at the start of a file\n",
+        "  const line2 = 2;\n",
+        "  ^^^^^^^^^^^^^^^^\n",
+        "  const line3 = 3;\n",
+        "  ^^^^^^^^^^^^^^^^\n",
+        "  ...\n",
+        "  const line8 = 8;\n",
+        "  ^^^^^^^^^^^^^^^^\n",
+        "  const line9 = 9;\n",
+        "  ^^^^^^^^^^^^^^^^",
+      ),
+    );
+  }
+
+  #[test]
+  fn display_with_snippet_truncates_long_line() {
+    let text = format!("{}#!{}\n", "a".repeat(250), "b".repeat(248));
+    let source = SourceTextInfo::from_string(text.clone());
+    let start = source.range().start + 250;
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(start, start + 2),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        max_line_width: Some(80),
+        ..Default::default()
+      }),
+      format!(
+        "{}\n  ...{}#!{}...\n  {}^^",
+        "mod.ts:1:251: A shebang (#!) is only allowed at the start of a file",
+        "a".repeat(20),
+        "b".repeat(58),
+        " ".repeat(23),
+      ),
+    );
+    assert_eq!(
+      diagnostic.display_with_snippet(SnippetOptions {
+        max_line_width: None,
+        ..Default::default()
+      }),
+      format!(
+        "{}\n  {}\n  {}^^",
+        "mod.ts:1:251: A shebang (#!) is only allowed at the start of a file",
+        text.trim_end(),
+        " ".repeat(250),
+      ),
+    );
+  }
+
+  #[test]
+  fn display_styled_multi_line_range() {
+    let text = "let a = {\n  b: 1,\n};";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let range = source.range();
+    let diagnostic = Diagnostic::new(
+      "mod.ts",
+      SourceRange::new(range.start + text.find('{').unwrap(), range.end),
+      DiagnosticKind::MisplacedShebang,
+      source,
+    );
+    let plain = concat!(
+      "mod.ts:1:9: A shebang (#!) is only allowed at the start of a file\n",
+      "  let a = {\n",
+      "          ^\n",
+      "    b: 1,\n",
+      "    ^^^^^\n",
+      "  };\n",
+      "  ^^",
+    );
+    assert_eq!(diagnostic.display_with_snippet(Default::default()), plain);
+    assert_eq!(diagnostic.display_styled(DisplayStyle::default()), plain);
+    assert_eq!(
+      diagnostic.display_styled(DisplayStyle {
+        color: true,
+        unicode: true,
+        snippet: SnippetOptions {
+          context_lines: 1,
+          ..Default::default()
+        },
+      }),
+      concat!(
+        "\x1b[2mmod.ts:1:9:\x1b[0m ",
+        "\x1b[1;31mA shebang (#!) is only allowed at the start of a file\x1b[0m\n",
+        "  let a = {\n",
+        "          \x1b[1;31m━\x1b[0m\n",
+        "    b: 1,\n",
+        "    \x1b[1;31m━━━━━\x1b[0m\n",
+        "  };\n",
+        "  \x1b[1;31m━━\x1b[0m",
+      ),
+    );
+  }
 }
@@ -0,0 +1,174 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::collections::HashMap;
+
+use crate::swc::ast::*;
+use crate::swc::atoms::Atom;
+use crate::swc::common::SyntaxContext;
+use crate::swc::visit::noop_visit_type;
+use crate::swc::visit::Visit;
+use crate::swc::visit::VisitWith;
+use crate::ParsedSource;
+use crate::SourceRange;
+use crate::SourceRangedForSpanned;
+
+/// References to an identifier that is not declared in the source,
+/// which is usually a global (ex. `Deno` or `window`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedRef {
+  pub name: Atom,
+  /// Ranges of the references in source order.
+  pub ranges: Vec<SourceRange>,
+}
+
+impl ParsedSource {
+  /// Gets the identifiers that are referenced, but not declared in the
+  /// source, sorted by name. This includes references in types.
+  ///
+  /// Returns `None` when the source doesn't have scope analysis.
+  pub fn unresolved_identifiers(&self) -> Option<Vec<UnresolvedRef>> {
+    let mut collector = UnresolvedIdentCollector {
+      unresolved_context: self.maybe_unresolved_context()?,
+      refs: HashMap::new(),
+    };
+    self.program_ref().visit_with(&mut collector);
+    let mut refs = collector
+      .refs
+      .into_iter()
+      .map(|(name, ranges)| UnresolvedRef { name, ranges })
+      .collect::<Vec<_>>();
+    refs.sort_by(|a, b| a.name.as_ref().cmp(b.name.as_ref()));
+    Some(refs)
+  }
+}
+
+struct UnresolvedIdentCollector {
+  unresolved_context: SyntaxContext,
+  refs: HashMap<Atom, Vec<SourceRange>>,
+}
+
+impl Visit for UnresolvedIdentCollector {
+  noop_visit_type!();
+
+  fn visit_ident(&mut self, n: &Ident) {
+    if n.span.ctxt == self.unresolved_context {
+      self.refs.entry(n.sym.clone()).or_default().push(n.range());
+    }
+  }
+
+  // property names are not references, so only visit computed ones
+
+  fn visit_member_prop(&mut self, n: &MemberProp) {
+    if let MemberProp::Computed(computed) = n {
+      computed.visit_with(self);
+    }
+  }
+
+  fn visit_super_prop(&mut self, n: &SuperProp) {
+    if let SuperProp::Computed(computed) = n {
+      computed.visit_with(self);
+    }
+  }
+
+  fn visit_prop_name(&mut self, n: &PropName) {
+    if let PropName::Computed(computed) = n {
+      computed.visit_with(self);
+    }
+  }
+}
+
+#[cfg(all(test, feature = "transforms"))]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use crate::parse_module;
+  use crate::MediaType;
+  use crate::ParseParams;
+  use crate::SourceTextInfo;
+
+  use super::*;
+
+  fn parse(text: &str, scope_analysis: bool) -> ParsedSource {
+    parse_module(
+      ParseParams::builder(
+        "file:///mod.ts",
+        SourceTextInfo::from_string(text.to_string()),
+      )
+      .media_type(MediaType::TypeScript)
+      .scope_analysis(scope_analysis)
+      .build(),
+    )
+    .unwrap()
+  }
+
+  fn unresolved_names(parsed_source: &ParsedSource) -> Vec<(String, usize)> {
+    parsed_source
+      .unresolved_identifiers()
+      .unwrap()
+      .into_iter()
+      .map(|r| (r.name.to_string(), r.ranges.len()))
+      .collect()
+  }
+
+  #[test]
+  fn globals() {
+    let parsed_source = parse(
+      concat!(
+        "const text = await Deno.readTextFile('a.txt');\n",
+        "console.log(text, Deno.args);\n",
+      ),
+      true,
+    );
+    let refs = parsed_source.unresolved_identifiers().unwrap();
+    assert_eq!(
+      refs
+        .iter()
+        .map(|r| (
+          r.name.to_string(),
+          r.ranges
+            .iter()
+            .map(|range| parsed_source.text_for_range(range))
+            .collect::<Vec<_>>()
+        ))
+        .collect::<Vec<_>>(),
+      vec![
+        ("Deno".to_string(), vec!["Deno", "Deno"]),
+        ("console".to_string(), vec!["console"]),
+      ]
+    );
+    assert!(refs[0].ranges[0].start < refs[0].ranges[1].start);
+  }
+
+  #[test]
+  fn shadowed_local() {
+    let parsed_source = parse(
+      concat!(
+        "function run(process: number) { return process + 1; }\n",
+        "const obj = { process: 1, window: 2 };\n",
+        "obj.process;\n",
+      ),
+      true,
+    );
+    assert_eq!(unresolved_names(&parsed_source), Vec::new());
+  }
+
+  #[test]
+  fn typeof_global() {
+    let parsed_source =
+      parse("if (typeof window !== 'undefined') { window.a = 1; }", true);
+    assert_eq!(
+      unresolved_names(&parsed_source),
+      vec![("window".to_string(), 2)]
+    );
+  }
+
+  #[test]
+  fn requires_scope_analysis() {
+    let parsed_source = parse("Deno.exit();", false);
+    assert_eq!(parsed_source.unresolved_identifiers(), None);
+    assert_eq!(
+      unresolved_names(&parsed_source.into_with_scope_analysis()),
+      vec![("Deno".to_string(), 1)]
+    );
+  }
+}
@@ -0,0 +1,162 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::lines::clamp_pos;
+use crate::LineAndColumnIndex;
+use crate::SourcePos;
+use crate::SourceRange;
+use crate::SourceTextInfo;
+
+/// Conversions between positions in the source text and UTF-16 code
+/// unit based line and character positions, such as the ones used by
+/// V8 and the language server protocol.
+///
+/// Only the text of the line being converted is scanned, with a fast
+/// path for lines that only contain ASCII characters.
+pub trait SourceTextInfoUtf16 {
+  /// Gets the 0-indexed line and UTF-16 code unit column of a position.
+  ///
+  /// Positions outside the text are clamped to the start or end of the
+  /// text and positions within a character are moved to its start.
+  fn line_and_column_utf16(&self, pos: SourcePos) -> LineAndColumnIndex;
+
+  /// Gets the position of a 0-indexed line and UTF-16 code unit column.
+  ///
+  /// A line past the last line is clamped to the end of the text and a
+  /// column past the end of the line is clamped to the end of the line.
+  /// A column within a surrogate pair is moved to the start of the pair.
+  fn pos_from_utf16(&self, line_index: usize, column_index: usize)
+    -> SourcePos;
+}
+
+impl SourceTextInfoUtf16 for SourceTextInfo {
+  fn line_and_column_utf16(&self, pos: SourcePos) -> LineAndColumnIndex {
+    let pos = clamp_pos(self, pos);
+    let line_index = self.line_index(pos);
+    let line_text =
+      self.range_text(&SourceRange::new(self.line_start(line_index), pos));
+    LineAndColumnIndex {
+      line_index,
+      column_index: if line_text.is_ascii() {
+        line_text.len()
+      } else {
+        line_text.encode_utf16().count()
+      },
+    }
+  }
+
+  fn pos_from_utf16(
+    &self,
+    line_index: usize,
+    column_index: usize,
+  ) -> SourcePos {
+    if line_index >= self.lines_count() {
+      return self.range().end;
+    }
+    let line_start = self.line_start(line_index);
+    let line_text = self.line_text(line_index);
+    if line_text.is_ascii() {
+      return line_start + std::cmp::min(column_index, line_text.len());
+    }
+    let mut utf16_index = 0;
+    let mut byte_index = 0;
+    for c in line_text.chars() {
+      utf16_index += c.len_utf16();
+      if utf16_index > column_index {
+        break;
+      }
+      byte_index += c.len_utf8();
+    }
+    line_start + byte_index
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn line_and_column(
+    line_index: usize,
+    column_index: usize,
+  ) -> LineAndColumnIndex {
+    LineAndColumnIndex {
+      line_index,
+      column_index,
+    }
+  }
+
+  #[test]
+  fn round_trips() {
+    let text = concat!(
+      "const cafe\u{301} = 'e\u{301}';\n",
+      "const a = '😀👍🏽';\r\n",
+      "const 𝑥 = 𝑥𝑦;\n",
+      "\n",
+      "// end",
+    );
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    for (byte_index, _) in text.char_indices().chain([(text.len(), ' ')]) {
+      if text[..byte_index].ends_with('\r') {
+        // within a line break
+        continue;
+      }
+      let pos = start + byte_index;
+      let position = source.line_and_column_utf16(pos);
+      assert_eq!(
+        source.pos_from_utf16(position.line_index, position.column_index),
+        pos,
+        "{:?}",
+        position,
+      );
+    }
+  }
+
+  #[test]
+  fn converts_to_utf16() {
+    let text = "let e\u{301} = 1;\nconst a = '😀';\nconst 𝑥 = 1;\n";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    let position_of = |search_text: &str| {
+      source.line_and_column_utf16(start + text.find(search_text).unwrap())
+    };
+    // combining characters are separate code units
+    assert_eq!(position_of(" = 1"), line_and_column(0, 6));
+    // emoji and astral plane identifiers are surrogate pairs
+    assert_eq!(position_of("';"), line_and_column(1, 13));
+    assert_eq!(position_of("𝑥"), line_and_column(2, 6));
+    assert_eq!(
+      source.line_and_column_utf16(start + text.find('𝑥').unwrap() + 4),
+      line_and_column(2, 8)
+    );
+    assert_eq!(
+      source.pos_from_utf16(2, 8),
+      start + text.find('𝑥').unwrap() + 4
+    );
+  }
+
+  #[test]
+  fn clamps_out_of_range_input() {
+    let text = "a😀b\r\nc\n";
+    let source = SourceTextInfo::from_string(text.to_string());
+    let start = source.range().start;
+    // within a character
+    assert_eq!(
+      source.line_and_column_utf16(start + 2),
+      line_and_column(0, 1)
+    );
+    // past the end of the text
+    assert_eq!(
+      source.line_and_column_utf16(source.range().end + 10),
+      line_and_column(2, 0)
+    );
+    // within a surrogate pair
+    assert_eq!(source.pos_from_utf16(0, 2), start + 1);
+    // past the end of the line
+    assert_eq!(source.pos_from_utf16(0, 100), start + 6);
+    assert_eq!(source.pos_from_utf16(1, 100), start + 9);
+    // past the last line
+    assert_eq!(source.pos_from_utf16(100, 0), source.range().end);
+  }
+}